@@ -0,0 +1,164 @@
+use anyhow::Result;
+use std::future::Future;
+use tracing::{info, warn};
+
+use crate::ollama::client::{GenerationStats, OllamaClient};
+
+/// Something that can run a prompt against a named model and report timing
+pub trait BenchBackend {
+    fn run(&self, model: &str, prompt: &str) -> impl Future<Output = Result<GenerationStats>> + Send;
+}
+
+impl BenchBackend for OllamaClient {
+    async fn run(&self, model: &str, prompt: &str) -> Result<GenerationStats> {
+        let mut client = self.clone();
+        client.set_model(model.to_string());
+        client.generate_with_stats(prompt).await
+    }
+}
+
+/// Averaged results for one model across the prompt set
+#[derive(Debug, Clone)]
+pub struct ModelBenchmark {
+    pub model: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub avg_latency_ms: Option<f64>,
+    pub avg_tokens_per_sec: Option<f64>,
+}
+
+impl ModelBenchmark {
+    pub fn from_metrics(model: &str, metrics: &[GenerationStats], failures: usize) -> Self {
+        let avg_latency_ms = average(metrics.iter().map(|m| m.latency_ms()));
+        let avg_tokens_per_sec = average(metrics.iter().filter_map(|m| m.tokens_per_sec()));
+
+        Self {
+            model: model.to_string(),
+            runs: metrics.len(),
+            failures,
+            avg_latency_ms,
+            avg_tokens_per_sec,
+        }
+    }
+}
+
+fn average(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+    if count == 0 {
+        None
+    } else {
+        Some(sum / count as f64)
+    }
+}
+
+/// Run every prompt against every model, one request at a time so timings don't interfere
+pub async fn run_benchmark<B: BenchBackend>(
+    backend: &B,
+    models: &[String],
+    prompts: &[String],
+) -> Vec<ModelBenchmark> {
+    let mut results = Vec::new();
+
+    for model in models {
+        info!("Benchmarking {} with {} prompts", model, prompts.len());
+        let mut metrics = Vec::new();
+        let mut failures = 0;
+
+        for prompt in prompts {
+            match backend.run(model, prompt).await {
+                Ok(m) => metrics.push(m),
+                Err(e) => {
+                    warn!("Benchmark prompt failed for {}: {}", model, e);
+                    failures += 1;
+                }
+            }
+        }
+
+        results.push(ModelBenchmark::from_metrics(model, &metrics, failures));
+    }
+
+    results
+}
+
+/// Render results as a plain-text comparison table
+pub fn format_table(results: &[ModelBenchmark]) -> String {
+    let width = results.iter().map(|r| r.model.len()).max().unwrap_or(0).max("Model".len());
+    let mut table = format!(
+        "{:<width$}  {:>12}  {:>10}  {:>8}\n",
+        "Model", "Latency (ms)", "Tokens/s", "Failed",
+        width = width
+    );
+
+    for result in results {
+        let latency = result.avg_latency_ms.map(|v| format!("{:.0}", v)).unwrap_or_else(|| "-".to_string());
+        let tps = result.avg_tokens_per_sec.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "-".to_string());
+        table.push_str(&format!(
+            "{:<width$}  {:>12}  {:>10}  {:>8}\n",
+            result.model,
+            latency,
+            tps,
+            format!("{}/{}", result.failures, result.runs + result.failures),
+            width = width
+        ));
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::time::Duration;
+
+    /// Fast model generates 100 tokens/s; the slow model takes twice as long and fails one prompt
+    struct MockBackend;
+
+    impl BenchBackend for MockBackend {
+        async fn run(&self, model: &str, prompt: &str) -> Result<GenerationStats> {
+            let scale = if model == "slow" { 2 } else { 1 };
+            if model == "slow" && prompt == "fail" {
+                return Err(anyhow!("model crashed"));
+            }
+            let base_ms = if prompt == "short" { 100 } else { 300 };
+            Ok(GenerationStats {
+                eval_tokens: 50,
+                duration: Duration::from_millis(base_ms * scale),
+                eval_duration: Some(Duration::from_millis(500 * scale)),
+                ..Default::default()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_averages_per_model() {
+        let models = vec!["fast".to_string(), "slow".to_string()];
+        let prompts = vec!["short".to_string(), "long".to_string(), "fail".to_string()];
+
+        let results = run_benchmark(&MockBackend, &models, &prompts).await;
+
+        assert_eq!(results.len(), 2);
+        let fast = &results[0];
+        assert_eq!((fast.runs, fast.failures), (3, 0));
+        // (100 + 300 + 300) / 3
+        assert!((fast.avg_latency_ms.unwrap() - 233.33).abs() < 0.01);
+        assert!((fast.avg_tokens_per_sec.unwrap() - 100.0).abs() < f64::EPSILON);
+
+        let slow = &results[1];
+        assert_eq!((slow.runs, slow.failures), (2, 1));
+        assert!((slow.avg_latency_ms.unwrap() - 400.0).abs() < f64::EPSILON);
+        assert!((slow.avg_tokens_per_sec.unwrap() - 50.0).abs() < f64::EPSILON);
+
+        assert!(format_table(&results).contains("1/3"));
+    }
+
+    #[test]
+    fn test_tokens_per_sec_needs_a_token_count() {
+        let metrics = GenerationStats {
+            duration: Duration::from_millis(250),
+            ..Default::default()
+        };
+        assert_eq!(metrics.latency_ms(), 250.0);
+        assert!(metrics.tokens_per_sec().is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use std::path::Path;
+
+use crate::db::CommandExecution;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+impl Role {
+    fn heading(&self) -> &'static str {
+        match self {
+            Role::User => "🧑 User",
+            Role::Assistant => "🤖 Assistant",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConversationTurn {
+    pub role: Role,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// An ordered record of agent queries and responses
+#[derive(Debug, Clone, Default)]
+pub struct Conversation {
+    pub turns: Vec<ConversationTurn>,
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a query and the response it got
+    pub fn push_exchange(&mut self, query: &str, asked_at: DateTime<Utc>, response: &str, answered_at: DateTime<Utc>) {
+        self.turns.push(ConversationTurn { role: Role::User, content: query.to_string(), timestamp: asked_at });
+        self.turns.push(ConversationTurn { role: Role::Assistant, content: response.to_string(), timestamp: answered_at });
+    }
+
+    /// Rebuild a conversation from recorded agent queries, oldest first; other commands are skipped
+    pub fn from_executions<'a>(executions: impl IntoIterator<Item = &'a CommandExecution>) -> Self {
+        let mut conversation = Self::new();
+        for execution in executions {
+            let Some(query) = &execution.agent_query else {
+                continue;
+            };
+            let answered_at = execution.timestamp + Duration::milliseconds(execution.duration_ms as i64);
+            conversation.push_exchange(query, execution.timestamp, &execution.output, answered_at);
+        }
+        conversation
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.turns.is_empty()
+    }
+
+    /// Number of query/response exchanges
+    pub fn exchanges(&self) -> usize {
+        self.turns.iter().filter(|turn| turn.role == Role::User).count()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!(
+            "# Agent Conversation\n\n_Exported {}_\n",
+            crate::offline::now().with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        );
+        for turn in &self.turns {
+            markdown.push_str(&format!(
+                "\n## {} ({})\n\n{}\n",
+                turn.role.heading(),
+                turn.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+                turn.content.trim_end()
+            ));
+        }
+        markdown
+    }
+
+    pub fn write_markdown(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_markdown())
+            .with_context(|| format!("Failed to write conversation to {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::ExecutionStatus;
+
+    #[test]
+    fn test_two_turn_conversation_markdown_is_ordered() {
+        let mut first = CommandExecution::new("agent 'what is a lifetime?'".to_string(), Some("What is a lifetime?".to_string()));
+        first.output = "A lifetime bounds how long a reference is valid.".to_string();
+        first.status = ExecutionStatus::Success;
+        first.timestamp = Utc::now() - Duration::minutes(5);
+        let mut second = CommandExecution::new("agent 'show an example'".to_string(), Some("Show an example".to_string()));
+        second.output = "fn longest<'a>(x: &'a str, y: &'a str) -> &'a str".to_string();
+        let other = CommandExecution::new("task list".to_string(), None);
+
+        let conversation = Conversation::from_executions([&first, &other, &second]);
+        assert_eq!(conversation.exchanges(), 2);
+
+        let markdown = conversation.to_markdown();
+        let positions: Vec<usize> = [
+            "## 🧑 User",
+            "What is a lifetime?",
+            "## 🤖 Assistant",
+            "A lifetime bounds",
+            "Show an example",
+            "fn longest",
+        ]
+        .iter()
+        .map(|needle| markdown.find(needle).unwrap_or_else(|| panic!("missing {:?}", needle)))
+        .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]), "sections out of order:\n{}", markdown);
+        assert_eq!(markdown.matches("## 🤖 Assistant").count(), 2);
+        assert!(!markdown.contains("task list"));
+    }
+}
@@ -5,11 +5,72 @@ use std::time::Duration;
 use tracing::{debug, info, warn};
 
 use crate::config::Config;
-use crate::ollama::client::OllamaClient;
+use crate::ollama::client::{GenerationStats, OllamaClient};
 use crate::ollama::OllamaConfig;
 use crate::ollama::client::ChatMessage as OllamaChatMessage;
+use crate::retry::RetryPolicy;
 
+pub mod bench;
+pub mod conversation;
 pub mod planner;
+pub mod suggestion;
+
+/// How many earlier query/response turns `Agent::converse` sends along with a new query
+pub const MAX_CONVERSATION_TURNS: usize = 10;
+
+/// Temperatures the model APIs accept
+pub const TEMPERATURE_RANGE: std::ops::RangeInclusive<f32> = 0.0..=2.0;
+
+/// Model settings for a single invocation (`--model`, `--temp`) that win over the config files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelOverrides {
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+}
+
+impl ModelOverrides {
+    /// Refuse a temperature outside `TEMPERATURE_RANGE` before anything is sent
+    pub fn validate(&self) -> Result<()> {
+        match self.temperature {
+            Some(temperature) if !TEMPERATURE_RANGE.contains(&temperature) => {
+                Err(anyhow!("Temperature {} is out of range; use a value from 0.0 to 2.0", temperature))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parse `--temp`, rejecting values outside `TEMPERATURE_RANGE`
+pub fn parse_temperature(value: &str) -> std::result::Result<f32, String> {
+    let temperature: f32 = value.trim().parse().map_err(|_| format!("'{}' isn't a number", value))?;
+    ModelOverrides { model: None, temperature: Some(temperature) }
+        .validate()
+        .map(|()| temperature)
+        .map_err(|e| e.to_string())
+}
+
+/// What the last connectivity check found out about the agent's model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// The model answered its health check
+    Online,
+    /// Queries go to a provider that isn't probed, with a key or server set for it
+    Configured,
+    /// Queries would get keyword fallback responses
+    Offline,
+}
+
+/// Ollama settings derived from the agent section of the config
+pub fn ollama_config(config: &Config) -> OllamaConfig {
+    OllamaConfig {
+        base_url: config.agent.ollama_host.clone(),
+        model: config.agent.ollama_model.clone(),
+        temperature: config.agent.temperature,
+        max_tokens: Some(config.agent.max_tokens),
+        timeout: Duration::from_secs(config.agent.timeout_seconds),
+        max_retries: config.agent.max_retries,
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum AIProvider {
@@ -24,6 +85,8 @@ pub struct Agent {
     api_key: Option<String>,
     provider: AIProvider,
     ollama_client: Option<OllamaClient>,
+    /// Earlier turns of the conversation, oldest first: alternating user and assistant messages
+    history: Vec<ChatMessage>,
 }
 
 #[derive(Debug, Serialize)]
@@ -71,7 +134,8 @@ impl Agent {
         
         // Determine provider based on config preference and API key availability
         let provider = match config.agent.preferred_provider.as_str() {
-            "openai" if config.get_openai_api_key().is_some() => AIProvider::OpenAI,
+            // Local OpenAI-compatible servers usually don't need a key
+            "openai" if config.get_openai_api_key().is_some() || config.agent.openai_base_url.is_some() => AIProvider::OpenAI,
             "ollama" => AIProvider::Ollama,
             _ => {
                 // Default to Ollama (phi4) as it's more powerful and local
@@ -79,19 +143,11 @@ impl Agent {
             }
         };
         
-        // Initialize Ollama client with phi4 model
+        // Initialize Ollama client with the configured model
         let ollama_client = if matches!(provider, AIProvider::Ollama) {
-            let ollama_config = OllamaConfig {
-                base_url: "http://localhost:11434".to_string(),
-                model: "phi4:latest".to_string(), // Use phi4 model
-                temperature: config.agent.temperature,
-                max_tokens: Some(config.agent.max_tokens),
-                timeout: Duration::from_secs(config.agent.timeout_seconds),
-            };
-            
-            match OllamaClient::new(ollama_config) {
+            match OllamaClient::new(ollama_config(config)) {
                 Ok(client) => {
-                    info!("✅ Ollama client initialized with phi4 model");
+                    info!("✅ Ollama client initialized with {} model", client.get_model());
                     Some(client)
                 }
                 Err(e) => {
@@ -109,52 +165,150 @@ impl Agent {
             api_key: config.get_openai_api_key(),
             provider,
             ollama_client,
+            history: Vec::new(),
         })
     }
     
+    pub fn provider_name(&self) -> &'static str {
+        match self.provider {
+            AIProvider::OpenAI => "openai",
+            AIProvider::Ollama => "ollama",
+        }
+    }
+    
+    /// The provider's name as shown in the TUI
+    pub fn provider_label(&self) -> &'static str {
+        match self.provider {
+            AIProvider::OpenAI => "OpenAI",
+            AIProvider::Ollama => "Ollama",
+        }
+    }
+    
+    /// Check, off the agent, whether queries would reach the model rather than get keyword
+    /// fallback responses: Ollama has to answer its health check, OpenAI needs a key or a
+    /// custom server. Nothing is sent to OpenAI, so it is only ever `Configured`, never `Online`.
+    pub fn connectivity_check(&self) -> impl std::future::Future<Output = Connectivity> + Send + 'static {
+        let provider = self.provider.clone();
+        let ollama_client = self.ollama_client.clone();
+        let openai_configured = self.api_key.is_some() || self.config.openai_base_url.is_some();
+        async move {
+            match provider {
+                AIProvider::Ollama => match ollama_client {
+                    Some(client) if client.health_check().await.unwrap_or(false) => Connectivity::Online,
+                    _ => Connectivity::Offline,
+                },
+                AIProvider::OpenAI if openai_configured && !crate::offline::is_enabled() => Connectivity::Configured,
+                AIProvider::OpenAI => Connectivity::Offline,
+            }
+        }
+    }
+    
+    /// The model answering queries, or `None` when only keyword fallback responses are available
+    pub fn model_name(&self) -> Option<&str> {
+        match self.provider {
+            AIProvider::OpenAI => Some(&self.config.model),
+            AIProvider::Ollama => self.ollama_client.as_ref().map(|client| client.get_model()),
+        }
+    }
+    
+    /// Switch the Ollama model for this agent only; returns false when Ollama isn't answering queries
+    pub fn set_model(&mut self, model: &str) -> bool {
+        match self.ollama_client.as_mut() {
+            Some(client) => {
+                client.set_model(model.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Tag an execution recording an agent query with the provider and model answering it
+    pub fn attribute(&self, execution: &mut crate::db::CommandExecution, overrides: &ModelOverrides) {
+        execution.provider = Some(self.provider_name().to_string());
+        execution.model = self.model_name().map(|model| overrides.model.as_deref().unwrap_or(model).to_string());
+    }
+    
+    /// Answer a one-off query; earlier turns of the conversation aren't sent
     pub async fn process_query(&self, query: &str) -> Result<String> {
+        Ok(self.process_query_with_stats(query, &ModelOverrides::default()).await?.0)
+    }
+    
+    /// `process_query` with `overrides` applied, along with the token counts and duration when
+    /// Ollama answered
+    pub async fn process_query_with_stats(&self, query: &str, overrides: &ModelOverrides) -> Result<(String, Option<GenerationStats>)> {
         info!("Processing agent query: {}", query);
-        
+        self.answer(query, &[], overrides).await
+    }
+    
+    /// Answer `query` as the next turn of the conversation, sending the last
+    /// `MAX_CONVERSATION_TURNS` turns along with it and remembering this one
+    pub async fn converse(&mut self, query: &str, overrides: &ModelOverrides) -> Result<String> {
+        info!("Processing agent query with {} earlier turn(s): {}", self.remembered_turns(), query);
+        let (response, _stats) = self.answer(query, &self.history, overrides).await?;
+        self.remember(query, &response);
+        Ok(response)
+    }
+    
+    /// Forget the conversation so the next query starts fresh
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+    
+    /// Number of query/response turns `converse` will send with the next query
+    pub fn remembered_turns(&self) -> usize {
+        self.history.len() / 2
+    }
+    
+    fn remember(&mut self, query: &str, response: &str) {
+        self.history.push(ChatMessage { role: "user".to_string(), content: query.to_string() });
+        self.history.push(ChatMessage { role: "assistant".to_string(), content: response.to_string() });
+        let excess = self.history.len().saturating_sub(MAX_CONVERSATION_TURNS * 2);
+        self.history.drain(..excess);
+    }
+    
+    async fn answer(&self, query: &str, history: &[ChatMessage], overrides: &ModelOverrides) -> Result<(String, Option<GenerationStats>)> {
+        overrides.validate()?;
         match self.provider {
-            AIProvider::OpenAI => self.process_openai_query(query).await,
-            AIProvider::Ollama => self.process_ollama_query(query).await,
+            AIProvider::OpenAI => Ok((self.process_openai_query(query, history, overrides).await?, None)),
+            AIProvider::Ollama => self.process_ollama_query(query, history, overrides).await,
         }
     }
     
-    async fn process_openai_query(&self, query: &str) -> Result<String> {
-        // Check if we have an API key
-        if self.api_key.is_none() {
+    /// The system prompt, then the earlier turns, then `query`
+    fn chat_messages(&self, query: &str, history: &[ChatMessage]) -> Vec<ChatMessage> {
+        let mut messages = Vec::with_capacity(history.len() + 2);
+        messages.push(ChatMessage { role: "system".to_string(), content: self.create_system_prompt() });
+        messages.extend(history.iter().cloned());
+        messages.push(ChatMessage { role: "user".to_string(), content: query.to_string() });
+        messages
+    }
+    
+    async fn process_openai_query(&self, query: &str, history: &[ChatMessage], overrides: &ModelOverrides) -> Result<String> {
+        // Check if we have an API key, or a custom server that may not need one
+        if (self.api_key.is_none() && self.config.openai_base_url.is_none()) || crate::offline::is_enabled() {
             return Ok(self.generate_fallback_response(query));
         }
         
-        // Create system prompt for command interpretation
-        let system_prompt = self.create_system_prompt();
-        
         let request = ChatRequest {
-            model: self.config.model.clone(),
-            messages: vec![
-                ChatMessage {
-                    role: "system".to_string(),
-                    content: system_prompt,
-                },
-                ChatMessage {
-                    role: "user".to_string(),
-                    content: query.to_string(),
-                },
-            ],
-            temperature: self.config.temperature,
+            model: overrides.model.clone().unwrap_or_else(|| self.config.model.clone()),
+            messages: self.chat_messages(query, history),
+            temperature: overrides.temperature.unwrap_or(self.config.temperature),
             max_tokens: self.config.max_tokens,
         };
         
         debug!("Sending request to OpenAI API");
         
-        let response = self.client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.api_key.as_ref().unwrap()))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+        let url = self.config.openai_chat_url();
+        let response = RetryPolicy::new(self.config.max_retries).send("OpenAI request", || {
+            let mut request_builder = self.client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .json(&request);
+            if let Some(api_key) = &self.api_key {
+                request_builder = request_builder.header("Authorization", format!("Bearer {}", api_key));
+            }
+            request_builder.send()
+        }).await?;
         
         if !response.status().is_success() {
             let error_text = response.text().await?;
@@ -171,7 +325,7 @@ impl Agent {
         }
     }
     
-    async fn process_ollama_query(&self, query: &str) -> Result<String> {
+    async fn process_ollama_query(&self, query: &str, history: &[ChatMessage], overrides: &ModelOverrides) -> Result<(String, Option<GenerationStats>)> {
         debug!("🤖 Sending request to Ollama phi4 model");
         
         // Check if we have an Ollama client
@@ -179,29 +333,38 @@ impl Agent {
             // First check if Ollama is healthy
             if !ollama_client.health_check().await.unwrap_or(false) {
                 warn!("⚠️  Ollama service not available, using fallback");
-                return Ok(self.generate_ollama_fallback_response(query));
+                return Ok((self.generate_ollama_fallback_response(query), None));
             }
             
             // Create structured chat messages for phi4
-            let system_prompt = self.create_system_prompt();
-            let messages = vec![
-                OllamaChatMessage::system(&system_prompt),
-                OllamaChatMessage::user(query),
-            ];
+            let mut messages = vec![OllamaChatMessage::system(&self.create_system_prompt())];
+            messages.extend(history.iter().map(|message| OllamaChatMessage {
+                role: message.role.clone(),
+                content: message.content.clone(),
+            }));
+            messages.push(OllamaChatMessage::user(query));
+            
+            let mut ollama_client = ollama_client.clone();
+            if let Some(model) = &overrides.model {
+                ollama_client.set_model(model.clone());
+            }
+            if let Some(temperature) = overrides.temperature {
+                ollama_client.set_temperature(temperature);
+            }
             
-            match ollama_client.chat(&messages).await {
-                Ok(response) => {
+            match ollama_client.chat_with_stats(&messages).await {
+                Ok(stats) => {
                     info!("🎯 phi4 model responded successfully");
-                    Ok(response.trim().to_string())
+                    Ok((stats.text.trim().to_string(), Some(stats)))
                 }
                 Err(e) => {
                     warn!("❌ phi4 model error: {}", e);
-                    Ok(self.generate_ollama_fallback_response(query))
+                    Ok((self.generate_ollama_fallback_response(query), None))
                 }
             }
         } else {
             info!("🔄 Ollama client not initialized, using enhanced fallback");
-            Ok(self.generate_ollama_fallback_response(query))
+            Ok((self.generate_ollama_fallback_response(query), None))
         }
     }
     
@@ -343,15 +506,50 @@ Response: "agentic task list --recent"
         )
     }
     
-    #[allow(dead_code)]
-    pub async fn interpret_command(&self, query: &str) -> Result<String> {
-        // This method specifically focuses on converting natural language to CLI commands
+    /// Turn a natural language request into one command, asking the model for JSON and falling
+    /// back to the free-text answer when it doesn't comply
+    pub async fn interpret_command(&self, query: &str) -> Result<InterpretedCommand> {
         let enhanced_query = format!(
-            "Convert this natural language request into a specific CLI command using the agentic CLI tool: {}",
+            "Convert this request into a single shell or agentic CLI command: {}\n\n\
+            Reply with ONLY a JSON object, no markdown, in this form:\n\
+            {{\"command\": \"<the command>\", \"explanation\": \"<one sentence>\", \"confidence\": <0.0 to 1.0>}}",
             query
         );
         
-        self.process_query(&enhanced_query).await
+        let response = self.process_query(&enhanced_query).await?;
+        Ok(InterpretedCommand::parse(&response))
+    }
+}
+
+/// A command the agent chose for a natural language request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterpretedCommand {
+    pub command: String,
+    #[serde(default)]
+    pub explanation: String,
+    /// How sure the model is, from 0.0 to 1.0; 0.0 when the reply wasn't the requested JSON
+    #[serde(default)]
+    pub confidence: f32,
+}
+
+impl InterpretedCommand {
+    /// Read the JSON object in `response`, allowing a code fence or prose around it. Anything
+    /// else is wrapped as the explanation, with the command `suggestion` finds in it (if any)
+    /// and no confidence.
+    pub fn parse(response: &str) -> Self {
+        let json = response.find('{').zip(response.rfind('}')).and_then(|(start, end)| response.get(start..=end));
+        match json.and_then(|json| serde_json::from_str::<Self>(json).ok()) {
+            Some(mut interpreted) if !interpreted.command.trim().is_empty() => {
+                interpreted.command = interpreted.command.trim().to_string();
+                interpreted.confidence = if interpreted.confidence.is_finite() { interpreted.confidence.clamp(0.0, 1.0) } else { 0.0 };
+                interpreted
+            }
+            _ => Self {
+                command: suggestion::suggested_command(response).unwrap_or_default(),
+                explanation: response.trim().to_string(),
+                confidence: 0.0,
+            },
+        }
     }
 }
 
@@ -371,4 +569,78 @@ mod tests {
         let response = agent.generate_fallback_response("start prep for exam");
         assert!(response.contains("agentic prep start"));
     }
+    
+    #[tokio::test]
+    async fn test_conversation_keeps_the_last_turns_until_cleared() {
+        let mut agent = Agent::new(&Config::default()).unwrap();
+        agent.ollama_client = None;
+        for turn in 0..MAX_CONVERSATION_TURNS + 3 {
+            agent.converse(&format!("question {}", turn), &ModelOverrides::default()).await.unwrap();
+        }
+        assert_eq!(agent.remembered_turns(), MAX_CONVERSATION_TURNS);
+        
+        let messages = agent.chat_messages("next", &agent.history);
+        let roles: Vec<&str> = messages.iter().map(|message| message.role.as_str()).collect();
+        assert_eq!(roles[..4], ["system", "user", "assistant", "user"]);
+        assert_eq!(messages[1].content, "question 3", "the oldest turns are dropped");
+        assert_eq!(messages.last().unwrap().content, "next");
+        
+        agent.process_query("one-off").await.unwrap();
+        assert_eq!(agent.remembered_turns(), MAX_CONVERSATION_TURNS, "one-off queries aren't remembered");
+        agent.clear_history();
+        assert_eq!(agent.remembered_turns(), 0);
+    }
+    
+    #[test]
+    fn test_interpreted_command_parses_json_or_wraps_the_reply() {
+        let reply = "```json\n{\"command\": \" git branch --list \", \"explanation\": \"Lists local branches\", \"confidence\": 1.4}\n```";
+        let interpreted = InterpretedCommand::parse(reply);
+        assert_eq!(interpreted.command, "git branch --list");
+        assert_eq!(interpreted.explanation, "Lists local branches");
+        assert_eq!(interpreted.confidence, 1.0);
+        
+        let interpreted = InterpretedCommand::parse("Try `ls -la` for hidden files.");
+        assert_eq!(interpreted.command, "ls -la");
+        assert_eq!(interpreted.explanation, "Try `ls -la` for hidden files.");
+        assert_eq!(interpreted.confidence, 0.0);
+        
+        assert_eq!(InterpretedCommand::parse("{\"command\": \"\"}").command, "", "an empty command isn't trusted");
+    }
+    
+    #[tokio::test]
+    async fn test_out_of_range_temperatures_are_refused() {
+        assert_eq!(parse_temperature("1.5"), Ok(1.5));
+        assert!(parse_temperature("2.1").unwrap_err().contains("0.0 to 2.0"));
+        assert!(parse_temperature("-0.1").is_err());
+        assert!(parse_temperature("warm").unwrap_err().contains("isn't a number"));
+        
+        let agent = Agent::new(&Config::default()).unwrap();
+        let overrides = ModelOverrides { model: Some("llama3".to_string()), temperature: Some(3.0) };
+        assert!(agent.process_query_with_stats("hi", &overrides).await.is_err());
+        
+        let mut execution = crate::db::CommandExecution::new("agent hi".to_string(), Some("hi".to_string()));
+        agent.attribute(&mut execution, &overrides);
+        if agent.model_name().is_some() {
+            assert_eq!(execution.model.as_deref(), Some("llama3"));
+        }
+    }
+    
+    #[test]
+    fn test_openai_compatible_base_url() {
+        let mut config = Config::default();
+        assert_eq!(config.agent.openai_chat_url(), "https://api.openai.com/v1/chat/completions");
+        
+        config.agent.preferred_provider = "openai".to_string();
+        config.agent.openai_base_url = Some("http://localhost:1234/v1/".to_string());
+        assert_eq!(config.agent.openai_chat_url(), "http://localhost:1234/v1/chat/completions");
+        assert_eq!(Agent::new(&config).unwrap().provider_name(), "openai", "a custom server doesn't need a key");
+    }
+
+    #[test]
+    fn test_ollama_host_comes_from_the_config() {
+        let mut config = Config::default();
+        assert_eq!(ollama_config(&config).base_url, "http://localhost:11434");
+        config.agent.ollama_host = "http://gpu-box:11434".to_string();
+        assert_eq!(ollama_config(&config).base_url, "http://gpu-box:11434");
+    }
 }
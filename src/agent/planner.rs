@@ -1,9 +1,11 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tracing::{debug, info};
+use std::future::Future;
+use tracing::{debug, info, warn};
 
 use super::Agent;
+use crate::warp::shell_runner::{ExecutionResult, ShellRunner};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecutionPlan {
@@ -22,19 +24,52 @@ pub struct ExecutionStep {
     pub retry_count: u32,
 }
 
+impl ExecutionPlan {
+    /// The steps ordered so each comes after the steps it depends on, otherwise keeping the
+    /// planned order. Dependencies on steps that aren't in the plan are ignored.
+    pub fn ordered_steps(&self) -> Result<Vec<&ExecutionStep>> {
+        let known = |dep: &String| self.steps.iter().any(|step| &step.id == dep);
+        let mut remaining: Vec<&ExecutionStep> = self.steps.iter().collect();
+        let mut ordered: Vec<&ExecutionStep> = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let ready = remaining.iter().position(|step| {
+                step.dependencies.iter().filter(|dep| known(dep)).all(|dep| ordered.iter().any(|done| &done.id == dep))
+            });
+            let Some(ready) = ready else {
+                let ids: Vec<&str> = remaining.iter().map(|step| step.id.as_str()).collect();
+                return Err(anyhow!("Plan steps depend on each other in a cycle: {}", ids.join(", ")));
+            };
+            ordered.push(remaining.remove(ready));
+        }
+        for step in &self.steps {
+            for dep in step.dependencies.iter().filter(|dep| !known(dep)) {
+                warn!("Step {} depends on unknown step {}; ignoring it", step.id, dep);
+            }
+        }
+        Ok(ordered)
+    }
+}
+
+/// Normalize a dependency written as `Step 2`, `2` or `step_2` to the step id `step_2`
+fn step_id(dependency: &str) -> String {
+    let digits: String = dependency.chars().filter(char::is_ascii_digit).collect();
+    if digits.is_empty() {
+        dependency.to_string()
+    } else {
+        format!("step_{}", digits)
+    }
+}
+
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct Planner {
     agent: Agent,
 }
 
 impl Planner {
-    #[allow(dead_code)]
     pub fn new(agent: Agent) -> Self {
         Self { agent }
     }
     
-    #[allow(dead_code)]
     pub async fn create_execution_plan(&self, goal: &str) -> Result<ExecutionPlan> {
         info!("Creating execution plan for goal: {}", goal);
         
@@ -50,7 +85,6 @@ impl Planner {
         Ok(plan)
     }
     
-    #[allow(dead_code)]
     fn create_planning_prompt(&self, goal: &str) -> String {
         format!(
             r#"Create a detailed execution plan for the following goal: {}
@@ -80,7 +114,6 @@ Focus on using the agentic CLI tool and standard terminal commands where appropr
         )
     }
     
-    #[allow(dead_code)]
     fn parse_plan_response(&self, response: &str, goal: &str) -> Result<ExecutionPlan> {
         let mut steps = Vec::new();
         let mut step_counter = 1;
@@ -132,7 +165,7 @@ Focus on using the agentic CLI tool and standard terminal commands where appropr
                 if deps_str != "None" {
                     current_dependencies = deps_str
                         .split(',')
-                        .map(|s| s.trim().to_string())
+                        .map(|s| step_id(s.trim()))
                         .collect();
                 }
             }
@@ -205,6 +238,49 @@ Focus on using the agentic CLI tool and standard terminal commands where appropr
         debug!("Optimized plan duration: {}s", optimized_plan.estimated_duration);
         Ok(optimized_plan)
     }
+    
+    /// Run every step of `plan` in dependency order without asking. With nobody to override it,
+    /// a step matching the runner's dangerous patterns stops the plan.
+    pub async fn execute_plan(&self, plan: &ExecutionPlan, runner: &ShellRunner) -> Result<Vec<ExecutionResult>> {
+        self.execute_plan_confirmed(plan, runner, |step| {
+            let pattern = runner.dangerous_match(&step.command);
+            if let Some(pattern) = pattern {
+                warn!("Step {} matches the dangerous pattern '{}'; not running it", step.id, pattern);
+            }
+            let allowed = pattern.is_none();
+            async move { allowed }
+        }).await
+    }
+    
+    /// Run the steps of `plan` in dependency order, asking `confirm` before each. A failing step
+    /// is retried up to its `retry_count`; the plan stops at a declined step or one that still fails.
+    /// Returns the outcome of each step that ran.
+    pub async fn execute_plan_confirmed<F, Fut>(&self, plan: &ExecutionPlan, runner: &ShellRunner, mut confirm: F) -> Result<Vec<ExecutionResult>>
+    where
+        F: FnMut(&ExecutionStep) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let mut results = Vec::new();
+        for step in plan.ordered_steps()? {
+            if !confirm(step).await {
+                info!("Stopping the plan before {}", step.id);
+                break;
+            }
+            let mut result = runner.execute(&step.command).await?;
+            let mut retries = 0;
+            while matches!(result, ExecutionResult::Error { .. }) && retries < step.retry_count {
+                retries += 1;
+                warn!("Step {} failed, retrying ({}/{})", step.id, retries, step.retry_count);
+                result = runner.execute(&step.command).await?;
+            }
+            let succeeded = matches!(result, ExecutionResult::Success { .. });
+            results.push(result);
+            if !succeeded {
+                break;
+            }
+        }
+        Ok(results)
+    }
 }
 
 #[cfg(test)]
@@ -230,6 +306,64 @@ mod tests {
         // The important thing is that the method doesn't panic
     }
     
+    fn step(id: &str, command: String, dependencies: &[&str], retry_count: u32) -> ExecutionStep {
+        ExecutionStep {
+            id: id.to_string(),
+            command,
+            description: id.to_string(),
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            expected_output: None,
+            retry_count,
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execute_plan_runs_in_dependency_order_and_stops_on_failure() {
+        let dir = std::env::temp_dir().join(format!("agentic-plan-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("log");
+        let marker = dir.join("marker");
+        let append = |word: &str| format!("echo {} >> {}", word, log.display());
+        let plan = ExecutionPlan {
+            steps: vec![
+                step("step_3", append("third"), &["step_2"], 0),
+                // Fails on the first try only
+                step("step_2", format!("test -f {0} || {{ touch {0}; exit 1; }}; {1}", marker.display(), append("second")), &["step_1"], 1),
+                step("step_1", append("first"), &[], 0),
+                step("step_4", "exit 4".to_string(), &["step_3"], 0),
+                step("step_5", append("never"), &["step_4"], 0),
+            ],
+            context: HashMap::new(),
+            estimated_duration: 0,
+        };
+        let planner = Planner::new(Agent::new(&Config::default()).unwrap());
+
+        let results = planner.execute_plan(&plan, &ShellRunner::new(false)).await.unwrap();
+        let written = std::fs::read_to_string(&log).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(written, "first\nsecond\nthird\n");
+        assert_eq!(results.len(), 4);
+        assert!(matches!(results[3], ExecutionResult::Error { exit_code: 4, .. }));
+    }
+
+    #[test]
+    fn test_dependency_cycles_are_rejected() {
+        let plan = ExecutionPlan {
+            steps: vec![
+                step("step_1", "true".to_string(), &["step_2"], 0),
+                step("step_2", "true".to_string(), &["step_1"], 0),
+                step("step_3", "true".to_string(), &["step_9"], 0),
+            ],
+            context: HashMap::new(),
+            estimated_duration: 0,
+        };
+        let error = plan.ordered_steps().unwrap_err();
+        assert!(error.to_string().contains("step_1, step_2"), "{}", error);
+        assert_eq!(step_id("Step 2"), "step_2");
+    }
+
     #[test]
     fn test_plan_parsing() {
         let config = Config::default();
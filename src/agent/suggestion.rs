@@ -0,0 +1,125 @@
+/// Words that introduce a command at the end of a sentence, e.g. "To list tasks, use: ..."
+const COMMAND_MARKERS: &[&str] = &["use:", "run:", "try:", "command:", "response:"];
+
+/// Shell builtins a suggestion may start with; anything else has to be on `PATH`
+const SHELL_BUILTINS: &[&str] = &["cd", "echo", "export", "source", "alias", "unset", "pwd", "type"];
+
+/// The one shell command `response` suggests, or `None` when it suggests none or several.
+/// In order of preference: the only line of its fenced code blocks if it reads like a command,
+/// its only inline code span that does, or the response itself when it is one line that does (optionally
+/// after "use:" and friends). A line reads like a command when it starts with a program on
+/// `PATH`, a shell builtin or `agentic`.
+pub fn suggested_command(response: &str) -> Option<String> {
+    let blocks = fenced_blocks(response);
+    if !blocks.is_empty() {
+        let lines: Vec<&str> = blocks
+            .iter()
+            .flat_map(|block| block.lines())
+            .map(clean)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        return match lines.as_slice() {
+            [line] if looks_like_command(line) => Some(line.to_string()),
+            _ => None,
+        };
+    }
+
+    let mut spans: Vec<&str> = inline_code_spans(response).into_iter().map(clean).filter(|span| looks_like_command(span)).collect();
+    spans.dedup();
+    if let [span] = spans.as_slice() {
+        return Some(span.to_string());
+    }
+
+    let lines: Vec<&str> = response.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    let [line] = lines.as_slice() else {
+        return None;
+    };
+    let candidate = clean(after_marker(line).unwrap_or(line));
+    looks_like_command(candidate).then(|| candidate.to_string())
+}
+
+/// The contents of each ``` fenced block
+fn fenced_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(lines) => blocks.push(lines.join("\n")),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(lines) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    blocks
+}
+
+/// Text between single backticks
+fn inline_code_spans(text: &str) -> Vec<&str> {
+    text.split('`').skip(1).step_by(2).collect()
+}
+
+/// What follows the last command marker on `line`
+fn after_marker(line: &str) -> Option<&str> {
+    let lower = line.to_lowercase();
+    COMMAND_MARKERS
+        .iter()
+        .filter_map(|marker| lower.rfind(marker).map(|at| at + marker.len()))
+        .max()
+        .and_then(|end| line.get(end..))
+}
+
+/// Trim a candidate and drop a `$ ` prompt or the quotes and backticks wrapped around it
+fn clean(candidate: &str) -> &str {
+    let mut candidate = candidate.trim();
+    candidate = candidate.strip_prefix("$ ").unwrap_or(candidate).trim();
+    for quote in ['`', '"', '\''] {
+        if candidate.len() > 1 && candidate.starts_with(quote) && candidate.ends_with(quote) {
+            candidate = candidate[1..candidate.len() - 1].trim();
+        }
+    }
+    candidate
+}
+
+fn looks_like_command(candidate: &str) -> bool {
+    if candidate.is_empty() || candidate.contains('\n') || candidate.ends_with(['.', '?', '!']) {
+        return false;
+    }
+    let Some(program) = candidate.split_whitespace().next() else {
+        return false;
+    };
+    program == "agentic" || SHELL_BUILTINS.contains(&program) || is_on_path(program)
+}
+
+fn is_on_path(program: &str) -> bool {
+    if program.contains('/') {
+        return std::path::Path::new(program).is_file();
+    }
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(program);
+        candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_commands_are_found_in_prose() {
+        let fenced = "List your branches with:\n\n```bash\n$ git branch --list\n```\n\nThe current one is starred.";
+        assert_eq!(suggested_command(fenced).as_deref(), Some("git branch --list"));
+        assert_eq!(suggested_command("Run `ls -la` to see hidden files.").as_deref(), Some("ls -la"));
+        assert_eq!(suggested_command("\"agentic task list --recent\"").as_deref(), Some("agentic task list --recent"));
+        assert_eq!(suggested_command("To list tasks, use: agentic task list").as_deref(), Some("agentic task list"));
+
+        assert_eq!(suggested_command("```\ngit fetch\ngit rebase origin/main\n```"), None, "two commands");
+        assert_eq!(suggested_command("Use `git stash` or `git commit` first."), None, "two choices");
+        assert_eq!(suggested_command("Rust ownership means each value has one owner."), None);
+        assert_eq!(suggested_command("definitely-not-a-program --flag"), None);
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use colored::*;
+use std::path::Path;
+
+use crate::agent::bench;
+use crate::agent::conversation::Conversation;
+use crate::agent::suggestion::suggested_command;
+use crate::agent::InterpretedCommand;
+use crate::config::Config;
+use crate::db::{CommandExecution, Database, ExecutionStatus, MAX_LIST_LIMIT};
+use crate::ollama::client::OllamaClient;
+use crate::warp::shell_runner::{ExecutionResult, ShellRunner};
+use super::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum AgentCommand {
+    /// Benchmark latency and throughput of Ollama models
+    Bench {
+        /// Model to benchmark (repeatable); defaults to every installed model
+        #[arg(long = "model", short)]
+        models: Vec<String>,
+        /// Prompt to run (repeatable); defaults to `agent.bench_prompts` from the config
+        #[arg(long = "prompt", short)]
+        prompts: Vec<String>,
+    },
+    /// Turn a request into one command with an explanation and confidence, without running it
+    Interpret {
+        /// What you want to do, e.g. "list my git branches"
+        query: String,
+    },
+}
+
+/// Run an agent subcommand and return its output for display; with `Output::Json`,
+/// `interpret` returns the command as JSON
+pub async fn execute(command: AgentCommand, config: &Config, output: Output) -> Result<String> {
+    match command {
+        AgentCommand::Interpret { query } => {
+            let interpreted = crate::agent::Agent::new(config)?.interpret_command(&query).await?;
+            if output.is_json() {
+                return Ok(serde_json::to_string_pretty(&interpreted)?);
+            }
+            Ok(render_interpreted(&interpreted))
+        }
+        AgentCommand::Bench { models, prompts } => {
+            let client = OllamaClient::new(crate::agent::ollama_config(config))?;
+
+            let models = if models.is_empty() {
+                match client.list_models().await {
+                    Ok(installed) if !installed.is_empty() => installed.into_iter().map(|m| m.name).collect(),
+                    _ => vec![client.get_model().to_string()],
+                }
+            } else {
+                models
+            };
+            let prompts = if prompts.is_empty() {
+                config.agent.bench_prompts.clone()
+            } else {
+                prompts
+            };
+            if prompts.is_empty() {
+                anyhow::bail!("No benchmark prompts configured; pass --prompt or set agent.bench_prompts");
+            }
+
+            let results = bench::run_benchmark(&client, &models, &prompts).await;
+            if results.iter().all(|r| r.runs == 0) {
+                anyhow::bail!("Every benchmark prompt failed; is Ollama running at {}?", crate::agent::ollama_config(config).base_url);
+            }
+            Ok(bench::format_table(&results))
+        }
+    }
+}
+
+/// The command, then the explanation and confidence
+pub fn render_interpreted(interpreted: &InterpretedCommand) -> String {
+    let command = if interpreted.command.is_empty() {
+        "(no single command found)".bright_black().to_string()
+    } else {
+        format!("$ {}", interpreted.command).yellow().to_string()
+    };
+    let confidence = if interpreted.confidence > 0.0 {
+        format!("{:.0}% confident", interpreted.confidence * 100.0)
+    } else {
+        "confidence unknown".to_string()
+    };
+    format!("{}\n{}\n{}", command, interpreted.explanation, confidence.bright_black())
+}
+
+/// Write every agent query recorded in the history, with its response, to `path` as markdown.
+/// Returns the number of exchanges written.
+pub async fn export_conversation(db: &Database, path: &Path) -> Result<usize> {
+    let mut history = db.get_command_history(MAX_LIST_LIMIT).await?;
+    history.reverse();
+    let conversation = Conversation::from_executions(&history);
+    if conversation.is_empty() {
+        anyhow::bail!("No agent conversation recorded yet; ask something with `agentic agent \"<query>\"` first");
+    }
+    conversation.write_markdown(path)?;
+    Ok(conversation.exchanges())
+}
+
+/// `agent --exec`: run the one shell command `response` suggests and return its record in the
+/// history, or `None` when the prompt was declined. Commands matching a `warp.safety` pattern
+/// are refused; the rest run after a y/N prompt unless `yes`, which a non-`interactive` caller
+/// must pass. With `interactive`, output streams to the terminal.
+pub async fn execute_suggestion(response: &str, db: &Database, config: &Config, yes: bool, interactive: bool) -> Result<Option<CommandExecution>> {
+    let command = suggested_command(response)
+        .ok_or_else(|| anyhow!("The response doesn't suggest a single shell command, so nothing was run"))?;
+    super::prep::check_focus_lock(db, &config.prep, &command).await?;
+    let agentic = crate::warp::config::AgenticConfig::load().await?;
+    let runner = ShellRunner::configured(interactive, &agentic)?;
+    if let Some(pattern) = runner.dangerous_match(&command) {
+        return Err(anyhow!("Not running `{}`: it matches the dangerous pattern '{}'", command, pattern));
+    }
+    if !yes {
+        if !interactive {
+            return Err(anyhow!("Add --yes to run `{}`", command));
+        }
+        println!("{} Run `{}`? (y/N): ", "▶".blue(), command.yellow());
+        let confirmed = crate::warp::read_answer().await.is_some_and(|answer| answer.trim().to_lowercase().starts_with('y'));
+        if !confirmed {
+            return Ok(None);
+        }
+    }
+
+    let result = runner.execute_safely(&agentic_on_path(&command)).await?;
+    let mut execution = CommandExecution::new(command.clone(), None);
+    let (status, output, duration) = match &result {
+        ExecutionResult::Success { stdout, duration, .. } => (ExecutionStatus::Success, stdout.clone(), *duration),
+        ExecutionResult::Error { stderr, duration, .. } => (ExecutionStatus::Error, stderr.clone(), *duration),
+        ExecutionResult::Cancelled { duration } => (ExecutionStatus::Cancelled, String::new(), *duration),
+        ExecutionResult::TimedOut { stderr, duration, .. } => (ExecutionStatus::Error, stderr.clone(), *duration),
+    };
+    execution.status = status;
+    execution.output = output;
+    execution.duration_ms = duration.as_millis() as u64;
+    execution.exit_code = Some(result.exit_code());
+    db.save_command_execution(&execution).await?;
+    Ok(Some(execution))
+}
+
+/// Suggestions name this tool `agentic`; run the current binary so it works without an install
+fn agentic_on_path(command: &str) -> String {
+    match (command.strip_prefix("agentic "), std::env::current_exe()) {
+        (Some(rest), Ok(exe)) => format!("{} {}", shell_words::quote(&exe.to_string_lossy()), rest),
+        _ => command.to_string(),
+    }
+}
@@ -0,0 +1,126 @@
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::Config;
+
+/// How many aliases may expand in a row before it counts as a loop
+pub const MAX_ALIAS_DEPTH: usize = 10;
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum AliasCommand {
+    /// Show the aliases from the config file
+    List,
+    /// Save an alias; a command starting with `name` runs `expansion` instead
+    Add {
+        /// The word to type, e.g. `t`
+        name: String,
+        /// What it stands for, e.g. `task`; quote it when it has spaces
+        expansion: String,
+    },
+}
+
+/// Run an alias subcommand and return its output for display
+pub async fn execute(command: AliasCommand, config: &mut Config) -> Result<String> {
+    match command {
+        AliasCommand::List => Ok(render_list(&config.aliases)),
+        AliasCommand::Add { name, expansion } => {
+            let path = Config::config_path();
+            persist_alias(config, &path, &name, &expansion).await?;
+            Ok(format!("🔗 {} now runs `{}`, saved in {}", name, expansion.trim(), path.display()))
+        }
+    }
+}
+
+/// Replace the first word of `command` while it names an alias. An alias whose expansion starts
+/// with its own name (`ls = "ls --color"`) expands once; any other chain longer than
+/// `MAX_ALIAS_DEPTH` is an error.
+pub fn expand(command: &str, aliases: &HashMap<String, String>) -> Result<String> {
+    let mut command = command.trim().to_string();
+    for _ in 0..MAX_ALIAS_DEPTH {
+        let (word, rest) = command.split_once(char::is_whitespace).unwrap_or((&command, ""));
+        let Some(expansion) = aliases.get(word) else {
+            return Ok(command);
+        };
+        let self_referencing = expansion.split_whitespace().next() == Some(word);
+        command = format!("{} {}", expansion.trim(), rest.trim_start()).trim_end().to_string();
+        if self_referencing {
+            return Ok(command);
+        }
+    }
+    let word = command.split_whitespace().next().unwrap_or_default();
+    if aliases.contains_key(word) {
+        Err(anyhow!("Alias '{}' expands more than {} times; check [aliases] for a loop", word, MAX_ALIAS_DEPTH))
+    } else {
+        Ok(command)
+    }
+}
+
+/// Add or replace an alias and write the config back to `path`, refusing names that can't be
+/// typed as one word and aliases that would loop
+pub async fn persist_alias(config: &mut Config, path: &Path, name: &str, expansion: &str) -> Result<()> {
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(anyhow!("Alias name '{}' must be a single word", name));
+    }
+    if expansion.trim().is_empty() {
+        return Err(anyhow!("Alias '{}' needs something to expand to", name));
+    }
+    let mut aliases = config.aliases.clone();
+    aliases.insert(name.to_string(), expansion.trim().to_string());
+    expand(name, &aliases)?;
+
+    config.aliases = aliases;
+    config.save_to(path).await
+}
+
+/// Aliases sorted by name
+pub fn render_list(aliases: &HashMap<String, String>) -> String {
+    if aliases.is_empty() {
+        return "🔗 Aliases\nNo aliases yet; add one with `agentic alias add <name> <expansion>`".to_string();
+    }
+    let mut aliases: Vec<_> = aliases.iter().collect();
+    aliases.sort();
+    let width = aliases.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut output = format!("🔗 Aliases ({})\n", aliases.len());
+    for (name, expansion) in aliases {
+        output.push_str(&format!("  {:width$} = {}\n", name, expansion, width = width));
+    }
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_aliases_expand_and_loops_are_refused() {
+        let aliases = HashMap::from([
+            ("t".to_string(), "task".to_string()),
+            ("tl".to_string(), "t list".to_string()),
+            ("ls".to_string(), "ls --color".to_string()),
+            ("ping".to_string(), "pong".to_string()),
+            ("pong".to_string(), "ping".to_string()),
+        ]);
+        assert_eq!(expand("tl --limit 5", &aliases).unwrap(), "task list --limit 5");
+        assert_eq!(expand("  t  ", &aliases).unwrap(), "task");
+        assert_eq!(expand("ls -l", &aliases).unwrap(), "ls --color -l");
+        assert_eq!(expand("echo t", &aliases).unwrap(), "echo t", "only the first word expands");
+        let error = expand("ping", &aliases).unwrap_err().to_string();
+        assert!(error.contains("loop"), "{}", error);
+
+        let dir = std::env::temp_dir().join(format!("agentic-alias-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.toml");
+        let mut config = Config::default();
+        persist_alias(&mut config, &path, "gs", "git status").await.unwrap();
+        persist_alias(&mut config, &path, "a", "b").await.unwrap();
+        assert!(persist_alias(&mut config, &path, "b", "a x").await.is_err());
+        assert!(persist_alias(&mut config, &path, "two words", "x").await.is_err());
+        let (saved, _) = Config::load_and_migrate(&path).await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(saved.aliases.get("gs").map(String::as_str), Some("git status"));
+        assert!(!saved.aliases.contains_key("b"), "a refused alias isn't saved");
+        assert_eq!(render_list(&saved.aliases), "🔗 Aliases (2)\n  a  = b\n  gs = git status");
+    }
+}
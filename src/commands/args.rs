@@ -0,0 +1,59 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Parse one `key=value` pair, splitting on the first `=` so values may contain `=` themselves.
+///
+/// Keys and values are trimmed; keys must be non-empty, values may be empty (`key=`).
+/// The signature fits clap's `value_parser`, so arguments can be checked as they're parsed.
+pub fn parse_key_value(pair: &str) -> Result<(String, String)> {
+    let (key, value) = pair
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid argument '{}', expected key=value", pair))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(anyhow!("Invalid argument '{}', the key before '=' is empty", pair));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// Parse a list of `key=value` pairs into a map, rejecting malformed pairs and repeated keys
+pub fn parse_key_values<S: AsRef<str>>(pairs: &[S]) -> Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+    for pair in pairs {
+        let (key, value) = parse_key_value(pair.as_ref())?;
+        if map.contains_key(&key) {
+            return Err(anyhow!("Argument '{}' was given more than once", key));
+        }
+        map.insert(key, value);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_valid_pairs() {
+        let map = parse_key_values(&["name=world", " count = 3 ", "empty="]).unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["name"], "world");
+        assert_eq!(map["count"], "3");
+        assert_eq!(map["empty"], "");
+    }
+
+    #[test]
+    fn test_value_may_contain_equals() {
+        assert_eq!(
+            parse_key_value("query=a=b&c=d").unwrap(),
+            ("query".to_string(), "a=b&c=d".to_string())
+        );
+    }
+
+    #[test]
+    fn test_malformed_pairs_are_rejected() {
+        assert!(parse_key_value("novalue").unwrap_err().to_string().contains("expected key=value"));
+        assert!(parse_key_value(" =value").unwrap_err().to_string().contains("key before '=' is empty"));
+        assert!(parse_key_values(&["a=1", "a=2"]).unwrap_err().to_string().contains("more than once"));
+    }
+}
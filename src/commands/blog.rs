@@ -1,9 +1,13 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, Utc};
 use clap::Subcommand;
 use colored::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use crate::db::Database;
+use crate::db::{Database, DEFAULT_LIST_LIMIT, MAX_LIST_LIMIT};
+use super::output::{print_json, Output};
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum BlogCommand {
@@ -12,8 +16,8 @@ pub enum BlogCommand {
         /// Blog title
         #[arg(long, short)]
         title: String,
-        /// Tags for the blog post
-        #[arg(long, short = 'g')]
+        /// Tags for the blog post (repeat or comma-separate)
+        #[arg(long, short = 'g', value_delimiter = ',')]
         tags: Vec<String>,
     },
     /// Edit an existing blog post
@@ -36,6 +40,9 @@ pub enum BlogCommand {
         /// Show only drafts
         #[arg(long)]
         drafts: bool,
+        /// Maximum number of posts to show (capped by `max_list_limit` in the config)
+        #[arg(long, default_value_t = DEFAULT_LIST_LIMIT)]
+        limit: usize,
     },
     /// Delete a blog post
     Delete {
@@ -49,110 +56,484 @@ pub enum BlogCommand {
         #[arg(long, short)]
         post_id: String,
     },
+    /// Show word counts and posts per tag across all posts
+    Stats,
+    /// Write published posts and an index.json manifest for a static site generator
+    Export {
+        /// Output directory
+        #[arg(long, short)]
+        out: PathBuf,
+        /// Export drafts as well, marked with `draft: true`
+        #[arg(long)]
+        include_drafts: bool,
+    },
 }
 
+/// A blog post; the body lives in `<id>.md` under the blog directory
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlogPost {
     pub id: String,
     pub title: String,
-    pub content: String,
     pub tags: Vec<String>,
     pub status: PostStatus,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum PostStatus {
     Draft,
     Published,
     Archived,
 }
 
-pub async fn execute(command: BlogCommand, _db: &Database) -> Result<()> {
+impl std::fmt::Display for PostStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PostStatus::Draft => write!(f, "Draft"),
+            PostStatus::Published => write!(f, "Published"),
+            PostStatus::Archived => write!(f, "Archived"),
+        }
+    }
+}
+
+impl std::str::FromStr for PostStatus {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "draft" => Ok(PostStatus::Draft),
+            "published" => Ok(PostStatus::Published),
+            "archived" => Ok(PostStatus::Archived),
+            _ => Err(anyhow!("Invalid post status: {}", s)),
+        }
+    }
+}
+
+impl BlogPost {
+    pub fn new(title: String, tags: Vec<String>) -> Self {
+        let now = crate::offline::now();
+        Self {
+            id: crate::offline::new_id(),
+            title,
+            tags,
+            status: PostStatus::Draft,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Location of the post's markdown file
+    pub fn path(&self, blog_dir: &Path) -> PathBuf {
+        blog_dir.join(format!("{}.md", self.id))
+    }
+}
+
+/// YAML block at the top of every post file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Frontmatter {
+    pub title: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub date: String,
+    pub status: String,
+}
+
+impl Frontmatter {
+    pub fn for_post(post: &BlogPost) -> Self {
+        Self {
+            title: post.title.clone(),
+            tags: post.tags.clone(),
+            date: post.created_at.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+            status: post.status.to_string().to_lowercase(),
+        }
+    }
+}
+
+/// Split a post into its frontmatter YAML (without the `---` fences) and body
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content.strip_prefix("---\n").or_else(|| content.strip_prefix("---\r\n")) else {
+        return (None, content);
+    };
+    match rest.find("\n---") {
+        Some(end) => {
+            let yaml = &rest[..end];
+            let body = rest[end + 4..].trim_start_matches(['\r', '\n']);
+            (Some(yaml), body)
+        }
+        None => (None, content),
+    }
+}
+
+/// Average reading speed used for reading-time estimates
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// Count words in a markdown body, ignoring bare markup such as `#`, `-` or `---`
+pub fn word_count(body: &str) -> usize {
+    body.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Estimated minutes to read `words`, rounded up
+pub fn reading_minutes(words: usize) -> usize {
+    words.div_ceil(WORDS_PER_MINUTE)
+}
+
+/// Word count of a post's file, without its frontmatter
+fn post_word_count(path: &Path) -> Option<usize> {
+    let content = std::fs::read_to_string(path).ok()?;
+    Some(word_count(split_frontmatter(&content).1))
+}
+
+/// Totals reported by `blog stats`
+#[derive(Debug, Default)]
+pub struct BlogStats {
+    pub posts: usize,
+    pub total_words: usize,
+    /// Posts whose markdown file could not be read
+    pub missing_files: usize,
+    pub posts_per_tag: BTreeMap<String, usize>,
+}
+
+impl BlogStats {
+    pub fn collect(posts: &[BlogPost], blog_dir: &Path) -> Self {
+        let mut stats = Self { posts: posts.len(), ..Default::default() };
+        for post in posts {
+            match post_word_count(&post.path(blog_dir)) {
+                Some(words) => stats.total_words += words,
+                None => stats.missing_files += 1,
+            }
+            for tag in &post.tags {
+                *stats.posts_per_tag.entry(tag.to_lowercase()).or_default() += 1;
+            }
+        }
+        stats
+    }
+}
+
+/// Turn a title into a URL slug: lowercase, with runs of non-alphanumerics collapsed into one hyphen
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "post".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Slugify every title, suffixing `-2`, `-3`, ... on collisions
+pub fn unique_slugs<'a>(titles: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut taken = std::collections::HashSet::new();
+    titles.into_iter().map(|title| {
+        let base = slugify(title);
+        let mut slug = base.clone();
+        let mut n = 2;
+        while !taken.insert(slug.clone()) {
+            slug = format!("{}-{}", base, n);
+            n += 1;
+        }
+        slug
+    }).collect()
+}
+
+/// Frontmatter understood by both Hugo and Jekyll
+#[derive(Debug, Serialize)]
+struct ExportFrontmatter<'a> {
+    title: &'a str,
+    date: String,
+    slug: &'a str,
+    tags: &'a [String],
+    draft: bool,
+}
+
+/// One entry of the exported `index.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportEntry {
+    pub title: String,
+    pub slug: String,
+    pub date: DateTime<Utc>,
+    pub tags: Vec<String>,
+}
+
+/// Write each post as `<slug>.md` under `out` plus an `index.json` manifest; returns the manifest
+fn export_posts(posts: &[BlogPost], blog_dir: &Path, out: &Path) -> Result<Vec<ExportEntry>> {
+    std::fs::create_dir_all(out)
+        .with_context(|| format!("Failed to create export directory {:?}", out))?;
+
+    // Oldest first, so an existing post keeps its slug when a newer one collides with it
+    let mut posts: Vec<&BlogPost> = posts.iter().collect();
+    posts.sort_by_key(|post| post.created_at);
+    let slugs = unique_slugs(posts.iter().map(|post| post.title.as_str()));
+
+    let mut index = Vec::new();
+    for (post, slug) in posts.into_iter().zip(slugs) {
+        let path = post.path(blog_dir);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => {
+                println!("{} Skipping {}: post file {:?} is missing", "⚠".yellow(), post.title, path);
+                continue;
+            }
+        };
+        let (_, body) = split_frontmatter(&content);
+
+        let frontmatter = ExportFrontmatter {
+            title: &post.title,
+            date: post.created_at.to_rfc3339(),
+            slug: &slug,
+            tags: &post.tags,
+            draft: post.status != PostStatus::Published,
+        };
+        let rendered = format!("---\n{}---\n\n{}", serde_yaml::to_string(&frontmatter)?, body);
+        let target = out.join(format!("{}.md", slug));
+        std::fs::write(&target, rendered)
+            .with_context(|| format!("Failed to write {:?}", target))?;
+
+        index.push(ExportEntry {
+            title: post.title.clone(),
+            slug,
+            date: post.created_at,
+            tags: post.tags.clone(),
+        });
+    }
+
+    std::fs::write(out.join("index.json"), serde_json::to_string_pretty(&index)?)?;
+    Ok(index)
+}
+
+fn render_post(frontmatter: &Frontmatter, body: &str) -> Result<String> {
+    Ok(format!("---\n{}---\n\n{}", serde_yaml::to_string(frontmatter)?, body))
+}
+
+/// Rewrite the `status` field of a post file's frontmatter, leaving everything else untouched
+fn write_frontmatter_status(path: &Path, status: &PostStatus) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read post file {:?}", path))?;
+    let (yaml, body) = split_frontmatter(&content);
+
+    let mut fields: serde_yaml::Mapping = match yaml {
+        Some(yaml) => serde_yaml::from_str(yaml)
+            .with_context(|| format!("Invalid frontmatter in {:?}", path))?,
+        None => serde_yaml::Mapping::new(),
+    };
+    fields.insert("status".into(), status.to_string().to_lowercase().into());
+
+    let updated = format!("---\n{}---\n\n{}", serde_yaml::to_string(&fields)?, body);
+    std::fs::write(path, updated)?;
+    Ok(())
+}
+
+async fn find_post(db: &Database, post_id: &str) -> Result<BlogPost> {
+    db.find_blog_post(post_id).await?
+        .ok_or_else(|| anyhow!("No blog post found matching '{}'", post_id))
+}
+
+/// Open `path` in `$EDITOR` (or a platform default) and wait for it to exit
+fn open_in_editor(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(target_os = "windows") { "notepad".to_string() } else { "vi".to_string() });
+    let mut parts = shell_words::split(&editor)
+        .map_err(|e| anyhow!("Invalid EDITOR '{}': {}", editor, e))?;
+    if parts.is_empty() {
+        return Err(anyhow!("EDITOR is empty"));
+    }
+    let program = parts.remove(0);
+
+    let status = std::process::Command::new(&program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{}'", program))?;
+    if !status.success() {
+        return Err(anyhow!("Editor exited with {}", status));
+    }
+    Ok(())
+}
+
+fn format_local(dt: DateTime<Utc>) -> String {
+    dt.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+}
+
+/// Run a blog subcommand; with `Output::Json`, `list` prints the posts as JSON instead
+pub async fn execute(command: BlogCommand, db: &Database, blog_dir: &Path, output: Output) -> Result<()> {
     match command {
         BlogCommand::New { title, tags } => {
-            println!("{} Starting a new blog post", "📝".green().bold());
-            println!("Title: {}", title.bold());
-            println!("Tags: {}", format!("{:?}", tags).yellow());
+            let post = BlogPost::new(title, tags);
+            let path = post.path(blog_dir);
 
-            // Simulate blog creation
-            let post_id = "blog_001";
+            std::fs::create_dir_all(blog_dir)
+                .with_context(|| format!("Failed to create blog directory {:?}", blog_dir))?;
+            let body = format!("# {}\n\n", post.title);
+            std::fs::write(&path, render_post(&Frontmatter::for_post(&post), &body)?)
+                .with_context(|| format!("Failed to write post file {:?}", path))?;
+            if let Err(e) = db.add_blog_post(&post).await {
+                let _ = std::fs::remove_file(&path);
+                return Err(e);
+            }
+
+            println!("{} Starting a new blog post", "📝".green().bold());
+            println!("Title: {}", post.title.bold());
+            println!("Tags: {}", post.tags.join(", ").yellow());
             println!("{} Blog post created successfully!", "✓".green().bold());
-            println!("Post ID: {}", post_id.bright_blue());
+            println!("Post ID: {}", post.id.bright_blue());
+            println!("File: {}", path.display().to_string().bright_black());
         }
 
         BlogCommand::Edit { post_id } => {
-            println!("{} Editing blog post: {}", "✏".yellow().bold(), post_id.bright_blue());
-            // Simulated editing
-            println!("Open editor for post {}
-Check your favorite Markdown editor and start editing!", post_id.bright_cyan());
+            let post = find_post(db, &post_id).await?;
+            let path = post.path(blog_dir);
+            if !path.exists() {
+                return Err(anyhow!("Post file {:?} is missing", path));
+            }
+
+            println!("{} Editing blog post: {}", "✏".yellow().bold(), post.title.bright_blue());
+            open_in_editor(&path)?;
+            
+            // The frontmatter is the source of truth for title and tags once the file is edited
+            let content = std::fs::read_to_string(&path)?;
+            let (title, tags) = match split_frontmatter(&content).0.map(serde_yaml::from_str::<Frontmatter>) {
+                Some(Ok(frontmatter)) => (frontmatter.title, frontmatter.tags),
+                _ => (post.title.clone(), post.tags.clone()),
+            };
+            db.update_blog_post_meta(&post.id, &title, &tags).await?;
+            println!("{} Saved {}", "✓".green().bold(), path.display());
         }
 
         BlogCommand::Publish { post_id } => {
-            println!("{} Publishing blog post: {}", "🚀".green().bold(), post_id.bright_blue());
-            // Simulated publishing
-            println!("Blog post {} has been published!", post_id.bold());
+            let post = find_post(db, &post_id).await?;
+            if post.status == PostStatus::Published {
+                println!("Blog post {} is already published.", post.title.bold());
+                return Ok(());
+            }
+
+            let path = post.path(blog_dir);
+            if path.exists() {
+                write_frontmatter_status(&path, &PostStatus::Published)?;
+            }
+            db.set_blog_post_status(&post.id, PostStatus::Published).await?;
+
+            println!("{} Publishing blog post: {}", "🚀".green().bold(), post.id.bright_blue());
+            println!("Blog post {} has been published!", post.title.bold());
         }
 
-        BlogCommand::List { tag, drafts } => {
+        BlogCommand::List { tag, drafts, limit } => {
+            let posts = db.list_blog_posts(tag.as_deref(), drafts, limit).await?;
+            if output.is_json() {
+                return print_json(&posts);
+            }
             println!("{} Your Blog Posts", "📚".blue().bold());
             println!();
 
-            // Mock data
-            let posts = vec![
-                ("blog_001", "Rust Tips", "Published", vec!["rust", "tips"]),
-                ("blog_002", "Async in Rust", "Draft", vec!["rust", "async"]),
-                ("blog_003", "Understanding Ownership", "Published", vec!["rust", "ownership"]),
-            ];
-
-            for (id, title, status, post_tags) in posts {
-                if let Some(ref tag_filter) = tag {
-                    if !post_tags.iter().any(|t| t == tag_filter) {
-                        continue;
-                    }
-                }
-
-                if drafts && status != "Draft" {
-                    continue;
-                }
+            if posts.is_empty() {
+                println!("No blog posts found. Start one with {}.", "agentic blog new --title <TITLE>".bright_cyan());
+            }
 
-                let status_color = match status {
-                    "Published" => "green",
-                    "Draft" => "yellow",
-                    "Archived" => "red",
-                    _ => "white",
+            for post in posts {
+                let status_color = match post.status {
+                    PostStatus::Published => "green",
+                    PostStatus::Draft => "yellow",
+                    PostStatus::Archived => "red",
                 };
 
-                println!("{} {} {} [{}] ({})", 
+                println!("{} {} {} [{}] ({})",
                     "•".bright_white(),
-                    title.bold(),
-                    status.color(status_color),
-                    id.bright_black(),
-                    format!("{:?}", post_tags).italic()
+                    post.title.bold(),
+                    post.status.to_string().color(status_color),
+                    post.id[..8].bright_black(),
+                    post.tags.join(", ").italic()
                 );
             }
         }
 
         BlogCommand::Delete { post_id } => {
-            println!("{} Deleting blog post: {}", "🗑".red().bold(), post_id.bright_blue());
-            // Simulated deletion
-            println!("Blog post {} has been deleted.", post_id.bold());
+            let post = find_post(db, &post_id).await?;
+            db.delete_blog_post(&post.id).await?;
+            let path = post.path(blog_dir);
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove post file {:?}", path))?;
+            }
+
+            println!("{} Deleting blog post: {}", "🗑".red().bold(), post.id.bright_blue());
+            println!("Blog post {} has been deleted.", post.title.bold());
         }
 
         BlogCommand::View { post_id } => {
-            println!("{} Viewing blog post: {}", "🔍".blue().bold(), post_id.bright_blue());
-            // Mock blog details
-            println!("Title: Rust Tricks");
-            println!("Tags: [{}]", "rust, tips".yellow());
-            println!("Content: {}
-{}
-{}
-", 
-                "Rust is a systems programming language...".italic().bright_black(),
-                "*/ Further details omitted for brevity /*".italic(),
-                "For full content, switch to your editor to view the Markdown entirely!".bright_cyan()
-            );
+            let post = find_post(db, &post_id).await?;
+            let path = post.path(blog_dir);
+
+            println!("{} Viewing blog post: {}", "🔍".blue().bold(), post.id.bright_blue());
+            println!("Title: {}", post.title.bold());
+            println!("Tags: [{}]", post.tags.join(", ").yellow());
+            println!("Status: {}", post.status);
+            println!("Created: {}", format_local(post.created_at));
+            println!("Updated: {}", format_local(post.updated_at));
+            println!("File: {}", path.display().to_string().bright_black());
+
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    let (_, body) = split_frontmatter(&content);
+                    let words = word_count(body);
+                    println!("Words: {} (~{} min read)", words.to_string().bright_white(), reading_minutes(words));
+                    println!();
+                    for line in body.lines().take(10) {
+                        println!("{}", line.bright_black());
+                    }
+                }
+                Err(_) => println!("{} Post file is missing", "⚠".yellow()),
+            }
+        }
+
+        BlogCommand::Stats => {
+            let posts = db.list_blog_posts(None, false, MAX_LIST_LIMIT).await?;
+            let stats = BlogStats::collect(&posts, blog_dir);
+
+            println!("{} Blog Statistics", "📊".blue().bold());
+            println!();
+            println!("Posts: {}", stats.posts.to_string().bright_white());
+            println!("Total Words: {} (~{} min read)", stats.total_words.to_string().green().bold(), reading_minutes(stats.total_words));
+            if let Some(average) = stats.total_words.checked_div(stats.posts) {
+                println!("Average Words: {}", average.to_string().bright_white());
+            }
+            if stats.missing_files > 0 {
+                println!("{} {} posts have no markdown file", "⚠".yellow(), stats.missing_files);
+            }
+
+            if !stats.posts_per_tag.is_empty() {
+                println!();
+                println!("{}", "🏷 Posts per Tag".bright_white().bold());
+                let mut tags: Vec<_> = stats.posts_per_tag.iter().collect();
+                tags.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+                for (tag, count) in tags {
+                    println!("{}: {}", tag.yellow(), count);
+                }
+            }
+        }
+
+        BlogCommand::Export { out, include_drafts } => {
+            let posts: Vec<BlogPost> = db.list_blog_posts(None, false, MAX_LIST_LIMIT).await?
+                .into_iter()
+                .filter(|post| match post.status {
+                    PostStatus::Published => true,
+                    PostStatus::Draft => include_drafts,
+                    PostStatus::Archived => false,
+                })
+                .collect();
+
+            let index = export_posts(&posts, blog_dir, &out)?;
+            println!("{} Exported {} posts to {}", "📦".green().bold(), index.len(), out.display().to_string().bright_blue());
+            if !include_drafts {
+                println!("Drafts were skipped; pass {} to include them.", "--include-drafts".bright_cyan());
+            }
         }
     }
 
@@ -162,6 +543,7 @@ Check your favorite Markdown editor and start editing!", post_id.bright_cyan());
 #[cfg(test)]
 mod tests {
     use super::*;
+    use uuid::Uuid;
 
     #[test]
     fn test_post_status() {
@@ -169,4 +551,112 @@ mod tests {
         let status = PostStatus::Draft;
         assert!(matches!(status, PostStatus::Draft));
     }
+
+    #[test]
+    fn test_split_frontmatter() {
+        let (yaml, body) = split_frontmatter("---\ntitle: Hi\n---\n\n# Hi\n");
+        assert_eq!(yaml, Some("title: Hi"));
+        assert_eq!(body, "# Hi\n");
+
+        assert_eq!(split_frontmatter("# No frontmatter"), (None, "# No frontmatter"));
+    }
+
+    #[test]
+    fn test_word_count_and_reading_time() {
+        assert_eq!(word_count("# Async Rust\n\n- futures are lazy\n---\n"), 5);
+        assert_eq!(reading_minutes(0), 0);
+        assert_eq!(reading_minutes(200), 1);
+        assert_eq!(reading_minutes(201), 2);
+    }
+
+    #[test]
+    fn test_blog_stats_counts_words_and_tags() {
+        let blog_dir = std::env::temp_dir().join(format!("agentic-blog-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&blog_dir).unwrap();
+        let first = BlogPost::new("One".to_string(), vec!["rust".to_string(), "async".to_string()]);
+        let second = BlogPost::new("Two".to_string(), vec!["Rust".to_string()]);
+        let missing = BlogPost::new("Three".to_string(), vec![]);
+        std::fs::write(first.path(&blog_dir), "---\ntitle: One\ntags: [rust]\n---\n\none two three").unwrap();
+        std::fs::write(second.path(&blog_dir), "four five").unwrap();
+
+        let stats = BlogStats::collect(&[first, second, missing], &blog_dir);
+        let _ = std::fs::remove_dir_all(&blog_dir);
+
+        assert_eq!((stats.posts, stats.total_words, stats.missing_files), (3, 5, 1));
+        assert_eq!(stats.posts_per_tag.get("rust"), Some(&2));
+        assert_eq!(stats.posts_per_tag.get("async"), Some(&1));
+    }
+
+    #[test]
+    fn test_slugs_are_deduplicated() {
+        assert_eq!(slugify("Async Rust: Pin & Unpin!"), "async-rust-pin-unpin");
+        assert_eq!(slugify("--"), "post");
+        assert_eq!(
+            unique_slugs(["Hello World", "hello, world", "Other", "Hello World"]),
+            vec!["hello-world", "hello-world-2", "other", "hello-world-3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_skips_drafts_and_writes_index() {
+        let db = crate::db::test_database().await;
+        let blog_dir = std::env::temp_dir().join(format!("agentic-blog-{}", Uuid::new_v4()));
+        let out = blog_dir.join("public");
+
+        for title in ["Hello World", "Hello, World", "Unfinished"] {
+            execute(BlogCommand::New { title: title.to_string(), tags: vec!["rust".to_string()] }, &db, &blog_dir, Output::Text).await.unwrap();
+        }
+        for post in db.list_blog_posts(None, false, 50).await.unwrap() {
+            if post.title != "Unfinished" {
+                execute(BlogCommand::Publish { post_id: post.id }, &db, &blog_dir, Output::Text).await.unwrap();
+            }
+        }
+
+        execute(BlogCommand::Export { out: out.clone(), include_drafts: false }, &db, &blog_dir, Output::Text).await.unwrap();
+        let index: Vec<ExportEntry> = serde_json::from_str(&std::fs::read_to_string(out.join("index.json")).unwrap()).unwrap();
+        let mut slugs: Vec<&str> = index.iter().map(|e| e.slug.as_str()).collect();
+        slugs.sort();
+        assert_eq!(slugs, vec!["hello-world", "hello-world-2"]);
+        let exported = std::fs::read_to_string(out.join("hello-world.md")).unwrap();
+        assert!(exported.contains("draft: false"));
+        assert!(exported.contains("slug: hello-world"));
+        assert!(!out.join("unfinished.md").exists());
+
+        execute(BlogCommand::Export { out: out.clone(), include_drafts: true }, &db, &blog_dir, Output::Text).await.unwrap();
+        assert!(std::fs::read_to_string(out.join("unfinished.md")).unwrap().contains("draft: true"));
+        let _ = std::fs::remove_dir_all(&blog_dir);
+    }
+
+    #[tokio::test]
+    async fn test_new_publish_and_list_posts() {
+        let db = crate::db::test_database().await;
+        let blog_dir = std::env::temp_dir().join(format!("agentic-blog-{}", Uuid::new_v4()));
+
+        let new = |title: &str, tags: &[&str]| BlogCommand::New {
+            title: title.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        };
+        execute(new("Async Rust", &["rust", "async"]), &db, &blog_dir, Output::Text).await.unwrap();
+        execute(new("Go Channels", &["go"]), &db, &blog_dir, Output::Text).await.unwrap();
+
+        let rust = db.list_blog_posts(Some("rust"), false, 50).await.unwrap();
+        assert_eq!(rust.len(), 1);
+        let post = rust[0].clone();
+        let content = std::fs::read_to_string(post.path(&blog_dir)).unwrap();
+        assert!(content.starts_with("---\ntitle: Async Rust\n"));
+        assert!(content.contains("status: draft"));
+
+        execute(BlogCommand::Publish { post_id: post.id[..8].to_string() }, &db, &blog_dir, Output::Text).await.unwrap();
+
+        let drafts = db.list_blog_posts(None, true, 50).await.unwrap();
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].title, "Go Channels");
+        let content = std::fs::read_to_string(post.path(&blog_dir)).unwrap();
+        assert!(content.contains("status: published"));
+        assert!(content.contains("# Async Rust"));
+
+        execute(BlogCommand::Delete { post_id: post.id.clone() }, &db, &blog_dir, Output::Text).await.unwrap();
+        assert!(!post.path(&blog_dir).exists());
+        let _ = std::fs::remove_dir_all(&blog_dir);
+    }
 }
@@ -0,0 +1,38 @@
+use clap_complete::Shell;
+
+/// Where each shell looks for the generated script; shown under `agentic completions --help`
+pub const INSTALL_HELP: &str = "\
+Save the script where your shell loads completions from:
+  bash:        agentic completions bash > ~/.local/share/bash-completion/completions/agentic
+  zsh:         agentic completions zsh > ~/.zfunc/_agentic
+               (with `fpath+=~/.zfunc` before `compinit` in ~/.zshrc)
+  fish:        agentic completions fish > ~/.config/fish/completions/agentic.fish
+  powershell:  agentic completions powershell >> $PROFILE
+Then open a new shell.";
+
+/// A completion script for `shell` covering every subcommand and flag of `command`
+pub fn render(shell: Shell, command: &mut clap::Command) -> String {
+    let name = command.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, command, name, &mut script);
+    String::from_utf8_lossy(&script).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_scripts_complete_subcommands_and_flags() {
+        let bash = render(Shell::Bash, &mut crate::Cli::command());
+        assert!(bash.contains("complete -F _agentic"), "{}", bash);
+        assert!(bash.contains("workflow"));
+        assert!(bash.contains("--json"));
+
+        let fish = render(Shell::Fish, &mut crate::Cli::command());
+        assert!(fish.contains("complete -c agentic"), "{}", fish);
+        assert!(fish.contains("__fish_seen_subcommand_from task"));
+        assert!(!render(Shell::Zsh, &mut crate::Cli::command()).is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use clap::Subcommand;
+use std::path::Path;
+
+use crate::config::{Config, CONFIG_VERSION};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Upgrade the config file to the current layout, keeping a backup of the original
+    Migrate,
+}
+
+/// Run a config subcommand and return its output for display
+pub async fn execute(command: ConfigCommand) -> Result<String> {
+    match command {
+        ConfigCommand::Migrate => migrate(&Config::config_path()).await,
+    }
+}
+
+/// Create or upgrade the config at `path`, mentioning a backup only if this run made one
+async fn migrate(path: &Path) -> Result<String> {
+    if !path.exists() {
+        Config::default().save_to(path).await?;
+        return Ok(format!("Created {} at version {}", path.display(), CONFIG_VERSION));
+    }
+
+    let (config, backup) = Config::load_and_migrate(path).await?;
+    Ok(match backup {
+        Some(backup) => format!(
+            "{} is at version {} (previous file saved as {})",
+            path.display(), config.version, backup.display()
+        ),
+        None => format!("{} is already at version {}", path.display(), config.version),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_migrate_reports_only_its_own_backup() {
+        let dir = std::env::temp_dir().join(format!("agentic-config-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.toml");
+        let created = migrate(&path).await.unwrap();
+        assert!(created.starts_with("Created"), "{}", created);
+
+        // A backup left by an earlier migration is not this run's
+        std::fs::write(dir.join("config.toml.bak"), "old").unwrap();
+        let current = migrate(&path).await.unwrap();
+        assert!(current.contains("already at version") && !current.contains("saved as"), "{}", current);
+
+        std::fs::write(&path, "max_history_size = 10\n").unwrap();
+        let upgraded = migrate(&path).await.unwrap();
+        let backup = std::fs::read_to_string(dir.join("config.toml.bak")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(upgraded.contains("previous file saved as"), "{}", upgraded);
+        assert_eq!(backup, "max_history_size = 10\n");
+    }
+}
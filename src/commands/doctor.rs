@@ -0,0 +1,358 @@
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::ollama::client::OllamaClient;
+use crate::warp::WarpConfig;
+use super::models;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Ok,
+    /// Something is off but the CLI still works
+    Warn,
+    /// A critical problem; `doctor` exits non-zero
+    Fail,
+}
+
+/// A remedy `doctor --fix` can apply for a problem it found
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    CreateDir(PathBuf),
+    WriteDefaultConfig(PathBuf),
+    PullModel(String),
+}
+
+impl Fix {
+    /// What applying it will do, phrased as a question for the confirmation prompt
+    pub fn describe(&self) -> String {
+        match self {
+            Fix::CreateDir(dir) => format!("Create the directory {}", dir.display()),
+            Fix::WriteDefaultConfig(path) => format!("Write a default config to {}", path.display()),
+            Fix::PullModel(model) => format!("Run `ollama pull {}`", model),
+        }
+    }
+
+    /// Whether applying it takes over the terminal, as `ollama pull` does to show its progress;
+    /// the TUI leaves those to `agentic doctor --fix`
+    pub fn needs_terminal(&self) -> bool {
+        matches!(self, Fix::PullModel(_))
+    }
+
+    /// Whether the problem is still there; writing the config also creates its directory
+    fn needed(&self) -> bool {
+        match self {
+            Fix::CreateDir(dir) => !dir.exists(),
+            Fix::WriteDefaultConfig(path) => !path.exists(),
+            Fix::PullModel(_) => true,
+        }
+    }
+
+    /// Apply the fix, returning what was done
+    pub async fn apply(&self) -> Result<String> {
+        match self {
+            Fix::CreateDir(dir) => {
+                tokio::fs::create_dir_all(dir).await?;
+                Ok(format!("Created directory {}", dir.display()))
+            }
+            Fix::WriteDefaultConfig(path) => {
+                Config::default().save_to(path).await?;
+                Ok(format!("Wrote a default config to {}", path.display()))
+            }
+            Fix::PullModel(model) => {
+                crate::offline::check_network()?;
+                // Inherit the terminal so Ollama's download progress shows
+                let status = tokio::process::Command::new("ollama").arg("pull").arg(model).status().await
+                    .map_err(|e| anyhow!("couldn't run `ollama pull {}`: {}", model, e))?;
+                if !status.success() {
+                    return Err(anyhow!("`ollama pull {}` exited with {}", model, status));
+                }
+                Ok(format!("Pulled {}", model))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// How to fix it, for warnings and failures
+    pub hint: Option<String>,
+    /// What `doctor --fix` can do about it
+    pub fix: Option<Fix>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Ok, detail: detail.into(), hint: None, fix: None }
+    }
+
+    fn problem(name: &'static str, status: CheckStatus, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into(), hint: Some(hint.into()), fix: None }
+    }
+
+    fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    /// No check failed; warnings are allowed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+
+    pub fn render(&self) -> String {
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        let mut report = String::from("🩺 Agentic Doctor\n\n");
+        for check in &self.checks {
+            let icon = match check.status {
+                CheckStatus::Ok => "✓",
+                CheckStatus::Warn => "⚠",
+                CheckStatus::Fail => "✗",
+            };
+            report.push_str(&format!("{} {:<width$}  {}\n", icon, check.name, check.detail, width = width));
+            if let Some(hint) = &check.hint {
+                report.push_str(&format!("  {:<width$}  → {}\n", "", hint, width = width));
+            }
+        }
+
+        let failures = self.checks.iter().filter(|c| c.status == CheckStatus::Fail).count();
+        if failures == 0 {
+            report.push_str("\nEverything needed is in place.\n");
+        } else {
+            report.push_str(&format!("\n{} critical problem(s) found.\n", failures));
+        }
+        let fixable = self.fixes().len();
+        if fixable > 0 {
+            report.push_str(&format!("Run `agentic doctor --fix` to fix {} of these automatically.\n", fixable));
+        }
+        report
+    }
+
+    /// The distinct fixes for the problems found, in report order
+    pub fn fixes(&self) -> Vec<&Fix> {
+        let mut fixes: Vec<&Fix> = Vec::new();
+        for fix in self.checks.iter().filter_map(|c| c.fix.as_ref()) {
+            if !fixes.contains(&fix) {
+                fixes.push(fix);
+            }
+        }
+        fixes
+    }
+}
+
+/// Apply each fix in `report` that `confirm` agrees to, returning a line per fix attempted
+pub async fn fix<F, Fut>(report: &DoctorReport, mut confirm: F) -> Vec<String>
+where
+    F: FnMut(&Fix) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let mut actions = Vec::new();
+    for fix in report.fixes() {
+        if !fix.needed() {
+            continue;
+        }
+        if !confirm(fix).await {
+            actions.push(format!("- Skipped: {}", fix.describe()));
+            continue;
+        }
+        match fix.apply().await {
+            Ok(done) => actions.push(format!("🔧 {}", done)),
+            Err(e) => actions.push(format!("✗ {} failed: {}", fix.describe(), e)),
+        }
+    }
+    actions
+}
+
+/// Parse the config file at `path`; a missing file only warns since defaults get written on first run
+pub fn check_config_file(path: &Path) -> (Check, Option<Config>) {
+    const NAME: &str = "Config file";
+    if !path.exists() {
+        let check = Check::problem(NAME, CheckStatus::Warn, format!("{} not found", path.display()),
+            "Run any agentic command to write the defaults")
+            .with_fix(Fix::WriteDefaultConfig(path.to_path_buf()));
+        return (check, None);
+    }
+    let parsed = std::fs::read_to_string(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Config::parse_and_migrate(&content));
+    match parsed {
+        Ok((config, migrated)) => {
+            let check = if migrated {
+                Check::problem(NAME, CheckStatus::Warn, format!("{} uses an old layout", path.display()),
+                    "Run `agentic config migrate` to upgrade it")
+            } else {
+                Check::ok(NAME, format!("{} is valid", path.display()))
+            };
+            (check, Some(config))
+        }
+        Err(e) => {
+            let check = Check::problem(NAME, CheckStatus::Fail, format!("{} is invalid: {}", path.display(), e),
+                "Fix the reported field, or move the file aside to regenerate defaults");
+            (check, None)
+        }
+    }
+}
+
+/// Make sure the history database can be created and written, without creating it
+pub fn check_database(path: &Path) -> Check {
+    const NAME: &str = "Database";
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty() && !dir.exists()) {
+        return Check::problem(NAME, CheckStatus::Warn, format!("data directory {} does not exist", dir.display()),
+            "It is created on first run, or by `agentic doctor --fix`")
+            .with_fix(Fix::CreateDir(dir.to_path_buf()));
+    }
+    match crate::db::check_writable(path) {
+        Ok(()) => Check::ok(NAME, format!("{} is writable", path.display())),
+        Err(e) => Check::problem(NAME, CheckStatus::Fail, format!("{} is not writable: {}", path.display(), e),
+            "Check the permissions of the file and its directory, or change `database_path` in the config"),
+    }
+}
+
+/// Whether an OpenAI key is available; only critical when OpenAI is the preferred provider
+pub fn check_openai_key(config: &Config) -> Check {
+    const NAME: &str = "OpenAI API key";
+    if config.get_openai_api_key().is_some() {
+        return Check::ok(NAME, "found");
+    }
+    let status = if config.agent.preferred_provider == "openai" { CheckStatus::Fail } else { CheckStatus::Warn };
+    Check::problem(NAME, status, "OPENAI_API_KEY is not set",
+        "Export OPENAI_API_KEY or set `openai_api_key` in the config (not needed when using Ollama)")
+}
+
+/// Parse `.agentic.toml`, which configures the Warp pipeline models
+pub async fn check_warp_config() -> (Check, WarpConfig) {
+    const NAME: &str = "Warp config";
+    match WarpConfig::load().await {
+        Ok(warp) => (Check::ok(NAME, ".agentic.toml is valid"), warp),
+        Err(e) => {
+            // TOML errors span several lines with a source excerpt; the first names the location
+            let detail = format!("{:#}", e).lines().next().unwrap_or_default().to_string();
+            let check = Check::problem(NAME, CheckStatus::Warn, detail,
+                "Fix the file; `agentic warp` shows the full error and fails until then");
+            (check, WarpConfig::default())
+        }
+    }
+}
+
+async fn check_ollama(config: &Config, warp: &WarpConfig) -> Vec<Check> {
+    let ollama_config = crate::agent::ollama_config(config);
+    let base_url = ollama_config.base_url.clone();
+    // Without Ollama the agent can only answer in keyword mode unless OpenAI is set up
+    let critical = config.agent.preferred_provider != "openai" || config.get_openai_api_key().is_none();
+    let severity = if critical { CheckStatus::Fail } else { CheckStatus::Warn };
+
+    let installed = match OllamaClient::new(ollama_config) {
+        Ok(client) => client.list_models().await,
+        Err(e) => Err(e),
+    };
+    let installed = match installed {
+        Ok(installed) => installed,
+        Err(_) => {
+            return vec![Check::problem("Ollama", severity, format!("not reachable at {}", base_url),
+                "Install Ollama from https://ollama.com and start it with `ollama serve`")];
+        }
+    };
+
+    let mut checks = vec![Check::ok("Ollama", format!("reachable at {} ({} models)", base_url, installed.len()))];
+    for wanted in models::configured_models(config, warp) {
+        let name = match wanted.role {
+            "agent" => "Agent model",
+            "planner" => "Planner model",
+            "coder" => "Coder model",
+            _ => "Fallback model",
+        };
+        if installed.iter().any(|m| m.matches(&wanted.model)) {
+            checks.push(Check::ok(name, format!("{} is installed", wanted.model)));
+        } else {
+            // The agent model is what the fallback-to-keywords problem hinges on
+            let status = if wanted.role == "agent" { severity } else { CheckStatus::Warn };
+            checks.push(Check::problem(name, status, format!("{} is not installed", wanted.model),
+                format!("ollama pull {}", wanted.model))
+                .with_fix(Fix::PullModel(wanted.model.clone())));
+        }
+    }
+    checks
+}
+
+/// Run every check against the config file at `config_path`, falling back to defaults if it can't be read
+pub async fn run(config_path: &Path) -> DoctorReport {
+    let (config_check, config) = check_config_file(config_path);
+    let config = config.unwrap_or_default();
+
+    let (warp_check, warp) = check_warp_config().await;
+    let mut checks = vec![config_check, warp_check, check_database(&config.database_path), check_openai_key(&config)];
+    checks.extend(check_ollama(&config, &warp).await);
+    DoctorReport { checks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_config_fails_the_report() {
+        let dir = std::env::temp_dir().join(format!("agentic-doctor-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "version = 1\ndatabase_path = 42\n").unwrap();
+
+        let (check, config) = check_config_file(&path);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(config.is_none());
+
+        let db_check = check_database(&dir.join("history.db"));
+        assert_eq!(db_check.status, CheckStatus::Ok);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1, "checking creates no database");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let report = DoctorReport { checks: vec![check, db_check] };
+        assert!(!report.passed());
+        let rendered = report.render();
+        assert!(rendered.contains("✗ Config file"));
+        assert!(rendered.contains("✓ Database"));
+        assert!(rendered.contains("1 critical problem"));
+    }
+
+    #[tokio::test]
+    async fn test_fix_creates_the_missing_data_directory() {
+        let dir = std::env::temp_dir().join(format!("agentic-doctor-{}", uuid::Uuid::new_v4()));
+        let data_dir = dir.join("data");
+        let check = check_database(&data_dir.join("history.db"));
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert_eq!(check.fix, Some(Fix::CreateDir(data_dir.clone())));
+
+        let report = DoctorReport { checks: vec![check] };
+        assert!(report.render().contains("agentic doctor --fix"));
+        let declined = fix(&report, |_| async { false }).await;
+        assert!(declined[0].starts_with("- Skipped"));
+        assert!(!data_dir.exists());
+
+        let actions = fix(&report, |_| async { true }).await;
+        assert!(data_dir.is_dir());
+        assert_eq!(actions, vec![format!("🔧 Created directory {}", data_dir.display())]);
+        assert_eq!(check_database(&data_dir.join("history.db")).status, CheckStatus::Ok);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_openai_key_is_critical_only_for_openai() {
+        let mut config = Config { openai_api_key: None, ..Config::default() };
+        if config.get_openai_api_key().is_some() {
+            return; // OPENAI_API_KEY is set in this environment
+        }
+        assert_eq!(check_openai_key(&config).status, CheckStatus::Warn);
+        config.agent.preferred_provider = "openai".to_string();
+        assert_eq!(check_openai_key(&config).status, CheckStatus::Fail);
+    }
+}
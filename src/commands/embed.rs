@@ -0,0 +1,34 @@
+use anyhow::{anyhow, Result};
+use serde_json::json;
+
+use super::output::Output;
+use crate::config::Config;
+use crate::ollama::client::OllamaClient;
+
+/// Embed `text` with `model`, or the agent's Ollama model, and return the vector as a JSON
+/// array; with `dims` only its length. With `Output::Json` both come wrapped in an object
+/// naming the model.
+pub async fn execute(text: &str, model: Option<String>, dims: bool, config: &Config, output: Output) -> Result<String> {
+    if text.trim().is_empty() {
+        return Err(anyhow!("Nothing to embed; pass the text to embed, e.g. `agentic embed \"git rebase\"`"));
+    }
+    let mut client = OllamaClient::new(crate::agent::ollama_config(config))?;
+    if let Some(model) = model {
+        client.set_model(model);
+    }
+    let embedding = client.embed(text).await?;
+    render(client.get_model(), &embedding, dims, output)
+}
+
+pub fn render(model: &str, embedding: &[f32], dims: bool, output: Output) -> Result<String> {
+    Ok(match (output.is_json(), dims) {
+        (true, true) => serde_json::to_string_pretty(&json!({ "model": model, "dimensions": embedding.len() }))?,
+        (true, false) => serde_json::to_string_pretty(&json!({
+            "model": model,
+            "dimensions": embedding.len(),
+            "embedding": embedding,
+        }))?,
+        (false, true) => embedding.len().to_string(),
+        (false, false) => serde_json::to_string(embedding)?,
+    })
+}
@@ -0,0 +1,343 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use clap::{Parser, Subcommand};
+use colored::*;
+use tracing::warn;
+
+use super::CommandRegistry;
+use crate::config::Config;
+use crate::db::{CommandExecution, Database, ExecutionStatus, HistoryFilter, DEFAULT_LIST_LIMIT};
+use crate::ollama::client::{OllamaClient, OllamaConfig};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum HistoryCommand {
+    /// Show recently run commands, newest first
+    List {
+        /// Maximum number of commands to show (capped by `max_list_limit` in the config)
+        #[arg(long, default_value_t = DEFAULT_LIST_LIMIT)]
+        limit: usize,
+    },
+    /// Find commands whose text or output contains `query`
+    Search {
+        query: String,
+        /// Maximum number of commands to show (capped by `max_list_limit` in the config)
+        #[arg(long, default_value_t = DEFAULT_LIST_LIMIT)]
+        limit: usize,
+        /// Rank commands by how close their meaning is to `query`, using `agent.embedding_model`
+        #[arg(long)]
+        semantic: bool,
+    },
+    /// Run a recorded command again
+    Run {
+        /// Id (or id prefix) shown by `history list`
+        id: String,
+    },
+    /// Delete recorded commands; without filters the whole history goes
+    Clear {
+        /// Only commands run before this day (YYYY-MM-DD, local time)
+        #[arg(long, value_name = "DATE")]
+        before: Option<String>,
+        /// Only commands that ended this way: success, error, cancelled or running
+        #[arg(long)]
+        status: Option<String>,
+        /// Delete without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+}
+
+/// Run a history subcommand and return its output for display. With `interactive`, a replayed
+/// command's output is printed as it runs rather than returned.
+pub async fn execute(
+    command: HistoryCommand,
+    registry: &CommandRegistry,
+    db: &Database,
+    config: &Config,
+    interactive: bool,
+) -> Result<String> {
+    match command {
+        HistoryCommand::List { limit } => {
+            Ok(render_list("📜 History", &db.get_command_history(limit).await?, None))
+        }
+        HistoryCommand::Search { query, limit, semantic: true } => match semantic_search(&query, limit, db, config).await {
+            Ok(ranked) => Ok(render_ranked(&format!("🧠 History similar to '{}'", query), &ranked)),
+            Err(e) => {
+                let title = format!("🔍 History matching '{}'", query);
+                let list = render_list(&title, &db.search_history(&query, limit).await?, Some(&query));
+                Ok(format!("{} Semantic search unavailable ({}); showing text matches\n{}", "⚠".yellow(), e, list))
+            }
+        },
+        HistoryCommand::Search { query, limit, semantic: false } => {
+            let title = format!("🔍 History matching '{}'", query);
+            Ok(render_list(&title, &db.search_history(&query, limit).await?, Some(&query)))
+        }
+        HistoryCommand::Run { id } => {
+            let execution = db.find_command_execution(&id).await?
+                .ok_or_else(|| anyhow!("No recorded command matching '{}'; `agentic history list` shows the ids", id))?;
+            let command = replayable_command(&execution)?;
+            super::prep::check_focus_lock(db, &config.prep, &command).await?;
+            if interactive {
+                println!("{} {}", "▶".blue(), command.yellow());
+            }
+            let output = registry.execute_raw_command(&command, interactive).await?;
+            Ok(if interactive { String::new() } else { format!("$ {}\n{}", command, output) })
+        }
+        HistoryCommand::Clear { before, status, yes } => {
+            let filter = HistoryFilter {
+                before: before.as_deref().map(parse_before).transpose()?,
+                status: status.as_deref().map(parse_status).transpose()?,
+            };
+            let matching = db.count_history(&filter).await?;
+            if matching == 0 {
+                return Ok("🗑 No recorded commands match".to_string());
+            }
+            if !yes {
+                if !interactive {
+                    return Err(anyhow!("This deletes {} recorded command(s); add --yes to confirm", matching));
+                }
+                println!("🗑 Delete {} recorded command(s)? (y/N): ", matching);
+                let confirmed = crate::warp::read_answer().await.is_some_and(|answer| answer.trim().to_lowercase().starts_with('y'));
+                if !confirmed {
+                    return Ok("Nothing deleted".to_string());
+                }
+            }
+            let deleted = db.delete_history(&filter).await?;
+            Ok(format!("🗑 Deleted {} recorded command(s)", deleted))
+        }
+    }
+}
+
+/// Recorded commands ranked by the cosine similarity of their embedding to `query`'s, best
+/// first. Commands without a cached embedding from the configured model are embedded first,
+/// so the first search after a while may take longer.
+async fn semantic_search(query: &str, limit: usize, db: &Database, config: &Config) -> Result<Vec<(CommandExecution, f32)>> {
+    let ollama_config = OllamaConfig { model: config.agent.embedding_model.clone(), ..crate::agent::ollama_config(config) };
+    let client = OllamaClient::new(ollama_config)?;
+    let query_embedding = client.embed(query).await?;
+    let model = client.get_model().to_string();
+
+    db.prune_command_embeddings().await?;
+    for command in db.commands_without_embedding(&model).await? {
+        // A command that can't be embedded is left out of this search and tried again next time
+        match client.embed(&command).await {
+            Ok(embedding) => db.save_command_embedding(&command, &model, &embedding).await?,
+            Err(e) => warn!("Couldn't embed {:?}: {:#}", command, e),
+        }
+    }
+
+    let mut ranked: Vec<(CommandExecution, f32)> = db.embedded_commands(&model).await?
+        .into_iter()
+        .map(|(execution, embedding)| (execution, cosine_similarity(&query_embedding, &embedding)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked.truncate(db.clamp_limit(limit));
+    Ok(ranked)
+}
+
+/// Cosine of the angle between two vectors, from -1 to 1; 0 when their lengths differ or
+/// either is all zeros
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+/// Local midnight at the start of a `YYYY-MM-DD` day
+pub fn parse_before(date: &str) -> Result<DateTime<Utc>> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .ok()
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("Invalid date '{}', expected YYYY-MM-DD", date))
+}
+
+pub fn parse_status(status: &str) -> Result<ExecutionStatus> {
+    match status.trim().to_lowercase().as_str() {
+        "success" | "ok" => Ok(ExecutionStatus::Success),
+        "error" | "failed" => Ok(ExecutionStatus::Error),
+        "cancelled" | "canceled" => Ok(ExecutionStatus::Cancelled),
+        "running" => Ok(ExecutionStatus::Running),
+        _ => Err(anyhow!("Invalid status '{}', expected success, error, cancelled or running", status)),
+    }
+}
+
+/// The shell command to run again. Input the TUI recorded as an agentic subcommand isn't a
+/// shell command, so only `run <command>` is unwrapped; other subcommands and agent answers
+/// are refused. Warp runs keep the request they came from as their query, but their command is
+/// the shell command that ran, so they replay like any other.
+pub fn replayable_command(execution: &CommandExecution) -> Result<String> {
+    let command = execution.command.trim();
+    if command.is_empty() {
+        return Err(anyhow!("Recorded command {} is empty", short_id(&execution.id)));
+    }
+    let agent_query = || match &execution.agent_query {
+        Some(query) => anyhow!("That was an agent query; ask again with `agentic agent {}`", shell_words::quote(query)),
+        None => anyhow!("That was an agent query; ask again with `agentic {}`", command),
+    };
+    // Input the TUI routed to the agent is recorded as its own query
+    if execution.agent_query.as_deref().map(str::trim) == Some(command) {
+        return Err(agent_query());
+    }
+    let parsed = shell_words::split(command).ok().and_then(|args| crate::TuiInput::try_parse_from(args).ok());
+    match parsed.map(|input| input.command) {
+        Some(crate::CliCommand::Session(crate::Commands::Run { command, .. })) => Ok(command),
+        Some(crate::CliCommand::Session(crate::Commands::Agent { agent_cmd: None, .. })) => Err(agent_query()),
+        Some(_) => Err(anyhow!("That was an agentic command; run it with `agentic {}`", command)),
+        None => Ok(command.to_string()),
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+fn status_icon(status: &ExecutionStatus) -> ColoredString {
+    match status {
+        ExecutionStatus::Success => "✓".green(),
+        ExecutionStatus::Error => "✗".red(),
+        ExecutionStatus::Running => "…".yellow(),
+        ExecutionStatus::Cancelled => "⏹".yellow(),
+        ExecutionStatus::Unknown => "?".bright_black(),
+    }
+}
+
+/// One line per command with its id, time and status; with `query`, commands that matched only
+/// on their output also show the first matching output line
+pub fn render_list(title: &str, executions: &[CommandExecution], query: Option<&str>) -> String {
+    if executions.is_empty() {
+        return format!("{}\nNo commands recorded", title);
+    }
+    let mut output = format!("{} ({})\n", title, executions.len());
+    for execution in executions {
+        output.push_str(&format!("  {}\n", execution_line(execution)));
+        let Some(query) = query.map(str::to_lowercase) else {
+            continue;
+        };
+        if !execution.command.to_lowercase().contains(&query) {
+            if let Some(line) = execution.output.lines().find(|line| line.to_lowercase().contains(&query)) {
+                output.push_str(&format!("      {}\n", line.trim().bright_black()));
+            }
+        }
+    }
+    output.trim_end().to_string()
+}
+
+/// Like `render_list`, with each command's similarity score in front
+pub fn render_ranked(title: &str, ranked: &[(CommandExecution, f32)]) -> String {
+    if ranked.is_empty() {
+        return format!("{}\nNo commands recorded", title);
+    }
+    let mut output = format!("{} ({})\n", title, ranked.len());
+    for (execution, score) in ranked {
+        output.push_str(&format!("  {} {}\n", format!("{:.2}", score).cyan(), execution_line(execution)));
+    }
+    output.trim_end().to_string()
+}
+
+/// A command's id, time, status and text
+fn execution_line(execution: &CommandExecution) -> String {
+    let time = if execution.has_unknown_timestamp() {
+        "????-??-?? ??:??".to_string()
+    } else {
+        execution.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+    };
+    format!("{} {} {} {}", short_id(&execution.id).bright_black(), time, status_icon(&execution.status), execution.command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_replays_a_recorded_shell_command() {
+        let db = crate::db::test_database().await;
+        let mut recorded = CommandExecution::new("run 'echo replayed'".to_string(), None);
+        recorded.output = "replayed".to_string();
+        recorded.status = ExecutionStatus::Success;
+        db.save_command_execution(&recorded).await.unwrap();
+        let query = CommandExecution::new("agent 'why'".to_string(), Some("why".to_string()));
+        db.save_command_execution(&query).await.unwrap();
+
+        let registry = CommandRegistry::new();
+        let config = Config::default();
+        let run = HistoryCommand::Run { id: recorded.id[..8].to_string() };
+        let output = execute(run, &registry, &db, &config, false).await.unwrap();
+        assert_eq!(output.trim_end(), "$ echo replayed\nreplayed");
+
+        let error = execute(HistoryCommand::Run { id: query.id.clone() }, &registry, &db, &config, false).await.unwrap_err();
+        assert!(error.to_string().contains("agent query"), "{}", error);
+
+        let mut warp = CommandExecution::new("echo warp".to_string(), Some("say warp".to_string()));
+        warp.provider = Some("ollama".to_string());
+        warp.status = ExecutionStatus::Success;
+        assert_eq!(replayable_command(&warp).unwrap(), "echo warp", "Warp runs replay the command that ran");
+        let routed = CommandExecution::new("why is the sky blue".to_string(), Some("why is the sky blue".to_string()));
+        assert!(replayable_command(&routed).unwrap_err().to_string().contains("agent query"));
+
+        let search = HistoryCommand::Search { query: "REPLAYED".to_string(), limit: 10, semantic: false };
+        let list = execute(search, &registry, &db, &config, false).await.unwrap();
+        assert!(list.contains("(1)") && list.contains("run 'echo replayed'"), "{}", list);
+    }
+
+    #[tokio::test]
+    async fn test_clear_needs_yes_outside_a_terminal() {
+        let db = crate::db::test_database().await;
+        let mut failed = CommandExecution::new("false".to_string(), None);
+        failed.status = ExecutionStatus::Error;
+        db.save_command_execution(&failed).await.unwrap();
+        db.save_command_execution(&CommandExecution::new("true".to_string(), None)).await.unwrap();
+
+        let (registry, config) = (CommandRegistry::new(), Config::default());
+        let clear = |yes| HistoryCommand::Clear { before: None, status: Some("error".to_string()), yes };
+        let error = execute(clear(false), &registry, &db, &config, false).await.unwrap_err();
+        assert!(error.to_string().contains("deletes 1 recorded command"), "{}", error);
+        assert_eq!(execute(clear(true), &registry, &db, &config, false).await.unwrap(), "🗑 Deleted 1 recorded command(s)");
+        assert_eq!(db.get_command_history(10).await.unwrap()[0].command, "true");
+
+        assert!(parse_before("2024-13-01").is_err());
+        assert!(parse_status("exploded").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_semantic_ranking_uses_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 0.0], &[-1.0, 0.0]) + 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0, "zero vector");
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 1.0]), 0.0, "mismatched lengths");
+
+        let db = crate::db::test_database().await;
+        for command in ["git status", "git status", "ls -la"] {
+            db.save_command_execution(&CommandExecution::new(command.to_string(), None)).await.unwrap();
+        }
+        let mut missing = db.commands_without_embedding("m").await.unwrap();
+        missing.sort();
+        assert_eq!(missing, ["git status", "ls -la"], "each command is embedded once");
+        db.save_command_embedding("git status", "m", &[0.5, -1.25]).await.unwrap();
+        assert_eq!(db.commands_without_embedding("m").await.unwrap(), ["ls -la"]);
+        assert_eq!(db.commands_without_embedding("other").await.unwrap().len(), 2);
+
+        let embedded = db.embedded_commands("m").await.unwrap();
+        assert_eq!(embedded.len(), 1, "only the newest run of a command is ranked");
+        assert_eq!((embedded[0].0.command.as_str(), embedded[0].1.as_slice()), ("git status", &[0.5, -1.25][..]));
+
+        db.save_command_embedding("no longer recorded", "m", &[1.0]).await.unwrap();
+        assert_eq!(db.prune_command_embeddings().await.unwrap(), 1);
+        assert_eq!(db.embedded_commands("m").await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_replayable_command_unwraps_run() {
+        let execution = |command: &str| CommandExecution::new(command.to_string(), None);
+        assert_eq!(replayable_command(&execution("ls -la")).unwrap(), "ls -la");
+        assert_eq!(replayable_command(&execution("run \"cargo test\"")).unwrap(), "cargo test");
+        let error = replayable_command(&execution("task list")).unwrap_err().to_string();
+        assert!(error.contains("agentic task list"), "{}", error);
+        assert!(replayable_command(&execution("  ")).is_err());
+    }
+}
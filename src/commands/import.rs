@@ -0,0 +1,30 @@
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+/// Read a list of import records from a JSON or YAML file.
+///
+/// The format is chosen from the file extension. Record types are expected to use
+/// `#[serde(deny_unknown_fields)]`, so a misspelled or unexpected field fails the
+/// whole file with the parser's line/column position instead of being dropped.
+pub fn read_records<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read import file: {:?}", path))?;
+
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("json") => serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid import file {:?}: {}", path, e)),
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&content)
+            .map_err(|e| anyhow!("Invalid import file {:?}: {}", path, e)),
+        _ => Err(anyhow!("Unsupported import format {:?}, expected .json, .yaml or .yml", path)),
+    }
+}
+
+/// Turn per-record validation errors into a single error listing all of them
+pub fn check_errors(errors: Vec<String>) -> Result<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("Import rejected, nothing was imported:\n  {}", errors.join("\n  ")))
+    }
+}
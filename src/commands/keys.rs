@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use std::path::Path;
+
+use crate::keybindings::keybinding_manager::{parse_keyset, KeyBindingManager, KeysetError};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum KeysCommand {
+    /// Show the TUI key bindings grouped by category
+    List,
+    /// Check a keyset for keys that don't parse and keys bound twice
+    Validate {
+        /// Keyset name (e.g. `default`) or path to a keyset file
+        name: String,
+    },
+}
+
+/// Run a keys subcommand and return its output for display
+pub async fn execute(command: KeysCommand) -> Result<String> {
+    match command {
+        KeysCommand::List => {
+            let mut keybindings = KeyBindingManager::with_defaults();
+            keybindings.load_default_keyset();
+            Ok(render_bindings(&keybindings))
+        }
+        KeysCommand::Validate { name } => {
+            let path = KeyBindingManager::new().keyset_path(&name)
+                .ok_or_else(|| anyhow!("Keyset '{}' not found", name))?;
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read keyset file: {:?}", path))?;
+            let keyset = parse_keyset(&content)
+                .with_context(|| format!("{} is not a valid keyset", path.display()))?;
+            let report = render_validation(&path, keyset.bindings.len(), &keyset.errors);
+            if keyset.errors.is_empty() {
+                Ok(report)
+            } else {
+                Err(anyhow!(report))
+            }
+        }
+    }
+}
+
+/// One line per problem, in file order
+pub fn render_validation(path: &Path, valid: usize, errors: &[KeysetError]) -> String {
+    if errors.is_empty() {
+        return format!("✓ {}: {} bindings, no problems", path.display(), valid);
+    }
+    let mut output = format!("✗ {}: {} problem(s), {} bindings usable\n", path.display(), errors.len(), valid);
+    for error in errors {
+        output.push_str(&format!("  {}\n", error));
+    }
+    output
+}
+
+/// Bindings by category, each sorted by action name
+pub fn render_bindings(keybindings: &KeyBindingManager) -> String {
+    let source = keybindings.get_current_keyset().map(String::as_str).unwrap_or("built-in");
+    let mut output = format!("⌨️  Key bindings (keyset: {})\n", source);
+
+    let mut categories: Vec<_> = keybindings.get_bindings_by_category().into_iter().collect();
+    categories.sort_by(|a, b| a.0.cmp(&b.0));
+    let width = keybindings.list_bindings().iter().map(|(command, _)| command.len()).max().unwrap_or(0);
+    for (category, mut bindings) in categories {
+        bindings.sort_by(|a, b| a.0.cmp(b.0));
+        output.push_str(&format!("\n{}\n", category));
+        for (command, binding) in bindings {
+            output.push_str(&format!("  {:<width$}  {}\n", command, keybindings.key_binding_to_string(binding), width = width));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keybindings::keybinding_manager::ParsedKeyset;
+
+    #[test]
+    fn test_validation_reports_bad_keys_and_conflicts_by_line() {
+        let content = "\"app:quit\": ctrl-q\nagent:toggle: ctrl-shift-hyper\n\n\"scroll:top\": home\n'scroll:up': home\n";
+        let ParsedKeyset { bindings, errors } = parse_keyset(content).unwrap();
+
+        assert_eq!(bindings.iter().map(|(command, _)| command.as_str()).collect::<Vec<_>>(), ["app:quit", "scroll:top"]);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].line(), Some(2));
+        assert!(errors[0].to_string().starts_with("line 2: agent:toggle has an invalid key 'ctrl-shift-hyper'"), "{}", errors[0]);
+        assert_eq!(errors[1].to_string(), "line 5: scroll:up uses 'home', which is already bound to scroll:top; ignored");
+
+        let report = render_validation(Path::new("custom.yaml"), bindings.len(), &errors);
+        assert!(report.starts_with("✗ custom.yaml: 2 problem(s), 2 bindings usable"));
+        assert!(parse_keyset("app:quit: [").is_err());
+    }
+
+    #[test]
+    fn test_default_keyset_is_written_once_and_matches_the_built_ins() {
+        let dir = std::env::temp_dir().join(format!("agentic-keysets-{}", uuid::Uuid::new_v4()));
+        let path = KeyBindingManager::write_default_keyset_in(&dir).unwrap().expect("written on first run");
+        assert!(KeyBindingManager::write_default_keyset_in(&dir).unwrap().is_none(), "an existing keyset is kept");
+
+        let mut loaded = KeyBindingManager::new();
+        loaded.add_keyset_directory(&dir);
+        loaded.load_keyset("default").unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(path.ends_with("default.yaml"));
+        assert_eq!(render_bindings(&loaded), render_bindings(&KeyBindingManager::with_defaults()).replace("built-in", "default"));
+
+        let listing = render_bindings(&loaded);
+        assert!(listing.contains("\napp\n  app:quit"), "{}", listing);
+        assert!(listing.contains("help:toggle         ?"), "{}", listing);
+        assert!(listing.contains("settings:toggle     ctrl-comma"), "{}", listing);
+    }
+
+    #[test]
+    fn test_an_older_keyset_keeps_the_built_ins_it_doesnt_mention() {
+        let dir = std::env::temp_dir().join(format!("agentic-keysets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("default.yaml"), "app:quit: ctrl-x\nagent:toggle: ctrl-p\n").unwrap();
+        let mut loaded = KeyBindingManager::new();
+        loaded.add_keyset_directory(&dir);
+        assert!(loaded.load_keyset("default").unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let key = |command: &str| loaded.get_key_for_command(command).map(|binding| loaded.key_binding_to_string(binding));
+        assert_eq!(key("app:quit").as_deref(), Some("ctrl-x"));
+        let built_ins = KeyBindingManager::with_defaults();
+        for command in ["search:start", "block:copy_output", "agent:reset"] {
+            let built_in = built_ins.get_key_for_command(command).map(|binding| built_ins.key_binding_to_string(binding));
+            assert_eq!(key(command), built_in, "{} keeps its built-in key", command);
+        }
+        assert!(!loaded.has_binding("palette:toggle"), "ctrl-p went to agent:toggle");
+    }
+}
@@ -1,77 +1,382 @@
 use anyhow::Result;
-use std::process::Stdio;
-use tokio::process::Command;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
-use crate::db::Database;
+use crate::config::Config;
+use crate::db::{CommandExecution, Database, ExecutionStatus};
+use crate::warp::config::AgenticConfig;
+use crate::warp::shell_runner::{ExecutionResult, ShellRunner, TIMED_OUT_EXIT_CODE};
 
+pub mod agent;
+pub mod alias;
+pub mod args;
+pub mod config;
+pub mod doctor;
+pub mod embed;
+pub mod history;
 pub mod task;
 pub mod prep;
 pub mod blog;
+pub mod completions;
+pub mod import;
+pub mod keys;
+pub mod models;
+pub mod output;
+pub mod plan;
+pub mod theme;
+pub mod version;
+pub mod warp;
+pub mod workflow;
 
+pub use agent::AgentCommand;
+pub use config::ConfigCommand;
+pub use output::Output;
 pub use task::TaskCommand;
 pub use prep::PrepCommand;
 pub use blog::BlogCommand;
 
 #[derive(Debug, Clone)]
 pub struct CommandRegistry {
-    // Add any state needed for command execution
+    /// `[aliases]` from the config, expanded before a shell command runs
+    aliases: HashMap<String, String>,
+    /// Settings shell commands run with; `.agentic.toml` is read when a command runs if unset
+    agentic: Option<AgenticConfig>,
 }
 
 impl CommandRegistry {
     pub fn new() -> Self {
-        Self {}
+        Self { aliases: HashMap::new(), agentic: None }
     }
     
-    pub async fn execute_task(&self, task_cmd: TaskCommand, db: &Database) -> Result<()> {
+    pub fn with_aliases(mut self, aliases: HashMap<String, String>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_agentic_config(mut self, agentic: AgenticConfig) -> Self {
+        self.agentic = Some(agentic);
+        self
+    }
+
+    /// A runner with the shell, environment and safety patterns from `.agentic.toml`
+    async fn runner(&self, streaming: bool) -> Result<ShellRunner> {
+        match &self.agentic {
+            Some(agentic) => ShellRunner::configured(streaming, agentic),
+            None => ShellRunner::configured(streaming, &AgenticConfig::load().await?),
+        }
+    }
+    
+    /// The warp pipeline with the models, shell and safety settings from `.agentic.toml`
+    pub async fn warp_pipeline(&self, config: &Config) -> Result<crate::warp::WarpPipeline> {
+        match &self.agentic {
+            Some(agentic) => crate::warp::WarpPipeline::from_agentic_config(agentic),
+            None => crate::warp::WarpPipeline::new(config).await,
+        }
+    }
+    
+    pub async fn execute_task(&self, task_cmd: TaskCommand, db: &Database, output: Output) -> Result<()> {
         info!("Executing task command: {:?}", task_cmd);
-        task::execute(task_cmd, db).await
+        task::execute(task_cmd, db, output).await
     }
     
-    pub async fn execute_prep(&self, prep_cmd: PrepCommand, db: &Database) -> Result<()> {
+    pub async fn execute_prep(&self, prep_cmd: PrepCommand, db: &Database, config: &Config, output: Output) -> Result<()> {
         info!("Executing prep command: {:?}", prep_cmd);
-        prep::execute(prep_cmd, db).await
+        prep::execute(prep_cmd, db, &config.prep, output).await
     }
     
-    pub async fn execute_blog(&self, blog_cmd: BlogCommand, db: &Database) -> Result<()> {
+    pub async fn execute_blog(&self, blog_cmd: BlogCommand, db: &Database, config: &Config, output: Output) -> Result<()> {
         info!("Executing blog command: {:?}", blog_cmd);
-        blog::execute(blog_cmd, db).await
+        blog::execute(blog_cmd, db, &config.blog_dir, output).await
+    }
+    
+    pub async fn execute_agent(&self, agent_cmd: AgentCommand, config: &Config, output: Output) -> Result<String> {
+        info!("Executing agent command: {:?}", agent_cmd);
+        agent::execute(agent_cmd, config, output).await
+    }
+    
+    pub fn execute_warp(&self, warp_cmd: warp::WarpCommand, output: Output) -> Result<String> {
+        info!("Executing warp command: {:?}", warp_cmd);
+        warp::execute(warp_cmd, &crate::warp::pipeline::PipelineStats::default_path(), output)
+    }
+    
+    /// Plan `goal` and run the steps with a confirmation before each; returns whether all succeeded
+    pub async fn execute_plan(&self, goal: &str, agent: &crate::agent::Agent, yes: bool) -> Result<bool> {
+        info!("Executing plan for: {}", goal);
+        plan::execute(goal, agent, yes).await
+    }
+    
+    pub async fn execute_config(&self, config_cmd: ConfigCommand) -> Result<String> {
+        info!("Executing config command: {:?}", config_cmd);
+        config::execute(config_cmd).await
+    }
+    
+    pub async fn execute_keys(&self, keys_cmd: keys::KeysCommand) -> Result<String> {
+        info!("Executing keys command: {:?}", keys_cmd);
+        keys::execute(keys_cmd).await
+    }
+    
+    pub async fn execute_theme(&self, theme_cmd: theme::ThemeCommand, config: &mut Config) -> Result<String> {
+        info!("Executing theme command: {:?}", theme_cmd);
+        theme::execute(theme_cmd, config).await
+    }
+    
+    pub async fn execute_alias(&self, alias_cmd: alias::AliasCommand, config: &mut Config) -> Result<String> {
+        info!("Executing alias command: {:?}", alias_cmd);
+        alias::execute(alias_cmd, config).await
+    }
+    
+    pub async fn execute_history(&self, history_cmd: history::HistoryCommand, db: &Database, config: &Config, interactive: bool) -> Result<String> {
+        info!("Executing history command: {:?}", history_cmd);
+        history::execute(history_cmd, self, db, config, interactive).await
     }
     
-    pub async fn execute_raw_command(&self, command_str: &str) -> Result<()> {
+    pub async fn execute_workflow(&self, workflow_cmd: workflow::WorkflowCommand, interactive: bool) -> Result<String> {
+        info!("Executing workflow command: {:?}", workflow_cmd);
+        workflow::execute(workflow_cmd, interactive).await
+    }
+    
+    /// Diagnose the setup; works even when the config file or database is broken
+    pub async fn execute_doctor(&self) -> doctor::DoctorReport {
+        info!("Executing doctor command");
+        doctor::run(&Config::config_path()).await
+    }
+    
+    /// Apply the fixes `report` offers, asking before each one unless `yes`
+    pub async fn execute_doctor_fix(&self, report: &doctor::DoctorReport, yes: bool) -> Vec<String> {
+        info!("Executing doctor fixes");
+        doctor::fix(report, |fix| {
+            let question = fix.describe();
+            async move {
+                if yes {
+                    return true;
+                }
+                println!("🔧 {}? (y/N): ", question);
+                crate::warp::read_answer().await.is_some_and(|answer| answer.trim().to_lowercase().starts_with('y'))
+            }
+        }).await
+    }
+    
+    /// Build and environment details; like `doctor`, works without a valid config
+    pub async fn execute_version(&self) -> String {
+        info!("Executing version command");
+        version::VersionInfo::gather(&Config::config_path()).await.render()
+    }
+    
+    pub async fn execute_embed(&self, text: &str, model: Option<String>, dims: bool, config: &Config, output: Output) -> Result<String> {
+        info!("Executing embed command");
+        embed::execute(text, model, dims, config, output).await
+    }
+    
+    pub async fn execute_models(&self, config: &Config) -> Result<String> {
+        info!("Executing models command");
+        models::execute(config).await
+    }
+    
+    /// Switch the agent's model; a one-shot CLI process always persists it, since the
+    /// switch would otherwise end with the process
+    pub async fn execute_models_use(&self, model: &str, config: &mut Config) -> Result<String> {
+        info!("Switching default model to {}", model);
+        let path = Config::config_path();
+        models::persist_default_model(config, &path, model).await?;
+        Ok(models::switch_message(model, Some(&path), config))
+    }
+    
+    /// Run a shell command, returning its stdout; with `streaming` the output is also echoed live
+    pub async fn execute_raw_command(&self, command_str: &str, streaming: bool) -> Result<String> {
         info!("Executing raw command: {}", command_str);
         
-        // Parse command and arguments
-        let parts: Vec<&str> = command_str.split_whitespace().collect();
-        if parts.is_empty() {
+        if command_str.trim().is_empty() {
             return Err(anyhow::anyhow!("Empty command"));
         }
         
-        let (cmd, args) = parts.split_at(1);
-        let cmd = cmd[0];
+        let command_str = alias::expand(command_str, &self.aliases)?;
+        debug!("Running command: {} (streaming: {})", command_str, streaming);
+        
+        raw_command_output(self.runner(streaming).await?.execute(&command_str).await?)
+    }
+    
+    /// Like `execute_raw_command`, but records the run in the command history.
+    /// If `cancel` completes first the command is killed, recorded as `Cancelled`,
+    /// and `Interrupted` is returned.
+    pub async fn execute_recorded_command(
+        &self,
+        command_str: &str,
+        streaming: bool,
+        db: &Database,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<String> {
+        info!("Executing raw command: {}", command_str);
         
-        debug!("Running command: {} with args: {:?}", cmd, args);
+        if command_str.trim().is_empty() {
+            return Err(anyhow::anyhow!("Empty command"));
+        }
         
-        let child = Command::new(cmd)
-            .args(args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
+        let command_str = alias::expand(command_str, &self.aliases)?;
+        let runner = self.runner(streaming).await?;
+        raw_command_output(run_and_record(&command_str, &runner, db, cancel).await?)
+    }
+    
+    /// Run a command `options.count` times, recording each run, and report per-run and aggregate
+    /// timing. `on_run` sees every run as it finishes. `cancel` stops the current run and the
+    /// series, leaving `interrupted` set.
+    pub async fn execute_repeated(
+        &self,
+        command_str: &str,
+        options: RepeatOptions,
+        streaming: bool,
+        db: &Database,
+        cancel: impl Future<Output = ()>,
+        mut on_run: impl FnMut(&RunRecord),
+    ) -> Result<RepeatSummary> {
+        info!("Executing raw command {} times: {}", options.count, command_str);
         
-        let output = child.wait_with_output().await?;
+        if command_str.trim().is_empty() {
+            return Err(anyhow::anyhow!("Empty command"));
+        }
         
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            if !stdout.trim().is_empty() {
-                println!("{}", stdout);
+        let command_str = alias::expand(command_str, &self.aliases)?;
+        let runner = self.runner(streaming).await?;
+        tokio::pin!(cancel);
+        let mut summary = RepeatSummary::default();
+        for index in 1..=options.count {
+            let (status, exit_code, duration) = match run_and_record(&command_str, &runner, db, cancel.as_mut()).await? {
+                ExecutionResult::Success { exit_code, duration, .. } => (ExecutionStatus::Success, exit_code, duration),
+                ExecutionResult::Error { exit_code, duration, .. } => (ExecutionStatus::Error, exit_code, duration),
+                ExecutionResult::TimedOut { duration, .. } => (ExecutionStatus::Error, TIMED_OUT_EXIT_CODE, duration),
+                ExecutionResult::Cancelled { .. } => {
+                    summary.interrupted = true;
+                    break;
+                }
+            };
+            let run = RunRecord { index, status, exit_code, duration };
+            on_run(&run);
+            let failed = !run.succeeded();
+            summary.runs.push(run);
+            if failed && options.fail_fast {
+                break;
             }
+        }
+        Ok(summary)
+    }
+}
+
+/// Save a `Running` history entry, run the command, then record how it ended
+async fn run_and_record(
+    command_str: &str,
+    runner: &ShellRunner,
+    db: &Database,
+    cancel: impl Future<Output = ()>,
+) -> Result<ExecutionResult> {
+    let execution = CommandExecution::new(command_str.to_string(), None);
+    db.save_command_execution(&execution).await?;
+    
+    let result = runner.execute_until(command_str, cancel).await;
+    let (status, output, duration) = match &result {
+        Ok(ExecutionResult::Success { stdout, duration, .. }) => (ExecutionStatus::Success, stdout.clone(), *duration),
+        Ok(ExecutionResult::Error { stderr, duration, .. }) => (ExecutionStatus::Error, stderr.clone(), *duration),
+        Ok(ExecutionResult::Cancelled { duration }) => (ExecutionStatus::Cancelled, String::new(), *duration),
+        Ok(ExecutionResult::TimedOut { stderr, duration, .. }) => (ExecutionStatus::Error, stderr.clone(), *duration),
+        Err(e) => (ExecutionStatus::Error, e.to_string(), Default::default()),
+    };
+    let exit_code = result.as_ref().ok().map(ExecutionResult::exit_code);
+    db.update_execution_status(&execution.id, status, &output, duration.as_millis() as u64, exit_code).await?;
+    result
+}
+
+/// How `run -n` repeats a command
+#[derive(Debug, Clone, Copy)]
+pub struct RepeatOptions {
+    pub count: u32,
+    /// Stop at the first failing run
+    pub fail_fast: bool,
+}
+
+/// One run of `run -n`
+#[derive(Debug, Clone)]
+pub struct RunRecord {
+    /// 1-based position in the series
+    pub index: u32,
+    pub status: ExecutionStatus,
+    pub exit_code: i32,
+    pub duration: Duration,
+}
+
+impl RunRecord {
+    pub fn succeeded(&self) -> bool {
+        matches!(self.status, ExecutionStatus::Success)
+    }
+    
+    pub fn describe(&self) -> String {
+        if self.succeeded() {
+            format!("✓ run {}: ok in {:.2}s", self.index, self.duration.as_secs_f64())
         } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+            format!("✗ run {}: exit code {} after {:.2}s", self.index, self.exit_code, self.duration.as_secs_f64())
+        }
+    }
+}
+
+/// Aggregate results of `run -n`
+#[derive(Debug, Clone, Default)]
+pub struct RepeatSummary {
+    pub runs: Vec<RunRecord>,
+    /// The series was stopped with Ctrl+C
+    pub interrupted: bool,
+}
+
+impl RepeatSummary {
+    pub fn successes(&self) -> usize {
+        self.runs.iter().filter(|r| r.succeeded()).count()
+    }
+    
+    pub fn all_succeeded(&self) -> bool {
+        !self.interrupted && self.successes() == self.runs.len()
+    }
+    
+    /// Minimum, average and maximum run duration
+    pub fn timing(&self) -> Option<(Duration, Duration, Duration)> {
+        let min = self.runs.iter().map(|r| r.duration).min()?;
+        let max = self.runs.iter().map(|r| r.duration).max()?;
+        let total: Duration = self.runs.iter().map(|r| r.duration).sum();
+        Some((min, total / self.runs.len() as u32, max))
+    }
+    
+    pub fn render(&self) -> String {
+        let mut report = format!(
+            "📊 {}/{} runs succeeded ({:.0}%)",
+            self.successes(),
+            self.runs.len(),
+            if self.runs.is_empty() { 0.0 } else { self.successes() as f64 * 100.0 / self.runs.len() as f64 }
+        );
+        if let Some((min, avg, max)) = self.timing() {
+            report.push_str(&format!(
+                "\n⏱ min {:.2}s / avg {:.2}s / max {:.2}s",
+                min.as_secs_f64(), avg.as_secs_f64(), max.as_secs_f64()
+            ));
+        }
+        if self.interrupted {
+            report.push_str("\n⏹ Interrupted before all runs finished");
+        }
+        report
+    }
+}
+
+/// Returned when a raw command is stopped with Ctrl+C before it finishes
+#[derive(Debug, thiserror::Error)]
+#[error("Command interrupted")]
+pub struct Interrupted;
+
+pub(crate) fn raw_command_output(result: ExecutionResult) -> Result<String> {
+    match result {
+        ExecutionResult::Success { stdout, .. } => Ok(stdout),
+        ExecutionResult::Error { stderr, exit_code, .. } => {
             warn!("Command failed with error: {}", stderr);
-            return Err(anyhow::anyhow!("Command failed: {}", stderr));
+            Err(anyhow::anyhow!("Command failed (exit code {}): {}", exit_code, stderr))
         }
-        
-        Ok(())
+        ExecutionResult::Cancelled { .. } => Err(Interrupted.into()),
+        ExecutionResult::TimedOut { duration, .. } => Err(anyhow::anyhow!("Command timed out after {}s", duration.as_secs())),
     }
 }
 
@@ -80,3 +385,83 @@ impl Default for CommandRegistry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_interrupted_command_is_recorded_cancelled() {
+        let db = crate::db::test_database().await;
+        let cancel = tokio::time::sleep(std::time::Duration::from_millis(200));
+
+        let err = CommandRegistry::new()
+            .execute_recorded_command("sleep 30", false, &db, cancel)
+            .await
+            .unwrap_err();
+
+        assert!(err.is::<Interrupted>());
+        let history = db.get_command_history(1).await.unwrap();
+        assert!(matches!(history[0].status, ExecutionStatus::Cancelled));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_shell_commands_run_in_the_configured_shell() {
+        let db = crate::db::test_database().await;
+        let agentic: AgenticConfig = toml::from_str("[warp.execution]\nshell = \"sh\"\n").unwrap();
+        let registry = CommandRegistry::new().with_agentic_config(agentic);
+
+        assert_eq!(registry.execute_raw_command("echo $0", false).await.unwrap().trim(), "sh");
+        let recorded = registry.execute_recorded_command("echo $0", false, &db, std::future::pending()).await.unwrap();
+        assert_eq!(recorded.trim(), "sh");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_sees_the_configured_env_file() {
+        let db = crate::db::test_database().await;
+        let dir = std::env::temp_dir().join(format!("agentic-env-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".env"), "FROM_DOTENV=yes\n").unwrap();
+        let toml = format!("[warp.execution]\nenv_file = \"{}\"\n", dir.join(".env").display());
+        let registry = CommandRegistry::new().with_agentic_config(toml::from_str(&toml).unwrap());
+
+        let output = registry.execute_raw_command("echo $FROM_DOTENV", false).await;
+        let recorded = registry.execute_recorded_command("echo $FROM_DOTENV", false, &db, std::future::pending()).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(output.unwrap().trim(), "yes");
+        assert_eq!(recorded.unwrap().trim(), "yes");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_repeated_runs_are_recorded_and_aggregated() {
+        let db = crate::db::test_database().await;
+        let registry = CommandRegistry::new();
+        let mut seen = Vec::new();
+
+        let summary = registry
+            .execute_repeated("sleep 0.05", RepeatOptions { count: 3, fail_fast: false }, false, &db, std::future::pending(), |run| seen.push(run.index))
+            .await
+            .unwrap();
+
+        assert_eq!(seen, vec![1, 2, 3]);
+        assert_eq!(db.get_command_history(10).await.unwrap().len(), 3);
+        assert!(summary.all_succeeded());
+        let (min, avg, max) = summary.timing().unwrap();
+        assert!(min >= Duration::from_millis(50));
+        assert!(min <= avg && avg <= max);
+        assert!(summary.render().contains("3/3 runs succeeded (100%)"));
+        assert!(summary.render().lines().nth(1).is_some_and(|line| line.starts_with("⏱ min ")), "{}", summary.render());
+
+        let failing = registry
+            .execute_repeated("exit 3", RepeatOptions { count: 3, fail_fast: true }, false, &db, std::future::pending(), |_| {})
+            .await
+            .unwrap();
+        assert_eq!(failing.runs.len(), 1, "--fail-fast stops after the first failure");
+        assert_eq!(failing.runs[0].exit_code, 3);
+        assert!(!failing.all_succeeded());
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use clap::Subcommand;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::ollama::client::{ModelInfo, OllamaClient};
+use crate::warp::WarpConfig;
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ModelsCommand {
+    /// Switch the Ollama model answering agent queries
+    Use {
+        /// Model name, e.g. `llama3:8b`
+        model: String,
+        /// In the TUI, also make it the default in the config file; outside it the switch is always saved
+        #[arg(long)]
+        save: bool,
+    },
+}
+
+/// A model the config refers to, and the role it plays
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfiguredModel {
+    pub role: &'static str,
+    pub model: String,
+}
+
+/// The models the agent and the Warp pipeline are set up to use
+pub fn configured_models(config: &Config, warp: &WarpConfig) -> Vec<ConfiguredModel> {
+    vec![
+        ConfiguredModel { role: "agent", model: crate::agent::ollama_config(config).model },
+        ConfiguredModel { role: "planner", model: warp.planner_model.clone() },
+        ConfiguredModel { role: "coder", model: warp.coder_model.clone() },
+        ConfiguredModel { role: "fallback", model: warp.fallback_model.clone() },
+    ]
+}
+
+/// Format a byte count as e.g. "4.7 GB" or "512 MB"
+pub fn format_size(bytes: u64) -> String {
+    const GB: f64 = 1_000_000_000.0;
+    const MB: f64 = 1_000_000.0;
+    let bytes = bytes as f64;
+    if bytes >= GB {
+        format!("{:.1} GB", bytes / GB)
+    } else {
+        format!("{:.0} MB", bytes / MB)
+    }
+}
+
+/// Render the installed models and whether each configured model is among them
+pub fn render_report(installed: &[ModelInfo], configured: &[ConfiguredModel]) -> String {
+    let width = installed.iter().map(|m| m.name.len()).max().unwrap_or(0).max("Model".len());
+    let mut report = String::from("📦 Installed Models\n");
+    if installed.is_empty() {
+        report.push_str("No models installed.\n");
+    } else {
+        report.push_str(&format!("{:<width$}  {:>8}  {}\n", "Model", "Size", "Modified", width = width));
+        for model in installed {
+            let modified = model.modified
+                .map(|dt| dt.with_timezone(&Local).format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "-".to_string());
+            report.push_str(&format!("{:<width$}  {:>8}  {}\n", model.name, format_size(model.size), modified, width = width));
+        }
+    }
+
+    report.push_str("\n🔧 Configured Models\n");
+    for wanted in configured {
+        if installed.iter().any(|m| m.matches(&wanted.model)) {
+            report.push_str(&format!("✓ {:<8} {}\n", wanted.role, wanted.model));
+        } else {
+            report.push_str(&format!("✗ {:<8} {} is not installed; run `ollama pull {}`\n", wanted.role, wanted.model, wanted.model));
+        }
+    }
+    report
+}
+
+/// Make `model` the default agent model and write the config back to `path`
+pub async fn persist_default_model(config: &mut Config, path: &Path, model: &str) -> Result<()> {
+    config.agent.ollama_model = model.to_string();
+    config.save_to(path).await
+}
+
+/// Describe a model switch; `saved_to` is the config file when it was made the default
+pub fn switch_message(model: &str, saved_to: Option<&Path>, config: &Config) -> String {
+    let mut message = match saved_to {
+        Some(path) => format!("🔄 Now using {}, saved as the default in {}", model, path.display()),
+        None => format!("🔄 Now using {} for this session (add --save to make it the default)", model),
+    };
+    if config.agent.preferred_provider == "openai" && config.get_openai_api_key().is_some() {
+        message.push_str("\n⚠ The agent is using OpenAI; the Ollama model applies once `preferred_provider` is \"ollama\"");
+    }
+    message
+}
+
+pub async fn execute(config: &Config) -> Result<String> {
+    let ollama_config = crate::agent::ollama_config(config);
+    let base_url = ollama_config.base_url.clone();
+    let client = OllamaClient::new(ollama_config)?;
+    let installed = client.list_models().await
+        .with_context(|| format!("Ollama is not reachable at {}; start it with `ollama serve`", base_url))?;
+    let warp = WarpConfig::load().await?;
+    Ok(render_report(&installed, &configured_models(config, &warp)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_persisted_model_is_in_the_saved_config() {
+        let dir = std::env::temp_dir().join(format!("agentic-models-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.toml");
+        let mut config = Config::default();
+
+        persist_default_model(&mut config, &path, "llama3:8b").await.unwrap();
+        let (saved, _) = Config::load_and_migrate(&path).await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(saved.agent.ollama_model, "llama3:8b");
+        assert_eq!(crate::agent::ollama_config(&saved).model, "llama3:8b");
+        assert!(switch_message("llama3:8b", Some(&path), &saved).contains("saved as the default"));
+        assert!(switch_message("llama3:8b", None, &saved).contains("for this session"));
+    }
+
+    #[test]
+    fn test_report_flags_missing_models() {
+        let installed = vec![
+            ModelInfo { name: "phi4:latest".to_string(), size: 9_100_000_000, modified: None },
+            ModelInfo { name: "codellama:7b".to_string(), size: 3_800_000_000, modified: None },
+        ];
+        let configured = vec![
+            ConfiguredModel { role: "planner", model: "phi4".to_string() },
+            ConfiguredModel { role: "coder", model: "codellama".to_string() },
+        ];
+
+        let report = render_report(&installed, &configured);
+
+        assert!(report.contains("9.1 GB"));
+        assert!(report.contains("✓ planner  phi4"));
+        // Only the 7b tag is installed, and a bare name means :latest
+        assert!(report.contains("✗ coder    codellama is not installed; run `ollama pull codellama`"));
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::io::IsTerminal;
+
+/// How a command reports its result: colored text for people, or JSON on stdout for scripts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Output {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Output {
+    pub fn from_json_flag(json: bool) -> Self {
+        if json { Output::Json } else { Output::Text }
+    }
+
+    pub fn is_json(self) -> bool {
+        self == Output::Json
+    }
+
+    /// Turn colors off for JSON, and for text when stdout isn't a terminal unless
+    /// `CLICOLOR_FORCE` asks for them anyway
+    pub fn configure_colors(self) {
+        let piped = !std::io::stdout().is_terminal() && std::env::var_os("CLICOLOR_FORCE").is_none();
+        if self.is_json() || piped {
+            colored::control::set_override(false);
+        }
+    }
+}
+
+/// Print `value` to stdout as pretty JSON
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use colored::*;
+
+use crate::agent::planner::{ExecutionPlan, ExecutionStep, Planner};
+use crate::agent::Agent;
+use crate::warp::config::AgenticConfig;
+use crate::warp::shell_runner::{ExecutionResult, ShellRunner};
+use crate::warp::{override_confirmed, read_answer};
+
+/// The steps of `plan` in the order they will run
+pub fn render_plan(goal: &str, plan: &ExecutionPlan) -> Result<String> {
+    let mut output = format!("🗺  Plan for: {}\n", goal);
+    for (n, step) in plan.ordered_steps()?.iter().enumerate() {
+        output.push_str(&format!("{}. {}\n   $ {}\n", n + 1, step.description, step.command));
+        if !step.dependencies.is_empty() {
+            output.push_str(&format!("   after {}\n", step.dependencies.join(", ")));
+        }
+    }
+    Ok(output)
+}
+
+/// How each step that ran went; the steps after a failure or a decline are listed as skipped
+pub fn render_results(steps: &[&ExecutionStep], results: &[ExecutionResult]) -> String {
+    let mut output = String::from("📊 Plan results\n");
+    for (n, step) in steps.iter().enumerate() {
+        let line = match results.get(n) {
+            Some(ExecutionResult::Success { duration, .. }) => format!("✓ {} ok in {:.2}s", step.id, duration.as_secs_f64()),
+            Some(ExecutionResult::Error { exit_code, .. }) => format!("✗ {} failed with exit code {}", step.id, exit_code),
+            Some(ExecutionResult::Cancelled { .. }) => format!("⏹ {} interrupted", step.id),
+            Some(ExecutionResult::TimedOut { duration, .. }) => format!("⏱ {} timed out after {}s", step.id, duration.as_secs()),
+            None => format!("- {} skipped", step.id),
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+/// Whether every step ran and succeeded
+pub fn completed(plan: &ExecutionPlan, results: &[ExecutionResult]) -> bool {
+    results.len() == plan.steps.len() && results.iter().all(|r| matches!(r, ExecutionResult::Success { .. }))
+}
+
+/// A shell runner honoring the Warp safety, environment and shell settings in `.agentic.toml`
+async fn runner(streaming: bool) -> Result<ShellRunner> {
+    ShellRunner::configured(streaming, &AgenticConfig::load().await?)
+}
+
+/// Plan `goal`, then run the steps, asking before each one unless `yes`. Dangerous steps always
+/// need a typed YES. Returns whether the whole plan succeeded.
+pub async fn execute(goal: &str, agent: &Agent, yes: bool) -> Result<bool> {
+    println!("{} {}", "🧠".blue(), "Planning...".cyan());
+    let planner = Planner::new(agent.clone());
+    let plan = planner.create_execution_plan(goal).await?;
+    println!("{}", render_plan(goal, &plan)?);
+
+    let runner = runner(true).await?;
+    let steps = plan.ordered_steps()?;
+    let total = steps.len();
+    let mut position = 0;
+    let results = planner.execute_plan_confirmed(&plan, &runner, |step| {
+        position += 1;
+        let header = format!("\n{} Step {}/{}: {}\n   $ {}", "▶".blue(), position, total, step.description, step.command.yellow());
+        let dangerous = runner.dangerous_match(&step.command).map(str::to_string);
+        async move {
+            println!("{}", header);
+            if let Some(pattern) = dangerous {
+                println!("{} {} this step matches the dangerous pattern '{}'", "🛑".red(), "Blocked:".red().bold(), pattern.red().bold());
+                println!("{} Type YES to run it anyway: ", "❓".yellow());
+                return read_answer().await.is_some_and(|answer| override_confirmed(&answer));
+            }
+            if yes {
+                return true;
+            }
+            println!("{} Run this step? (y/N): ", "❓".yellow());
+            read_answer().await.is_some_and(|answer| answer.trim().to_lowercase().starts_with('y'))
+        }
+    }).await?;
+
+    println!("\n{}", render_results(&steps, &results));
+    Ok(completed(&plan, &results))
+}
+
+/// Plan `goal` for display, and with `run` also execute it without prompts; dangerous steps are
+/// refused since there is nobody to type YES. Used where stdin isn't available, like the TUI.
+pub async fn report(goal: &str, agent: &Agent, run: bool) -> Result<(String, bool)> {
+    let planner = Planner::new(agent.clone());
+    let plan = planner.create_execution_plan(goal).await?;
+    let mut output = render_plan(goal, &plan)?;
+    if !run {
+        output.push_str("\nAdd --yes to run these steps");
+        return Ok((output, true));
+    }
+
+    let runner = runner(false).await?;
+    let results = planner.execute_plan(&plan, &runner).await?;
+    output.push('\n');
+    output.push_str(&render_results(&plan.ordered_steps()?, &results));
+    Ok((output, completed(&plan, &results)))
+}
@@ -1,9 +1,15 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
 use clap::Subcommand;
 use colored::*;
 use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
 
-use crate::db::Database;
+use crate::config::PrepConfig;
+use crate::db::{Database, ExamPrepStats, DEFAULT_LIST_LIMIT};
+use super::import;
+use super::output::{print_json, Output};
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum PrepCommand {
@@ -18,6 +24,12 @@ pub enum PrepCommand {
         /// Session duration in minutes
         #[arg(long, short, default_value = "60")]
         duration: u32,
+        /// Time the session with 25/5 minute work/break cycles
+        #[arg(long)]
+        pomodoro: bool,
+        /// Block distracting commands (`prep.blocked_commands`) in `agentic run` until the session ends
+        #[arg(long)]
+        focus: bool,
     },
     /// List preparation sessions
     List {
@@ -27,6 +39,9 @@ pub enum PrepCommand {
         /// Show only active sessions
         #[arg(long)]
         active: bool,
+        /// Maximum number of sessions to show (capped by `max_list_limit` in the config)
+        #[arg(long, default_value_t = DEFAULT_LIST_LIMIT)]
+        limit: usize,
     },
     /// Stop current preparation session
     Stop {
@@ -63,6 +78,11 @@ pub enum PrepCommand {
         #[arg(long, short, default_value = "5")]
         count: u32,
     },
+    /// Import preparation sessions from a JSON or YAML file
+    Import {
+        /// Path to a .json/.yaml file containing a list of sessions
+        file: PathBuf,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,6 +94,9 @@ pub struct PrepSession {
     pub status: SessionStatus,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Started with `--focus`, so the focus lock applies while it is active
+    #[serde(default)]
+    pub focus: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,9 +107,316 @@ pub enum SessionStatus {
     Cancelled,
 }
 
-pub async fn execute(command: PrepCommand, _db: &Database) -> Result<()> {
+impl std::fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionStatus::Active => write!(f, "Active"),
+            SessionStatus::Completed => write!(f, "Completed"),
+            SessionStatus::Paused => write!(f, "Paused"),
+            SessionStatus::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl std::str::FromStr for SessionStatus {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "active" => Ok(SessionStatus::Active),
+            "completed" | "complete" | "done" => Ok(SessionStatus::Completed),
+            "paused" => Ok(SessionStatus::Paused),
+            "cancelled" | "canceled" => Ok(SessionStatus::Cancelled),
+            _ => Err(anyhow!("Invalid session status: {}", s)),
+        }
+    }
+}
+
+/// A study topic tracked for review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrepTopic {
+    pub id: String,
+    pub exam: String,
+    pub topic: String,
+    /// 1 (low) to 5 (high)
+    pub priority: u8,
+    pub last_reviewed: Option<DateTime<Utc>>,
+    /// 0 to 100
+    pub mastery: u8,
+}
+
+impl PrepTopic {
+    pub fn new(exam: String, topic: String, priority: u8) -> Result<Self> {
+        if !(1..=5).contains(&priority) {
+            return Err(anyhow!("Priority must be between 1 and 5, got {}", priority));
+        }
+        Ok(Self {
+            id: crate::offline::new_id(),
+            exam,
+            topic,
+            priority,
+            last_reviewed: None,
+            mastery: 0,
+        })
+    }
+
+    /// Short label describing how well the topic is known
+    pub fn mastery_label(&self) -> &'static str {
+        match self.mastery {
+            0..=39 => "Weak area",
+            40..=69 => "Needs review",
+            _ => "Confident",
+        }
+    }
+}
+
+/// Time window for `prep stats`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsPeriod {
+    Week,
+    Month,
+    All,
+}
+
+impl std::str::FromStr for StatsPeriod {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "week" => Ok(StatsPeriod::Week),
+            "month" => Ok(StatsPeriod::Month),
+            "all" => Ok(StatsPeriod::All),
+            _ => Err(anyhow!("Invalid period '{}', expected week, month or all", s)),
+        }
+    }
+}
+
+impl PrepSession {
+    /// A new active session; `duration_minutes` is the planned length until the session is stopped
+    pub fn new(exam_type: String, session_name: String, duration_minutes: u32) -> Self {
+        let now = crate::offline::now();
+        Self {
+            id: crate::offline::new_id(),
+            exam_type,
+            session_name,
+            duration_minutes,
+            status: SessionStatus::Active,
+            created_at: now,
+            updated_at: now,
+            focus: false,
+        }
+    }
+}
+
+/// Return the block list entry that `command` matches, if any.
+/// Entries match a program name, or a host (including its subdomains) in a URL argument.
+pub fn blocked_entry<'a>(command: &str, blocked: &'a [String]) -> Option<&'a str> {
+    let words = shell_words::split(command)
+        .unwrap_or_else(|_| command.split_whitespace().map(str::to_string).collect());
+    
+    words.iter().find_map(|word| {
+        let word = word.to_lowercase();
+        let program = word.rsplit(['/', '\\']).next().unwrap_or(&word).trim_end_matches(".exe");
+        let host = word.split_once("://").map(|(_, rest)| rest).unwrap_or(&word);
+        let host = host.split(['/', '?', '#', ':']).next().unwrap_or(host);
+        
+        blocked.iter().map(|entry| entry.trim()).find(|entry| {
+            let entry = entry.to_lowercase();
+            !entry.is_empty()
+                && (program == entry
+                    || word == entry
+                    || host == entry
+                    || host.ends_with(&format!(".{}", entry)))
+        })
+    })
+}
+
+/// Refuse `command` if an active `--focus` session is running and it is on the block list
+pub async fn check_focus_lock(db: &Database, config: &PrepConfig, command: &str) -> Result<()> {
+    if !config.focus_lock {
+        return Ok(());
+    }
+    let Some(session) = db.find_active_prep_session(None).await? else {
+        return Ok(());
+    };
+    if !session.focus {
+        return Ok(());
+    }
+    match blocked_entry(command, &config.blocked_commands) {
+        Some(entry) => Err(anyhow!(
+            "🔒 '{}' is blocked during your {} study session. Stay focused! Run `agentic prep stop` when you're done.",
+            entry, session.exam_type
+        )),
+        None => Ok(()),
+    }
+}
+
+pub const POMODORO_WORK_MINUTES: u32 = 25;
+pub const POMODORO_BREAK_MINUTES: u32 = 5;
+
+/// One block of a Pomodoro session, with its length in minutes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PomodoroPhase {
+    Work(u32),
+    Break(u32),
+}
+
+/// Split `total_minutes` into alternating work/break blocks; the last block is cut short to fit
+pub fn pomodoro_schedule(total_minutes: u32) -> Vec<PomodoroPhase> {
+    let mut phases = Vec::new();
+    let mut remaining = total_minutes;
+    while remaining > 0 {
+        let work = remaining.min(POMODORO_WORK_MINUTES);
+        phases.push(PomodoroPhase::Work(work));
+        remaining -= work;
+        if remaining > 0 {
+            let rest = remaining.min(POMODORO_BREAK_MINUTES);
+            phases.push(PomodoroPhase::Break(rest));
+            remaining -= rest;
+        }
+    }
+    phases
+}
+
+/// Minutes studied when stopping `seconds` into `phases[current]`: the work blocks before it,
+/// plus the time spent in it if it is a work block; breaks don't count
+pub fn pomodoro_minutes_studied(phases: &[PomodoroPhase], current: usize, seconds: u64) -> u32 {
+    let finished: u32 = phases[..current.min(phases.len())].iter()
+        .map(|phase| match phase {
+            PomodoroPhase::Work(minutes) => *minutes,
+            PomodoroPhase::Break(_) => 0,
+        })
+        .sum();
+    let partial = match phases.get(current) {
+        Some(PomodoroPhase::Work(_)) => (seconds / 60) as u32,
+        _ => 0,
+    };
+    finished + partial
+}
+
+/// Count down each Pomodoro phase in place, ringing the bell at every transition.
+/// Ctrl+C saves the work time so far as a paused session and exits.
+async fn run_pomodoro(session: &PrepSession, db: &Database) -> Result<()> {
+    let phases = pomodoro_schedule(session.duration_minutes);
+    let mut stdout = std::io::stdout();
+    
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    
+    println!("{} Pomodoro: {} blocks, press Ctrl+C to stop early", "🍅".red(), phases.len());
+    for (index, phase) in phases.iter().enumerate() {
+        let (label, minutes) = match phase {
+            PomodoroPhase::Work(m) => ("Focus".green().bold(), *m),
+            PomodoroPhase::Break(m) => ("Break".yellow().bold(), *m),
+        };
+        print!("\x07");
+        
+        let mut remaining = minutes as u64 * 60;
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(1));
+        while remaining > 0 {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    print!("\r{} {}/{}  {:02}:{:02} remaining ", label, index + 1, phases.len(), remaining / 60, remaining % 60);
+                    stdout.flush()?;
+                    remaining -= 1;
+                }
+                _ = &mut ctrl_c => {
+                    let elapsed = pomodoro_minutes_studied(&phases, index, minutes as u64 * 60 - remaining);
+                    db.finish_prep_session(&session.id, SessionStatus::Paused, elapsed).await?;
+                    println!();
+                    println!("{} Pomodoro interrupted, saved {} of study time.", "⏸".yellow().bold(), format_minutes(elapsed));
+                    std::process::exit(130);
+                }
+            }
+        }
+    }
+    
+    let elapsed = pomodoro_minutes_studied(&phases, phases.len(), 0);
+    db.finish_prep_session(&session.id, SessionStatus::Completed, elapsed).await?;
+    println!("\x07");
+    println!("{} Pomodoro session complete: {} studied.", "🎉".bright_yellow(), format_minutes(elapsed));
+    Ok(())
+}
+
+/// `prep stats` for scripts: the totals across exams plus each exam's figures, in minutes
+pub fn stats_json(period: &str, exam: Option<&str>, stats: &[ExamPrepStats]) -> serde_json::Value {
+    let sessions: usize = stats.iter().map(|s| s.sessions).sum();
+    let total: u64 = stats.iter().map(|s| s.total_minutes).sum();
+    serde_json::json!({
+        "period": period,
+        "exam": exam,
+        "sessions": sessions,
+        "total_minutes": total,
+        "average_minutes": total.checked_div(sessions as u64).unwrap_or(0),
+        "longest_minutes": stats.iter().map(|s| s.longest_minutes).max().unwrap_or(0),
+        "exams": stats.iter().map(|s| serde_json::json!({
+            "exam_type": s.exam_type,
+            "sessions": s.sessions,
+            "total_minutes": s.total_minutes,
+            "average_minutes": s.average_minutes(),
+            "longest_minutes": s.longest_minutes,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// Format a number of minutes as e.g. "1h 23m" or "45m"
+pub fn format_minutes(minutes: u32) -> String {
+    if minutes >= 60 {
+        format!("{}h {}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// A session record as accepted by `prep import`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrepSessionImport {
+    pub exam_type: String,
+    pub session_name: String,
+    #[serde(default)]
+    pub duration_minutes: u32,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+impl PrepSessionImport {
+    /// Validate the record and build the session it describes
+    pub fn into_session(self) -> Result<PrepSession> {
+        if self.exam_type.trim().is_empty() {
+            return Err(anyhow!("exam_type must not be empty"));
+        }
+        let status = match &self.status {
+            Some(s) => s.parse::<SessionStatus>()?,
+            None => SessionStatus::Completed,
+        };
+        let created_at = match &self.created_at {
+            Some(ts) => DateTime::parse_from_rfc3339(ts)
+                .map_err(|_| anyhow!("invalid created_at '{}', expected RFC 3339", ts))?
+                .with_timezone(&Utc),
+            None => crate::offline::now(),
+        };
+        Ok(PrepSession {
+            id: crate::offline::new_id(),
+            exam_type: self.exam_type,
+            session_name: self.session_name,
+            duration_minutes: self.duration_minutes,
+            status,
+            created_at,
+            updated_at: created_at,
+            focus: false,
+        })
+    }
+}
+
+/// Run a prep subcommand; with `Output::Json`, `stats` prints JSON instead
+pub async fn execute(command: PrepCommand, db: &Database, config: &PrepConfig, output: Output) -> Result<()> {
     match command {
-        PrepCommand::Start { exam, schedule, duration } => {
+        PrepCommand::Start { exam, schedule, duration, pomodoro, focus } => {
+            if pomodoro && duration == 0 {
+                return Err(anyhow!("A Pomodoro session needs a duration of at least one minute"));
+            }
+            
             println!("{}", "🎯 Starting Preparation Session".green().bold());
             println!();
             println!("Exam: {}", exam.bright_blue().bold());
@@ -94,10 +424,22 @@ pub async fn execute(command: PrepCommand, _db: &Database) -> Result<()> {
             println!("Duration: {} minutes", duration.to_string().bright_white());
             println!();
             
-            // Simulate session creation
-            let session_id = "prep_sess_001";
+            if let Some(current) = db.find_active_prep_session(None).await? {
+                println!("{} Session {} is still active; {} stops the most recent one.",
+                    "⚠".yellow(), current.id[..8].bright_blue(), "agentic prep stop".bright_cyan());
+            }
+            
+            let mut session = PrepSession::new(exam.clone(), format!("{} {} session", exam, schedule), duration);
+            session.focus = focus;
+            db.add_prep_session(&session).await?;
             println!("{} Session started successfully!", "✓".green().bold());
-            println!("Session ID: {}", session_id.bright_blue());
+            println!("Session ID: {}", session.id.bright_blue());
+            if focus && config.focus_lock {
+                println!("{} Focus lock on: {} blocked in {} until the session ends",
+                    "🔒".yellow(), config.blocked_commands.join(", ").bright_black(), "agentic run".bright_cyan());
+            } else if focus {
+                println!("{} Focus lock is disabled in the config (prep.focus_lock)", "⚠".yellow());
+            }
             println!();
             
             // Display study plan
@@ -128,59 +470,70 @@ pub async fn execute(command: PrepCommand, _db: &Database) -> Result<()> {
             println!("• Focus on understanding concepts, not just memorizing");
             
             println!();
-            println!("Use {} to stop the session when done.", "agentic prep stop".bright_cyan());
+            if pomodoro {
+                run_pomodoro(&session, db).await?;
+            } else {
+                println!("Use {} to stop the session when done.", "agentic prep stop".bright_cyan());
+            }
         }
         
-        PrepCommand::List { exam, active } => {
+        PrepCommand::List { exam, active, limit } => {
+            let sessions = db.list_prep_sessions(exam.as_deref(), active, limit).await?;
             println!("{}", "📊 Preparation Sessions".blue().bold());
             println!();
             
-            // Mock data
-            let sessions = vec![
-                ("CET-2024-01", "CET Mathematics", "Completed", "2h 15m", "Today"),
-                ("CET-2024-02", "CET Physics", "Active", "45m", "Now"),
-                ("JEE-2024-01", "JEE Chemistry", "Completed", "1h 30m", "Yesterday"),
-            ];
+            if sessions.is_empty() {
+                println!("No preparation sessions found. Start one with {}.", "agentic prep start --exam <EXAM>".bright_cyan());
+            }
             
-            for (_id, name, status, duration, time) in sessions {
-                if let Some(ref exam_filter) = exam {
-                    if !name.to_lowercase().contains(&exam_filter.to_lowercase()) {
-                        continue;
-                    }
-                }
-                
-                if active && status != "Active" {
-                    continue;
-                }
+            for session in sessions {
+                let status = session.status.to_string();
+                let status_color = match session.status {
+                    SessionStatus::Active => "green",
+                    SessionStatus::Completed => "blue",
+                    SessionStatus::Paused => "yellow",
+                    SessionStatus::Cancelled => "red",
+                };
                 
-                let status_color = match status {
-                    "Active" => "green",
-                    "Completed" => "blue",
-                    "Paused" => "yellow",
-                    _ => "red",
+                // Active sessions store their planned length, so show how long they've been running
+                let duration = match session.status {
+                    SessionStatus::Active => {
+                        let elapsed = (crate::offline::now() - session.created_at).num_minutes().max(0) as u32;
+                        format!("{} so far", format_minutes(elapsed))
+                    }
+                    _ => format_minutes(session.duration_minutes),
                 };
                 
-                println!("{} {} {} [{}] ({})", 
+                println!("{} {} {} {} [{}] ({})", 
                     "•".bright_white(),
-                    name.bold(),
+                    session.id[..8].bright_black(),
+                    session.session_name.bold(),
                     status.color(status_color),
                     duration.bright_black(),
-                    time.italic()
+                    session.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string().italic()
                 );
             }
         }
         
         PrepCommand::Stop { session_id } => {
-            let id = session_id.unwrap_or_else(|| "current".to_string());
-            println!("{} Stopping preparation session: {}", "⏹".yellow().bold(), id.bright_blue());
+            let session = db.find_active_prep_session(session_id.as_deref()).await?
+                .ok_or_else(|| match &session_id {
+                    Some(id) => anyhow!("No active preparation session matching '{}'", id),
+                    None => anyhow!("No active preparation session"),
+                })?;
+            
+            let elapsed = (crate::offline::now() - session.created_at).num_minutes().max(0) as u32;
+            db.finish_prep_session(&session.id, SessionStatus::Completed, elapsed).await?;
+            
+            println!("{} Stopping preparation session: {}", "⏹".yellow().bold(), session.id.bright_blue());
             println!();
             
-            // Mock session summary
             println!("{}", "📈 Session Summary".green().bold());
-            println!("Duration: {}", "1h 23m".bright_white());
-            println!("Topics Covered: {}", "3".bright_white());
-            println!("Practice Questions: {}", "15 solved".bright_white());
-            println!("Accuracy: {}", "87%".green().bold());
+            println!("Session: {}", session.session_name.bold());
+            println!("Exam: {}", session.exam_type.bright_blue());
+            println!("Started: {}", session.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string().bright_white());
+            println!("Duration: {}", format_minutes(elapsed).bright_white());
+            println!("Planned: {}", format_minutes(session.duration_minutes).bright_black());
             
             println!();
             println!("{} Great work! Session completed successfully.", "🎉".bright_yellow());
@@ -188,36 +541,54 @@ pub async fn execute(command: PrepCommand, _db: &Database) -> Result<()> {
         }
         
         PrepCommand::Stats { exam, period } => {
+            let period_filter: StatsPeriod = period.parse()?;
+            let stats = db.prep_stats(period_filter, exam.as_deref()).await?;
+            if output.is_json() {
+                return print_json(&stats_json(&period, exam.as_deref(), &stats));
+            }
+            
             println!("{} Preparation Statistics", "📊".blue().bold());
-            if let Some(exam_type) = exam {
+            if let Some(exam_type) = &exam {
                 println!("Exam: {}", exam_type.bright_blue().bold());
             }
             println!("Period: {}", period.yellow());
             println!();
             
-            // Mock statistics
-            println!("{}", "⏱ Time Spent".bright_white().bold());
-            println!("Total Study Time: {}", "24h 30m".green().bold());
-            println!("Average Session: {}", "1h 15m".bright_white());
-            println!("Longest Session: {}", "2h 45m".bright_white());
-            println!();
+            if stats.is_empty() {
+                println!("No finished sessions in this period.");
+                return Ok(());
+            }
             
-            println!("{}", "📚 Topics Covered".bright_white().bold());
-            println!("Mathematics: {}", "12 topics (85% complete)".green());
-            println!("Physics: {}", "8 topics (60% complete)".yellow());
-            println!("Chemistry: {}", "6 topics (45% complete)".red());
+            let sessions: usize = stats.iter().map(|s| s.sessions).sum();
+            let total: u64 = stats.iter().map(|s| s.total_minutes).sum();
+            let longest = stats.iter().map(|s| s.longest_minutes).max().unwrap_or(0);
+            
+            println!("{}", "⏱ Time Spent".bright_white().bold());
+            println!("Total Study Time: {}", format_minutes(total as u32).green().bold());
+            println!("Sessions: {}", sessions.to_string().bright_white());
+            println!("Average Session: {}", format_minutes((total / sessions as u64) as u32).bright_white());
+            println!("Longest Session: {}", format_minutes(longest as u32).bright_white());
             println!();
             
-            println!("{}", "🎯 Performance".bright_white().bold());
-            println!("Practice Questions: {}", "156 solved".bright_white());
-            println!("Average Accuracy: {}", "82%".green().bold());
-            println!("Improvement: {}", "+12% this week".green());
+            println!("{}", "📚 By Exam".bright_white().bold());
+            for exam_stats in &stats {
+                println!("{}: {} across {} sessions (avg {}, longest {})",
+                    exam_stats.exam_type.bright_blue().bold(),
+                    format_minutes(exam_stats.total_minutes as u32).green(),
+                    exam_stats.sessions,
+                    format_minutes(exam_stats.average_minutes() as u32),
+                    format_minutes(exam_stats.longest_minutes as u32)
+                );
+            }
         }
         
         PrepCommand::Add { topic, exam, priority } => {
+            let prep_topic = PrepTopic::new(exam, topic, priority)?;
+            db.add_prep_topic(&prep_topic).await?;
+            
             println!("{} Adding study material", "📝".green().bold());
-            println!("Topic: {}", topic.bold());
-            println!("Exam: {}", exam.bright_blue());
+            println!("Topic: {}", prep_topic.topic.bold());
+            println!("Exam: {}", prep_topic.exam.bright_blue());
             println!("Priority: {}/5", priority.to_string().yellow());
             
             println!();
@@ -229,38 +600,59 @@ pub async fn execute(command: PrepCommand, _db: &Database) -> Result<()> {
         }
         
         PrepCommand::Review { exam, count } => {
+            let topics = db.topics_for_review(&exam, count as usize).await?;
+            
             println!("{} Review Session - {}", "🔄".blue().bold(), exam.bright_blue().bold());
-            println!("Reviewing {} topics", count.to_string().bright_white());
+            if topics.is_empty() {
+                println!("No topics stored for {}. Add some with {}.", exam, "agentic prep add --topic <TOPIC> --exam <EXAM>".bright_cyan());
+                return Ok(());
+            }
+            println!("Reviewing {} topics", topics.len().to_string().bright_white());
             println!();
             
-            // Mock review topics
-            let topics = vec![
-                ("Quadratic Equations", "Mathematics", "Need practice"),
-                ("Newton's Laws", "Physics", "Well understood"),
-                ("Chemical Bonding", "Chemistry", "Needs review"),
-                ("Probability", "Mathematics", "Confident"),
-                ("Thermodynamics", "Physics", "Weak area"),
-            ];
-            
-            for (i, (topic, subject, status)) in topics.iter().take(count as usize).enumerate() {
-                let status_color = match *status {
-                    "Well understood" | "Confident" => "green",
-                    "Need practice" | "Needs review" => "yellow",
-                    "Weak area" => "red",
-                    _ => "white",
+            for (i, topic) in topics.iter().enumerate() {
+                let label = topic.mastery_label();
+                let status_color = match label {
+                    "Confident" => "green",
+                    "Needs review" => "yellow",
+                    _ => "red",
                 };
+                let last_reviewed = topic.last_reviewed
+                    .map(|dt| dt.with_timezone(&Local).format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "never".to_string());
                 
-                println!("{}. {} ({}) - {}", 
+                println!("{}. {} (priority {}/5, last reviewed {}) - {}", 
                     (i + 1).to_string().bright_white(),
-                    topic.bold(),
-                    subject.italic(),
-                    status.color(status_color)
+                    topic.topic.bold(),
+                    topic.priority,
+                    last_reviewed.italic(),
+                    label.color(status_color)
                 );
             }
             
+            let ids: Vec<String> = topics.iter().map(|t| t.id.clone()).collect();
+            db.mark_topics_reviewed(&ids).await?;
+            
             println!();
             println!("{} Focus on the weak areas in your next study session.", "💡".yellow());
         }
+        
+        PrepCommand::Import { file } => {
+            let records: Vec<PrepSessionImport> = import::read_records(&file)?;
+            
+            let mut sessions = Vec::new();
+            let mut errors = Vec::new();
+            for (index, record) in records.into_iter().enumerate() {
+                match record.into_session() {
+                    Ok(session) => sessions.push(session),
+                    Err(e) => errors.push(format!("session {}: {}", index + 1, e)),
+                }
+            }
+            import::check_errors(errors)?;
+            
+            let count = db.import_prep_sessions(&sessions).await?;
+            println!("{} Imported {} preparation sessions from {}", "✓".green().bold(), count, file.display());
+        }
     }
     
     Ok(())
@@ -269,6 +661,7 @@ pub async fn execute(command: PrepCommand, _db: &Database) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use uuid::Uuid;
     
     #[test]
     fn test_session_status() {
@@ -276,4 +669,116 @@ mod tests {
         let status = SessionStatus::Active;
         assert!(matches!(status, SessionStatus::Active));
     }
+
+    #[tokio::test]
+    async fn test_import_rejects_unknown_field_without_inserting() {
+        let db = crate::db::test_database().await;
+        let file = std::env::temp_dir().join(format!("agentic-import-{}.yaml", Uuid::new_v4()));
+        std::fs::write(&file, "- exam_type: CET\n  session_name: Maths\n- exam_type: JEE\n  session_name: Physics\n  score: 90\n").unwrap();
+
+        let result = execute(PrepCommand::Import { file: file.clone() }, &db, &PrepConfig::default(), Output::Text).await;
+        let _ = std::fs::remove_file(&file);
+
+        assert!(result.unwrap_err().to_string().contains("score"));
+        assert_eq!(db.count_prep_sessions().await.unwrap(), 0);
+    }
+
+    #[test]
+    fn test_format_minutes() {
+        assert_eq!(format_minutes(45), "45m");
+        assert_eq!(format_minutes(83), "1h 23m");
+    }
+
+    #[tokio::test]
+    async fn test_stop_completes_active_session() {
+        let db = crate::db::test_database().await;
+        assert!(execute(PrepCommand::Stop { session_id: None }, &db, &PrepConfig::default(), Output::Text).await.is_err());
+
+        let mut session = PrepSession::new("CET".to_string(), "CET daily session".to_string(), 60);
+        session.created_at = Utc::now() - chrono::Duration::minutes(90);
+        db.add_prep_session(&session).await.unwrap();
+
+        execute(PrepCommand::Stop { session_id: Some(session.id[..8].to_string()) }, &db, &PrepConfig::default(), Output::Text).await.unwrap();
+
+        assert!(db.find_active_prep_session(None).await.unwrap().is_none());
+        assert!(execute(PrepCommand::Stop { session_id: None }, &db, &PrepConfig::default(), Output::Text).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stats_rejects_unknown_period() {
+        let db = crate::db::test_database().await;
+        let err = execute(PrepCommand::Stats { exam: None, period: "fortnight".to_string() }, &db, &PrepConfig::default(), Output::Text).await.unwrap_err();
+        assert!(err.to_string().contains("expected week, month or all"));
+    }
+
+    #[test]
+    fn test_stats_json_totals_every_exam() {
+        let stats = [
+            ExamPrepStats { exam_type: "CET".to_string(), sessions: 2, total_minutes: 90, longest_minutes: 60 },
+            ExamPrepStats { exam_type: "JEE".to_string(), sessions: 1, total_minutes: 30, longest_minutes: 30 },
+        ];
+        let json = stats_json("week", None, &stats);
+        assert_eq!(json["sessions"], 3);
+        assert_eq!(json["total_minutes"], 120);
+        assert_eq!(json["average_minutes"], 40);
+        assert_eq!(json["longest_minutes"], 60);
+        assert!(json["exam"].is_null());
+        assert_eq!(json["exams"][0]["exam_type"], "CET");
+        assert_eq!(json["exams"][0]["average_minutes"], 45);
+        assert_eq!(stats_json("all", Some("CET"), &[])["average_minutes"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_review_rotates_least_recently_reviewed_topics() {
+        let db = crate::db::test_database().await;
+        for topic in ["Limits", "Vectors", "Optics"] {
+            let add = PrepCommand::Add { topic: topic.to_string(), exam: "CET".to_string(), priority: 3 };
+            execute(add, &db, &PrepConfig::default(), Output::Text).await.unwrap();
+        }
+        assert!(PrepTopic::new("CET".to_string(), "Bad".to_string(), 9).is_err());
+
+        let first: Vec<String> = db.topics_for_review("cet", 2).await.unwrap().into_iter().map(|t| t.topic).collect();
+        execute(PrepCommand::Review { exam: "CET".to_string(), count: 2 }, &db, &PrepConfig::default(), Output::Text).await.unwrap();
+
+        // The topic skipped last time has never been reviewed, so it comes first now
+        let next = db.topics_for_review("CET", 3).await.unwrap();
+        assert!(next[0].last_reviewed.is_none());
+        assert!(!first.contains(&next[0].topic));
+        assert!(next[1].last_reviewed.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_focus_lock_blocks_distracting_commands() {
+        let db = crate::db::test_database().await;
+        let config = PrepConfig::default();
+        check_focus_lock(&db, &config, "xdg-open https://www.youtube.com/watch?v=1").await.unwrap();
+
+        let mut session = PrepSession::new("CET".to_string(), "CET daily session".to_string(), 60);
+        session.focus = true;
+        db.add_prep_session(&session).await.unwrap();
+
+        let err = check_focus_lock(&db, &config, "xdg-open https://www.youtube.com/watch?v=1").await.unwrap_err();
+        assert!(err.to_string().contains("study session"));
+        assert!(check_focus_lock(&db, &config, "/usr/bin/steam").await.is_err());
+        check_focus_lock(&db, &config, "cargo test").await.unwrap();
+        check_focus_lock(&db, &config, "curl https://docs.rs/x.com-client").await.unwrap();
+
+        let disabled = PrepConfig { focus_lock: false, ..PrepConfig::default() };
+        check_focus_lock(&db, &disabled, "steam").await.unwrap();
+    }
+
+    #[test]
+    fn test_pomodoro_schedule() {
+        use PomodoroPhase::*;
+        assert_eq!(pomodoro_schedule(60), vec![Work(25), Break(5), Work(25), Break(5)]);
+        assert_eq!(pomodoro_schedule(40), vec![Work(25), Break(5), Work(10)]);
+        assert_eq!(pomodoro_schedule(27), vec![Work(25), Break(2)]);
+        assert!(pomodoro_schedule(0).is_empty());
+
+        let phases = pomodoro_schedule(60);
+        assert_eq!(pomodoro_minutes_studied(&phases, 0, 10 * 60 + 59), 10);
+        assert_eq!(pomodoro_minutes_studied(&phases, 1, 4 * 60), 25, "a break isn't study time");
+        assert_eq!(pomodoro_minutes_studied(&phases, 2, 60), 26);
+        assert_eq!(pomodoro_minutes_studied(&phases, phases.len(), 0), 50);
+    }
 }
@@ -1,11 +1,13 @@
-use anyhow::Result;
-use chrono::Utc;
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate, Utc};
 use clap::Subcommand;
 use colored::*;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use std::path::PathBuf;
 
-use crate::db::Database;
+use crate::db::{Database, DEFAULT_LIST_LIMIT};
+use super::import;
+use super::output::{print_json, Output};
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum TaskCommand {
@@ -20,6 +22,12 @@ pub enum TaskCommand {
         /// Task priority (low, medium, high)
         #[arg(long, short, default_value = "medium")]
         priority: String,
+        /// Tags for the task
+        #[arg(long = "tag", short = 'g')]
+        tags: Vec<String>,
+        /// Due date (YYYY-MM-DD)
+        #[arg(long)]
+        due: Option<String>,
     },
     /// List tasks
     List {
@@ -32,12 +40,25 @@ pub enum TaskCommand {
         /// Filter by priority
         #[arg(long)]
         priority: Option<String>,
+        /// Maximum number of tasks to show (capped by `max_list_limit` in the config)
+        #[arg(long, default_value_t = DEFAULT_LIST_LIMIT)]
+        limit: usize,
     },
     /// Mark task as complete
     Complete {
         /// Task ID or partial title
         task_id: String,
     },
+    /// Start working on a task (mark it in progress)
+    Start {
+        /// Task ID or partial title
+        task_id: String,
+    },
+    /// Pause an in-progress task (move it back to todo)
+    Pause {
+        /// Task ID or partial title
+        task_id: String,
+    },
     /// Delete a task
     Delete {
         /// Task ID or partial title
@@ -55,6 +76,19 @@ pub enum TaskCommand {
         /// Task ID or partial title
         task_id: String,
     },
+    /// Show task statistics
+    Stats,
+    /// Show open tasks day by day for the coming days, with overdue and undated ones
+    Agenda {
+        /// Number of days to show, starting today (at most a year)
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+    },
+    /// Import tasks from a JSON or YAML file
+    Import {
+        /// Path to a .json/.yaml file containing a list of tasks
+        file: PathBuf,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,10 +98,31 @@ pub struct Task {
     pub description: Option<String>,
     pub priority: Priority,
     pub status: TaskStatus,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
     pub created_at: chrono::DateTime<Utc>,
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+/// A task record as accepted by `task import`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskImport {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub due_date: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Priority {
     Low,
@@ -129,13 +184,15 @@ impl std::str::FromStr for Priority {
 
 impl Task {
     pub fn new(title: String, description: Option<String>, priority: Priority) -> Self {
-        let now = Utc::now();
+        let now = crate::offline::now();
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: crate::offline::new_id(),
             title,
             description,
             priority,
             status: TaskStatus::Todo,
+            tags: Vec::new(),
+            due_date: None,
             created_at: now,
             updated_at: now,
         }
@@ -158,11 +215,107 @@ impl Task {
     }
 }
 
-pub async fn execute(command: TaskCommand, db: &Database) -> Result<()> {
+impl TaskImport {
+    /// Validate the record and build the task it describes
+    pub fn into_task(self) -> Result<Task> {
+        if self.title.trim().is_empty() {
+            return Err(anyhow!("title must not be empty"));
+        }
+        let priority = match &self.priority {
+            Some(p) => p.parse::<Priority>()?,
+            None => Priority::Medium,
+        };
+        let mut task = Task::new(self.title, self.description, priority);
+        if let Some(status) = &self.status {
+            task.status = status.parse::<TaskStatus>()?;
+        }
+        task.tags = self.tags;
+        task.due_date = self.due_date.as_deref().map(parse_due_date).transpose()?;
+        Ok(task)
+    }
+}
+
+/// The most days `task agenda` shows
+pub const MAX_AGENDA_DAYS: u32 = 366;
+
+/// Open tasks grouped by when they are due, relative to a given day
+#[derive(Debug, Default)]
+pub struct Agenda {
+    pub overdue: Vec<Task>,
+    /// Every day in the window, including the ones with nothing due
+    pub days: Vec<(NaiveDate, Vec<Task>)>,
+    /// Open tasks due after the last day shown
+    pub later: usize,
+    pub no_due_date: Vec<Task>,
+}
+
+impl Agenda {
+    /// Bucket the open tasks into the `days` days starting at `today`, between one and
+    /// `MAX_AGENDA_DAYS`; each section is sorted by priority, highest first
+    pub fn build(tasks: Vec<Task>, today: NaiveDate, days: u32) -> Self {
+        let days = days.clamp(1, MAX_AGENDA_DAYS);
+        let mut agenda = Agenda {
+            days: today.iter_days().take(days as usize).map(|day| (day, Vec::new())).collect(),
+            ..Default::default()
+        };
+        for task in tasks.into_iter().filter(|t| !matches!(t.status, TaskStatus::Complete)) {
+            match task.due_date {
+                None => agenda.no_due_date.push(task),
+                Some(due) if due < today => agenda.overdue.push(task),
+                Some(due) => match agenda.days.iter_mut().find(|(day, _)| *day == due) {
+                    Some((_, section)) => section.push(task),
+                    None => agenda.later += 1,
+                },
+            }
+        }
+        let by_priority = |a: &Task, b: &Task| b.priority.rank().cmp(&a.priority.rank());
+        agenda.overdue.sort_by(by_priority);
+        agenda.no_due_date.sort_by(by_priority);
+        for (_, section) in agenda.days.iter_mut() {
+            section.sort_by(by_priority);
+        }
+        agenda
+    }
+}
+
+impl Priority {
+    fn rank(&self) -> u8 {
+        match self {
+            Priority::Low => 0,
+            Priority::Medium => 1,
+            Priority::High => 2,
+        }
+    }
+}
+
+fn print_agenda_task(task: &Task, show_due: bool) {
+    let due = match task.due_date {
+        Some(due) if show_due => format!(" due {}", due.format("%Y-%m-%d")).red().to_string(),
+        _ => String::new(),
+    };
+    println!("   {} {} {}{} [{}]",
+        task.status_icon(),
+        format!("{:<4}", task.priority.to_string()).color(task.priority_color()),
+        task.title.bold(),
+        due,
+        &task.id[..8].bright_black()
+    );
+}
+
+/// Parse a `YYYY-MM-DD` due date given on the command line
+pub fn parse_due_date(s: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid due date '{}', expected YYYY-MM-DD", s))
+}
+
+/// Run a task subcommand; with `Output::Json`, `list` prints the tasks as JSON instead
+pub async fn execute(command: TaskCommand, db: &Database, output: Output) -> Result<()> {
     match command {
-        TaskCommand::Add { title, description, priority } => {
+        TaskCommand::Add { title, description, priority, tags, due } => {
             let priority = priority.parse::<Priority>()?;
-            let task = Task::new(title, description, priority);
+            let mut task = Task::new(title, description, priority);
+            task.tags = tags;
+            task.due_date = due.as_deref().map(parse_due_date).transpose()?;
             db.add_task(&task).await?;
             println!("{}", "✓ Task created successfully!".green().bold());
             println!("ID: {}", task.id.bright_blue());
@@ -172,9 +325,18 @@ pub async fn execute(command: TaskCommand, db: &Database) -> Result<()> {
             }
             println!("Priority: {}", format!("{}", task.priority).color(task.priority_color()));
             println!("Status: {}", task.status);
+            if !task.tags.is_empty() {
+                println!("Tags: {}", task.tags.join(", ").yellow());
+            }
+            if let Some(due) = task.due_date {
+                println!("Due: {}", due.format("%Y-%m-%d"));
+            }
         }
-        TaskCommand::List { .. } => {
-            let tasks = db.list_tasks().await?;
+        TaskCommand::List { limit, .. } => {
+            let tasks = db.list_tasks(limit).await?;
+            if output.is_json() {
+                return print_json(&tasks);
+            }
             println!("{}", "📋 Your Tasks".blue().bold());
             for (index, task) in tasks.iter().enumerate() {
                 println!("{}. {} {} {} [{}]", 
@@ -194,6 +356,31 @@ pub async fn execute(command: TaskCommand, db: &Database) -> Result<()> {
             db.complete_task(&task_id).await?;
             println!("{} Task '{}' marked as complete!", "✓".green().bold(), task_id.bold());
         }
+        TaskCommand::Start { task_id } => {
+            let task = db.find_task(&task_id).await?
+                .ok_or_else(|| anyhow!("No task found matching '{}'", task_id))?;
+            match task.status {
+                TaskStatus::Complete => {
+                    return Err(anyhow!("Task '{}' is already complete and can't be started", task.title));
+                }
+                TaskStatus::InProgress => {
+                    println!("{} Task '{}' is already in progress", "◐".yellow(), task.title.bold());
+                }
+                TaskStatus::Todo => {
+                    db.set_task_status(&task.id, TaskStatus::InProgress).await?;
+                    println!("{} Started task '{}'", "◐".yellow().bold(), task.title.bold());
+                }
+            }
+        }
+        TaskCommand::Pause { task_id } => {
+            let task = db.find_task(&task_id).await?
+                .ok_or_else(|| anyhow!("No task found matching '{}'", task_id))?;
+            if !matches!(task.status, TaskStatus::InProgress) {
+                return Err(anyhow!("Task '{}' is not in progress (status: {})", task.title, task.status));
+            }
+            db.set_task_status(&task.id, TaskStatus::Todo).await?;
+            println!("{} Paused task '{}'", "○".bright_white().bold(), task.title.bold());
+        }
         TaskCommand::Delete { task_id } => {
             db.delete_task(&task_id).await?;
             println!("{} Task '{}' deleted!", "🗑".red(), task_id.bold());
@@ -211,9 +398,108 @@ pub async fn execute(command: TaskCommand, db: &Database) -> Result<()> {
             );
         }
         TaskCommand::Show { task_id } => {
+            let task = db.find_task(&task_id).await?
+                .ok_or_else(|| anyhow!("No task found matching '{}'", task_id))?;
+
             println!("{} Task Details", "🔍".blue());
-            println!("Searching for task: {}", task_id.bold());
-            // You can implement a db.get_task_by_id here for full details
+            println!("ID: {}", task.id.bright_blue());
+            println!("Title: {}", task.title.bold());
+            if let Some(desc) = &task.description {
+                println!("Description: {}", desc);
+            }
+            println!("Priority: {}", format!("{}", task.priority).color(task.priority_color()));
+            println!("Status: {} {}", task.status_icon(), task.status);
+            if !task.tags.is_empty() {
+                println!("Tags: {}", task.tags.join(", ").yellow());
+            }
+            if let Some(due) = task.due_date {
+                println!("Due: {}", due.format("%Y-%m-%d"));
+            }
+            println!("Created: {}", task.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"));
+            println!("Updated: {}", task.updated_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"));
+        }
+        TaskCommand::Stats => {
+            let stats = db.task_stats().await?;
+            println!("{}", "📊 Task Statistics".blue().bold());
+            println!();
+            
+            println!("{}", "By Status".bright_white().bold());
+            println!("○ Todo: {}", stats.todo.to_string().bright_white());
+            println!("◐ In Progress: {}", stats.in_progress.to_string().yellow());
+            println!("● Complete: {}", stats.complete.to_string().green());
+            println!();
+            
+            println!("{}", "By Priority".bright_white().bold());
+            println!("{}: {}", "HIGH".red(), stats.high_priority);
+            println!("{}: {}", "MED".yellow(), stats.medium_priority);
+            println!("{}: {}", "LOW".green(), stats.low_priority);
+            println!();
+            
+            println!("Total Tasks: {}", stats.total.to_string().bold());
+            println!("Completion Rate: {}", format!("{:.1}%", stats.completion_rate()).green().bold());
+            println!("Completed This Week: {}", stats.completed_this_week.to_string().bright_white());
+            match stats.average_open_age_days {
+                Some(days) => println!("Average Open Task Age: {}", format!("{:.1} days", days).bright_white()),
+                None => println!("Average Open Task Age: {}", "no open tasks".bright_black()),
+            }
+        }
+        TaskCommand::Agenda { days } => {
+            let today = crate::offline::now().with_timezone(&Local).date_naive();
+            let end = today + chrono::Duration::days(days.clamp(1, MAX_AGENDA_DAYS) as i64);
+            let (tasks, later) = db.open_tasks_due_before(end).await?;
+            let mut agenda = Agenda::build(tasks, today, days);
+            agenda.later += later;
+            println!("{}", format!("📅 Agenda for the next {} days", agenda.days.len()).blue().bold());
+            
+            if !agenda.overdue.is_empty() {
+                println!();
+                println!("{}", format!("Overdue ({})", agenda.overdue.len()).red().bold());
+                for task in &agenda.overdue {
+                    print_agenda_task(task, true);
+                }
+            }
+            for (day, tasks) in &agenda.days {
+                println!();
+                let header = day.format("%a %b %d").to_string();
+                if *day == today {
+                    println!("{} {}", header.bright_white().bold(), "(today)".cyan());
+                } else {
+                    println!("{}", header.bright_white().bold());
+                }
+                if tasks.is_empty() {
+                    println!("   {}", "·".bright_black());
+                }
+                for task in tasks {
+                    print_agenda_task(task, false);
+                }
+            }
+            if agenda.later > 0 {
+                println!();
+                println!("{}", format!("+ {} more due later", agenda.later).bright_black());
+            }
+            if !agenda.no_due_date.is_empty() {
+                println!();
+                println!("{}", format!("No due date ({})", agenda.no_due_date.len()).bright_white().bold());
+                for task in &agenda.no_due_date {
+                    print_agenda_task(task, false);
+                }
+            }
+        }
+        TaskCommand::Import { file } => {
+            let records: Vec<TaskImport> = import::read_records(&file)?;
+            
+            let mut tasks = Vec::new();
+            let mut errors = Vec::new();
+            for (index, record) in records.into_iter().enumerate() {
+                match record.into_task() {
+                    Ok(task) => tasks.push(task),
+                    Err(e) => errors.push(format!("task {}: {}", index + 1, e)),
+                }
+            }
+            import::check_errors(errors)?;
+            
+            let count = db.import_tasks(&tasks).await?;
+            println!("{} Imported {} tasks from {}", "✓".green().bold(), count, file.display());
         }
     }
     Ok(())
@@ -222,6 +508,7 @@ pub async fn execute(command: TaskCommand, db: &Database) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use uuid::Uuid;
     
     #[test]
     fn test_priority_parsing() {
@@ -243,5 +530,109 @@ mod tests {
         assert_eq!(task.description, Some("Test description".to_string()));
         assert!(matches!(task.priority, Priority::High));
         assert!(matches!(task.status, TaskStatus::Todo));
+        assert!(task.tags.is_empty());
+        assert!(task.due_date.is_none());
+    }
+
+    #[test]
+    fn test_agenda_buckets_tasks_by_due_day() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let task = |title: &str, priority: Priority, due: Option<&str>| {
+            let mut task = Task::new(title.to_string(), None, priority);
+            task.due_date = due.map(|d| parse_due_date(d).unwrap());
+            task
+        };
+        let mut done = task("Already done", Priority::High, Some("2024-03-10"));
+        done.status = TaskStatus::Complete;
+        let tasks = vec![
+            task("Late report", Priority::Low, Some("2024-03-08")),
+            task("Low today", Priority::Low, Some("2024-03-10")),
+            task("High today", Priority::High, Some("2024-03-10")),
+            task("Day after tomorrow", Priority::Medium, Some("2024-03-12")),
+            task("Next month", Priority::High, Some("2024-04-01")),
+            task("Someday", Priority::Medium, None),
+            done,
+        ];
+
+        let agenda = Agenda::build(tasks, today, 3);
+        let titles = |tasks: &[Task]| tasks.iter().map(|t| t.title.clone()).collect::<Vec<_>>();
+
+        assert_eq!(titles(&agenda.overdue), ["Late report"]);
+        assert_eq!(agenda.days.len(), 3);
+        assert_eq!(agenda.days[0].0, today);
+        assert_eq!(titles(&agenda.days[0].1), ["High today", "Low today"]);
+        assert!(agenda.days[1].1.is_empty());
+        assert_eq!(titles(&agenda.days[2].1), ["Day after tomorrow"]);
+        assert_eq!(agenda.later, 1);
+        assert_eq!(titles(&agenda.no_due_date), ["Someday"]);
+        assert_eq!(Agenda::build(Vec::new(), today, u32::MAX).days.len(), MAX_AGENDA_DAYS as usize);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_unknown_field_without_inserting() {
+        let db = crate::db::test_database().await;
+        let file = std::env::temp_dir().join(format!("agentic-import-{}.json", Uuid::new_v4()));
+        std::fs::write(&file, r#"[
+            {"title": "Valid task", "priority": "high"},
+            {"title": "Bad task", "urgency": "now"}
+        ]"#).unwrap();
+
+        let result = execute(TaskCommand::Import { file: file.clone() }, &db, Output::Text).await;
+        let _ = std::fs::remove_file(&file);
+
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("urgency"));
+        assert!(db.list_tasks(DEFAULT_LIST_LIMIT).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_invalid_values_without_inserting() {
+        let db = crate::db::test_database().await;
+        let file = std::env::temp_dir().join(format!("agentic-import-{}.yaml", Uuid::new_v4()));
+        std::fs::write(&file, "- title: First\n- title: Second\n  priority: urgent\n").unwrap();
+
+        let result = execute(TaskCommand::Import { file: file.clone() }, &db, Output::Text).await;
+        let _ = std::fs::remove_file(&file);
+
+        assert!(result.unwrap_err().to_string().contains("task 2"));
+        assert!(db.list_tasks(DEFAULT_LIST_LIMIT).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_start_and_pause_transitions() {
+        let db = crate::db::test_database().await;
+        let task = Task::new("Write report".to_string(), None, Priority::Medium);
+        db.add_task(&task).await.unwrap();
+
+        execute(TaskCommand::Start { task_id: task.id.clone() }, &db, Output::Text).await.unwrap();
+        let started = db.find_task(&task.id).await.unwrap().unwrap();
+        assert!(matches!(started.status, TaskStatus::InProgress));
+        assert_eq!(started.status_icon(), "◐");
+
+        execute(TaskCommand::Pause { task_id: task.id.clone() }, &db, Output::Text).await.unwrap();
+        let paused = db.find_task(&task.id).await.unwrap().unwrap();
+        assert!(matches!(paused.status, TaskStatus::Todo));
+        assert!(execute(TaskCommand::Pause { task_id: task.id.clone() }, &db, Output::Text).await.is_err());
+
+        db.complete_task(&task.id).await.unwrap();
+        assert!(execute(TaskCommand::Start { task_id: task.id.clone() }, &db, Output::Text).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_limit_keeps_the_newest_tasks() {
+        let db = crate::db::test_database().await;
+        for (title, days_ago) in [("oldest", 3), ("newest", 0), ("middle", 1)] {
+            let mut task = Task::new(title.to_string(), None, Priority::Low);
+            task.created_at -= chrono::Duration::days(days_ago);
+            db.add_task(&task).await.unwrap();
+        }
+        let titles: Vec<String> = db.list_tasks(2).await.unwrap().into_iter().map(|task| task.title).collect();
+        assert_eq!(titles, ["newest", "middle"]);
+    }
+
+    #[test]
+    fn test_due_date_parsing() {
+        assert_eq!(parse_due_date("2024-05-01").unwrap(), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap());
+        assert!(parse_due_date("05/01/2024").is_err());
     }
 }
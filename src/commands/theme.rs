@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use colored::*;
+use ratatui::style::Color;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::themes::theme_manager::{Theme, ThemeManager};
+use crate::ui::styles::AppTheme;
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ThemeCommand {
+    /// List the themes in ./themes and ~/.agentic/themes
+    List,
+    /// Use a theme in the TUI and save the choice to the config file
+    Set {
+        /// Theme name, e.g. `Dracula`
+        name: String,
+    },
+    /// Show a sample of a theme's colors
+    Preview {
+        /// Theme name, e.g. `Dracula`
+        name: String,
+    },
+}
+
+/// Run a theme subcommand and return its output for display
+pub async fn execute(command: ThemeCommand, config: &mut Config) -> Result<String> {
+    let mut manager = ThemeManager::new();
+    manager.load_themes()?;
+
+    match command {
+        ThemeCommand::List => Ok(render_list(manager.list_themes(), config.theme.name.as_deref())),
+        ThemeCommand::Set { name } => {
+            let theme = find(&manager, &name)?;
+            let path = Config::config_path();
+            persist_theme(config, &path, &theme.name).await?;
+            Ok(format!("🎨 Now using the {} theme, saved in {}", theme.name, path.display()))
+        }
+        ThemeCommand::Preview { name } => {
+            let theme = find(&manager, &name)?;
+            Ok(render_preview(theme, &AppTheme::from_theme(theme)?))
+        }
+    }
+}
+
+/// A theme by name, checking its colors parse so a broken file is never saved as the choice
+pub fn find<'a>(manager: &'a ThemeManager, name: &str) -> Result<&'a Theme> {
+    let theme = manager.find_theme(name)
+        .ok_or_else(|| anyhow!("Theme '{}' not found; `agentic theme list` shows the available ones", name))?;
+    AppTheme::from_theme(theme).map_err(|e| anyhow!("Theme '{}' has an invalid color: {}", theme.name, e))?;
+    Ok(theme)
+}
+
+/// Make `name` the TUI theme and write the config back to `path`
+pub async fn persist_theme(config: &mut Config, path: &Path, name: &str) -> Result<()> {
+    config.theme.name = Some(name.to_string());
+    config.save_to(path).await
+}
+
+/// Themes sorted by name with their descriptions; `current` is marked
+pub fn render_list(mut themes: Vec<&Theme>, current: Option<&str>) -> String {
+    if themes.is_empty() {
+        return "🎨 Themes\nNo themes found in ./themes or ~/.agentic/themes".to_string();
+    }
+    themes.sort_by_key(|theme| theme.name.to_lowercase());
+    let mut output = format!("🎨 Themes ({})\n", themes.len());
+    for theme in themes {
+        let marker = if current.is_some_and(|name| name.eq_ignore_ascii_case(&theme.name)) { "*" } else { " " };
+        output.push_str(&format!("{} {}", marker, theme.name));
+        if let Some(description) = &theme.description {
+            output.push_str(&format!(" - {}", description));
+        }
+        if !theme.tags.is_empty() {
+            output.push_str(&format!(" [{}]", theme.tags.join(", ")));
+        }
+        output.push('\n');
+    }
+    output.trim_end().to_string()
+}
+
+fn paint(text: &str, color: Color) -> ColoredString {
+    match color {
+        Color::Rgb(r, g, b) => text.truecolor(r, g, b),
+        _ => text.normal(),
+    }
+}
+
+fn on(text: ColoredString, color: Color) -> ColoredString {
+    match color {
+        Color::Rgb(r, g, b) => text.on_truecolor(r, g, b),
+        _ => text,
+    }
+}
+
+/// A few lines in the theme's colors, laid out like the TUI's command history
+pub fn render_preview(theme: &Theme, colors: &AppTheme) -> String {
+    let text = |text: &str, color: Color| on(paint(text, color), colors.background_color);
+    let line = |parts: Vec<ColoredString>| parts.iter().map(ToString::to_string).collect::<String>();
+    let swatches: Vec<String> = [
+        ("primary", colors.primary_color),
+        ("accent", colors.accent_color),
+        ("success", colors.success_color),
+        ("error", colors.error_color),
+        ("warning", colors.warning_color),
+        ("info", colors.info_color),
+    ]
+    .into_iter()
+    .map(|(name, color)| format!("{} {}", paint("██", color), name))
+    .collect();
+
+    [
+        format!("🎨 {}", theme.name),
+        swatches.join("  "),
+        String::new(),
+        line(vec![on(paint(" NORMAL ", colors.text_color).bold(), colors.primary_color), text(" | Ctrl+Q quit ", colors.secondary_color)]),
+        line(vec![text("  ✅ ", colors.success_color), text("cargo build --release", colors.text_color).bold()]),
+        line(vec![text("     12:00:01 | 2.4s", colors.secondary_color)]),
+        line(vec![text("     Finished release target(s)", colors.accent_color)]),
+        line(vec![text("  ❌ ", colors.error_color), text("cargo test", colors.text_color).bold()]),
+        line(vec![text("     warning: unused variable", colors.warning_color)]),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_saves_the_theme_and_list_marks_it() {
+        let mut manager = ThemeManager::new();
+        manager.load_themes().unwrap();
+        let theme = find(&manager, "dracula").unwrap();
+        assert_eq!(theme.name, "Dracula");
+        assert!(find(&manager, "No Such Theme").is_err());
+
+        let dir = std::env::temp_dir().join(format!("agentic-theme-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.toml");
+        let mut config = Config::default();
+        persist_theme(&mut config, &path, &theme.name).await.unwrap();
+        let (saved, _) = Config::load_and_migrate(&path).await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(saved.theme.name.as_deref(), Some("Dracula"));
+
+        let list = render_list(manager.list_themes(), saved.theme.name.as_deref());
+        assert!(list.contains("* Dracula"), "{}", list);
+        assert!(list.contains("  Nord - An arctic"), "{}", list);
+        assert!(render_preview(theme, &AppTheme::from_theme(theme).unwrap()).contains("cargo build"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::ollama::client::OllamaClient;
+use crate::warp::shell_runner;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Short hash of the commit this binary was built from, or "unknown"
+pub const GIT_COMMIT: &str = env!("AGENTIC_GIT_COMMIT");
+pub const RUSTC_VERSION: &str = env!("AGENTIC_RUSTC_VERSION");
+pub const TARGET: &str = env!("AGENTIC_TARGET");
+
+/// What `agentic --version` prints
+pub const LONG_VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"), " (", env!("AGENTIC_GIT_COMMIT"), ")\n",
+    env!("AGENTIC_RUSTC_VERSION"), "\n",
+    env!("AGENTIC_TARGET"),
+);
+
+/// Build and environment details worth attaching to a bug report
+#[derive(Debug, Clone)]
+pub struct VersionInfo {
+    /// The shell commands are run with
+    pub shell: &'static str,
+    /// The user's login shell from `$SHELL`/`%ComSpec%`
+    pub login_shell: Option<String>,
+    pub ollama_url: String,
+    pub ollama_reachable: bool,
+    pub config_path: PathBuf,
+    pub database_path: PathBuf,
+}
+
+impl VersionInfo {
+    /// Gather the environment for the config file at `config_path`, falling back to defaults if it can't be read
+    pub async fn gather(config_path: &Path) -> Self {
+        let config = std::fs::read_to_string(config_path)
+            .ok()
+            .and_then(|content| Config::parse_and_migrate(&content).ok())
+            .map(|(config, _)| config)
+            .unwrap_or_default();
+        let ollama_config = crate::agent::ollama_config(&config);
+        let ollama_url = ollama_config.base_url.clone();
+        let ollama_reachable = match OllamaClient::new(ollama_config) {
+            Ok(client) => client.list_models().await.is_ok(),
+            Err(_) => false,
+        };
+
+        Self {
+            shell: shell_runner::shell_program(),
+            login_shell: std::env::var("SHELL").or_else(|_| std::env::var("ComSpec")).ok(),
+            ollama_url,
+            ollama_reachable,
+            config_path: config_path.to_path_buf(),
+            database_path: config.database_path,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let shell = match &self.login_shell {
+            Some(login) => format!("{} (login shell {})", self.shell, login),
+            None => self.shell.to_string(),
+        };
+        let ollama = if self.ollama_reachable {
+            format!("reachable at {}", self.ollama_url)
+        } else {
+            format!("not reachable at {}", self.ollama_url)
+        };
+        let rows = [
+            ("Commit", GIT_COMMIT.to_string()),
+            ("Rustc", RUSTC_VERSION.to_string()),
+            ("Target", TARGET.to_string()),
+            ("Shell", shell),
+            ("Ollama", ollama),
+            ("Config", self.config_path.display().to_string()),
+            ("Database", format!("{} (schema version {})", self.database_path.display(), crate::db::SCHEMA_VERSION)),
+        ];
+
+        let mut report = format!("agentic {}\n", VERSION);
+        for (label, value) in rows {
+            report.push_str(&format!("{:<9} {}\n", label, value));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_version_includes_crate_version_and_config_path() {
+        let dir = std::env::temp_dir().join(format!("agentic-version-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config.toml");
+        let config = Config { database_path: dir.join("custom.db"), ..Config::default() };
+        std::fs::write(&config_path, toml::to_string_pretty(&config).unwrap()).unwrap();
+
+        let output = VersionInfo::gather(&config_path).await.render();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(output.starts_with(&format!("agentic {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(output.contains(&format!("Config    {}", config_path.display())));
+        assert!(output.contains(&format!("Database  {} (schema version", dir.join("custom.db").display())));
+        assert!(LONG_VERSION.starts_with(VERSION));
+    }
+}
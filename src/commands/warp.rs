@@ -0,0 +1,83 @@
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use std::path::Path;
+
+use super::output::Output;
+use crate::warp::pipeline::PipelineStats;
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum WarpCommand {
+    /// Show how warp runs have gone: totals, success rate, average duration and the most common commands
+    Stats,
+}
+
+/// Run a warp subcommand against the stats in `stats_path` and return its output for display;
+/// with `Output::Json`, the stats as JSON
+pub fn execute(command: WarpCommand, stats_path: &Path, output: Output) -> Result<String> {
+    match command {
+        WarpCommand::Stats => {
+            let stats = PipelineStats::load(stats_path)?;
+            if output.is_json() {
+                return Ok(serde_json::to_string_pretty(&serde_json::json!({
+                    "total_executions": stats.total_executions,
+                    "successful_executions": stats.successful_executions,
+                    "failed_executions": stats.failed_executions,
+                    "cancelled_executions": stats.cancelled_executions,
+                    "success_rate": stats.success_rate(),
+                    "average_duration_ms": stats.average_duration.as_millis() as u64,
+                    "most_common_commands": stats.most_common_commands,
+                }))?);
+            }
+            Ok(render_stats(&stats))
+        }
+    }
+}
+
+pub fn render_stats(stats: &PipelineStats) -> String {
+    if stats.total_executions == 0 {
+        return "📊 No warp runs recorded yet; try `agentic warp \"show disk usage\"`".to_string();
+    }
+    let mut output = format!("📊 {}\n", "Warp stats".bold());
+    output.push_str(&format!(
+        "  Runs        {} ({} ✓, {} ✗, {} ⏹)\n",
+        stats.total_executions, stats.successful_executions, stats.failed_executions, stats.cancelled_executions
+    ));
+    output.push_str(&format!("  Success     {:.1}%\n", stats.success_rate()));
+    output.push_str(&format!("  Average     {:.2}s\n", stats.average_duration.as_secs_f64()));
+    if !stats.most_common_commands.is_empty() {
+        output.push_str("  Most common\n");
+        for (command, count) in &stats.most_common_commands {
+            output.push_str(&format!("    {:>4}× {}\n", count, command.yellow()));
+        }
+    }
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_render_as_text_and_json() {
+        let path = std::env::temp_dir().join(format!("agentic-warp-stats-{}.json", uuid::Uuid::new_v4()));
+        assert!(execute(WarpCommand::Stats, &path, Output::Text).unwrap().contains("No warp runs"));
+
+        let mut stats = PipelineStats::new();
+        stats.total_executions = 4;
+        stats.successful_executions = 3;
+        stats.failed_executions = 1;
+        stats.average_duration = std::time::Duration::from_millis(1500);
+        stats.most_common_commands = vec![("ls -la".to_string(), 3)];
+        stats.save(&path).unwrap();
+
+        let text = execute(WarpCommand::Stats, &path, Output::Text).unwrap();
+        assert!(text.contains("Runs        4 (3 ✓, 1 ✗, 0 ⏹)"), "{}", text);
+        assert!(text.contains("75.0%") && text.contains("1.50s") && text.contains("3× "), "{}", text);
+
+        let json: serde_json::Value = serde_json::from_str(&execute(WarpCommand::Stats, &path, Output::Json).unwrap()).unwrap();
+        assert_eq!(json["success_rate"], 75.0);
+        assert_eq!(json["most_common_commands"][0][0], "ls -la");
+        let _ = std::fs::remove_file(&path);
+    }
+}
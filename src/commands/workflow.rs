@@ -0,0 +1,293 @@
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use colored::*;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+
+use super::args::parse_key_values;
+use crate::offline;
+use crate::warp::config::AgenticConfig;
+use crate::warp::read_answer;
+use crate::warp::shell_runner::ShellRunner;
+use crate::workflows::workflow_manager::{
+    import_workflows, user_workflow_dir, ImportReport, Workflow, WorkflowArgument, WorkflowManager,
+};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum WorkflowCommand {
+    /// List the available workflows
+    List,
+    /// Find workflows whose id, name, description or tags match
+    Search {
+        query: String,
+    },
+    /// Show a workflow's command and arguments
+    Show {
+        /// Workflow id, e.g. `git/clone_with_ssh`
+        id: String,
+    },
+    /// Fill in a workflow's arguments and run the resulting command
+    Run {
+        /// Workflow id, e.g. `git/clone_with_ssh`
+        id: String,
+        /// Argument value; may be repeated. Arguments left out use their defaults.
+        #[arg(long = "arg", value_name = "KEY=VALUE")]
+        args: Vec<String>,
+    },
+    /// Copy workflow YAML files (e.g. Warp's workflow repository) into ~/.agentic/workflows
+    Import {
+        /// Directory or git URL to import from
+        #[arg(long)]
+        from: String,
+    },
+}
+
+/// Run a workflow subcommand and return its output for display. With `interactive`, required
+/// arguments left off the command line are asked for, and a workflow's output is printed as it
+/// runs rather than returned.
+pub async fn execute(command: WorkflowCommand, interactive: bool) -> Result<String> {
+    let mut manager = WorkflowManager::new();
+    manager.load_workflows()?;
+
+    match command {
+        WorkflowCommand::List => Ok(render_list("📚 Workflows", manager.list_workflows(), manager.current_shell())),
+        WorkflowCommand::Search { query } => {
+            let title = format!("🔍 Workflows matching '{}'", query);
+            Ok(render_list(&title, manager.search_workflows(&query), manager.current_shell()))
+        }
+        WorkflowCommand::Show { id } => {
+            let workflow = manager.get_workflow(&id).ok_or_else(|| anyhow!("Workflow '{}' not found", id))?;
+            Ok(render_workflow(&id, workflow))
+        }
+        WorkflowCommand::Run { id, args } => {
+            let mut args = parse_key_values(&args)?;
+            if interactive {
+                let workflow = manager.get_workflow(&id).ok_or_else(|| anyhow!("Workflow '{}' not found", id))?;
+                prompt_for_args(workflow, &mut args, |arg| {
+                    println!("{} {}: ", "❓".yellow(), describe_arg(arg, workflow.requires(arg)));
+                    read_answer()
+                }).await;
+            }
+            let missing = manager.missing_args(&id, &args)?;
+            if !missing.is_empty() {
+                return Err(anyhow!(render_missing(&id, &missing)));
+            }
+            let agentic = AgenticConfig::load().await?;
+            let runner = ShellRunner::configured(interactive, &agentic)?;
+            // The shells a workflow supports are checked against the one it will run in
+            manager.set_current_shell(runner.shell());
+            let command = manager.execute_workflow(&id, args)?;
+
+            if interactive {
+                println!("{} {}", "▶".blue(), command.yellow());
+            }
+            let output = super::raw_command_output(runner.execute_safely(&command).await?)?;
+            Ok(if interactive { String::new() } else { format!("$ {}\n{}", command, output) })
+        }
+        WorkflowCommand::Import { from } => {
+            let dest = user_workflow_dir().ok_or_else(|| anyhow!("Could not determine the home directory"))?;
+            let report = if is_git_url(&from) {
+                import_from_git(&from, &dest).await?
+            } else {
+                import_workflows(Path::new(&from), &dest)?
+            };
+            manager.reload_workflows()?;
+            Ok(render_import(&report, &dest, manager.list_workflows().len()))
+        }
+    }
+}
+
+/// Whether `from` names a repository to clone rather than a local directory
+pub fn is_git_url(from: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "git@"].iter().any(|prefix| from.starts_with(prefix))
+        || (from.ends_with(".git") && !Path::new(from).exists())
+}
+
+/// Shallow-clone `url` into a scratch directory, import from it, and remove the clone
+async fn import_from_git(url: &str, dest: &Path) -> Result<ImportReport> {
+    offline::check_network()?;
+    let checkout = std::env::temp_dir().join(format!("agentic-workflows-{}", offline::new_id()));
+    let status = tokio::process::Command::new("git")
+        .args(["clone", "--depth", "1", "--quiet", url])
+        .arg(&checkout)
+        .status()
+        .await
+        .map_err(|e| anyhow!("couldn't run `git clone {}`: {}", url, e))?;
+    let report = if status.success() {
+        import_workflows(&checkout, dest)
+    } else {
+        Err(anyhow!("`git clone {}` exited with {}", url, status))
+    };
+    let _ = std::fs::remove_dir_all(&checkout);
+    report
+}
+
+/// What an import copied, which files failed to parse and why, and how many workflows there are now
+pub fn render_import(report: &ImportReport, dest: &Path, available: usize) -> String {
+    let mut output = format!("📥 Imported {} workflow(s) into {}\n", report.imported.len(), dest.display());
+    if !report.failed.is_empty() {
+        output.push_str(&format!("✗ {} file(s) failed to parse:\n", report.failed.len()));
+        for (path, error) in &report.failed {
+            output.push_str(&format!("  {}: {}\n", path.display(), error));
+        }
+    }
+    output.push_str(&format!("{} workflow(s) available", available));
+    output
+}
+
+/// Ask for each argument the workflow requires that is missing from `args`, in the workflow's order. An empty answer
+/// keeps the default, or leaves the argument unset when there is none; no answer at all stops
+/// asking.
+pub async fn prompt_for_args<F, Fut>(workflow: &Workflow, args: &mut HashMap<String, String>, mut ask: F)
+where
+    F: FnMut(&WorkflowArgument) -> Fut,
+    Fut: Future<Output = Option<String>>,
+{
+    let unset: Vec<&WorkflowArgument> = workflow.arguments.iter()
+        .filter(|arg| workflow.requires(arg) && !args.contains_key(&arg.name))
+        .collect();
+    for arg in unset {
+        let Some(answer) = ask(arg).await else {
+            break;
+        };
+        let answer = answer.trim();
+        if !answer.is_empty() {
+            args.insert(arg.name.clone(), answer.to_string());
+        } else if let Some(default) = &arg.default_value {
+            args.insert(arg.name.clone(), default.clone());
+        }
+    }
+}
+
+/// Workflows sorted by id, one per line with the description and the shells they run in; those
+/// that can't run in `shell` are marked
+pub fn render_list(title: &str, mut workflows: Vec<(&String, &Workflow)>, shell: &str) -> String {
+    if workflows.is_empty() {
+        return format!("{}\nNo workflows found", title);
+    }
+    workflows.sort_by(|a, b| a.0.cmp(b.0));
+    let mut output = format!("{} ({})\n", title, workflows.len());
+    for (id, workflow) in workflows {
+        let shells = if workflow.shells.is_empty() { "any shell".to_string() } else { workflow.shells.join(", ") };
+        output.push_str(&format!("  {} - {} [{}]", id, workflow.description, shells));
+        if !workflow.supports_shell(shell) {
+            output.push_str(&format!(" (not for {})", shell));
+        }
+        output.push('\n');
+    }
+    output.trim_end().to_string()
+}
+
+/// Everything needed to run a workflow: its command and what each argument means
+pub fn render_workflow(id: &str, workflow: &Workflow) -> String {
+    let mut output = format!("📄 {} ({})\n{}\n", workflow.name, id, workflow.description);
+    if !workflow.tags.is_empty() {
+        output.push_str(&format!("Tags: {}\n", workflow.tags.join(", ")));
+    }
+    if !workflow.shells.is_empty() {
+        output.push_str(&format!("Shells: {}\n", workflow.shells.join(", ")));
+    }
+    output.push_str(&format!("\nCommand:\n{}\n", workflow.command));
+    if !workflow.arguments.is_empty() {
+        output.push_str("\nArguments:\n");
+        for arg in &workflow.arguments {
+            output.push_str(&format!("  {}\n", describe_arg(arg, workflow.requires(arg))));
+        }
+    }
+    if let Some(author) = &workflow.author {
+        output.push_str(&format!("\nAuthor: {}\n", author));
+    }
+    output.trim_end().to_string()
+}
+
+/// Why a run can't start, listing each argument still needed and how to pass it
+pub fn render_missing(id: &str, missing: &[&WorkflowArgument]) -> String {
+    let mut output = format!("Workflow '{}' needs these arguments:\n", id);
+    for arg in missing {
+        output.push_str(&format!("  {}\n", describe_arg(arg, true)));
+    }
+    let example: Vec<String> = missing.iter().map(|arg| format!("--arg {}=...", arg.name)).collect();
+    output.push_str(&format!("Pass them with: agentic workflow run {} {}", id, example.join(" ")));
+    output
+}
+
+fn describe_arg(arg: &WorkflowArgument, required: bool) -> String {
+    let mut line = format!("{} - {}", arg.name, arg.description);
+    match &arg.default_value {
+        Some(default) => line.push_str(&format!(" (default: {})", default)),
+        None if required => line.push_str(" (required)"),
+        None => {}
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argument(name: &str, default_value: Option<&str>, required: bool) -> WorkflowArgument {
+        WorkflowArgument {
+            name: name.to_string(),
+            description: format!("The {}", name),
+            default_value: default_value.map(str::to_string),
+            required,
+        }
+    }
+
+    #[test]
+    fn test_list_shows_each_workflows_shells() {
+        let workflow = |description: &str, shells: &[&str]| Workflow {
+            name: description.to_string(),
+            command: "true".to_string(),
+            description: description.to_string(),
+            tags: Vec::new(),
+            arguments: Vec::new(),
+            author: None,
+            author_url: None,
+            source_url: None,
+            shells: shells.iter().map(|s| s.to_string()).collect(),
+        };
+        let (any, posix, windows) = (workflow("Anywhere", &[]), workflow("Posix", &["bash", "zsh"]), workflow("Windows", &["pwsh"]));
+        let ids = ["any".to_string(), "posix".to_string(), "windows".to_string()];
+        let list = render_list("📚 Workflows", vec![(&ids[2], &windows), (&ids[0], &any), (&ids[1], &posix)], "zsh");
+        assert_eq!(list, "📚 Workflows (3)\n  any - Anywhere [any shell]\n  posix - Posix [bash, zsh]\n  windows - Windows [pwsh] (not for zsh)");
+    }
+
+    #[tokio::test]
+    async fn test_prompts_only_for_missing_required_args() {
+        let workflow = Workflow {
+            name: "Greet".to_string(),
+            command: "echo {{greeting}} {{name}} {{target}}".to_string(),
+            description: "Say hi".to_string(),
+            tags: Vec::new(),
+            arguments: vec![
+                argument("greeting", Some("hello"), true),
+                argument("name", None, true),
+                argument("target", None, true),
+                argument("optional", None, false),
+            ],
+            author: None,
+            author_url: None,
+            source_url: None,
+            shells: Vec::new(),
+        };
+        let mut args = HashMap::from([("target".to_string(), "everyone".to_string())]);
+        let mut answers = vec!["\n".to_string(), " world\n".to_string()].into_iter();
+        let mut asked = Vec::new();
+        prompt_for_args(&workflow, &mut args, |arg| {
+            asked.push(arg.name.clone());
+            std::future::ready(answers.next())
+        }).await;
+
+        assert_eq!(asked, ["greeting", "name"]);
+        assert_eq!(args["greeting"], "hello", "enter accepts the default");
+        assert_eq!(args["name"], "world");
+        assert_eq!(args["target"], "everyone");
+        assert!(!args.contains_key("optional"));
+
+        let mut args = HashMap::new();
+        prompt_for_args(&workflow, &mut args, |_| std::future::ready(Some(String::new()))).await;
+        assert_eq!(args.len(), 1, "an empty answer without a default leaves the argument missing");
+    }
+}
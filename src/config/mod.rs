@@ -1,20 +1,58 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
+use tracing::{info, warn};
+
+/// Layout version written by this build; bump it when adding a migration step
+pub const CONFIG_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Layout version of the file; files written before versioning have none and count as 0
+    #[serde(default)]
+    pub version: u32,
     pub database_path: PathBuf,
     pub openai_api_key: Option<String>,
     pub theme: Theme,
     pub agent: AgentConfig,
     pub aliases: std::collections::HashMap<String, String>,
+    /// Upper bound applied to every `--limit` on list commands
+    #[serde(default = "default_max_list_limit")]
+    pub max_list_limit: usize,
+    /// Recorded commands to keep; the oldest are deleted as new ones are saved
+    #[serde(default = "default_max_history_size")]
+    pub max_history_size: usize,
+    /// Directory holding the markdown files of blog posts
+    #[serde(default = "default_blog_dir")]
+    pub blog_dir: PathBuf,
+    #[serde(default)]
+    pub prep: PrepConfig,
+    #[serde(default)]
+    pub transcript: TranscriptConfig,
+    #[serde(default)]
+    pub history_display: HistoryDisplayConfig,
+}
+
+fn default_max_list_limit() -> usize {
+    crate::db::MAX_LIST_LIMIT
+}
+
+fn default_max_history_size() -> usize {
+    crate::db::DEFAULT_MAX_HISTORY_SIZE
+}
+
+fn default_blog_dir() -> PathBuf {
+    home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".agentic").join("blog")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Theme {
+    /// A theme from `themes/` or `~/.agentic/themes/` by name (e.g. "Dracula"); its colors
+    /// replace the built-in palette
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     pub dark_mode: bool,
     pub primary_color: String,
     pub secondary_color: String,
@@ -30,6 +68,164 @@ pub struct AgentConfig {
     pub max_tokens: u32,
     pub timeout_seconds: u64,
     pub preferred_provider: String, // "openai" or "ollama"
+    /// An OpenAI-compatible server (LM Studio, vLLM, Together...) to use instead of
+    /// `OPENAI_BASE_URL`, e.g. `http://localhost:1234/v1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openai_base_url: Option<String>,
+    /// How many times a model request is resent after a 429, 5xx or timeout
+    #[serde(default = "default_agent_max_retries")]
+    pub max_retries: u32,
+    /// Prompts used by `agentic agent bench`
+    #[serde(default = "default_bench_prompts")]
+    pub bench_prompts: Vec<String>,
+    /// In the TUI, guess whether unrecognised input is a shell command or a question
+    /// instead of only following the current mode
+    #[serde(default = "default_smart_routing")]
+    pub smart_routing: bool,
+    /// Where the Ollama server answering agent queries, embeddings and `doctor` listens
+    #[serde(default = "default_ollama_host")]
+    pub ollama_host: String,
+    /// Ollama model answering agent queries; `agentic models use <name> --save` updates it
+    #[serde(default = "default_ollama_model")]
+    pub ollama_model: String,
+    /// Ollama model that embeds commands for `agentic history search --semantic`
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+}
+
+/// The official OpenAI API, used when `openai_base_url` isn't set
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+impl AgentConfig {
+    /// Where OpenAI chat completions are sent
+    pub fn openai_chat_url(&self) -> String {
+        let base = self.openai_base_url.as_deref().unwrap_or(OPENAI_BASE_URL);
+        format!("{}/chat/completions", base.trim_end_matches('/'))
+    }
+}
+
+fn default_agent_max_retries() -> u32 {
+    crate::retry::DEFAULT_MAX_RETRIES
+}
+
+fn default_ollama_host() -> String {
+    crate::ollama::client::DEFAULT_HOST.to_string()
+}
+
+fn default_ollama_model() -> String {
+    "phi4:latest".to_string()
+}
+
+fn default_embedding_model() -> String {
+    crate::ollama::client::EMBEDDING_MODELS[0].to_string()
+}
+
+fn default_smart_routing() -> bool {
+    true
+}
+
+fn default_bench_prompts() -> Vec<String> {
+    vec![
+        "List the files in the current directory sorted by size.".to_string(),
+        "Explain what `git rebase` does in two sentences.".to_string(),
+        "Write a Rust function that reverses a string.".to_string(),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrepConfig {
+    /// Whether `prep start --focus` blocks the commands below
+    #[serde(default = "default_focus_lock")]
+    pub focus_lock: bool,
+    /// Programs, hosts or URLs that `agentic run` refuses during a focus session
+    #[serde(default = "default_blocked_commands")]
+    pub blocked_commands: Vec<String>,
+}
+
+fn default_focus_lock() -> bool {
+    true
+}
+
+fn default_blocked_commands() -> Vec<String> {
+    ["youtube.com", "instagram.com", "twitter.com", "x.com", "reddit.com", "netflix.com", "steam", "discord"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+impl Default for PrepConfig {
+    fn default() -> Self {
+        Self {
+            focus_lock: default_focus_lock(),
+            blocked_commands: default_blocked_commands(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptConfig {
+    /// Append every TUI command and its output to a transcript file as it runs
+    #[serde(default)]
+    pub enabled: bool,
+    /// Directory holding the transcripts, one timestamped file per TUI session
+    #[serde(default = "default_transcript_dir")]
+    pub dir: PathBuf,
+    /// Values given to keys containing one of these words (`password=...`, `--token ...`)
+    /// are written as `[REDACTED]`
+    #[serde(default = "default_redact_keys")]
+    pub redact_keys: Vec<String>,
+}
+
+fn default_transcript_dir() -> PathBuf {
+    home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".agentic").join("transcripts")
+}
+
+fn default_redact_keys() -> Vec<String> {
+    ["password", "passwd", "secret", "token", "api_key", "apikey", "authorization"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+impl Default for TranscriptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_transcript_dir(),
+            redact_keys: default_redact_keys(),
+        }
+    }
+}
+
+/// A piece of metadata a TUI command block can show under the command
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryField {
+    Timestamp,
+    Duration,
+    ExitCode,
+    Provider,
+    Model,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryDisplayConfig {
+    /// Metadata shown for each command in the TUI history, in this order; empty hides the line
+    #[serde(default = "default_history_fields")]
+    pub fields: Vec<HistoryField>,
+}
+
+/// Metadata shown for each command when `[history_display]` doesn't list any
+pub const DEFAULT_HISTORY_FIELDS: [HistoryField; 2] = [HistoryField::Timestamp, HistoryField::Duration];
+
+fn default_history_fields() -> Vec<HistoryField> {
+    DEFAULT_HISTORY_FIELDS.to_vec()
+}
+
+impl Default for HistoryDisplayConfig {
+    fn default() -> Self {
+        Self { fields: default_history_fields() }
+    }
 }
 
 impl Default for Config {
@@ -38,11 +234,18 @@ impl Default for Config {
         let config_dir = home.join(".agentic");
         
         Self {
+            version: CONFIG_VERSION,
             database_path: config_dir.join("history.db"),
             openai_api_key: None,
             theme: Theme::default(),
             agent: AgentConfig::default(),
             aliases: std::collections::HashMap::new(),
+            max_list_limit: default_max_list_limit(),
+            max_history_size: default_max_history_size(),
+            blog_dir: default_blog_dir(),
+            prep: PrepConfig::default(),
+            transcript: TranscriptConfig::default(),
+            history_display: HistoryDisplayConfig::default(),
         }
     }
 }
@@ -50,6 +253,7 @@ impl Default for Config {
 impl Default for Theme {
     fn default() -> Self {
         Self {
+            name: None,
             dark_mode: true,
             primary_color: "#61dafb".to_string(),
             secondary_color: "#282c34".to_string(),
@@ -68,6 +272,13 @@ impl Default for AgentConfig {
             max_tokens: 1000,
             timeout_seconds: 30,
             preferred_provider: "ollama".to_string(), // Default to free Ollama
+            openai_base_url: None,
+            max_retries: default_agent_max_retries(),
+            bench_prompts: default_bench_prompts(),
+            smart_routing: default_smart_routing(),
+            ollama_host: default_ollama_host(),
+            ollama_model: default_ollama_model(),
+            embedding_model: default_embedding_model(),
         }
     }
 }
@@ -77,8 +288,7 @@ impl Config {
         let config_path = Self::config_path();
         
         if config_path.exists() {
-            let content = fs::read_to_string(&config_path).await?;
-            let config: Config = toml::from_str(&content)?;
+            let (config, _backup) = Self::load_and_migrate(&config_path).await?;
             Ok(config)
         } else {
             let config = Config::default();
@@ -87,20 +297,64 @@ impl Config {
         }
     }
     
-    pub async fn save(&self) -> Result<()> {
-        let config_path = Self::config_path();
+    /// Load the config at `path`, upgrading and rewriting it if it is older than
+    /// `CONFIG_VERSION`. Returns the backup of the original file when it was rewritten.
+    pub async fn load_and_migrate(path: &Path) -> Result<(Self, Option<PathBuf>)> {
+        let content = fs::read_to_string(path).await?;
+        let (config, migrated) = Self::parse_and_migrate(&content)
+            .with_context(|| format!("Failed to load config {:?}", path))?;
+        
+        if !migrated {
+            return Ok((config, None));
+        }
+        
+        let backup = path.with_extension("toml.bak");
+        fs::copy(path, &backup).await?;
+        fs::write(path, toml::to_string_pretty(&config)?).await?;
+        info!("Upgraded config {:?} to version {} (backup at {:?})", path, CONFIG_VERSION, backup);
+        Ok((config, Some(backup)))
+    }
+    
+    /// Parse config text, migrating older layouts; the flag says whether a migration ran
+    pub fn parse_and_migrate(content: &str) -> Result<(Self, bool)> {
+        let mut value: toml::Value = toml::from_str(content)?;
+        let version = value.get("version")
+            .and_then(|v| v.as_integer())
+            .unwrap_or(0) as u32;
+        
+        if version > CONFIG_VERSION {
+            warn!("Config version {} is newer than this build supports ({})", version, CONFIG_VERSION);
+        }
+        if version >= CONFIG_VERSION {
+            return Ok((value.try_into()?, false));
+        }
         
-        if let Some(parent) = config_path.parent() {
+        for from in version..CONFIG_VERSION {
+            value = migrate_step(value, from)?;
+        }
+        if let Some(table) = value.as_table_mut() {
+            table.insert("version".to_string(), toml::Value::Integer(CONFIG_VERSION as i64));
+        }
+        Ok((value.try_into()?, true))
+    }
+    
+    pub async fn save(&self) -> Result<()> {
+        self.save_to(&Self::config_path()).await
+    }
+    
+    pub async fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
         }
         
         let content = toml::to_string_pretty(self)?;
-        fs::write(&config_path, content).await?;
+        fs::write(path, content).await
+            .with_context(|| format!("Failed to write config {:?}", path))?;
         
         Ok(())
     }
     
-    fn config_path() -> PathBuf {
+    pub fn config_path() -> PathBuf {
         let home = home_dir().unwrap_or_else(|| PathBuf::from("."));
         home.join(".agentic").join("config.toml")
     }
@@ -110,3 +364,78 @@ impl Config {
             .or_else(|| std::env::var("OPENAI_API_KEY").ok())
     }
 }
+
+/// Upgrade a config from version `from` to `from + 1`
+fn migrate_step(value: toml::Value, from: u32) -> Result<toml::Value> {
+    match from {
+        // Unversioned files predate preferred_provider, bench_prompts and friends;
+        // fill anything missing from the defaults while keeping the user's values
+        0 => {
+            let defaults = toml::Value::try_from(Config::default())?;
+            Ok(merge_missing(defaults, value))
+        }
+        _ => Err(anyhow!("No migration from config version {}", from)),
+    }
+}
+
+/// Overlay `user` onto `defaults`, recursing into tables so nested keys are filled too
+fn merge_missing(defaults: toml::Value, user: toml::Value) -> toml::Value {
+    match (defaults, user) {
+        (toml::Value::Table(mut base), toml::Value::Table(overlay)) => {
+            for (key, user_value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(default_value) => merge_missing(default_value, user_value),
+                    None => user_value,
+                };
+                base.insert(key, merged);
+            }
+            toml::Value::Table(base)
+        }
+        (_, user) => user,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_old_config_is_upgraded_with_defaults() {
+        let old = r##"
+database_path = "/tmp/agentic/history.db"
+
+[theme]
+dark_mode = false
+primary_color = "#ffffff"
+secondary_color = "#282c34"
+accent_color = "#98c379"
+background_color = "#1e1e1e"
+text_color = "#ffffff"
+
+[agent]
+model = "llama3"
+temperature = 0.2
+max_tokens = 500
+timeout_seconds = 10
+
+[aliases]
+t = "task"
+"##;
+        let (config, migrated) = Config::parse_and_migrate(old).unwrap();
+
+        assert!(migrated);
+        assert_eq!(config.version, CONFIG_VERSION);
+        // User values survive
+        assert_eq!(config.agent.model, "llama3");
+        assert!(!config.theme.dark_mode);
+        assert_eq!(config.aliases.get("t").map(String::as_str), Some("task"));
+        // Missing fields come from the defaults
+        assert_eq!(config.agent.preferred_provider, "ollama");
+        assert!(!config.agent.bench_prompts.is_empty());
+        assert_eq!(config.max_list_limit, crate::db::MAX_LIST_LIMIT);
+
+        let rewritten = toml::to_string_pretty(&config).unwrap();
+        let (_, migrated_again) = Config::parse_and_migrate(&rewritten).unwrap();
+        assert!(!migrated_again);
+    }
+}
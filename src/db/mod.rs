@@ -1,15 +1,54 @@
-use anyhow::Result;
-use chrono::{DateTime, Utc};
-use rusqlite::{Connection, params};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Row, params};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use tokio::task;
-use uuid::Uuid;
+use tracing::warn;
+use crate::commands::blog::{BlogPost, PostStatus};
+use crate::commands::prep::{PrepSession, PrepTopic, SessionStatus, StatsPeriod};
 use crate::commands::task::{Task, Priority, TaskStatus};
 
+/// Number of rows list commands return when `--limit` isn't given
+pub const DEFAULT_LIST_LIMIT: usize = 50;
+
+/// Upper bound on any list query unless overridden by `max_list_limit` in the config
+pub const MAX_LIST_LIMIT: usize = 1000;
+
+/// Recorded commands kept unless overridden by `max_history_size` in the config
+pub const DEFAULT_MAX_HISTORY_SIZE: usize = 1000;
+
 #[derive(Debug, Clone)]
 pub struct Database {
-    db_path: String,
+    /// Opened once and shared by every clone
+    conn: Arc<Mutex<Connection>>,
+    max_limit: usize,
+    max_history: usize,
+    /// Removed once the last clone is dropped, after the connection above is closed
+    #[cfg(test)]
+    temp_file: Option<Arc<TempDbFile>>,
+}
+
+/// Which recorded commands `Database::delete_history` removes; an empty filter matches them all
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// Only commands run before this time
+    pub before: Option<DateTime<Utc>>,
+    /// Only commands that ended this way
+    pub status: Option<ExecutionStatus>,
+}
+
+impl HistoryFilter {
+    /// The `WHERE` clause and its two parameters, `before` then the JSON-encoded status
+    fn to_sql(&self) -> Result<(&'static str, Option<String>, Option<String>)> {
+        let status = self.status.as_ref().map(serde_json::to_string).transpose()?;
+        Ok((
+            "(?1 IS NULL OR timestamp < ?1) AND (?2 IS NULL OR status = ?2)",
+            self.before.map(|before| before.to_rfc3339()),
+            status,
+        ))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +60,55 @@ pub struct CommandExecution {
     pub timestamp: DateTime<Utc>,
     pub duration_ms: u64,
     pub agent_query: Option<String>,
+    /// AI provider that answered an agent query
+    pub provider: Option<String>,
+    /// Model that answered an agent query
+    pub model: Option<String>,
+    /// Exit status of a shell command, when one ran to completion or was interrupted
+    pub exit_code: Option<i32>,
+}
+
+/// Aggregated task counts computed by `Database::task_stats`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskStats {
+    pub total: usize,
+    pub todo: usize,
+    pub in_progress: usize,
+    pub complete: usize,
+    pub low_priority: usize,
+    pub medium_priority: usize,
+    pub high_priority: usize,
+    pub completed_this_week: usize,
+    /// Average age of tasks that are not complete, in days
+    pub average_open_age_days: Option<f64>,
+}
+
+impl TaskStats {
+    /// Percentage of tasks that are complete, 0 when there are no tasks
+    pub fn completion_rate(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        (self.complete as f64 / self.total as f64) * 100.0
+    }
+}
+
+/// Study time for one exam computed by `Database::prep_stats`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExamPrepStats {
+    pub exam_type: String,
+    pub sessions: usize,
+    pub total_minutes: u64,
+    pub longest_minutes: u64,
+}
+
+impl ExamPrepStats {
+    pub fn average_minutes(&self) -> u64 {
+        if self.sessions == 0 {
+            return 0;
+        }
+        self.total_minutes / self.sessions as u64
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,19 +117,33 @@ pub enum ExecutionStatus {
     Success,
     Error,
     Cancelled,
+    /// The stored status could not be parsed
+    Unknown,
 }
 
 impl Database {
     pub async fn new(db_path: &Path) -> Result<Self> {
-        let path_str = db_path.to_string_lossy().to_string();
-        
         // Create parent directory if it doesn't exist
         if let Some(parent) = db_path.parent() {
             tokio::fs::create_dir_all(parent).await?;
         }
         
+        let path = db_path.to_path_buf();
+        let conn = task::spawn_blocking(move || -> Result<Connection> {
+            let conn = Connection::open(&path)?;
+            // WAL lets the TUI read while another agentic process writes; the timeout makes
+            // writers wait for each other instead of failing with "database is locked"
+            conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get::<_, String>(0))?;
+            conn.busy_timeout(std::time::Duration::from_secs(5))?;
+            Ok(conn)
+        }).await??;
+        
         let db = Database {
-            db_path: path_str.clone(),
+            conn: Arc::new(Mutex::new(conn)),
+            max_limit: MAX_LIST_LIMIT,
+            max_history: DEFAULT_MAX_HISTORY_SIZE,
+            #[cfg(test)]
+            temp_file: None,
         };
         
         // Initialize database schema
@@ -50,67 +152,53 @@ impl Database {
         Ok(db)
     }
     
+    /// Run `f` with the shared connection on the blocking thread pool, one call at a time
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        task::spawn_blocking(move || {
+            let mut conn = conn.lock().map_err(|_| anyhow!("The database connection was poisoned by a panic"))?;
+            f(&mut conn)
+        }).await?
+    }
+    
+    /// Override the maximum number of rows a list query may return
+    pub fn with_max_limit(mut self, max_limit: usize) -> Self {
+        self.max_limit = max_limit.max(1);
+        self
+    }
+    
+    /// Override how many recorded commands are kept; older ones are pruned as new ones are saved
+    pub fn with_max_history(mut self, max_history: usize) -> Self {
+        self.max_history = max_history.max(1);
+        self
+    }
+    
+    /// Clamp a caller-supplied limit to the configured maximum
+    pub fn clamp_limit(&self, limit: usize) -> usize {
+        if limit > self.max_limit {
+            warn!("Requested limit {} exceeds the maximum of {}, clamping", limit, self.max_limit);
+        }
+        limit.clamp(1, self.max_limit)
+    }
+    
     async fn init_schema(&self) -> Result<()> {
-        let db_path = self.db_path.clone();
-        
-        task::spawn_blocking(move || -> Result<()> {
-            let conn = Connection::open(&db_path)?;
-            
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS command_executions (
-                    id TEXT PRIMARY KEY,
-                    command TEXT NOT NULL,
-                    output TEXT NOT NULL,
-                    status TEXT NOT NULL,
-                    timestamp TEXT NOT NULL,
-                    duration_ms INTEGER NOT NULL,
-                    agent_query TEXT
-                )",
-                [],
-            )?;
-            
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS tasks (
-                    id TEXT PRIMARY KEY,
-                    title TEXT NOT NULL,
-                    description TEXT,
-                    priority TEXT NOT NULL,
-                    status TEXT NOT NULL,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                )",
-                [],
-            )?;
-            
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS prep_sessions (
-                    id TEXT PRIMARY KEY,
-                    exam_type TEXT NOT NULL,
-                    session_name TEXT NOT NULL,
-                    status TEXT NOT NULL,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                )",
-                [],
-            )?;
-            
-            Ok(())
-        }).await??;
-        
-        Ok(())
+        self.with_conn(|conn| migrate(conn, MIGRATIONS).map(|_| ())).await
     }
     
+    /// Record a command, then drop the oldest ones beyond `max_history_size`
     pub async fn save_command_execution(&self, execution: &CommandExecution) -> Result<()> {
-        let db_path = self.db_path.clone();
         let execution = execution.clone();
+        let max_history = self.max_history;
         
-        task::spawn_blocking(move || -> Result<()> {
-            let conn = Connection::open(&db_path)?;
-            
+        self.with_conn(move |conn| -> Result<()> {
             conn.execute(
                 "INSERT INTO command_executions 
-                (id, command, output, status, timestamp, duration_ms, agent_query) 
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                (id, command, output, status, timestamp, duration_ms, agent_query, provider, model, exit_code) 
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
                 params![
                     execution.id,
                     execution.command,
@@ -119,44 +207,59 @@ impl Database {
                     execution.timestamp.to_rfc3339(),
                     execution.duration_ms as i64,
                     execution.agent_query,
+                    execution.provider,
+                    execution.model,
+                    execution.exit_code,
                 ],
             )?;
             
+            conn.execute(
+                "DELETE FROM command_executions WHERE id NOT IN 
+                (SELECT id FROM command_executions ORDER BY timestamp DESC LIMIT ?1)",
+                params![max_history as i64],
+            )?;
+            
             Ok(())
-        }).await??;
+        }).await?;
         
         Ok(())
     }
     
+    /// How many recorded commands `filter` matches
+    pub async fn count_history(&self, filter: &HistoryFilter) -> Result<usize> {
+        let (clause, before, status) = filter.to_sql()?;
+        let count = self.with_conn(move |conn| -> Result<usize> {
+            let count: i64 = conn.query_row(
+                &format!("SELECT COUNT(*) FROM command_executions WHERE {}", clause),
+                params![before, status],
+                |row| row.get(0),
+            )?;
+            Ok(count as usize)
+        }).await?;
+        Ok(count)
+    }
+    
+    /// Delete the recorded commands `filter` matches, returning how many were removed
+    pub async fn delete_history(&self, filter: &HistoryFilter) -> Result<usize> {
+        let (clause, before, status) = filter.to_sql()?;
+        let deleted = self.with_conn(move |conn| -> Result<usize> {
+            Ok(conn.execute(&format!("DELETE FROM command_executions WHERE {}", clause), params![before, status])?)
+        }).await?;
+        Ok(deleted)
+    }
+    
     pub async fn get_command_history(&self, limit: usize) -> Result<Vec<CommandExecution>> {
-        let db_path = self.db_path.clone();
+        let limit = self.clamp_limit(limit);
         
-        let executions = task::spawn_blocking(move || -> Result<Vec<CommandExecution>> {
-            let conn = Connection::open(&db_path)?;
-            
-            let mut stmt = conn.prepare(
-                "SELECT id, command, output, status, timestamp, duration_ms, agent_query 
+        let executions = self.with_conn(move |conn| -> Result<Vec<CommandExecution>> {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, command, output, status, timestamp, duration_ms, agent_query, provider, model, exit_code 
                 FROM command_executions 
                 ORDER BY timestamp DESC 
                 LIMIT ?1"
             )?;
             
-            let rows = stmt.query_map(params![limit], |row| {
-                let status_str: String = row.get(3)?;
-                let timestamp_str: String = row.get(4)?;
-                
-                Ok(CommandExecution {
-                    id: row.get(0)?,
-                    command: row.get(1)?,
-                    output: row.get(2)?,
-                    status: serde_json::from_str(&status_str).unwrap_or(ExecutionStatus::Error),
-                    timestamp: DateTime::parse_from_rfc3339(&timestamp_str)
-                        .unwrap_or_else(|_| Utc::now().into())
-                        .with_timezone(&Utc),
-                    duration_ms: row.get::<_, i64>(5)? as u64,
-                    agent_query: row.get(6)?,
-                })
-            })?;
+            let rows = stmt.query_map(params![limit], row_to_execution)?;
             
             let mut executions = Vec::new();
             for row in rows {
@@ -164,131 +267,1285 @@ impl Database {
             }
             
             Ok(executions)
-        }).await??;
+        }).await?;
         
         Ok(executions)
     }
     
+    /// Newest commands whose text or output contains `query`, ignoring ASCII case
+    pub async fn search_history(&self, query: &str, limit: usize) -> Result<Vec<CommandExecution>> {
+        let limit = self.clamp_limit(limit);
+        let pattern = format!("%{}%", escape_like(query));
+        
+        let executions = self.with_conn(move |conn| -> Result<Vec<CommandExecution>> {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, command, output, status, timestamp, duration_ms, agent_query, provider, model, exit_code 
+                FROM command_executions 
+                WHERE command LIKE ?1 ESCAPE '\\' OR output LIKE ?1 ESCAPE '\\' 
+                ORDER BY timestamp DESC 
+                LIMIT ?2"
+            )?;
+            
+            let rows = stmt.query_map(params![pattern, limit], row_to_execution)?;
+            
+            let mut executions = Vec::new();
+            for row in rows {
+                executions.push(row?);
+            }
+            
+            Ok(executions)
+        }).await?;
+        
+        Ok(executions)
+    }
+    
+    /// Distinct recorded commands that have no embedding from `model` yet
+    pub async fn commands_without_embedding(&self, model: &str) -> Result<Vec<String>> {
+        let model = model.to_string();
+        let commands = self.with_conn(move |conn| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare_cached(
+                "SELECT DISTINCT command FROM command_executions 
+                WHERE command NOT IN (SELECT command FROM command_embeddings WHERE model = ?1)"
+            )?;
+            let rows = stmt.query_map(params![model], |row| row.get(0))?;
+            Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+        }).await?;
+        Ok(commands)
+    }
+    
+    /// Cache `command`'s embedding from `model`
+    pub async fn save_command_embedding(&self, command: &str, model: &str, embedding: &[f32]) -> Result<()> {
+        let (command, model) = (command.to_string(), model.to_string());
+        let blob = embedding_to_blob(embedding);
+        self.with_conn(move |conn| -> Result<()> {
+            conn.execute(
+                "INSERT OR REPLACE INTO command_embeddings (command, model, embedding) VALUES (?1, ?2, ?3)",
+                params![command, model, blob],
+            )?;
+            Ok(())
+        }).await
+    }
+    
+    /// Drop cached embeddings for commands that are no longer in the history, returning how many
+    pub async fn prune_command_embeddings(&self) -> Result<usize> {
+        self.with_conn(|conn| -> Result<usize> {
+            Ok(conn.execute(
+                "DELETE FROM command_embeddings WHERE command NOT IN (SELECT command FROM command_executions)",
+                [],
+            )?)
+        }).await
+    }
+    
+    /// The newest run of each recorded command that has an embedding from `model`, with that embedding
+    pub async fn embedded_commands(&self, model: &str) -> Result<Vec<(CommandExecution, Vec<f32>)>> {
+        let model = model.to_string();
+        let embedded = self.with_conn(move |conn| -> Result<Vec<(CommandExecution, Vec<f32>)>> {
+            let mut stmt = conn.prepare_cached(
+                "SELECT e.id, e.command, e.output, e.status, e.timestamp, e.duration_ms, e.agent_query, e.provider, e.model, e.exit_code, 
+                    m.embedding 
+                FROM command_executions e JOIN command_embeddings m ON m.command = e.command AND m.model = ?1 
+                ORDER BY e.timestamp DESC"
+            )?;
+            let rows = stmt.query_map(params![model], |row| {
+                Ok((row_to_execution(row)?, blob_to_embedding(&row.get::<_, Vec<u8>>(10)?)))
+            })?;
+            
+            let mut seen = std::collections::HashSet::new();
+            let mut embedded = Vec::new();
+            for row in rows {
+                let (execution, embedding) = row?;
+                if seen.insert(execution.command.clone()) {
+                    embedded.push((execution, embedding));
+                }
+            }
+            Ok(embedded)
+        }).await?;
+        Ok(embedded)
+    }
+    
+    /// Resolve a recorded command by exact id or unambiguous id prefix
+    pub async fn find_command_execution(&self, needle: &str) -> Result<Option<CommandExecution>> {
+        let needle = needle.trim().to_string();
+        if needle.is_empty() {
+            return Err(anyhow!("Give a recorded command id; `agentic history list` shows them"));
+        }
+        let found = self.with_conn(move |conn| -> Result<Option<CommandExecution>> {
+            let mut stmt = conn.prepare(
+                "SELECT id, command, output, status, timestamp, duration_ms, agent_query, provider, model, exit_code 
+                FROM command_executions WHERE id = ?1 OR id LIKE ?2 || '%' ESCAPE '\\' 
+                ORDER BY id = ?1 DESC, timestamp DESC",
+            )?;
+            let mut matches = stmt.query_map(params![needle, escape_like(&needle)], row_to_execution)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            if matches.first().is_some_and(|execution| execution.id == needle) {
+                matches.truncate(1);
+            }
+            match matches.len() {
+                0 | 1 => Ok(matches.pop()),
+                count => {
+                    let candidates: Vec<String> = matches.iter()
+                        .map(|execution| format!("  {}  {}", execution.id, execution.command))
+                        .collect();
+                    Err(anyhow!("'{}' matches {} recorded commands; use more of the id:\n{}", needle, count, candidates.join("\n")))
+                }
+            }
+        }).await?;
+        Ok(found)
+    }
+    
     pub async fn update_execution_status(
         &self, 
         execution_id: &str, 
         status: ExecutionStatus,
         output: &str,
         duration_ms: u64,
+        exit_code: Option<i32>,
     ) -> Result<()> {
-        let db_path = self.db_path.clone();
         let execution_id = execution_id.to_string();
         let status_json = serde_json::to_string(&status)?;
         let output = output.to_string();
         
-        task::spawn_blocking(move || -> Result<()> {
-            let conn = Connection::open(&db_path)?;
-            
+        self.with_conn(move |conn| -> Result<()> {
             conn.execute(
                 "UPDATE command_executions 
-                SET status = ?1, output = ?2, duration_ms = ?3 
-                WHERE id = ?4",
-                params![status_json, output, duration_ms as i64, execution_id],
+                SET status = ?1, output = ?2, duration_ms = ?3, exit_code = ?4 
+                WHERE id = ?5",
+                params![status_json, output, duration_ms as i64, exit_code, execution_id],
             )?;
             
             Ok(())
-        }).await??;
+        }).await?;
         
         Ok(())
     }
 
     pub async fn add_task(&self, task: &Task) -> Result<()> {
-        let db_path = self.db_path.clone();
         let task = task.clone();
-        task::spawn_blocking(move || -> Result<()> {
-            let conn = Connection::open(&db_path)?;
+        self.with_conn(move |conn| -> Result<()> {
+            insert_task(conn, &task)?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    /// Insert all tasks in a single transaction; either every task is stored or none are
+    pub async fn import_tasks(&self, tasks: &[Task]) -> Result<usize> {
+        let tasks = tasks.to_vec();
+        let count = self.with_conn(move |conn| -> Result<usize> {
+            let tx = conn.transaction()?;
+            for task in &tasks {
+                insert_task(&tx, task)?;
+            }
+            tx.commit()?;
+            Ok(tasks.len())
+        }).await?;
+        Ok(count)
+    }
+
+    /// Insert all prep sessions in a single transaction; either every session is stored or none are
+    pub async fn import_prep_sessions(&self, sessions: &[PrepSession]) -> Result<usize> {
+        let sessions = sessions.to_vec();
+        let count = self.with_conn(move |conn| -> Result<usize> {
+            let tx = conn.transaction()?;
+            for session in &sessions {
+                insert_prep_session(&tx, session)?;
+            }
+            tx.commit()?;
+            Ok(sessions.len())
+        }).await?;
+        Ok(count)
+    }
+
+    pub async fn add_prep_session(&self, session: &PrepSession) -> Result<()> {
+        let session = session.clone();
+        self.with_conn(move |conn| -> Result<()> {
+            insert_prep_session(conn, &session)
+        }).await?;
+        Ok(())
+    }
+
+    /// Find the most recently started active session, optionally by exact id or id prefix
+    pub async fn find_active_prep_session(&self, session_id: Option<&str>) -> Result<Option<PrepSession>> {
+        let session_id = session_id.map(|id| id.trim().to_string());
+        let found = self.with_conn(move |conn| -> Result<Option<PrepSession>> {
+            let session = conn.query_row(
+                &format!(
+                    "SELECT {} FROM prep_sessions
+                     WHERE UPPER(status) = 'ACTIVE' AND (?1 IS NULL OR id = ?1 OR id LIKE ?1 || '%')
+                     ORDER BY created_at DESC LIMIT 1",
+                    PREP_SESSION_COLUMNS
+                ),
+                params![session_id],
+                row_to_prep_session,
+            ).optional()?;
+            Ok(session)
+        }).await?;
+        Ok(found)
+    }
+
+    /// End a session with `status`, recording how long it actually ran
+    pub async fn finish_prep_session(&self, session_id: &str, status: SessionStatus, duration_minutes: u32) -> Result<()> {
+        let session_id = session_id.to_string();
+        let status = status.to_string();
+        let now = crate::offline::now().to_rfc3339();
+        self.with_conn(move |conn| -> Result<()> {
             conn.execute(
-                "INSERT INTO tasks (id, title, description, priority, status, created_at, updated_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                "UPDATE prep_sessions SET status = ?1, duration_minutes = ?2, updated_at = ?3 WHERE id = ?4",
+                params![status, duration_minutes, now, session_id],
+            )?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    /// List sessions newest first, optionally restricted to one exam and/or active sessions
+    pub async fn list_prep_sessions(&self, exam: Option<&str>, active_only: bool, limit: usize) -> Result<Vec<PrepSession>> {
+        let exam = exam.map(|e| e.to_string());
+        let limit = self.clamp_limit(limit);
+        let sessions = self.with_conn(move |conn| -> Result<Vec<PrepSession>> {
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT {} FROM prep_sessions
+                 WHERE (?1 IS NULL OR UPPER(exam_type) = UPPER(?1))
+                   AND (?2 = 0 OR UPPER(status) = 'ACTIVE')
+                 ORDER BY created_at DESC LIMIT ?3",
+                PREP_SESSION_COLUMNS
+            ))?;
+            let rows = stmt.query_map(params![exam, active_only, limit], row_to_prep_session)?;
+            let mut sessions = Vec::new();
+            for row in rows {
+                sessions.push(row?);
+            }
+            Ok(sessions)
+        }).await?;
+        Ok(sessions)
+    }
+
+    /// Aggregate study time of finished sessions per exam over `period`
+    pub async fn prep_stats(&self, period: StatsPeriod, exam: Option<&str>) -> Result<Vec<ExamPrepStats>> {
+        let since = period_start(period).map(|dt| dt.to_rfc3339());
+        let exam = exam.map(|e| e.to_string());
+        let stats = self.with_conn(move |conn| -> Result<Vec<ExamPrepStats>> {
+            // Active sessions only hold their planned duration, so they are left out until stopped
+            let mut stmt = conn.prepare_cached(
+                "SELECT exam_type, COUNT(*), COALESCE(SUM(duration_minutes), 0), COALESCE(MAX(duration_minutes), 0)
+                 FROM prep_sessions
+                 WHERE UPPER(status) != 'ACTIVE'
+                   AND (?1 IS NULL OR created_at >= ?1)
+                   AND (?2 IS NULL OR UPPER(exam_type) = UPPER(?2))
+                 GROUP BY exam_type
+                 ORDER BY SUM(duration_minutes) DESC",
+            )?;
+            let rows = stmt.query_map(params![since, exam], |row| {
+                Ok(ExamPrepStats {
+                    exam_type: row.get(0)?,
+                    sessions: row.get::<_, i64>(1)? as usize,
+                    total_minutes: row.get::<_, i64>(2)? as u64,
+                    longest_minutes: row.get::<_, i64>(3)? as u64,
+                })
+            })?;
+            let mut stats = Vec::new();
+            for row in rows {
+                stats.push(row?);
+            }
+            Ok(stats)
+        }).await?;
+        Ok(stats)
+    }
+
+    pub async fn add_prep_topic(&self, topic: &PrepTopic) -> Result<()> {
+        let topic = topic.clone();
+        self.with_conn(move |conn| -> Result<()> {
+            conn.execute(
+                "INSERT INTO prep_topics (id, exam, topic, priority, last_reviewed, mastery)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
                 params![
-                    task.id,
-                    task.title,
-                    task.description,
-                    task.priority.to_string(),
-                    task.status.to_string(),
-                    task.created_at.to_rfc3339(),
-                    task.updated_at.to_rfc3339(),
+                    topic.id,
+                    topic.exam,
+                    topic.topic,
+                    topic.priority,
+                    topic.last_reviewed.map(|dt| dt.to_rfc3339()),
+                    topic.mastery,
                 ],
             )?;
             Ok(())
-        }).await??;
+        }).await?;
         Ok(())
     }
 
-    pub async fn list_tasks(&self) -> Result<Vec<Task>> {
-        let db_path = self.db_path.clone();
-        let tasks = task::spawn_blocking(move || -> Result<Vec<Task>> {
-            let conn = Connection::open(&db_path)?;
-            let mut stmt = conn.prepare("SELECT id, title, description, priority, status, created_at, updated_at FROM tasks")?;
-            let rows = stmt.query_map([], |row| {
-                let priority_str: String = row.get(3)?;
-                let status_str: String = row.get(4)?;
-                let created_at_str: String = row.get(5)?;
-                let updated_at_str: String = row.get(6)?;
-                Ok(Task {
+    /// Pick the topics most in need of review: lowest mastery first, then never or least recently reviewed
+    pub async fn topics_for_review(&self, exam: &str, count: usize) -> Result<Vec<PrepTopic>> {
+        let exam = exam.to_string();
+        let count = self.clamp_limit(count);
+        let topics = self.with_conn(move |conn| -> Result<Vec<PrepTopic>> {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, exam, topic, priority, last_reviewed, mastery FROM prep_topics
+                 WHERE UPPER(exam) = UPPER(?1)
+                 ORDER BY mastery ASC, last_reviewed IS NOT NULL, last_reviewed ASC, priority DESC
+                 LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![exam, count], |row| {
+                let last_reviewed: Option<String> = row.get(4)?;
+                Ok(PrepTopic {
                     id: row.get(0)?,
-                    title: row.get(1)?,
-                    description: row.get(2)?,
-                    priority: priority_str.parse().unwrap_or(Priority::Medium),
-                    status: status_str.parse().unwrap_or(TaskStatus::Todo),
-                    created_at: created_at_str.parse().unwrap_or_else(|_| Utc::now()),
-                    updated_at: updated_at_str.parse().unwrap_or_else(|_| Utc::now()),
+                    exam: row.get(1)?,
+                    topic: row.get(2)?,
+                    priority: row.get(3)?,
+                    last_reviewed: last_reviewed.and_then(|ts| ts.parse().ok()),
+                    mastery: row.get(5)?,
                 })
             })?;
+            let mut topics = Vec::new();
+            for row in rows {
+                topics.push(row?);
+            }
+            Ok(topics)
+        }).await?;
+        Ok(topics)
+    }
+
+    pub async fn mark_topics_reviewed(&self, topic_ids: &[String]) -> Result<()> {
+        let topic_ids = topic_ids.to_vec();
+        let now = crate::offline::now().to_rfc3339();
+        self.with_conn(move |conn| -> Result<()> {
+            let tx = conn.transaction()?;
+            for id in &topic_ids {
+                tx.execute("UPDATE prep_topics SET last_reviewed = ?1 WHERE id = ?2", params![now, id])?;
+            }
+            tx.commit()?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    pub async fn add_blog_post(&self, post: &BlogPost) -> Result<()> {
+        let post = post.clone();
+        self.with_conn(move |conn| -> Result<()> {
+            conn.execute(
+                "INSERT INTO blog_posts (id, title, tags, status, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    post.id,
+                    post.title,
+                    serde_json::to_string(&post.tags)?,
+                    post.status.to_string(),
+                    post.created_at.to_rfc3339(),
+                    post.updated_at.to_rfc3339(),
+                ],
+            )?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    /// List posts newest first, optionally only those carrying `tag` and/or only drafts
+    pub async fn list_blog_posts(&self, tag: Option<&str>, drafts_only: bool, limit: usize) -> Result<Vec<BlogPost>> {
+        let tag = tag.map(|t| t.to_string());
+        let limit = self.clamp_limit(limit);
+        let posts = self.with_conn(move |conn| -> Result<Vec<BlogPost>> {
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT {} FROM blog_posts
+                 WHERE (?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(blog_posts.tags) WHERE LOWER(value) = LOWER(?1)))
+                   AND (?2 = 0 OR UPPER(status) = 'DRAFT')
+                 ORDER BY created_at DESC LIMIT ?3",
+                BLOG_POST_COLUMNS
+            ))?;
+            let rows = stmt.query_map(params![tag, drafts_only, limit], row_to_blog_post)?;
+            let mut posts = Vec::new();
+            for row in rows {
+                posts.push(row?);
+            }
+            Ok(posts)
+        }).await?;
+        Ok(posts)
+    }
+
+    /// Resolve a post by exact id or id prefix
+    pub async fn find_blog_post(&self, needle: &str) -> Result<Option<BlogPost>> {
+        let needle = needle.trim().to_string();
+        if needle.is_empty() {
+            return Ok(None);
+        }
+        let found = self.with_conn(move |conn| -> Result<Option<BlogPost>> {
+            let post = conn.query_row(
+                &format!("SELECT {} FROM blog_posts WHERE id = ?1 OR id LIKE ?1 || '%' ORDER BY id = ?1 DESC LIMIT 1", BLOG_POST_COLUMNS),
+                params![needle],
+                row_to_blog_post,
+            ).optional()?;
+            Ok(post)
+        }).await?;
+        Ok(found)
+    }
+
+    pub async fn set_blog_post_status(&self, post_id: &str, status: PostStatus) -> Result<()> {
+        let post_id = post_id.to_string();
+        let status = status.to_string();
+        let now = crate::offline::now().to_rfc3339();
+        self.with_conn(move |conn| -> Result<()> {
+            conn.execute(
+                "UPDATE blog_posts SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                params![status, now, post_id],
+            )?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    /// Sync title and tags from an edited post file and bump `updated_at`
+    pub async fn update_blog_post_meta(&self, post_id: &str, title: &str, tags: &[String]) -> Result<()> {
+        let post_id = post_id.to_string();
+        let title = title.to_string();
+        let tags = serde_json::to_string(tags)?;
+        let now = crate::offline::now().to_rfc3339();
+        self.with_conn(move |conn| -> Result<()> {
+            conn.execute(
+                "UPDATE blog_posts SET title = ?1, tags = ?2, updated_at = ?3 WHERE id = ?4",
+                params![title, tags, now, post_id],
+            )?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    pub async fn delete_blog_post(&self, post_id: &str) -> Result<()> {
+        let post_id = post_id.to_string();
+        self.with_conn(move |conn| -> Result<()> {
+            conn.execute("DELETE FROM blog_posts WHERE id = ?1", params![post_id])?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub async fn count_prep_sessions(&self) -> Result<usize> {
+        let count = self.with_conn(move |conn| -> Result<usize> {
+            let count: i64 = conn.query_row("SELECT COUNT(*) FROM prep_sessions", [], |row| row.get(0))?;
+            Ok(count as usize)
+        }).await?;
+        Ok(count)
+    }
+
+    pub async fn list_tasks(&self, limit: usize) -> Result<Vec<Task>> {
+        let limit = self.clamp_limit(limit);
+        let tasks = self.with_conn(move |conn| -> Result<Vec<Task>> {
+            let mut stmt = conn.prepare_cached(&format!("SELECT {} FROM tasks ORDER BY created_at DESC LIMIT ?1", TASK_COLUMNS))?;
+            let rows = stmt.query_map(params![limit], row_to_task)?;
             let mut tasks = Vec::new();
             for row in rows {
                 tasks.push(row?);
             }
             Ok(tasks)
-        }).await??;
+        }).await?;
         Ok(tasks)
     }
 
+    /// Open tasks with no due date or one before `due_before`, and how many more are due later.
+    /// Unlike `list_tasks` this isn't limited, so old open tasks aren't missed.
+    pub async fn open_tasks_due_before(&self, due_before: NaiveDate) -> Result<(Vec<Task>, usize)> {
+        let due_before = due_before.format("%Y-%m-%d").to_string();
+        self.with_conn(move |conn| -> Result<(Vec<Task>, usize)> {
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT {} FROM tasks WHERE UPPER(status) != 'COMPLETE' AND (due_date IS NULL OR due_date < ?1)
+                ORDER BY created_at DESC",
+                TASK_COLUMNS
+            ))?;
+            let rows = stmt.query_map(params![due_before], row_to_task)?;
+            let mut tasks = Vec::new();
+            for row in rows {
+                tasks.push(row?);
+            }
+            let later: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM tasks WHERE UPPER(status) != 'COMPLETE' AND due_date >= ?1",
+                params![due_before],
+                |row| row.get(0),
+            )?;
+            Ok((tasks, later as usize))
+        }).await
+    }
+
+    /// Resolve a task by exact id, id prefix, or case-insensitive partial title. More than one
+    /// match is an error listing them, so a command never acts on a guess.
+    pub async fn find_task(&self, needle: &str) -> Result<Option<Task>> {
+        let needle = needle.trim().to_string();
+        if needle.is_empty() {
+            return Err(anyhow!("Give a task id or part of its title"));
+        }
+        let found = self.with_conn(move |conn| -> Result<Option<Task>> {
+            let exact = conn.query_row(
+                &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS),
+                params![needle],
+                row_to_task,
+            ).optional()?;
+            if exact.is_some() {
+                return Ok(exact);
+            }
+            
+            let pattern = escape_like(&needle);
+            for condition in ["id LIKE ?1 || '%'", "LOWER(title) LIKE '%' || LOWER(?1) || '%'"] {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM tasks WHERE {} ESCAPE '\\' ORDER BY created_at DESC", TASK_COLUMNS, condition
+                ))?;
+                let mut matches = stmt.query_map(params![pattern], row_to_task)?.collect::<rusqlite::Result<Vec<_>>>()?;
+                match matches.len() {
+                    0 => continue,
+                    1 => return Ok(matches.pop()),
+                    count => {
+                        let candidates: Vec<String> = matches.iter()
+                            .map(|task| format!("  {}  {}", task.id.get(..8).unwrap_or(&task.id), task.title))
+                            .collect();
+                        return Err(anyhow!("'{}' matches {} tasks; use more of the id:\n{}", needle, count, candidates.join("\n")));
+                    }
+                }
+            }
+            Ok(None)
+        }).await?;
+        Ok(found)
+    }
+
+    pub async fn task_stats(&self) -> Result<TaskStats> {
+        let week_start = period_start(StatsPeriod::Week).unwrap_or_else(Utc::now).to_rfc3339();
+        
+        let stats = self.with_conn(move |conn| -> Result<TaskStats> {
+            // Statuses are stored via Display ("IN PROGRESS") but older rows may use other casings
+            let stats = conn.query_row(
+                "SELECT
+                    COUNT(*),
+                    COALESCE(SUM(UPPER(status) = 'TODO'), 0),
+                    COALESCE(SUM(UPPER(status) IN ('IN PROGRESS', 'INPROGRESS')), 0),
+                    COALESCE(SUM(UPPER(status) = 'COMPLETE'), 0),
+                    COALESCE(SUM(UPPER(priority) = 'LOW'), 0),
+                    COALESCE(SUM(UPPER(priority) = 'MED'), 0),
+                    COALESCE(SUM(UPPER(priority) = 'HIGH'), 0),
+                    COALESCE(SUM(UPPER(status) = 'COMPLETE' AND updated_at >= ?1), 0),
+                    AVG(CASE WHEN UPPER(status) != 'COMPLETE'
+                        THEN julianday('now') - julianday(created_at) END)
+                FROM tasks",
+                params![week_start],
+                |row| {
+                    Ok(TaskStats {
+                        total: row.get::<_, i64>(0)? as usize,
+                        todo: row.get::<_, i64>(1)? as usize,
+                        in_progress: row.get::<_, i64>(2)? as usize,
+                        complete: row.get::<_, i64>(3)? as usize,
+                        low_priority: row.get::<_, i64>(4)? as usize,
+                        medium_priority: row.get::<_, i64>(5)? as usize,
+                        high_priority: row.get::<_, i64>(6)? as usize,
+                        completed_this_week: row.get::<_, i64>(7)? as usize,
+                        average_open_age_days: row.get(8)?,
+                    })
+                },
+            )?;
+            Ok(stats)
+        }).await?;
+        Ok(stats)
+    }
+
     pub async fn complete_task(&self, task_id: &str) -> Result<()> {
-        let db_path = self.db_path.clone();
         let task_id = task_id.to_string();
-        let now = Utc::now().to_rfc3339();
-        task::spawn_blocking(move || -> Result<()> {
-            let conn = Connection::open(&db_path)?;
+        let now = crate::offline::now().to_rfc3339();
+        self.with_conn(move |conn| -> Result<()> {
             conn.execute(
                 "UPDATE tasks SET status = 'Complete', updated_at = ?1 WHERE id = ?2",
                 params![now, task_id],
             )?;
             Ok(())
-        }).await??;
+        }).await?;
+        Ok(())
+    }
+
+    pub async fn set_task_status(&self, task_id: &str, status: TaskStatus) -> Result<()> {
+        let task_id = task_id.to_string();
+        let status = status.to_string();
+        let now = crate::offline::now().to_rfc3339();
+        self.with_conn(move |conn| -> Result<()> {
+            conn.execute(
+                "UPDATE tasks SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                params![status, now, task_id],
+            )?;
+            Ok(())
+        }).await?;
         Ok(())
     }
 
     pub async fn delete_task(&self, task_id: &str) -> Result<()> {
-        let db_path = self.db_path.clone();
         let task_id = task_id.to_string();
-        task::spawn_blocking(move || -> Result<()> {
-            let conn = Connection::open(&db_path)?;
+        self.with_conn(move |conn| -> Result<()> {
             conn.execute(
                 "DELETE FROM tasks WHERE id = ?1",
                 params![task_id],
             )?;
             Ok(())
-        }).await??;
+        }).await?;
         Ok(())
     }
 }
 
+fn insert_task(conn: &Connection, task: &Task) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tasks (id, title, description, priority, status, created_at, updated_at, tags, due_date)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            task.id,
+            task.title,
+            task.description,
+            task.priority.to_string(),
+            task.status.to_string(),
+            task.created_at.to_rfc3339(),
+            task.updated_at.to_rfc3339(),
+            serde_json::to_string(&task.tags)?,
+            task.due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+        ],
+    )?;
+    Ok(())
+}
+
+const TASK_COLUMNS: &str = "id, title, description, priority, status, created_at, updated_at, tags, due_date";
+
+fn row_to_task(row: &Row) -> rusqlite::Result<Task> {
+    let priority_str: String = row.get(3)?;
+    let status_str: String = row.get(4)?;
+    let created_at_str: String = row.get(5)?;
+    let updated_at_str: String = row.get(6)?;
+    let tags_str: Option<String> = row.get(7)?;
+    let due_date_str: Option<String> = row.get(8)?;
+    Ok(Task {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        priority: priority_str.parse().unwrap_or(Priority::Medium),
+        status: status_str.parse().unwrap_or(TaskStatus::Todo),
+        tags: tags_str
+            .and_then(|t| serde_json::from_str(&t).ok())
+            .unwrap_or_default(),
+        due_date: due_date_str
+            .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()),
+        created_at: created_at_str.parse().unwrap_or_else(|_| Utc::now()),
+        updated_at: updated_at_str.parse().unwrap_or_else(|_| Utc::now()),
+    })
+}
+
+/// Start of the current period in UTC; weeks start on Monday and months on the 1st, at local midnight
+fn period_start(period: StatsPeriod) -> Option<DateTime<Utc>> {
+    let today = crate::offline::now().with_timezone(&chrono::Local).date_naive();
+    let start = match period {
+        StatsPeriod::Week => today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64),
+        StatsPeriod::Month => today.with_day(1)?,
+        StatsPeriod::All => return None,
+    };
+    start
+        .and_hms_opt(0, 0, 0)
+        .and_then(|dt| dt.and_local_timezone(chrono::Local).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn insert_prep_session(conn: &Connection, session: &PrepSession) -> Result<()> {
+    conn.execute(
+        "INSERT INTO prep_sessions (id, exam_type, session_name, status, created_at, updated_at, duration_minutes, focus)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            session.id,
+            session.exam_type,
+            session.session_name,
+            session.status.to_string(),
+            session.created_at.to_rfc3339(),
+            session.updated_at.to_rfc3339(),
+            session.duration_minutes,
+            session.focus,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Map a `command_executions` row, flagging corrupt fields instead of guessing: a bad status
+/// becomes `Unknown` and a bad timestamp becomes `UNKNOWN_TIMESTAMP`, each with a warning
+fn row_to_execution(row: &Row) -> rusqlite::Result<CommandExecution> {
+    let id: String = row.get(0)?;
+    let status_str: String = row.get(3)?;
+    let timestamp_str: String = row.get(4)?;
+    
+    let status = serde_json::from_str(&status_str).unwrap_or_else(|_| {
+        warn!("Command execution {} has an unreadable status {:?}", id, status_str);
+        ExecutionStatus::Unknown
+    });
+    let timestamp = match DateTime::parse_from_rfc3339(&timestamp_str) {
+        Ok(ts) => ts.with_timezone(&Utc),
+        Err(_) => {
+            warn!("Command execution {} has an unreadable timestamp {:?}", id, timestamp_str);
+            UNKNOWN_TIMESTAMP
+        }
+    };
+    
+    Ok(CommandExecution {
+        id,
+        command: row.get(1)?,
+        output: row.get(2)?,
+        status,
+        timestamp,
+        duration_ms: row.get::<_, i64>(5)? as u64,
+        agent_query: row.get(6)?,
+        provider: row.get(7)?,
+        model: row.get(8)?,
+        exit_code: row.get(9)?,
+    })
+}
+
+const PREP_SESSION_COLUMNS: &str = "id, exam_type, session_name, status, created_at, updated_at, duration_minutes, focus";
+
+fn row_to_prep_session(row: &Row) -> rusqlite::Result<PrepSession> {
+    let status_str: String = row.get(3)?;
+    let created_at_str: String = row.get(4)?;
+    let updated_at_str: String = row.get(5)?;
+    Ok(PrepSession {
+        id: row.get(0)?,
+        exam_type: row.get(1)?,
+        session_name: row.get(2)?,
+        status: status_str.parse().unwrap_or(SessionStatus::Completed),
+        created_at: created_at_str.parse().unwrap_or_else(|_| Utc::now()),
+        updated_at: updated_at_str.parse().unwrap_or_else(|_| Utc::now()),
+        duration_minutes: row.get(6)?,
+        focus: row.get(7)?,
+    })
+}
+
+const BLOG_POST_COLUMNS: &str = "id, title, tags, status, created_at, updated_at";
+
+fn row_to_blog_post(row: &Row) -> rusqlite::Result<BlogPost> {
+    let tags_str: String = row.get(2)?;
+    let status_str: String = row.get(3)?;
+    let created_at_str: String = row.get(4)?;
+    let updated_at_str: String = row.get(5)?;
+    Ok(BlogPost {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        tags: serde_json::from_str(&tags_str).unwrap_or_default(),
+        status: status_str.parse().unwrap_or(PostStatus::Draft),
+        created_at: created_at_str.parse().unwrap_or_else(|_| Utc::now()),
+        updated_at: updated_at_str.parse().unwrap_or_else(|_| Utc::now()),
+    })
+}
+
+/// A schema change, run inside a transaction
+type Migration = fn(&Connection) -> Result<()>;
+
+/// Schema changes in the order they were made; a database at version N has had the first N
+/// applied. Append new ones (e.g. added columns) at the end and never edit or reorder the list.
+const MIGRATIONS: &[Migration] = &[create_baseline_schema, add_command_embeddings];
+
+/// Version of a database with every migration in this build applied
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Apply the migrations the database hasn't had yet, each in its own transaction together with
+/// its version bump, so a failing one leaves the database at the previous version. Returns the
+/// version reached.
+fn migrate(conn: &mut Connection, migrations: &[Migration]) -> Result<usize> {
+    conn.execute("CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL)", [])?;
+    let current: Option<i64> = conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0))?;
+    let current = current.unwrap_or(0) as usize;
+    if current > migrations.len() {
+        warn!("Database schema version {} is newer than this build knows ({}); continuing", current, migrations.len());
+        return Ok(current);
+    }
+    
+    for (index, migration) in migrations.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let tx = conn.transaction()?;
+        migration(&tx).with_context(|| format!("Database migration to schema version {} failed", version))?;
+        tx.execute("DELETE FROM schema_version", [])?;
+        tx.execute("INSERT INTO schema_version (version) VALUES (?1)", params![version as i64])?;
+        tx.commit()?;
+    }
+    Ok(migrations.len())
+}
+
+/// Version 1: the tables as they were before versioning. Databases from then may be missing
+/// later columns, so those are added when absent.
+fn create_baseline_schema(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS command_executions (
+            id TEXT PRIMARY KEY,
+            command TEXT NOT NULL,
+            output TEXT NOT NULL,
+            status TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            agent_query TEXT
+        )",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tasks (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            description TEXT,
+            priority TEXT NOT NULL,
+            status TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            tags TEXT,
+            due_date TEXT
+        )",
+        [],
+    )?;
+    
+    // Databases created before versioning may predate these columns
+    ensure_column(conn, "command_executions", "provider", "TEXT")?;
+    ensure_column(conn, "command_executions", "model", "TEXT")?;
+    ensure_column(conn, "command_executions", "exit_code", "INTEGER")?;
+    ensure_column(conn, "tasks", "tags", "TEXT")?;
+    ensure_column(conn, "tasks", "due_date", "TEXT")?;
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS prep_sessions (
+            id TEXT PRIMARY KEY,
+            exam_type TEXT NOT NULL,
+            session_name TEXT NOT NULL,
+            status TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            duration_minutes INTEGER NOT NULL DEFAULT 0,
+            focus INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    
+    ensure_column(conn, "prep_sessions", "duration_minutes", "INTEGER NOT NULL DEFAULT 0")?;
+    ensure_column(conn, "prep_sessions", "focus", "INTEGER NOT NULL DEFAULT 0")?;
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS blog_posts (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            tags TEXT NOT NULL DEFAULT '[]',
+            status TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS prep_topics (
+            id TEXT PRIMARY KEY,
+            exam TEXT NOT NULL,
+            topic TEXT NOT NULL,
+            priority INTEGER NOT NULL,
+            last_reviewed TEXT,
+            mastery INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    
+    Ok(())
+}
+
+/// Version 2: embeddings of command texts for semantic history search, keyed by the model
+/// that made them
+fn add_command_embeddings(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS command_embeddings (
+            command TEXT NOT NULL,
+            model TEXT NOT NULL,
+            embedding BLOB NOT NULL,
+            PRIMARY KEY (command, model)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Store an embedding as little-endian `f32`s
+fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4).map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])).collect()
+}
+
+/// Make `%`, `_` and the escape character itself match literally in a `LIKE ... ESCAPE '\'` pattern
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Add a column to an existing table if an older schema is missing it
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+    
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+/// Stands in for a stored timestamp that could not be parsed
+pub const UNKNOWN_TIMESTAMP: DateTime<Utc> = DateTime::<Utc>::UNIX_EPOCH;
+
 impl CommandExecution {
+    pub fn has_unknown_timestamp(&self) -> bool {
+        self.timestamp == UNKNOWN_TIMESTAMP
+    }
+    
+    /// Time of day the command ran, or `??:??:??` when the stored timestamp was corrupt
+    pub fn time_label(&self) -> String {
+        if self.has_unknown_timestamp() {
+            "??:??:??".to_string()
+        } else {
+            self.timestamp.format("%H:%M:%S").to_string()
+        }
+    }
+    
     pub fn new(command: String, agent_query: Option<String>) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: crate::offline::new_id(),
             command,
             output: String::new(),
             status: ExecutionStatus::Running,
-            timestamp: Utc::now(),
+            timestamp: crate::offline::now(),
             duration_ms: 0,
             agent_query,
+            provider: None,
+            model: None,
+            exit_code: None,
         }
     }
 }
+
+/// Check that the database at `path` can be written, or created if it doesn't exist yet,
+/// without creating or changing it
+pub fn check_writable(path: &Path) -> Result<()> {
+    if path.exists() {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        // Taking the write lock fails on read-only files and directories
+        conn.execute_batch("BEGIN IMMEDIATE; ROLLBACK;")?;
+        return Ok(());
+    }
+    // Creating the database needs a writable directory; a file made there is removed right away
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let probe = dir.join(format!(".agentic-probe-{}", uuid::Uuid::new_v4().simple()));
+    std::fs::File::create(&probe)?;
+    std::fs::remove_file(&probe)?;
+    Ok(())
+}
+
+/// A database file that is deleted, along with its WAL and shared-memory files, on drop
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct TempDbFile(pub std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDbFile {
+    pub fn new(prefix: &str) -> Self {
+        TempDbFile(std::env::temp_dir().join(format!("{}-{}.db", prefix, uuid::Uuid::new_v4())))
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDbFile {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = self.0.clone().into_os_string();
+            path.push(suffix);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Create a database in a fresh temporary file for tests; the file goes away with the last clone
+#[cfg(test)]
+pub(crate) async fn test_database() -> Database {
+    let file = TempDbFile::new("agentic-test");
+    let mut db = Database::new(&file.0).await.unwrap();
+    db.temp_file = Some(Arc::new(file));
+    db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_corrupt_history_rows_are_flagged() {
+        let db = test_database().await;
+        let good = CommandExecution::new("ls".to_string(), None);
+        db.save_command_execution(&good).await.unwrap();
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO command_executions (id, command, output, status, timestamp, duration_ms, agent_query)
+                 VALUES ('bad', 'pwd', '', '\"Exploded\"', 'yesterday-ish', 5, NULL)",
+                [],
+            )?;
+            Ok(())
+        }).await.unwrap();
+
+        let history = db.get_command_history(10).await.unwrap();
+        let bad = history.iter().find(|e| e.id == "bad").unwrap();
+        assert!(bad.has_unknown_timestamp());
+        assert!((Utc::now() - bad.timestamp).num_days() > 365, "bad timestamp must not become now");
+        assert!(matches!(bad.status, ExecutionStatus::Unknown));
+        assert_eq!(bad.time_label(), "??:??:??");
+
+        let good = history.iter().find(|e| e.id == good.id).unwrap();
+        assert!(!good.has_unknown_timestamp());
+        assert!(matches!(good.status, ExecutionStatus::Running));
+    }
+
+    #[tokio::test]
+    async fn test_find_task_by_id_prefix_and_title() {
+        let db = test_database().await;
+        let mut task = Task::new("Build dashboard".to_string(), None, Priority::High);
+        task.tags = vec!["work".to_string()];
+        task.due_date = NaiveDate::from_ymd_opt(2024, 5, 1);
+        db.add_task(&task).await.unwrap();
+
+        let by_prefix = db.find_task(&task.id[..8]).await.unwrap().unwrap();
+        assert_eq!(by_prefix.id, task.id);
+        assert_eq!(by_prefix.tags, vec!["work".to_string()]);
+        assert_eq!(by_prefix.due_date, task.due_date);
+
+        let by_title = db.find_task("dashboard").await.unwrap().unwrap();
+        assert_eq!(by_title.id, task.id);
+
+        assert!(db.find_task("nonexistent").await.unwrap().is_none());
+        assert!(db.find_task("%").await.unwrap().is_none(), "% isn't a wildcard");
+        assert!(db.find_task("  ").await.is_err());
+
+        let other = Task::new("Build dashboard tests".to_string(), None, Priority::Low);
+        db.add_task(&other).await.unwrap();
+        let err = db.find_task("dashboard").await.unwrap_err().to_string();
+        assert!(err.contains("matches 2 tasks") && err.contains(&task.id[..8]) && err.contains(&other.id[..8]), "{}", err);
+        assert_eq!(db.find_task("dashboard tests").await.unwrap().unwrap().id, other.id);
+    }
+
+    #[tokio::test]
+    async fn test_open_tasks_for_the_agenda_ignore_the_list_limit() {
+        let db = test_database().await.with_max_limit(2);
+        let due = |date: &str| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok();
+        for (title, due_date, status) in [
+            ("old overdue", due("2024-01-02"), TaskStatus::Todo),
+            ("undated", None, TaskStatus::InProgress),
+            ("done", due("2024-03-10"), TaskStatus::Complete),
+            ("next year", due("2025-03-10"), TaskStatus::Todo),
+            ("this week", due("2024-03-11"), TaskStatus::Todo),
+        ] {
+            let mut task = Task::new(title.to_string(), None, Priority::Medium);
+            task.due_date = due_date;
+            task.status = status;
+            db.add_task(&task).await.unwrap();
+        }
+
+        let (tasks, later) = db.open_tasks_due_before(due("2024-03-17").unwrap()).await.unwrap();
+        let mut titles: Vec<_> = tasks.iter().map(|task| task.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, ["old overdue", "this week", "undated"]);
+        assert_eq!(later, 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_tasks_is_all_or_nothing() {
+        let db = test_database().await;
+        let task = Task::new("Duplicate".to_string(), None, Priority::Low);
+
+        // The second insert violates the primary key, so the first must be rolled back
+        assert!(db.import_tasks(&[task.clone(), task]).await.is_err());
+        assert!(db.list_tasks(DEFAULT_LIST_LIMIT).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_task_stats_counts() {
+        let db = test_database().await;
+        assert_eq!(db.task_stats().await.unwrap().completion_rate(), 0.0);
+
+        let mut old = Task::new("Old".to_string(), None, Priority::High);
+        old.created_at = Utc::now() - chrono::Duration::days(4);
+        db.add_task(&old).await.unwrap();
+        db.add_task(&Task::new("Fresh".to_string(), None, Priority::Low)).await.unwrap();
+        let done = Task::new("Done".to_string(), None, Priority::High);
+        db.add_task(&done).await.unwrap();
+        db.complete_task(&done.id).await.unwrap();
+
+        let stats = db.task_stats().await.unwrap();
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.todo, 2);
+        assert_eq!(stats.complete, 1);
+        assert_eq!(stats.high_priority, 2);
+        assert_eq!(stats.low_priority, 1);
+        assert_eq!(stats.completed_this_week, 1);
+        let age = stats.average_open_age_days.unwrap();
+        assert!(age > 1.9 && age < 2.1, "unexpected average age {}", age);
+        assert!((stats.completion_rate() - 33.33).abs() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_prep_sessions_list_and_stats() {
+        let db = test_database().await;
+        let mut sessions = Vec::new();
+        for (exam, minutes, status, days_ago) in [
+            ("CET", 30, SessionStatus::Completed, 0),
+            ("CET", 90, SessionStatus::Completed, 0),
+            ("JEE", 45, SessionStatus::Completed, 400),
+            ("CET", 60, SessionStatus::Active, 0),
+        ] {
+            let mut session = PrepSession::new(exam.to_string(), format!("{} session", exam), minutes);
+            session.status = status;
+            session.created_at = Utc::now() - chrono::Duration::days(days_ago);
+            sessions.push(session);
+        }
+        db.import_prep_sessions(&sessions).await.unwrap();
+
+        assert_eq!(db.list_prep_sessions(Some("cet"), false, 50).await.unwrap().len(), 3);
+        let active = db.list_prep_sessions(None, true, 50).await.unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].duration_minutes, 60);
+
+        let week = db.prep_stats(StatsPeriod::Week, None).await.unwrap();
+        assert_eq!(week.len(), 1);
+        assert_eq!(week[0].exam_type, "CET");
+        assert_eq!((week[0].sessions, week[0].total_minutes, week[0].longest_minutes), (2, 120, 90));
+        assert_eq!(week[0].average_minutes(), 60);
+
+        let all = db.prep_stats(StatsPeriod::All, None).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(db.prep_stats(StatsPeriod::All, Some("neet")).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_history_matches_command_or_output_literally() {
+        let db = test_database().await;
+        let commands = [("cargo build", "Compiling agentic"), ("ls", "Cargo.toml\nsrc"), ("echo 100%", "100%"), ("pwd", "/home")];
+        for (age, (command, output)) in commands.iter().enumerate() {
+            let mut execution = CommandExecution::new(command.to_string(), None);
+            execution.output = output.to_string();
+            execution.timestamp = Utc::now() - chrono::Duration::minutes(age as i64);
+            db.save_command_execution(&execution).await.unwrap();
+        }
+
+        let found: Vec<String> = db.search_history("cargo", 10).await.unwrap().into_iter().map(|e| e.command).collect();
+        assert_eq!(found, ["cargo build", "ls"], "newest first, matching the output too");
+        assert_eq!(db.search_history("0%", 10).await.unwrap().len(), 1, "% is matched literally");
+        assert!(db.search_history("_", 10).await.unwrap().is_empty());
+        assert_eq!(db.search_history("cargo", 1).await.unwrap().len(), 1);
+
+        let ls = db.search_history("ls", 10).await.unwrap().remove(0);
+        let found = db.find_command_execution(&ls.id[..8]).await.unwrap().unwrap();
+        assert_eq!(found.command, "ls");
+        assert!(db.find_command_execution("nope").await.unwrap().is_none());
+        assert!(db.find_command_execution("  ").await.is_err(), "an empty id must not pick the newest command");
+
+        for (id, command) in [("abc1", "pwd"), ("abc12", "whoami")] {
+            let mut execution = CommandExecution::new(command.to_string(), None);
+            execution.id = id.to_string();
+            db.save_command_execution(&execution).await.unwrap();
+        }
+        let error = db.find_command_execution("abc").await.unwrap_err().to_string();
+        assert!(error.contains("matches 2 recorded commands") && error.contains("abc1  pwd") && error.contains("abc12  whoami"), "{}", error);
+        assert_eq!(db.find_command_execution("abc1").await.unwrap().unwrap().command, "pwd", "an exact id wins");
+        assert_eq!(db.find_command_execution("abc12").await.unwrap().unwrap().command, "whoami");
+    }
+
+    #[tokio::test]
+    async fn test_history_is_pruned_and_deleted_by_filter() {
+        let db = test_database().await.with_max_history(4);
+        for (age, status) in [ExecutionStatus::Success, ExecutionStatus::Error, ExecutionStatus::Error, ExecutionStatus::Success, ExecutionStatus::Error]
+            .into_iter()
+            .enumerate()
+            .rev()
+        {
+            let mut execution = CommandExecution::new(format!("cmd-{}", age), None);
+            execution.status = status;
+            execution.timestamp = Utc::now() - chrono::Duration::days(age as i64);
+            db.save_command_execution(&execution).await.unwrap();
+        }
+        let kept: Vec<String> = db.get_command_history(10).await.unwrap().into_iter().map(|e| e.command).collect();
+        assert_eq!(kept, ["cmd-0", "cmd-1", "cmd-2", "cmd-3"], "the oldest run is pruned");
+
+        let old_errors = HistoryFilter { before: Some(Utc::now() - chrono::Duration::hours(36)), status: Some(ExecutionStatus::Error) };
+        assert_eq!(db.count_history(&old_errors).await.unwrap(), 1);
+        assert_eq!(db.delete_history(&old_errors).await.unwrap(), 1);
+        let errors = HistoryFilter { status: Some(ExecutionStatus::Error), ..Default::default() };
+        assert_eq!(db.delete_history(&errors).await.unwrap(), 1);
+        assert_eq!(db.delete_history(&HistoryFilter::default()).await.unwrap(), 2);
+        assert!(db.get_command_history(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_migrations_upgrade_old_databases_once() {
+        let file = TempDbFile::new("agentic-legacy");
+        let path = file.0.clone();
+        {
+            // A database from before versioning, without the provider/model/exit_code columns
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE command_executions (id TEXT PRIMARY KEY, command TEXT NOT NULL, output TEXT NOT NULL,
+                    status TEXT NOT NULL, timestamp TEXT NOT NULL, duration_ms INTEGER NOT NULL, agent_query TEXT);
+                 INSERT INTO command_executions VALUES ('old', 'ls', '', '\"Success\"', '2024-01-01T00:00:00+00:00', 3, NULL);",
+            ).unwrap();
+        }
+
+        let db = Database::new(&path).await.unwrap();
+        let history = db.get_command_history(10).await.unwrap();
+        assert_eq!((history[0].id.as_str(), history[0].exit_code), ("old", None));
+        db.save_command_execution(&CommandExecution::new("pwd".to_string(), None)).await.unwrap();
+        Database::new(&path).await.unwrap();
+
+        let mut conn = Connection::open(&path).unwrap();
+        let version: i64 = conn.query_row("SELECT version FROM schema_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version as usize, SCHEMA_VERSION);
+
+        // A failing migration is rolled back along with its version bump
+        let add_column: Migration = |conn| Ok(conn.execute_batch("ALTER TABLE tasks ADD COLUMN estimate INTEGER")?);
+        let broken: Migration = |conn| {
+            conn.execute_batch("ALTER TABLE tasks ADD COLUMN half_done TEXT")?;
+            Err(anyhow::anyhow!("boom"))
+        };
+        let migrations = [MIGRATIONS, &[add_column, broken]].concat();
+        assert!(migrate(&mut conn, &migrations).is_err());
+        let version: i64 = conn.query_row("SELECT version FROM schema_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version as usize, SCHEMA_VERSION + 1);
+        assert!(conn.prepare("SELECT estimate FROM tasks").is_ok());
+        assert!(conn.prepare("SELECT half_done FROM tasks").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_test_databases_leave_no_files_behind() {
+        let db = test_database().await;
+        let path = db.temp_file.as_ref().unwrap().0.clone();
+        db.save_command_execution(&CommandExecution::new("ls".to_string(), None)).await.unwrap();
+        let clone = db.clone();
+        drop(db);
+        assert!(path.exists());
+        drop(clone);
+        let wal = format!("{}-wal", path.display());
+        assert!(!path.exists() && !Path::new(&wal).exists());
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_one_wal_connection() {
+        let db = test_database().await;
+        let mode = db.with_conn(|conn| Ok(conn.query_row("PRAGMA journal_mode", [], |row| row.get::<_, String>(0))?)).await.unwrap();
+        assert_eq!(mode, "wal");
+
+        let saves = (0..20).map(|i| {
+            let db = db.clone();
+            tokio::spawn(async move { db.save_command_execution(&CommandExecution::new(format!("echo {}", i), None)).await })
+        });
+        for save in saves.collect::<Vec<_>>() {
+            save.await.unwrap().unwrap();
+        }
+        assert_eq!(db.get_command_history(50).await.unwrap().len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_absurd_limit_is_clamped() {
+        let db = test_database().await;
+        assert_eq!(db.clamp_limit(1_000_000_000), MAX_LIST_LIMIT);
+        assert_eq!(db.clamp_limit(0), 1);
+        assert_eq!(db.clamp_limit(20), 20);
+
+        let db = db.with_max_limit(200);
+        assert_eq!(db.clamp_limit(usize::MAX), 200);
+    }
+}
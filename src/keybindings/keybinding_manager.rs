@@ -3,6 +3,39 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Keyset loaded when the TUI starts
+pub const DEFAULT_KEYSET: &str = "default";
+
+/// Built-in bindings of the TUI's named actions, used when no keyset file overrides them
+pub const DEFAULT_BINDINGS: &[(&str, &str)] = &[
+    ("app:quit", "ctrl-q"),
+    ("agent:toggle", "ctrl-a"),
+    ("agent:reset", "ctrl-n"),
+    ("help:toggle", "?"),
+    ("settings:toggle", "ctrl-comma"),
+    ("scroll:up", "up"),
+    ("scroll:down", "down"),
+    ("scroll:page_up", "pageup"),
+    ("scroll:page_down", "pagedown"),
+    ("scroll:top", "home"),
+    ("scroll:bottom", "end"),
+    ("block:inspect", "i"),
+    ("block:toggle", "space"),
+    ("block:scroll_up", "shift-up"),
+    ("block:scroll_down", "shift-down"),
+    ("block:copy_command", "y"),
+    ("block:copy_output", "Y"),
+    ("search:start", "/"),
+    ("input:focus", "enter"),
+    ("palette:toggle", "ctrl-p"),
+];
+
+/// `~/.agentic/keysets`, where user keysets live
+pub fn user_keyset_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".agentic").join("keysets")
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct KeyBinding {
@@ -70,7 +103,12 @@ impl KeyBinding {
             "comma" => KeyCode::Char(','),
             "period" => KeyCode::Char('.'),
             key if key.len() == 1 => {
-                let ch = key.chars().next().unwrap();
+                // A lone capital is a shifted letter, which terminals report as that capital;
+                // with Ctrl or Alt they report the lowercase letter
+                let ch = match key_part.chars().next().unwrap() {
+                    upper if modifiers.is_empty() && upper.is_ascii_uppercase() => upper,
+                    _ => key.chars().next().unwrap(),
+                };
                 KeyCode::Char(ch)
             }
             _ => anyhow::bail!("Unknown key: {}", key_part),
@@ -84,6 +122,86 @@ impl KeyBinding {
     }
 }
 
+/// A problem in a keyset file, with the 1-based line it is on when that can be found
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeysetError {
+    /// The key string doesn't parse; the action is left unbound
+    InvalidKey { line: Option<usize>, command: String, key: String, reason: String },
+    /// The key is already bound to an earlier action, which keeps it
+    Conflict { line: Option<usize>, command: String, key: String, bound_to: String },
+}
+
+impl KeysetError {
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            KeysetError::InvalidKey { line, .. } | KeysetError::Conflict { line, .. } => *line,
+        }
+    }
+}
+
+impl std::fmt::Display for KeysetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line() {
+            write!(f, "line {}: ", line)?;
+        }
+        match self {
+            KeysetError::InvalidKey { command, key, reason, .. } => {
+                write!(f, "{} has an invalid key '{}': {}", command, key, reason)
+            }
+            KeysetError::Conflict { command, key, bound_to, .. } => {
+                write!(f, "{} uses '{}', which is already bound to {}; ignored", command, key, bound_to)
+            }
+        }
+    }
+}
+
+/// The line defining `command` in keyset YAML, quoted or not
+fn line_of(content: &str, command: &str) -> Option<usize> {
+    let forms = [format!("{}:", command), format!("\"{}\":", command), format!("'{}':", command)];
+    content
+        .lines()
+        .position(|line| forms.iter().any(|form| line.trim_start().starts_with(form.as_str())))
+        .map(|index| index + 1)
+}
+
+/// The usable bindings of a keyset file, in file order, and the problems with the rest
+#[derive(Debug, Default)]
+pub struct ParsedKeyset {
+    pub bindings: Vec<(String, KeyBinding)>,
+    pub errors: Vec<KeysetError>,
+}
+
+pub fn parse_keyset(content: &str) -> Result<ParsedKeyset> {
+    let mapping: serde_yaml::Mapping = serde_yaml::from_str(content)?;
+    let mut bindings: Vec<(String, KeyBinding)> = Vec::new();
+    let mut errors = Vec::new();
+    for (command, key) in mapping {
+        let command = match command {
+            serde_yaml::Value::String(command) => command,
+            other => serde_yaml::to_string(&other).unwrap_or_default().trim().to_string(),
+        };
+        let line = line_of(content, &command);
+        let Some(key_str) = key.as_str() else {
+            let key = serde_yaml::to_string(&key).unwrap_or_default().trim().to_string();
+            errors.push(KeysetError::InvalidKey { line, command, key, reason: "expected a key like ctrl-q".to_string() });
+            continue;
+        };
+        match KeyBinding::from_string(key_str) {
+            Ok(binding) => match bindings.iter().find(|(_, existing)| *existing == binding) {
+                Some((bound_to, _)) => errors.push(KeysetError::Conflict {
+                    line,
+                    command,
+                    key: key_str.to_string(),
+                    bound_to: bound_to.clone(),
+                }),
+                None => bindings.push((command, binding)),
+            },
+            Err(e) => errors.push(KeysetError::InvalidKey { line, command, key: key_str.to_string(), reason: e.to_string() }),
+        }
+    }
+    Ok(ParsedKeyset { bindings, errors })
+}
+
 pub struct KeyBindingManager {
     bindings: HashMap<String, KeyBinding>,
     reverse_bindings: HashMap<KeyBinding, String>,
@@ -98,51 +216,115 @@ impl KeyBindingManager {
             reverse_bindings: HashMap::new(),
             keyset_directories: vec![
                 PathBuf::from("keysets"),
-                PathBuf::from("~/.agentic/keysets"),
+                user_keyset_dir(),
             ],
             current_keyset: None,
         }
     }
 
-    pub fn add_keyset_directory<P: AsRef<Path>>(&mut self, path: P) {
-        self.keyset_directories.push(path.as_ref().to_path_buf());
+    /// A manager holding `DEFAULT_BINDINGS`
+    pub fn with_defaults() -> Self {
+        let mut manager = Self::new();
+        manager.reset_to_defaults();
+        manager
     }
 
-    pub fn load_keyset(&mut self, keyset_name: &str) -> Result<()> {
-        let mut keyset_path = None;
-        
-        // Find the keyset file
-        for dir in &self.keyset_directories {
-            let path = dir.join(format!("{}.yaml", keyset_name));
-            if path.exists() {
-                keyset_path = Some(path);
-                break;
+    fn reset_to_defaults(&mut self) {
+        self.bindings.clear();
+        self.reverse_bindings.clear();
+        for (command, key) in DEFAULT_BINDINGS {
+            let binding = KeyBinding::from_string(key).expect("built-in key bindings parse");
+            self.add_binding(command.to_string(), binding);
+        }
+    }
+
+    /// Apply the `default` keyset over the built-in bindings if one exists; a broken keyset
+    /// file is reported and the built-ins are kept
+    pub fn load_default_keyset(&mut self) {
+        match self.load_keyset(DEFAULT_KEYSET) {
+            Ok(_) => debug!("Loaded keyset '{}'", DEFAULT_KEYSET),
+            Err(e) if self.find_keyset(DEFAULT_KEYSET).is_none() => debug!("Using built-in key bindings: {}", e),
+            Err(e) => warn!("Keeping built-in key bindings: {:#}", e),
+        }
+    }
+
+    /// Write `DEFAULT_BINDINGS` to `~/.agentic/keysets/default.yaml` unless it exists, so the
+    /// bindings can be edited; returns the path when the file was created
+    pub fn write_default_keyset() -> Result<Option<PathBuf>> {
+        Self::write_default_keyset_in(&user_keyset_dir())
+    }
+
+    pub fn write_default_keyset_in(dir: &Path) -> Result<Option<PathBuf>> {
+        let path = dir.join(format!("{}.yaml", DEFAULT_KEYSET));
+        if path.exists() {
+            return Ok(None);
+        }
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create keyset directory: {:?}", dir))?;
+
+        let mut yaml = String::from(
+            "# Agentic TUI key bindings, as `action: key`\n\
+             # Modifiers: ctrl-, alt-, shift- (e.g. ctrl-shift-r); keys: a-z, enter, esc, tab, up,\n\
+             # down, pageup, pagedown, home, end, f1-f12, space, comma, period, slash, grave\n",
+        );
+        let mut category = "";
+        for (command, key) in DEFAULT_BINDINGS {
+            let prefix = command.split(':').next().unwrap_or_default();
+            if prefix != category {
+                yaml.push_str(&format!("\n# {}\n", prefix));
+                category = prefix;
             }
+            yaml.push_str(&format!("\"{}\": \"{}\"\n", command, key));
+        }
+        fs::write(&path, yaml)
+            .with_context(|| format!("Failed to write keyset to: {:?}", path))?;
+        Ok(Some(path))
+    }
+
+    fn find_keyset(&self, keyset_name: &str) -> Option<PathBuf> {
+        self.keyset_directories
+            .iter()
+            .map(|dir| dir.join(format!("{}.yaml", keyset_name)))
+            .find(|path| path.exists())
+    }
+
+    /// The file of the keyset called `name`, or `name` itself when it is a path to a file
+    pub fn keyset_path(&self, name: &str) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.is_file() {
+            return Some(path.to_path_buf());
         }
+        self.find_keyset(name)
+    }
+
+    pub fn add_keyset_directory<P: AsRef<Path>>(&mut self, path: P) {
+        self.keyset_directories.push(path.as_ref().to_path_buf());
+    }
 
-        let path = keyset_path
+    /// Reset to `DEFAULT_BINDINGS` and apply the keyset's bindings on top, so a keyset written
+    /// before an action existed still gets its built-in key. A keyset entry takes its key from
+    /// whichever built-in had it. Entries with a bad key or one that is already taken in the
+    /// file are skipped, logged, and returned so they can be shown.
+    pub fn load_keyset(&mut self, keyset_name: &str) -> Result<Vec<KeysetError>> {
+        let path = self.find_keyset(keyset_name)
             .ok_or_else(|| anyhow::anyhow!("Keyset '{}' not found", keyset_name))?;
 
         let content = fs::read_to_string(&path)
             .with_context(|| format!("Failed to read keyset file: {:?}", path))?;
 
-        let keyset_data: HashMap<String, String> = serde_yaml::from_str(&content)
+        let ParsedKeyset { bindings: keyset_data, errors } = parse_keyset(&content)
             .with_context(|| format!("Failed to parse keyset YAML: {:?}", path))?;
+        for error in &errors {
+            warn!("Keyset {:?}: {}", path, error);
+        }
 
-        // Clear existing bindings
-        self.bindings.clear();
-        self.reverse_bindings.clear();
-
-        // Load new bindings
-        for (command, key_str) in keyset_data {
-            if let Ok(key_binding) = KeyBinding::from_string(&key_str) {
-                self.bindings.insert(command.clone(), key_binding.clone());
-                self.reverse_bindings.insert(key_binding, command);
-            }
+        self.reset_to_defaults();
+        for (command, key_binding) in keyset_data {
+            self.add_binding(command, key_binding);
         }
 
         self.current_keyset = Some(keyset_name.to_string());
-        Ok(())
+        Ok(errors)
     }
 
     pub fn get_command_for_key(&self, event: &KeyEvent) -> Option<&String> {
@@ -150,7 +332,12 @@ impl KeyBindingManager {
             key: event.code,
             modifiers: event.modifiers,
         };
-        self.reverse_bindings.get(&key_binding)
+        self.reverse_bindings.get(&key_binding).or_else(|| {
+            // Terminals report `?` as Shift+?; the character already says shift was held
+            let KeyCode::Char(_) = event.code else { return None };
+            let unshifted = KeyBinding { key: event.code, modifiers: event.modifiers - KeyModifiers::SHIFT };
+            self.reverse_bindings.get(&unshifted)
+        })
     }
 
     pub fn get_key_for_command(&self, command: &str) -> Option<&KeyBinding> {
@@ -226,7 +413,7 @@ impl KeyBindingManager {
         Ok(())
     }
 
-    fn key_binding_to_string(&self, key_binding: &KeyBinding) -> String {
+    pub fn key_binding_to_string(&self, key_binding: &KeyBinding) -> String {
         let mut parts = Vec::new();
 
         if key_binding.modifiers.contains(KeyModifiers::CONTROL) {
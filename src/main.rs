@@ -1,5 +1,5 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use tracing::{info, warn};
 use tracing_subscriber;
 
@@ -10,6 +10,10 @@ mod db;
 mod ui;
 mod warp;
 mod ollama;
+mod offline;
+mod retry;
+#[cfg(test)]
+mod mock_http;
 
 // Warp-inspired modules
 mod themes;
@@ -25,10 +29,10 @@ use ui::App;
 #[derive(Parser)]
 #[command(name = "agentic")]
 #[command(about = "A Warp-inspired agentic terminal interface")]
-#[command(version = "0.1.0")]
+#[command(version, long_version = commands::version::LONG_VERSION)]
 struct Cli {
     #[command(subcommand)]
-    command: Option<Commands>,
+    command: Option<CliCommand>,
     
     /// Enable debug logging
     #[arg(long, short)]
@@ -37,6 +41,26 @@ struct Cli {
     /// Use interactive TUI mode
     #[arg(long, short)]
     interactive: bool,
+    
+    /// Print JSON to stdout instead of text (task list, prep stats, blog list, agent queries, warp dry runs)
+    #[arg(long, global = true)]
+    json: bool,
+}
+
+/// What the TUI accepts: a subcommand, without the flags that only make sense at startup
+#[derive(Parser)]
+#[command(name = "agentic", no_binary_name = true)]
+struct TuiInput {
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    #[command(flatten)]
+    Session(Commands),
+    #[command(flatten)]
+    Standalone(StandaloneCommand),
 }
 
 #[derive(Subcommand)]
@@ -57,86 +81,379 @@ enum Commands {
         blog_cmd: commands::blog::BlogCommand,
     },
     /// Agent interaction commands
+    #[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
     Agent {
+        #[command(subcommand)]
+        agent_cmd: Option<commands::agent::AgentCommand>,
         /// Natural language query for the agent
-        query: String,
+        query: Option<String>,
+        /// Save the recorded agent conversation as markdown to this path
+        #[arg(long, value_name = "PATH")]
+        export: Option<std::path::PathBuf>,
+        /// Ask this model instead of the configured one
+        #[arg(long, value_name = "NAME")]
+        model: Option<String>,
+        /// Sampling temperature for this query, from 0.0 to 2.0
+        #[arg(long, value_name = "0.0-2.0", value_parser = agent::parse_temperature)]
+        temp: Option<f32>,
+        /// Run the shell command the answer suggests, after asking; dangerous ones are refused
+        #[arg(long)]
+        exec: bool,
+        /// With --exec, run the command without asking
+        #[arg(long, short, requires = "exec")]
+        yes: bool,
     },
     /// Warp-mode pipeline: natural language to shell commands
+    #[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
     Warp {
+        #[command(subcommand)]
+        warp_cmd: Option<commands::warp::WarpCommand>,
         /// Natural language description of what you want to do
-        request: String,
-        /// Execute in dry-run mode (no actual execution)
+        request: Option<String>,
+        /// Execute in dry-run mode (no actual execution); with --json print the plan, command
+        /// and the models that wrote them as JSON
         #[arg(long)]
         dry_run: bool,
+        /// Stream command output as it is produced, overriding the config
+        #[arg(long, conflicts_with = "no_stream")]
+        stream: bool,
+        /// Buffer command output until the command finishes, overriding the config
+        #[arg(long)]
+        no_stream: bool,
+        /// Run the suggested command without asking for confirmation; dangerous ones are still confirmed twice
+        #[arg(long, short)]
+        yes: bool,
+        /// Run the command in this directory instead of the configured working_directory
+        #[arg(long, value_name = "DIR")]
+        cwd: Option<String>,
+        /// Plan and write the command with this model instead of the configured ones
+        #[arg(long, value_name = "NAME")]
+        model: Option<String>,
+        /// Sampling temperature for this run, from 0.0 to 2.0
+        #[arg(long, value_name = "0.0-2.0", value_parser = agent::parse_temperature)]
+        temp: Option<f32>,
+    },
+    /// Break a goal into dependent shell steps and run them one by one
+    Plan {
+        /// What you want to get done
+        goal: String,
+        /// Run each step without asking; dangerous steps still need YES
+        #[arg(long, short)]
+        yes: bool,
     },
     /// Run arbitrary commands
     Run {
         /// Command to execute
         command: String,
+        /// Run the command this many times and report aggregate timing
+        #[arg(long, short = 'n', default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        count: u32,
+        /// With --count, stop at the first failing run
+        #[arg(long)]
+        fail_fast: bool,
+        /// Stream command output as it is produced, overriding the config
+        #[arg(long, conflicts_with = "no_stream")]
+        stream: bool,
+        /// Buffer command output until the command finishes, overriding the config
+        #[arg(long)]
+        no_stream: bool,
+    },
+    /// Print the embedding vector Ollama computes for some text
+    Embed {
+        /// Text to embed
+        text: String,
+        /// Embedding model to use instead of the agent's model, e.g. `nomic-embed-text`
+        #[arg(long, value_name = "NAME")]
+        model: Option<String>,
+        /// Print only the number of dimensions
+        #[arg(long)]
+        dims: bool,
+    },
+    /// List local Ollama models and check the configured ones are installed
+    Models {
+        #[command(subcommand)]
+        models_cmd: Option<commands::models::ModelsCommand>,
+    },
+    /// Configuration file management
+    Config {
+        #[command(subcommand)]
+        config_cmd: commands::config::ConfigCommand,
+    },
+    /// TUI key bindings
+    Keys {
+        #[command(subcommand)]
+        keys_cmd: commands::keys::KeysCommand,
+    },
+    /// List, preview and choose TUI color themes
+    Theme {
+        #[command(subcommand)]
+        theme_cmd: commands::theme::ThemeCommand,
+    },
+    /// Search recorded commands and run them again
+    History {
+        #[command(subcommand)]
+        history_cmd: commands::history::HistoryCommand,
+    },
+    /// Short names for commands, from `[aliases]` in the config
+    Alias {
+        #[command(subcommand)]
+        alias_cmd: commands::alias::AliasCommand,
+    },
+    /// List, inspect and run workflows from ./workflows and ~/.agentic/workflows
+    Workflow {
+        #[command(subcommand)]
+        workflow_cmd: commands::workflow::WorkflowCommand,
     },
     /// Start the interactive TUI
     Tui,
 }
 
+/// Subcommands that run before the config and database are loaded, so they still work, and can
+/// report on them, when those are broken
+#[derive(Subcommand)]
+enum StandaloneCommand {
+    /// Check Ollama, models, API keys, config and database for setup problems
+    Doctor {
+        /// Offer to fix what can be fixed: create missing directories, write a default config, pull missing models
+        #[arg(long)]
+        fix: bool,
+        /// Apply the fixes without asking
+        #[arg(long, short, requires = "fix")]
+        yes: bool,
+    },
+    /// Show build and environment details for bug reports
+    Version,
+    /// Print a shell completion script for bash, zsh, fish or powershell
+    #[command(after_help = commands::completions::INSTALL_HELP)]
+    Completions {
+        shell: clap_complete::Shell,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     
-    // Initialize tracing
-    if cli.debug {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::DEBUG)
-            .init();
+    let output = commands::Output::from_json_flag(cli.json);
+    output.configure_colors();
+    
+    // Initialize tracing; with --json stdout carries only the JSON, so logs go to stderr
+    let level = if cli.debug { tracing::Level::DEBUG } else { tracing::Level::INFO };
+    let subscriber = tracing_subscriber::fmt().with_max_level(level);
+    if output.is_json() {
+        subscriber.with_writer(std::io::stderr).init();
     } else {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::INFO)
-            .init();
+        subscriber.init();
     }
     
+    let command = match cli.command {
+        Some(CliCommand::Standalone(command)) => return run_standalone(command).await,
+        Some(CliCommand::Session(command)) => Some(command),
+        None => None,
+    };
     info!("Starting agentic-cli");
     
     // Initialize configuration
     let config = Config::load().await?;
+    // An editable copy of the TUI key bindings; not having one only means the built-ins are used
+    match keybindings::keybinding_manager::KeyBindingManager::write_default_keyset() {
+        Ok(Some(path)) => info!("Wrote the default keyset to {:?}", path),
+        Ok(None) => {}
+        Err(e) => warn!("Couldn't write the default keyset: {:#}", e),
+    }
     // Initialize database
-    let db = Database::new(&config.database_path).await?;
+    let db = Database::new(&config.database_path).await?
+        .with_max_limit(config.max_list_limit)
+        .with_max_history(config.max_history_size);
     // Initialize agent
     let agent = Agent::new(&config)?;
     
     // Initialize command registry
-    let command_registry = CommandRegistry::new();
+    let command_registry = CommandRegistry::new().with_aliases(config.aliases.clone());
     
-    match cli.command {
+    match command {
         Some(Commands::Task { task_cmd }) => {
-            command_registry.execute_task(task_cmd, &db).await?;
+            command_registry.execute_task(task_cmd, &db, output).await?;
         }
         Some(Commands::Prep { prep_cmd }) => {
-            command_registry.execute_prep(prep_cmd, &db).await?;
+            command_registry.execute_prep(prep_cmd, &db, &config, output).await?;
         }
         Some(Commands::Blog { blog_cmd }) => {
-            command_registry.execute_blog(blog_cmd, &db).await?;
+            command_registry.execute_blog(blog_cmd, &db, &config, output).await?;
         }
-        Some(Commands::Agent { query }) => {
-            let response = agent.process_query(&query).await?;
-            println!("{}", response);
+        Some(Commands::Agent { agent_cmd: Some(agent_cmd), .. }) => {
+            let output = command_registry.execute_agent(agent_cmd, &config, output).await?;
+            println!("{}", output);
         }
-        Some(Commands::Warp { request, dry_run }) => {
-            let pipeline = warp::WarpPipeline::new(&config)?;
-            if dry_run {
-                let (_plan, command) = pipeline.dry_run(&request).await?;
-                println!("\n{} Would execute: {}", "📋", command);
+        Some(Commands::Agent { query, export, model, temp, exec, yes, .. }) => {
+            if exec && output.is_json() {
+                anyhow::bail!("--exec can't be combined with --json");
+            }
+            if let Some(query) = query {
+                let overrides = agent::ModelOverrides { model, temperature: temp };
+                let mut execution = db::CommandExecution::new(
+                    format!("agent {}", shell_words::quote(&query)),
+                    Some(query.clone()),
+                );
+                agent.attribute(&mut execution, &overrides);
+                let started = std::time::Instant::now();
+                let (response, stats) = agent.process_query_with_stats(&query, &overrides).await?;
+                execution.output = response.clone();
+                execution.status = db::ExecutionStatus::Success;
+                execution.duration_ms = started.elapsed().as_millis() as u64;
+                db.save_command_execution(&execution).await?;
+                if output.is_json() {
+                    commands::output::print_json(&serde_json::json!({
+                        "query": query,
+                        "response": response,
+                        "provider": execution.provider,
+                        "model": execution.model,
+                        "duration_ms": execution.duration_ms,
+                    }))?;
+                } else {
+                    println!("{}", response);
+                }
+                if let Some(stats) = stats.filter(|_| cli.debug) {
+                    let message = format!("⚡ {}", stats.summary());
+                    if output.is_json() {
+                        eprintln!("{}", message);
+                    } else {
+                        println!("{}", message);
+                    }
+                }
+                if exec {
+                    match commands::agent::execute_suggestion(&response, &db, &config, yes, true).await? {
+                        Some(run) if !matches!(run.status, db::ExecutionStatus::Success) => {
+                            anyhow::bail!("`{}` failed (exit code {})", run.command, run.exit_code.unwrap_or(1));
+                        }
+                        Some(_) => {}
+                        None => println!("Nothing run"),
+                    }
+                }
+            }
+            if let Some(path) = export {
+                let exchanges = commands::agent::export_conversation(&db, &path).await?;
+                let message = format!("📝 Exported {} agent exchanges to {}", exchanges, path.display());
+                if output.is_json() {
+                    eprintln!("{}", message);
+                } else {
+                    println!("{}", message);
+                }
+            }
+        }
+        Some(Commands::Warp { warp_cmd: Some(warp_cmd), .. }) => {
+            println!("{}", command_registry.execute_warp(warp_cmd, output)?);
+        }
+        Some(Commands::Warp { request, dry_run, stream, no_stream, yes, cwd, model, temp, .. }) => {
+            let request = request.unwrap_or_default();
+            let pipeline = warp::WarpPipeline::new(&config).await?
+                .with_model_overrides(&agent::ModelOverrides { model, temperature: temp })
+                .with_streaming(warp::streaming_override(stream, no_stream))
+                .with_auto_confirm(yes)
+                .with_working_dir(cwd.as_deref());
+            if dry_run && output.is_json() {
+                commands::output::print_json(&pipeline.plan_only(&request).await?)?;
+            } else if dry_run {
+                let dry_run = pipeline.dry_run(&request).await?;
+                println!("\n{} Would execute: {}", "📋", dry_run.command);
+            } else if output.is_json() {
+                anyhow::bail!("--json only works with --dry-run for warp");
             } else {
                 let result = pipeline.execute(&request).await?;
+                db.save_command_execution(&pipeline.record(&result)).await?;
+                if result.is_interrupted() {
+                    std::process::exit(warp::shell_runner::INTERRUPTED_EXIT_CODE);
+                }
                 if !result.is_success() && !result.cancelled {
                     std::process::exit(1);
                 }
             }
         }
-        Some(Commands::Run { command }) => {
-            command_registry.execute_raw_command(&command).await?;
+        Some(Commands::Plan { goal, yes }) => {
+            if !command_registry.execute_plan(&goal, &agent, yes).await? {
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Run { command, count, fail_fast, stream, no_stream }) => {
+            let streaming = warp::streaming_override(stream, no_stream)
+                .unwrap_or(warp::WarpConfig::load().await?.streaming);
+            commands::prep::check_focus_lock(&db, &config.prep, &command).await?;
+            if count > 1 {
+                let summary = command_registry
+                    .execute_repeated(&command, commands::RepeatOptions { count, fail_fast }, streaming, &db, warp::shell_runner::ctrl_c(), |run| {
+                        println!("{}", run.describe());
+                    })
+                    .await?;
+                println!("\n{}", summary.render());
+                if summary.interrupted {
+                    std::process::exit(warp::shell_runner::INTERRUPTED_EXIT_CODE);
+                }
+                if !summary.all_succeeded() {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            let result = command_registry
+                .execute_recorded_command(&command, streaming, &db, warp::shell_runner::ctrl_c())
+                .await;
+            match result {
+                Ok(stdout) => {
+                    if !streaming && !stdout.trim().is_empty() {
+                        println!("{}", stdout);
+                    }
+                }
+                Err(e) if e.is::<commands::Interrupted>() => {
+                    eprintln!("\n⏹ Interrupted, command stopped");
+                    std::process::exit(warp::shell_runner::INTERRUPTED_EXIT_CODE);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Some(Commands::Embed { text, model, dims }) => {
+            println!("{}", command_registry.execute_embed(&text, model, dims, &config, output).await?);
+        }
+        Some(Commands::Models { models_cmd: None }) => {
+            let output = command_registry.execute_models(&config).await?;
+            println!("{}", output);
+        }
+        Some(Commands::Models { models_cmd: Some(commands::models::ModelsCommand::Use { model, .. }) }) => {
+            let mut config = config;
+            let output = command_registry.execute_models_use(&model, &mut config).await?;
+            println!("{}", output);
+        }
+        Some(Commands::Config { config_cmd }) => {
+            let output = command_registry.execute_config(config_cmd).await?;
+            println!("{}", output);
+        }
+        Some(Commands::Keys { keys_cmd }) => {
+            let output = command_registry.execute_keys(keys_cmd).await?;
+            println!("{}", output);
+        }
+        Some(Commands::Theme { theme_cmd }) => {
+            let mut config = config;
+            let output = command_registry.execute_theme(theme_cmd, &mut config).await?;
+            println!("{}", output);
+        }
+        Some(Commands::History { history_cmd }) => {
+            let output = command_registry.execute_history(history_cmd, &db, &config, true).await?;
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+        }
+        Some(Commands::Alias { alias_cmd }) => {
+            let mut config = config;
+            let output = command_registry.execute_alias(alias_cmd, &mut config).await?;
+            println!("{}", output);
+        }
+        Some(Commands::Workflow { workflow_cmd }) => {
+            let output = command_registry.execute_workflow(workflow_cmd, true).await?;
+            if !output.is_empty() {
+                println!("{}", output);
+            }
         }
         Some(Commands::Tui) | None => {
             // Start interactive TUI mode
-            if cli.interactive || cli.command.is_none() {
+            if cli.interactive || command.is_none() {
                 start_tui_mode(config, db, agent, command_registry).await?;
             }
         }
@@ -145,6 +462,30 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+async fn run_standalone(command: StandaloneCommand) -> Result<()> {
+    match command {
+        StandaloneCommand::Doctor { fix, yes } => {
+            let registry = CommandRegistry::new();
+            let mut report = registry.execute_doctor().await;
+            println!("{}", report.render());
+            if fix && !report.fixes().is_empty() {
+                for action in registry.execute_doctor_fix(&report, yes).await {
+                    println!("{}", action);
+                }
+                report = registry.execute_doctor().await;
+                println!("\n{}", report.render());
+            }
+            if !report.passed() {
+                std::process::exit(1);
+            }
+        }
+        StandaloneCommand::Version => print!("{}", CommandRegistry::new().execute_version().await),
+        // Completion scripts are redirected into files, so nothing else may reach stdout
+        StandaloneCommand::Completions { shell } => print!("{}", commands::completions::render(shell, &mut Cli::command())),
+    }
+    Ok(())
+}
+
 async fn start_tui_mode(
     config: Config,
     db: Database,
@@ -0,0 +1,57 @@
+//! A tiny HTTP server on localhost for tests of the model API clients
+
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// How long the server waits for the next request before it stops
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Answer each request with what `respond` returns for it, as `(status, body)`; the server
+/// stops when `respond` returns `None` or no request arrives for a while. Returns the base URL
+/// and the raw requests that were received.
+pub async fn serve<F>(mut respond: F) -> (String, JoinHandle<Vec<String>>)
+where
+    F: FnMut(&str) -> Option<(u16, String)> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        while let Ok(Ok((mut socket, _))) = tokio::time::timeout(IDLE_TIMEOUT, listener.accept()).await {
+            let mut buf = vec![0u8; 64 * 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let reply = respond(&request);
+            requests.push(request);
+            let Some((status, body)) = reply else { break };
+            let reply = format!(
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status, body.len(), body
+            );
+            socket.write_all(reply.as_bytes()).await.unwrap();
+        }
+        requests
+    });
+    (base_url, handle)
+}
+
+/// Answer each request with the next of `replies`, then stop
+pub async fn serve_in_order(replies: Vec<(u16, String)>) -> (String, JoinHandle<Vec<String>>) {
+    let mut replies = replies.into_iter();
+    serve(move |_| replies.next()).await
+}
+
+/// Answer each request with the reply registered for its path, or a 404
+pub async fn serve_routes(routes: Vec<(&'static str, u16, &'static str)>) -> (String, JoinHandle<Vec<String>>) {
+    serve(move |request| {
+        let request_line = request.lines().next().unwrap_or_default();
+        let (status, body) = routes.iter()
+            .find(|(path, _, _)| request_line.contains(&format!(" {} ", path)))
+            .map(|(_, status, body)| (*status, *body))
+            .unwrap_or((404, ""));
+        Some((status, body.to_string()))
+    })
+    .await
+}
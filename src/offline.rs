@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Set to `1` to run without network access, with a fixed clock and sequential ids, so the
+/// command flow can be tested reproducibly. Only debug builds honor it.
+pub const ENV_VAR: &str = "AGENTIC_OFFLINE";
+
+/// Where the offline clock starts: 2024-01-01T00:00:00Z
+const EPOCH_SECONDS: i64 = 1_704_067_200;
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+static CLOCK_TICKS: AtomicU64 = AtomicU64::new(0);
+static IDS_ISSUED: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(test)]
+thread_local! {
+    static FORCED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Whether offline mode is on. A release build ignores the variable so a stray export can't
+/// silently turn off the models and scramble timestamps for real use.
+pub fn is_enabled() -> bool {
+    #[cfg(test)]
+    if FORCED.with(|forced| forced.get()) {
+        return true;
+    }
+    *ENABLED.get_or_init(|| {
+        let requested = std::env::var(ENV_VAR).is_ok_and(|value| value == "1");
+        if requested && !cfg!(debug_assertions) {
+            warn!("{} is ignored in release builds", ENV_VAR);
+            return false;
+        }
+        requested
+    })
+}
+
+/// The current time; offline, a clock that starts at a fixed instant and advances one second per call
+pub fn now() -> DateTime<Utc> {
+    if is_enabled() {
+        clock_at(CLOCK_TICKS.fetch_add(1, Ordering::SeqCst))
+    } else {
+        Utc::now()
+    }
+}
+
+/// A new record id; offline, ids count up from `00000000-0000-0000-0000-000000000001`
+pub fn new_id() -> String {
+    if is_enabled() {
+        id_at(IDS_ISSUED.fetch_add(1, Ordering::SeqCst) + 1)
+    } else {
+        Uuid::new_v4().to_string()
+    }
+}
+
+/// Fail a network call up front when offline, so callers take their fallback path
+pub fn check_network() -> Result<()> {
+    if is_enabled() {
+        return Err(anyhow!("network access is disabled ({}=1)", ENV_VAR));
+    }
+    Ok(())
+}
+
+fn clock_at(tick: u64) -> DateTime<Utc> {
+    Utc.timestamp_opt(EPOCH_SECONDS + tick as i64, 0).unwrap()
+}
+
+fn id_at(n: u64) -> String {
+    Uuid::from_u128(n as u128).to_string()
+}
+
+/// Turn offline mode on for the current test thread and restart the clock and ids
+#[cfg(test)]
+pub fn force_for_current_thread() {
+    FORCED.with(|forced| forced.set(true));
+    CLOCK_TICKS.store(0, Ordering::SeqCst);
+    IDS_ISSUED.store(0, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Agent;
+    use crate::config::Config;
+    use crate::db::CommandExecution;
+
+    async fn run_flow() -> (String, String, DateTime<Utc>, String) {
+        force_for_current_thread();
+        let agent = Agent::new(&Config::default()).unwrap();
+        let response = agent.process_query("make a study plan for the week").await.unwrap();
+        let first = CommandExecution::new("agent 'make a study plan'".to_string(), None);
+        let second = CommandExecution::new("task list".to_string(), None);
+        (response, first.id, second.timestamp, second.id)
+    }
+
+    #[tokio::test]
+    async fn test_offline_flow_is_deterministic() {
+        let first_run = run_flow().await;
+        let second_run = run_flow().await;
+
+        assert_eq!(first_run, second_run);
+        let (response, first_id, timestamp, second_id) = first_run;
+        assert!(response.contains("Study Plan"), "offline answers come from the fallback: {}", response);
+        assert_eq!(first_id, "00000000-0000-0000-0000-000000000001");
+        assert_eq!(second_id, "00000000-0000-0000-0000-000000000002");
+        assert_eq!(timestamp.to_rfc3339(), "2024-01-01T00:00:01+00:00");
+        assert!(check_network().is_err());
+    }
+}
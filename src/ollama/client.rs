@@ -1,7 +1,7 @@
 use anyhow::{Context, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn};
 use url::Url;
 
@@ -12,16 +12,22 @@ pub struct OllamaConfig {
     pub temperature: f32,
     pub max_tokens: Option<u32>,
     pub timeout: Duration,
+    /// How many times a request is resent after a 429, 5xx or timeout
+    pub max_retries: u32,
 }
 
+/// Where Ollama listens unless configured otherwise
+pub const DEFAULT_HOST: &str = "http://localhost:11434";
+
 impl Default for OllamaConfig {
     fn default() -> Self {
         Self {
-            base_url: "http://localhost:11434".to_string(),
+            base_url: DEFAULT_HOST.to_string(),
             model: "phi4:latest".to_string(),
             temperature: 0.7,
             max_tokens: Some(2048),
             timeout: Duration::from_secs(60),
+            max_retries: crate::retry::DEFAULT_MAX_RETRIES,
         }
     }
 }
@@ -68,6 +74,119 @@ pub struct OllamaResponse {
     pub eval_duration: Option<u64>,
 }
 
+/// Body of a native `/api/chat` request
+#[derive(Debug, Serialize)]
+pub struct OllamaChatRequest<'a> {
+    pub model: String,
+    pub messages: &'a [ChatMessage],
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<OllamaOptions>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaChatResponse {
+    pub message: ChatMessage,
+    #[serde(default)]
+    pub prompt_eval_count: Option<u32>,
+    #[serde(default)]
+    pub eval_count: Option<u32>,
+    #[serde(default)]
+    pub eval_duration: Option<u64>,
+    #[serde(default)]
+    pub total_duration: Option<u64>,
+}
+
+/// A reply with the token counts and times Ollama reports for it
+#[derive(Debug, Clone, Default)]
+pub struct GenerationStats {
+    pub text: String,
+    /// Tokens in the prompt the model read
+    pub prompt_tokens: u32,
+    /// Tokens the model generated
+    pub eval_tokens: u32,
+    /// Ollama's total duration for the request, or the measured time when it isn't reported
+    pub duration: Duration,
+    /// Time the model spent generating the reply, when Ollama reports it
+    pub eval_duration: Option<Duration>,
+}
+
+impl GenerationStats {
+    fn new(text: String, prompt_tokens: Option<u32>, eval_tokens: Option<u32>, timings: (Option<u64>, Option<u64>), started: Instant) -> Self {
+        let (total_duration_ns, eval_duration_ns) = timings;
+        Self {
+            text,
+            prompt_tokens: prompt_tokens.unwrap_or(0),
+            eval_tokens: eval_tokens.unwrap_or(0),
+            duration: total_duration_ns.map(Duration::from_nanos).unwrap_or_else(|| started.elapsed()),
+            eval_duration: eval_duration_ns.map(Duration::from_nanos),
+        }
+    }
+
+    /// `duration` in milliseconds, the latency `models bench` compares
+    pub fn latency_ms(&self) -> f64 {
+        self.duration.as_secs_f64() * 1000.0
+    }
+
+    /// Generated tokens per second: over the time spent generating when Ollama reports it,
+    /// otherwise over the whole request, prompt processing included
+    pub fn tokens_per_sec(&self) -> Option<f64> {
+        let seconds = self.eval_duration.unwrap_or(self.duration).as_secs_f64();
+        (self.eval_tokens > 0 && seconds > 0.0).then(|| self.eval_tokens as f64 / seconds)
+    }
+
+    /// One line for `--debug` output, e.g. `42 tokens in 1.3s (32.3 tokens/s), prompt 105 tokens`
+    pub fn summary(&self) -> String {
+        let rate = self.tokens_per_sec().map(|rate| format!(" ({:.1} tokens/s)", rate)).unwrap_or_default();
+        format!(
+            "{} tokens in {:.1}s{}, prompt {} tokens",
+            self.eval_tokens,
+            self.duration.as_secs_f64(),
+            rate,
+            self.prompt_tokens
+        )
+    }
+}
+
+/// Embedding models worth suggesting when the configured one can't embed
+pub const EMBEDDING_MODELS: &[&str] = &["nomic-embed-text", "mxbai-embed-large", "all-minilm"];
+
+#[derive(Debug, Serialize)]
+struct EmbeddingsRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingsResponse {
+    #[serde(default)]
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<ModelInfo>,
+}
+
+/// A model installed in the local Ollama instance, as listed by `/api/tags`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelInfo {
+    pub name: String,
+    /// Size on disk in bytes
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default, rename = "modified_at")]
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ModelInfo {
+    /// Whether this is the model `wanted` refers to; a bare name means its `:latest` tag
+    pub fn matches(&self, wanted: &str) -> bool {
+        self.name == wanted || (!wanted.contains(':') && self.name == format!("{}:latest", wanted))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct OllamaClient {
     client: Client,
@@ -94,17 +213,26 @@ impl OllamaClient {
         })
     }
 
-    pub async fn generate(&self, prompt: &str) -> Result<String> {
+    /// Generate a completion along with its token counts and duration
+    pub async fn generate_with_stats(&self, prompt: &str) -> Result<GenerationStats> {
+        let started = Instant::now();
+        let response = self.send_generate(prompt).await?;
+        Ok(GenerationStats::new(
+            response.response,
+            response.prompt_eval_count,
+            response.eval_count,
+            (response.total_duration, response.eval_duration),
+            started,
+        ))
+    }
+
+    async fn send_generate(&self, prompt: &str) -> Result<OllamaResponse> {
+        crate::offline::check_network()?;
         let request = OllamaRequest {
             model: self.config.model.clone(),
             prompt: prompt.to_string(),
             stream: false,
-            options: Some(OllamaOptions {
-                temperature: Some(self.config.temperature),
-                num_predict: self.config.max_tokens,
-                top_p: Some(0.9),
-                top_k: Some(40),
-            }),
+            options: Some(self.options()),
         };
 
         debug!("Sending request to Ollama: {}", prompt);
@@ -112,11 +240,8 @@ impl OllamaClient {
         let url = self.base_url.join("/api/generate")
             .context("Failed to construct Ollama API URL")?;
 
-        let response = self
-            .client
-            .post(url)
-            .json(&request)
-            .send()
+        let response = self.retry_policy()
+            .send("Ollama request", || self.client.post(url.clone()).json(&request).send())
             .await
             .context("Failed to send request to Ollama")?;
 
@@ -137,13 +262,72 @@ impl OllamaClient {
             ollama_response.total_duration.map(|d| d / 1_000_000) // Convert to ms
         );
 
-        Ok(ollama_response.response)
+        Ok(ollama_response)
+    }
+
+    fn retry_policy(&self) -> crate::retry::RetryPolicy {
+        crate::retry::RetryPolicy::new(self.config.max_retries)
+    }
+
+    fn options(&self) -> OllamaOptions {
+        OllamaOptions {
+            temperature: Some(self.config.temperature),
+            num_predict: self.config.max_tokens,
+            top_p: Some(0.9),
+            top_k: Some(40),
+        }
     }
 
-    pub async fn chat(&self, messages: &[ChatMessage]) -> Result<String> {
-        // Convert chat messages to a single prompt for Ollama
-        let prompt = self.format_chat_prompt(messages);
-        self.generate(&prompt).await
+    /// Send the conversation to `/api/chat` so the model's own chat template is applied, and
+    /// return the reply with its token counts and duration. Older servers without that endpoint
+    /// get the messages flattened into one prompt instead.
+    pub async fn chat_with_stats(&self, messages: &[ChatMessage]) -> Result<GenerationStats> {
+        crate::offline::check_network()?;
+        let started = Instant::now();
+        let request = OllamaChatRequest {
+            model: self.config.model.clone(),
+            messages,
+            stream: false,
+            options: Some(self.options()),
+        };
+
+        let url = self.base_url.join("/api/chat")
+            .context("Failed to construct Ollama API URL")?;
+
+        let response = self.retry_policy()
+            .send("Ollama chat request", || self.client.post(url.clone()).json(&request).send())
+            .await
+            .context("Failed to send request to Ollama")?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            warn!("Ollama has no /api/chat for {}, falling back to a flattened prompt", self.config.model);
+            let prompt = self.format_chat_prompt(messages);
+            return self.generate_with_stats(&prompt).await;
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            anyhow::bail!("Ollama API error {}: {}", status, text);
+        }
+
+        let chat_response: OllamaChatResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama chat response")?;
+
+        debug!(
+            "Ollama chat response: {} tokens, duration: {:?}ms",
+            chat_response.eval_count.unwrap_or(0),
+            chat_response.total_duration.map(|d| d / 1_000_000)
+        );
+
+        Ok(GenerationStats::new(
+            chat_response.message.content,
+            chat_response.prompt_eval_count,
+            chat_response.eval_count,
+            (chat_response.total_duration, chat_response.eval_duration),
+            started,
+        ))
     }
 
     fn format_chat_prompt(&self, messages: &[ChatMessage]) -> String {
@@ -171,18 +355,81 @@ impl OllamaClient {
     }
 
     pub async fn health_check(&self) -> Result<bool> {
+        if crate::offline::is_enabled() {
+            return Ok(false);
+        }
         let url = self.base_url.join("/api/tags")
             .context("Failed to construct Ollama health check URL")?;
 
         match self.client.get(url).send().await {
             Ok(response) => Ok(response.status().is_success()),
+            // Checked in the background while the TUI is up, where a log line would land on screen
             Err(e) => {
-                warn!("Ollama health check failed: {}", e);
+                debug!("Ollama health check failed: {}", e);
                 Ok(false)
             }
         }
     }
 
+    /// Embed `text` with the configured model via `/api/embeddings`
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        crate::offline::check_network()?;
+        let url = self.base_url.join("/api/embeddings")
+            .context("Failed to construct Ollama embeddings URL")?;
+        let request = EmbeddingsRequest { model: &self.config.model, prompt: text };
+
+        let response = self.retry_policy()
+            .send("Ollama embeddings request", || self.client.post(url.clone()).json(&request).send())
+            .await
+            .context("Failed to send request to Ollama")?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            if text.contains("not support") || text.contains("not found") {
+                return Err(self.cannot_embed());
+            }
+            anyhow::bail!("Ollama API error {}: {}", status, text);
+        }
+
+        let embeddings: EmbeddingsResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama embeddings response")?;
+        if embeddings.embedding.is_empty() {
+            return Err(self.cannot_embed());
+        }
+        Ok(embeddings.embedding)
+    }
+
+    fn cannot_embed(&self) -> anyhow::Error {
+        anyhow::anyhow!(
+            "{} can't make embeddings; pull an embedding model (e.g. `ollama pull {}`, or one of {}) and pass it with --model",
+            self.config.model,
+            EMBEDDING_MODELS[0],
+            EMBEDDING_MODELS.join(", ")
+        )
+    }
+
+    /// Models installed in the local Ollama instance
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        crate::offline::check_network()?;
+        let url = self.base_url.join("/api/tags")
+            .context("Failed to construct Ollama tags URL")?;
+
+        let response = self.client.get(url).send().await
+            .context("Failed to reach Ollama")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Ollama API error {}", response.status());
+        }
+
+        let tags: TagsResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama model list")?;
+
+        Ok(tags.models)
+    }
+
     pub fn get_model(&self) -> &str {
         &self.config.model
     }
@@ -191,6 +438,10 @@ impl OllamaClient {
         info!("Switching Ollama model from {} to {}", self.config.model, model);
         self.config.model = model;
     }
+
+    pub fn set_temperature(&mut self, temperature: f32) {
+        self.config.temperature = temperature;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -221,3 +472,75 @@ impl ChatMessage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http;
+
+    /// Answer each incoming request with the response registered for its path; returns the
+    /// base URL and the request lines that were received
+    async fn mock_ollama(routes: Vec<(&'static str, u16, &'static str)>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let (base_url, server) = mock_http::serve_routes(routes).await;
+        let request_lines = tokio::spawn(async move {
+            server.await.unwrap().iter().map(|request| request.lines().next().unwrap_or_default().to_string()).collect()
+        });
+        (base_url, request_lines)
+    }
+
+    fn client(base_url: String) -> OllamaClient {
+        OllamaClient::new(OllamaConfig { base_url, ..Default::default() }).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chat_uses_native_endpoint() {
+        let (base_url, server) = mock_ollama(vec![
+            ("/api/chat", 200, r#"{"message":{"role":"assistant","content":"Hello!"},"done":true}"#),
+        ]).await;
+
+        let reply = client(base_url).chat_with_stats(&[ChatMessage::system("Be brief"), ChatMessage::user("Hi")]).await.unwrap().text;
+
+        assert_eq!(reply, "Hello!");
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_embed_returns_the_vector_or_names_embedding_models() {
+        let (base_url, _server) = mock_ollama(vec![("/api/embeddings", 200, r#"{"embedding":[0.5,-1.0,0.25]}"#)]).await;
+        assert_eq!(client(base_url).embed("hello").await.unwrap(), vec![0.5, -1.0, 0.25]);
+
+        let (base_url, _server) = mock_ollama(vec![
+            ("/api/embeddings", 400, r#"{"error":"\"phi4:latest\" does not support embeddings"}"#),
+        ]).await;
+        let error = client(base_url).embed("hello").await.unwrap_err().to_string();
+        assert!(error.starts_with("phi4:latest can't make embeddings") && error.contains("nomic-embed-text"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_stats_reports_tokens_and_duration() {
+        let (base_url, _server) = mock_ollama(vec![
+            ("/api/chat", 200, r#"{"message":{"role":"assistant","content":"Hi"},"done":true,"prompt_eval_count":12,"eval_count":40,"total_duration":2000000000}"#),
+        ]).await;
+
+        let stats = client(base_url).chat_with_stats(&[ChatMessage::user("Hi")]).await.unwrap();
+
+        assert_eq!((stats.text.as_str(), stats.prompt_tokens, stats.eval_tokens), ("Hi", 12, 40));
+        assert_eq!(stats.duration, Duration::from_secs(2));
+        assert_eq!(stats.summary(), "40 tokens in 2.0s (20.0 tokens/s), prompt 12 tokens");
+        assert_eq!(GenerationStats::default().tokens_per_sec(), None);
+    }
+
+    #[tokio::test]
+    async fn test_chat_falls_back_to_generate_on_404() {
+        let (base_url, server) = mock_ollama(vec![
+            ("/api/generate", 200, r#"{"model":"phi4","response":"Flattened","done":true}"#),
+        ]).await;
+
+        let reply = client(base_url).chat_with_stats(&[ChatMessage::user("Hi")]).await.unwrap().text;
+
+        assert_eq!(reply, "Flattened");
+        let seen = server.await.unwrap();
+        assert!(seen[0].contains("/api/chat"));
+        assert!(seen[1].contains("/api/generate"));
+    }
+}
@@ -0,0 +1,123 @@
+use reqwest::{Response, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tracing::warn;
+
+/// Retries after the first attempt when the config doesn't say otherwise
+pub const DEFAULT_MAX_RETRIES: u32 = 2;
+
+/// How often and how patiently to resend a model API request that failed for a reason that may
+/// clear up on its own: rate limiting (429), a server error (5xx) or a timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Wait before the first retry; each later retry waits twice as long
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32) -> Self {
+        Self { max_retries, ..Self::default() }
+    }
+
+    /// Wait before retry number `attempt` (from 1): the doubled delay, capped at `max_delay`,
+    /// with its upper half randomized so clients that failed together don't retry together
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = backoff / 2;
+        half + half.mul_f64(jitter())
+    }
+
+    /// Call `send` until it gets a response that isn't worth retrying or the retries run out,
+    /// returning the last response or error. `what` names the request in the log.
+    pub async fn send<F, Fut>(&self, what: &str, mut send: F) -> reqwest::Result<Response>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = reqwest::Result<Response>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = send().await;
+            let reason = match &result {
+                Ok(response) if is_retryable_status(response.status()) => response.status().to_string(),
+                Err(e) if is_retryable_error(e) => e.to_string(),
+                _ => return result,
+            };
+            if attempt >= self.max_retries {
+                return result;
+            }
+            attempt += 1;
+            let delay = self.delay(attempt);
+            warn!("{} failed ({}), retrying in {}ms ({}/{})", what, reason, delay.as_millis(), attempt, self.max_retries);
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Rate limiting and server errors; other 4xx responses mean the request itself is wrong
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+pub fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_timeout()
+}
+
+/// A number in [0, 1) from the randomly keyed std hasher, to avoid a dependency for jitter
+fn jitter() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http;
+
+    /// Answer one request per status in order; returns the URL and how many requests arrived
+    async fn mock_server(statuses: Vec<u16>) -> (String, tokio::task::JoinHandle<usize>) {
+        let (base_url, server) = mock_http::serve_in_order(statuses.into_iter().map(|status| (status, "ok".to_string())).collect()).await;
+        (format!("{}/api", base_url), tokio::spawn(async move { server.await.unwrap().len() }))
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_failures_only() {
+        let policy = RetryPolicy { max_retries: 3, base_delay: Duration::from_millis(1), max_delay: Duration::from_millis(4) };
+        let client = reqwest::Client::new();
+
+        let (url, server) = mock_server(vec![503, 429, 200]).await;
+        let response = policy.send("test", || client.get(&url).send()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.await.unwrap(), 3);
+
+        let (url, server) = mock_server(vec![400, 200]).await;
+        let response = policy.send("test", || client.get(&url).send()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "other 4xx errors aren't retried");
+        assert_eq!(server.await.unwrap(), 1);
+
+        let (url, server) = mock_server(vec![500, 502]).await;
+        let response = RetryPolicy { max_retries: 1, ..policy }.send("test", || client.get(&url).send()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY, "the last failure is returned");
+        assert_eq!(server.await.unwrap(), 2);
+
+        for attempt in 1..6 {
+            let delay = policy.delay(attempt);
+            assert!(delay <= policy.max_delay && delay >= policy.base_delay.min(policy.max_delay) / 2, "{:?}", delay);
+        }
+    }
+}
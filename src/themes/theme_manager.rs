@@ -26,6 +26,11 @@ pub struct Theme {
     pub tags: Vec<String>,
 }
 
+/// Where user themes live: `~/.agentic/themes`
+pub fn user_theme_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".agentic").join("themes"))
+}
+
 pub struct ThemeManager {
     themes: HashMap<String, Theme>,
     theme_directories: Vec<PathBuf>,
@@ -36,10 +41,10 @@ impl ThemeManager {
     pub fn new() -> Self {
         Self {
             themes: HashMap::new(),
-            theme_directories: vec![
-                PathBuf::from("themes"),
-                PathBuf::from("~/.agentic/themes"),
-            ],
+            theme_directories: [Some(PathBuf::from("themes")), user_theme_dir()]
+                .into_iter()
+                .flatten()
+                .collect(),
             current_theme: None,
         }
     }
@@ -99,6 +104,12 @@ impl ThemeManager {
         self.themes.get(name)
     }
 
+    /// Look a theme up by name, ignoring case when there's no exact match
+    pub fn find_theme(&self, name: &str) -> Option<&Theme> {
+        self.get_theme(name)
+            .or_else(|| self.themes.values().find(|theme| theme.name.eq_ignore_ascii_case(name)))
+    }
+
     pub fn list_themes(&self) -> Vec<&Theme> {
         self.themes.values().collect()
     }
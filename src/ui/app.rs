@@ -1,5 +1,5 @@
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -8,31 +8,69 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use clap::Parser;
 
 use crate::{
-    agent::Agent,
-    commands::CommandRegistry,
+    agent::{conversation::Conversation, Agent, Connectivity, ModelOverrides},
+    commands::{CommandRegistry, Output},
     config::Config,
     db::{CommandExecution, Database, ExecutionStatus},
+    keybindings::keybinding_manager::KeyBindingManager,
 };
 
 use super::{
-    components::{InputBar, StatusBar, Sidebar},
+    blocks::{keep_animating, rank_history, CommandBlock, CommandPalette, BLOCK_HEIGHT},
+    clipboard::{Clipboard, Copied},
+    components::{InputAction, InputBar, StatusBar, Sidebar},
+    confirmation::{ConfirmAction, ConfirmStage, WarpConfirmation},
+    search::{HistorySearch, SearchAction},
     events::EventHandler,
     layout::AppLayout,
     styles::AppTheme,
-    performance::VirtualScroller,
+    performance::{detect_language, AnimationSystem, PerformanceManager, VirtualScroller},
+    routing::{classify_input, is_on_path, InputRoute},
+    transcript::{Redactor, Transcript},
 };
 
+/// How many recent history entries the command palette draws its suggestions from
+const PALETTE_HISTORY_LIMIT: usize = 500;
+
+/// How often the sidebar checks whether the agent's model answers
+const CONNECTIVITY_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A warp suggestion waiting on its confirmation widget, with the pipeline that will run it
+pub struct PendingWarp {
+    pipeline: crate::warp::WarpPipeline,
+    suggestion: crate::warp::pipeline::DryRun,
+    /// Receives the command's output once it runs; its sender is in the pipeline
+    lines: tokio::sync::mpsc::UnboundedReceiver<String>,
+    /// The block of the `warp` command that asked
+    block_id: String,
+    pub confirmation: WarpConfirmation,
+}
+
+/// A confirmed warp command running in the background, its output streaming into its own block
+pub struct WarpRun {
+    block_id: String,
+    lines: tokio::sync::mpsc::UnboundedReceiver<String>,
+    task: tokio::task::JoinHandle<Result<CommandExecution>>,
+    /// Kills the command; taken once Ctrl+C or Esc has asked for that
+    cancel: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMode {
     Normal,
     Agent,
     Help,
     Settings,
+    /// Full-screen view of the selected execution
+    Inspect,
+    /// Showing only the history matching a query typed after `/`
+    Search,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -50,10 +88,26 @@ pub struct App {
     // UI State
     pub mode: AppMode,
     pub input_mode: InputMode,
-    pub input: String,
     pub command_history: Vec<CommandExecution>,
-    #[allow(dead_code)]
+    /// Agent queries asked in this TUI session, for `agent --export`
+    pub agent_session: Conversation,
+    /// Index into `command_history` of the highlighted execution
     pub selected_block: usize,
+    /// Output scroll offset of the inspector
+    pub inspector_scroll: u16,
+    /// Ids of the executions whose blocks show their whole output; ids rather than positions,
+    /// since every new command moves the blocks down
+    pub expanded: HashSet<String>,
+    /// What each finished execution's output is highlighted as, by id; detecting it parses the
+    /// output, so it is done the first time the block is drawn rather than every frame
+    pub output_languages: HashMap<String, Option<&'static str>>,
+    /// Output rows scrolled past in the selected block when it is expanded
+    pub block_scroll: usize,
+    /// Index into `command_history` of the command recalled into the input bar with Up/Down;
+    /// `None` while the input is the user's own line
+    pub history_cursor: Option<usize>,
+    /// What was typed before recalling history, restored by moving back down past the newest entry
+    pub history_draft: String,
     pub should_quit: bool,
     
     // Theme and Layout
@@ -64,17 +118,51 @@ pub struct App {
     
     // Components
     pub input_bar: InputBar,
+    /// Ctrl+P overlay for picking a command to put in the input bar
+    pub command_palette: CommandPalette,
     pub status_bar: StatusBar,
     pub sidebar: Sidebar,
+    /// Where `y` and `Y` copy the selected block to
+    pub clipboard: Clipboard,
+    /// History search opened with `/`; the history shows only its matches while it is open
+    pub search: Option<HistorySearch>,
     
     // Event handling
     #[allow(dead_code)]
     pub event_handler: EventHandler,
     #[allow(dead_code)]
     pub last_render: Instant,
+    /// Spinners and progress bars of the running blocks
+    pub animations: AnimationSystem,
+    /// Whether the next loop iteration draws: after input, after changes made outside it, and
+    /// on every frame while something animates
+    pub needs_redraw: bool,
+    
+    /// Caps on how much history and output the TUI keeps in memory
+    pub performance: PerformanceManager,
     
     // Scrolling
     pub scroller: VirtualScroller,
+    /// Where the history pane and each block in it were drawn last frame, for the mouse
+    pub history_area: Rect,
+    pub block_areas: Vec<(usize, Rect)>,
+    
+    /// Session log, when `[transcript] enabled` is set
+    pub transcript: Option<Transcript>,
+    
+    /// Maps keys to the named actions run in normal mode
+    pub keybindings: KeyBindingManager,
+    
+    /// Warp suggestion on screen for confirmation; it takes every key until answered
+    pub pending_warp: Option<PendingWarp>,
+    /// Confirmed warp command still running
+    pub warp_run: Option<WarpRun>,
+    
+    /// What the last background check found; `None` until one finishes
+    pub provider_online: Option<Connectivity>,
+    /// The background check under way, and when the last one started
+    pub connectivity_check: Option<tokio::task::JoinHandle<Connectivity>>,
+    pub last_connectivity_check: Option<Instant>,
 }
 
 impl App {
@@ -95,23 +183,48 @@ impl App {
             
             mode: AppMode::Normal,
             input_mode: InputMode::Normal,
-            input: String::new(),
             command_history: Vec::new(),
+            agent_session: Conversation::new(),
             selected_block: 0,
+            inspector_scroll: 0,
+            expanded: HashSet::new(),
+            output_languages: HashMap::new(),
+            block_scroll: 0,
+            history_cursor: None,
+            history_draft: String::new(),
             should_quit: false,
             
             theme,
             layout,
             
             input_bar: InputBar::new(),
+            command_palette: CommandPalette::new(),
             status_bar: StatusBar::new(),
             sidebar: Sidebar::new(),
+            clipboard: Clipboard::new(),
+            search: None,
             
             event_handler: EventHandler::new(Duration::from_millis(16)), // 60 FPS
             last_render: Instant::now(),
+            animations: AnimationSystem::new(),
+            needs_redraw: true,
+            
+            performance: PerformanceManager::new().with_max_history(config.max_history_size),
             
             // Initialize scroller with default values
-            scroller: VirtualScroller::new(10, 4), // 4 lines per command execution
+            scroller: VirtualScroller::new(10, BLOCK_HEIGHT as usize),
+            history_area: Rect::default(),
+            block_areas: Vec::new(),
+            
+            transcript: None,
+            
+            keybindings: KeyBindingManager::with_defaults(),
+            
+            pending_warp: None,
+            warp_run: None,
+            provider_online: None,
+            connectivity_check: None,
+            last_connectivity_check: None,
         }
     }
     
@@ -120,16 +233,22 @@ impl App {
         
         // Load command history
         self.load_command_history().await?;
+        self.start_transcript();
+        self.keybindings.load_default_keyset();
         
         loop {
-            // Render the UI
-            terminal.draw(|f| self.render(f))?;
+            // Render the UI, unless nothing has changed since the last frame
+            if self.needs_redraw {
+                terminal.draw(|f| self.render(f))?;
+                self.needs_redraw = false;
+            }
             
             // Handle events
             if let Ok(event) = event::poll(Duration::from_millis(16)) {
                 if event {
                     if let Ok(event) = event::read() {
                         self.handle_event(event).await?;
+                        self.needs_redraw = true;
                     }
                 }
             }
@@ -156,7 +275,7 @@ impl App {
             .constraints([
                 Constraint::Length(1),     // Status bar
                 Constraint::Min(0),        // Main content
-                Constraint::Length(3),     // Input bar
+                Constraint::Length(self.input_bar.visible_lines() as u16 + 2), // Input bar, growing with its lines
             ])
             .split(size);
         
@@ -185,8 +304,15 @@ impl App {
         match self.mode {
             AppMode::Help => self.render_help_overlay(frame, size),
             AppMode::Settings => self.render_settings_overlay(frame, size),
+            AppMode::Inspect => self.render_inspector_overlay(frame, size),
             _ => {}
         }
+        
+        // Drawn last so it sits above everything else; does nothing while hidden
+        self.command_palette.render(frame, size);
+        if let Some(pending) = &self.pending_warp {
+            pending.confirmation.render(frame, size);
+        }
     }
     
     fn render_status_bar(&self, frame: &mut Frame, area: Rect) {
@@ -195,128 +321,160 @@ impl App {
             AppMode::Agent => "AGENT",
             AppMode::Help => "HELP",
             AppMode::Settings => "SETTINGS",
+            AppMode::Inspect => "INSPECT",
+            AppMode::Search => "SEARCH",
         };
         
         let mode_color = match self.mode {
-            AppMode::Normal => Color::Blue,
-            AppMode::Agent => Color::Green,
+            AppMode::Normal => self.theme.primary_color,
+            AppMode::Agent => self.theme.success_color,
             AppMode::Help => Color::Yellow,
             AppMode::Settings => Color::Magenta,
+            AppMode::Inspect => Color::Cyan,
+            AppMode::Search => Color::Yellow,
         };
         
-        let status_line = Line::from(vec![
+        let mut status_line = Line::from(vec![
             Span::styled(
                 format!(" {} ", mode_text),
                 Style::default()
-                    .fg(Color::White)
+                    .fg(self.theme.text_color)
                     .bg(mode_color)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(" | "),
-            Span::styled("Ctrl+Q", Style::default().fg(Color::Gray)),
+            Span::styled("Ctrl+Q", self.theme.secondary_style()),
             Span::raw(" quit | "),
-            Span::styled("Ctrl+A", Style::default().fg(Color::Gray)),
+            Span::styled("Ctrl+A", self.theme.secondary_style()),
             Span::raw(" agent | "),
-            Span::styled("?", Style::default().fg(Color::Gray)),
+            Span::styled("i", self.theme.secondary_style()),
+            Span::raw(" inspect | "),
+            Span::styled("?", self.theme.secondary_style()),
             Span::raw(" help"),
         ]);
+        if let Some(notice) = self.status_bar.notice() {
+            status_line.spans.push(Span::raw(" | "));
+            status_line.spans.push(Span::styled(notice.to_string(), Style::default().fg(self.theme.success_color)));
+        }
         
         let status_paragraph = Paragraph::new(status_line)
-            .style(Style::default().bg(Color::Black));
+            .style(Style::default().fg(self.theme.text_color).bg(self.theme.background_color));
         
         frame.render_widget(status_paragraph, area);
     }
     
     fn render_main_content(&mut self, frame: &mut Frame, area: Rect) {
-        // Create command execution blocks
-        let mut items = Vec::new();
-        
-        // Update scroller with current viewport height and total items
-        self.scroller.viewport_height = area.height as usize;
-        self.scroller.update_total_items(self.command_history.len());
+        // The blocks are drawn inside the history pane's border
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(self.theme.primary_style());
+        let inner = block.inner(area);
+        self.history_area = area;
+        self.block_areas.clear();
         
-        // Get visible range based on scroll position
-        let (start_idx, end_idx) = self.scroller.get_visible_range();
+        let shown: Vec<usize> = match &self.search {
+            Some(search) => search.matches(&self.command_history),
+            None => (0..self.command_history.len()).collect(),
+        };
         
-        // Only render visible items
-        for (_index, execution) in self.command_history.iter().enumerate().skip(start_idx).take(end_idx - start_idx) {
-            let status_icon = match execution.status {
-                ExecutionStatus::Running => "⏳",
-                ExecutionStatus::Success => "✅",
-                ExecutionStatus::Error => "❌",
-                ExecutionStatus::Cancelled => "🚫",
-            };
-            
-            let status_color = match execution.status {
-                ExecutionStatus::Running => Color::Yellow,
-                ExecutionStatus::Success => Color::Green,
-                ExecutionStatus::Error => Color::Red,
-                ExecutionStatus::Cancelled => Color::Gray,
+        // Update scroller with current viewport height and each block's height: collapsed
+        // blocks are all BLOCK_HEIGHT, expanded ones grow with their output up to the pane's
+        // height, and blocks a search hides take no room
+        let mut heights = vec![0; self.command_history.len()];
+        for &index in &shown {
+            let height = if self.expanded.contains(&self.command_history[index].id) {
+                let mut expanded = self.history_block(index);
+                expanded.expanded = true;
+                expanded.height(inner.width)
+            } else {
+                BLOCK_HEIGHT
             };
-            
-            let item = ListItem::new(vec![
-                Line::from(vec![
-                    Span::styled(
-                        format!("{} ", status_icon),
-                        Style::default().fg(status_color),
-                    ),
-                    Span::styled(
-                        execution.command.clone(),
-                        Style::default()
-                            .fg(Color::White)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                ]),
-                Line::from(vec![
-                    Span::raw("  "),
-                    Span::styled(
-                        execution.timestamp.format("%H:%M:%S").to_string(),
-                        Style::default().fg(Color::Gray),
-                    ),
-                    Span::raw(" | "),
-                    Span::styled(
-                        format!("{}{}", execution.duration_ms, "ms"),
-                        Style::default().fg(Color::Gray),
-                    ),
-                ]),
-                Line::from(vec![
-                    Span::raw("  "),
-                    Span::styled(
-                        if execution.output.len() > 100 {
-                            format!("{}{}", &execution.output[..100], "...")
-                        } else {
-                            execution.output.clone()
-                        },
-                        Style::default().fg(Color::Cyan),
-                    ),
-                ]),
-                Line::from(vec![Span::raw("")]), // Empty line separator
-            ]);
-            
-            items.push(item);
+            heights[index] = height.min(inner.height) as usize;
         }
+        self.scroller.viewport_height = inner.height as usize;
+        self.scroller.set_item_heights(heights);
+        // Expanding a block can push the selection out of view without it moving
+        self.scroller.scroll_to_item(self.selected_block);
+        
+        // Get visible range based on scroll position
+        let (start_idx, end_idx) = self.scroller.get_visible_range();
         
         // Add scroll indicator if needed
-        let title = if self.command_history.len() > (end_idx - start_idx) {
+        let title = if let Some(search) = &self.search {
+            let position = shown.iter().position(|&index| index == self.selected_block)
+                .map(|at| format!(", {} of {}", at + 1, shown.len()))
+                .unwrap_or_default();
+            format!("Command History (Search \"{}\": {} match(es){})", search.query(), shown.len(), position)
+        } else if self.command_history.len() > (end_idx - start_idx) {
             format!("Command History (Scroll: {}/{})", start_idx + 1, self.command_history.len())
         } else {
             "Command History".to_string()
         };
+        frame.render_widget(block.title(title), area);
         
-        let block = Block::default()
-            .title(title)
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Blue));
-        
-        let list = List::new(items)
-            .block(block)
-            .highlight_style(
-                Style::default()
-                    .bg(Color::DarkGray)
-                    .add_modifier(Modifier::BOLD),
-            );
-        
-        frame.render_stateful_widget(list, area, &mut self.sidebar.list_state);
+        if inner.width < 4 {
+            return;
+        }
+        // Keep the selected block's scroll within its output, now that the width is known
+        if self.selected_is_expanded() {
+            let mut selected = self.history_block(self.selected_block);
+            selected.expanded = true;
+            let visible = selected.height(inner.width).min(inner.height).saturating_sub(4) as usize;
+            self.block_scroll = self.block_scroll.min(selected.output_rows(inner.width).saturating_sub(visible));
+        }
+        let visible: Vec<usize> = shown.iter().copied().filter(|index| (start_idx..end_idx).contains(index)).collect();
+        for &index in &visible {
+            self.cache_output_language(index);
+        }
+        let block_for = |index: usize| {
+            let mut command_block = self.history_block(index).animated_by(&self.animations);
+            command_block.language = self.output_languages.get(&command_block.execution.id).copied().flatten();
+            command_block.is_selected = index == self.selected_block;
+            // An expanded block grows to show its whole output, up to the pane's height
+            command_block.expanded = self.expanded.contains(&command_block.execution.id);
+            if command_block.is_selected {
+                command_block.output_scroll = self.block_scroll;
+            }
+            command_block.fields = self.config.history_display.fields.clone();
+            command_block.highlight = self.search.as_ref()
+                .map(|search| search.query().to_string())
+                .filter(|query| !query.is_empty());
+            command_block
+        };
+
+        // Only render visible items, stacked top to bottom
+        let mut drawn = Vec::new();
+        let mut y = inner.y;
+        for index in visible {
+            let command_block = block_for(index);
+            let height = command_block.height(inner.width).min(inner.height);
+            if y + height > inner.bottom() {
+                break;
+            }
+            let block_area = Rect { y, height, ..inner };
+            command_block.render(frame, block_area);
+            drawn.push((index, block_area));
+            y += height;
+        }
+        self.block_areas = drawn;
+    }
+    
+    /// The search query in place of the input bar while the history is being searched
+    fn render_search_bar(&self, search: &HistorySearch, frame: &mut Frame, area: Rect) {
+        let title = if search.typing {
+            "Search history (Enter to browse matches • Esc to close)"
+        } else {
+            "Search history (n/N next/previous match • / edit • Esc to close)"
+        };
+        let style = if search.typing { Style::default().fg(Color::Yellow) } else { Style::default() };
+        let input = Paragraph::new(format!("/ {}", search.query()))
+            .style(style)
+            .block(Block::default().borders(Borders::ALL).border_style(style).title(title));
+        frame.render_widget(input, area);
+        if search.typing {
+            let cursor_col = search.field.content[..search.field.cursor_position].chars().count();
+            frame.set_cursor(area.x + 3 + cursor_col as u16, area.y + 1);
+        }
     }
     
     fn render_sidebar(&self, frame: &mut Frame, area: Rect) {
@@ -334,27 +492,21 @@ impl App {
                 Span::styled("🤖 Agent", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
             ]),
             Line::from(vec![
-                Span::raw("Model: "),
-                Span::styled(self.config.agent.model.clone(), Style::default().fg(Color::Yellow)),
+                Span::raw("Provider: "),
+                Span::styled(self.agent.provider_label(), Style::default().fg(Color::Yellow)),
             ]),
             Line::from(vec![
-                Span::raw("Status: "),
-                Span::styled("Ready", Style::default().fg(Color::Green)),
+                Span::raw("Model: "),
+                Span::styled(self.agent.model_name().unwrap_or("none").to_string(), Style::default().fg(Color::Yellow)),
             ]),
             Line::from(vec![
-                Span::raw("API: "),
-                Span::styled(
-                    if self.config.get_openai_api_key().is_some() {
-                        "Connected"
-                    } else {
-                        "No API Key"
-                    },
-                    Style::default().fg(if self.config.get_openai_api_key().is_some() {
-                        Color::Green
-                    } else {
-                        Color::Red
-                    }),
-                ),
+                Span::raw("Status: "),
+                match self.provider_online {
+                    Some(Connectivity::Online) => Span::styled("● online", Style::default().fg(Color::Green)),
+                    Some(Connectivity::Configured) => Span::styled("● configured", Style::default().fg(Color::Yellow)),
+                    Some(Connectivity::Offline) => Span::styled("● offline", Style::default().fg(Color::Red)),
+                    None => Span::styled("○ checking…", Style::default().fg(Color::Gray)),
+                },
             ]),
         ];
         
@@ -390,6 +542,10 @@ impl App {
     }
     
     fn render_input_bar(&self, frame: &mut Frame, area: Rect) {
+        if let Some(search) = &self.search {
+            self.render_search_bar(search, frame, area);
+            return;
+        }
         let input_style = match self.input_mode {
             InputMode::Normal => Style::default(),
             InputMode::Editing => Style::default().fg(Color::Yellow),
@@ -400,9 +556,35 @@ impl App {
             _ => "$ ",
         };
         
-        let input_text = format!("{}{}", mode_indicator, self.input);
+        // Continuation lines are indented to line up with the first one
+        let indicator_width = mode_indicator.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum::<usize>();
+        let lines: Vec<Line> = self.input_bar.content
+            .split('\n')
+            .enumerate()
+            .map(|(i, line)| {
+                let prefix = if i == 0 { mode_indicator.to_string() } else { " ".repeat(indicator_width) };
+                Line::from(format!("{}{}", prefix, line))
+            })
+            .collect();
+        
+        // Keep the cursor line in view once the input outgrows the bar
+        let (cursor_line, cursor_col) = self.input_bar.cursor_line_col();
+        let visible = self.input_bar.visible_lines();
+        let scroll = (cursor_line + 1).saturating_sub(visible);
         
-        let input = Paragraph::new(input_text)
+        let mut title = match (&self.mode, self.input_bar.is_multiline()) {
+            (AppMode::Agent, false) => "Agent Query",
+            (AppMode::Agent, true) => "Agent Query (Ctrl+Enter/Ctrl+D to submit)",
+            (_, false) => "Command",
+            (_, true) => "Command (Ctrl+Enter/Ctrl+D to submit)",
+        }.to_string();
+        let remembered = self.agent.remembered_turns();
+        if self.mode == AppMode::Agent && remembered > 0 {
+            title.push_str(&format!(" · remembering {} turn(s), Ctrl+N forgets", remembered));
+        }
+        
+        let input = Paragraph::new(lines)
+            .scroll((scroll as u16, 0))
             .style(input_style)
             .block(
                 Block::default()
@@ -411,18 +593,15 @@ impl App {
                         InputMode::Normal => Style::default(),
                         InputMode::Editing => Style::default().fg(Color::Yellow),
                     })
-                    .title(match self.mode {
-                        AppMode::Agent => "Agent Query",
-                        _ => "Command",
-                    }),
+                    .title(title),
             );
         
         frame.render_widget(input, area);
         
         if self.input_mode == InputMode::Editing {
-            // Calculate cursor position
-            let cursor_x = area.x + self.input.len() as u16 + 3; // +3 for prompt and border
-            let cursor_y = area.y + 1; // +1 for border
+            // +1 for the border, plus the prompt width
+            let cursor_x = area.x + 1 + (indicator_width + cursor_col) as u16;
+            let cursor_y = area.y + 1 + (cursor_line - scroll) as u16;
             
             frame.set_cursor(cursor_x, cursor_y);
         }
@@ -447,10 +626,22 @@ impl App {
                 Span::styled("  Ctrl+A", Style::default().fg(Color::Green)),
                 Span::raw("  - Toggle agent mode"),
             ]),
+            Line::from(vec![
+                Span::styled("  Ctrl+N", Style::default().fg(Color::Green)),
+                Span::raw("  - Forget the agent conversation"),
+            ]),
             Line::from(vec![
                 Span::styled("  Enter", Style::default().fg(Color::Green)),
                 Span::raw("   - Execute command"),
             ]),
+            Line::from(vec![
+                Span::styled("  Alt+Enter", Style::default().fg(Color::Green)),
+                Span::raw(" - New line (multi-line input)"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Ctrl+Enter", Style::default().fg(Color::Green)),
+                Span::raw(" - Submit multi-line input (or Ctrl+D)"),
+            ]),
             Line::from(vec![
                 Span::styled("  Esc", Style::default().fg(Color::Green)),
                 Span::raw("     - Exit input mode"),
@@ -459,6 +650,10 @@ impl App {
                 Span::styled("  ?", Style::default().fg(Color::Green)),
                 Span::raw("       - Toggle this help"),
             ]),
+            Line::from(vec![
+                Span::styled("  i", Style::default().fg(Color::Green)),
+                Span::raw("       - Inspect selected command (full output)"),
+            ]),
             // Add scrolling key bindings
             Line::from(vec![Span::raw("")]),
             Line::from(vec![
@@ -466,7 +661,23 @@ impl App {
             ]),
             Line::from(vec![
                 Span::styled("  ↑/↓", Style::default().fg(Color::Green)),
-                Span::raw("     - Scroll up/down"),
+                Span::raw("     - Select previous/next command"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Space", Style::default().fg(Color::Green)),
+                Span::raw("     - Expand/collapse the selected command's output"),
+            ]),
+            Line::from(vec![
+                Span::styled("  Shift+↑/↓", Style::default().fg(Color::Green)),
+                Span::raw(" - Scroll inside an expanded command"),
+            ]),
+            Line::from(vec![
+                Span::styled("  /", Style::default().fg(Color::Green)),
+                Span::raw("         - Search the history; n/N step through matches"),
+            ]),
+            Line::from(vec![
+                Span::styled("  y/Y", Style::default().fg(Color::Green)),
+                Span::raw("       - Copy the selected command/its output"),
             ]),
             Line::from(vec![
                 Span::styled("  PgUp/PgDn", Style::default().fg(Color::Green)),
@@ -474,7 +685,7 @@ impl App {
             ]),
             Line::from(vec![
                 Span::styled("  Home/End", Style::default().fg(Color::Green)),
-                Span::raw("  - Scroll to top/bottom"),
+                Span::raw("  - Select newest/oldest"),
             ]),
             Line::from(vec![Span::raw("")]),
             Line::from(vec![
@@ -511,6 +722,27 @@ impl App {
         frame.render_widget(help_paragraph, popup_area);
     }
     
+    /// The block of the execution at `index` in the history pane, whose output is cut to what
+    /// is worth rendering there; copying, searching and the inspector use the whole output
+    fn history_block(&self, index: usize) -> CommandBlock {
+        let mut execution = self.command_history[index].clone();
+        execution.output = self.performance.optimize_command_output(&execution.output);
+        CommandBlock::new(execution)
+    }
+
+    fn render_inspector_overlay(&self, frame: &mut Frame, area: Rect) {
+        let Some(execution) = self.command_history.get(self.selected_block) else {
+            return;
+        };
+        let popup_area = centered_rect(90, 90, area);
+        let mut block = CommandBlock::new(execution.clone()).detailed(self.inspector_scroll);
+        block.is_selected = true;
+        block.language = self.output_languages.get(&execution.id).copied().flatten();
+        
+        frame.render_widget(Clear, popup_area);
+        block.render(frame, popup_area);
+    }
+    
     fn render_settings_overlay(&self, frame: &mut Frame, area: Rect) {
         let popup_area = centered_rect(50, 60, area);
         
@@ -559,125 +791,398 @@ impl App {
     async fn handle_event(&mut self, event: Event) -> Result<()> {
         match event {
             Event::Key(key) if key.kind == KeyEventKind::Press => {
+                if let Some(cancel) = self.warp_run.as_mut().filter(|_| is_cancel_key(&key)).and_then(|run| run.cancel.take()) {
+                    let _ = cancel.send(());
+                    self.status_bar.notify("⏹ Cancelling the warp command");
+                    return Ok(());
+                }
+                if self.pending_warp.is_some() {
+                    return self.handle_confirmation_key(key).await;
+                }
+                if self.command_palette.is_visible {
+                    self.handle_palette_key(key);
+                    return Ok(());
+                }
                 match self.input_mode {
                     InputMode::Normal => self.handle_normal_key(key).await?,
                     InputMode::Editing => self.handle_editing_key(key).await?,
                 }
             }
+            Event::Mouse(mouse) => self.handle_mouse(mouse),
             _ => {}
         }
         Ok(())
     }
     
-    async fn handle_normal_key(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
-        match key.code {
-            KeyCode::Char('q') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                self.should_quit = true;
-            }
-            KeyCode::Char('a') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                self.mode = if self.mode == AppMode::Agent {
-                    AppMode::Normal
-                } else {
-                    AppMode::Agent
-                };
-            }
-            KeyCode::Char('?') => {
-                self.mode = if self.mode == AppMode::Help {
-                    AppMode::Normal
-                } else {
-                    AppMode::Help
-                };
-            }
-            KeyCode::Char(',') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                self.mode = if self.mode == AppMode::Settings {
-                    AppMode::Normal
-                } else {
-                    AppMode::Settings
-                };
+    /// The wheel scrolls whatever is under the cursor (open overlays first, then the history
+    /// pane; the sidebar doesn't scroll) and a left click selects the block clicked on
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if self.pending_warp.is_some() {
+            return;
+        }
+        let over = |area: Rect| (area.left()..area.right()).contains(&mouse.column) && (area.top()..area.bottom()).contains(&mouse.row);
+        let up = match mouse.kind {
+            MouseEventKind::ScrollUp => true,
+            MouseEventKind::ScrollDown => false,
+            MouseEventKind::Down(MouseButton::Left) if self.mode != AppMode::Inspect && !self.command_palette.is_visible => {
+                if let Some(&(index, _)) = self.block_areas.iter().find(|(_, area)| over(*area)) {
+                    self.select_block(index);
+                }
+                return;
             }
-            // Add scrolling with arrow keys
-            KeyCode::Up => {
+            _ => return,
+        };
+        if self.command_palette.is_visible {
+            self.command_palette.move_selection(if up { -1 } else { 1 });
+        } else if self.mode == AppMode::Inspect {
+            self.handle_inspector_key(if up { KeyCode::Up } else { KeyCode::Down }.into());
+        } else if over(self.history_area) {
+            if up {
                 self.scroller.scroll_up(1);
-            }
-            KeyCode::Down => {
+            } else {
                 self.scroller.scroll_down(1);
             }
-            KeyCode::PageUp => {
+            self.keep_selection_visible();
+        }
+    }
+    
+    async fn handle_normal_key(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
+        if self.mode == AppMode::Inspect {
+            self.handle_inspector_key(key);
+            return Ok(());
+        }
+        if self.mode == AppMode::Search {
+            if let Some(search) = self.search.as_mut() {
+                match search.handle_key(key, &self.command_history, self.selected_block) {
+                    SearchAction::Pending => return Ok(()),
+                    SearchAction::Jump(index) => {
+                        self.select_block(index);
+                        return Ok(());
+                    }
+                    SearchAction::Exit => {
+                        self.close_search();
+                        return Ok(());
+                    }
+                    SearchAction::Unhandled => {}
+                }
+            }
+        }
+        if let Some(action) = self.keybindings.get_command_for_key(&key).cloned() {
+            self.run_action(&action).await;
+        }
+        Ok(())
+    }
+    
+    /// Run a named action from the keyset; unknown names are logged and ignored
+    async fn run_action(&mut self, action: &str) {
+        match action {
+            "app:quit" => self.should_quit = true,
+            "agent:toggle" => self.toggle_mode(AppMode::Agent),
+            "help:toggle" => self.toggle_mode(AppMode::Help),
+            "settings:toggle" => self.toggle_mode(AppMode::Settings),
+            // Move the selection, scrolling it into view
+            "scroll:up" => self.select_block(self.selected_block.saturating_sub(1)),
+            "scroll:down" => self.select_block(self.selected_block + 1),
+            "scroll:page_up" => {
                 self.scroller.scroll_up(5);
+                self.keep_selection_visible();
             }
-            KeyCode::PageDown => {
+            "scroll:page_down" => {
                 self.scroller.scroll_down(5);
+                self.keep_selection_visible();
             }
-            KeyCode::Home => {
-                self.scroller.scroll_offset = 0;
+            "scroll:top" => self.select_block(0),
+            "scroll:bottom" => self.select_block(usize::MAX),
+            "block:inspect" if !self.command_history.is_empty() => {
+                self.inspector_scroll = 0;
+                self.mode = AppMode::Inspect;
+            }
+            "block:inspect" => {}
+            "block:toggle" => {
+                if let Some(id) = self.command_history.get(self.selected_block).map(|exec| exec.id.clone()) {
+                    if !self.expanded.remove(&id) {
+                        self.expanded.insert(id);
+                    }
+                    self.block_scroll = 0;
+                }
             }
-            KeyCode::End => {
-                self.scroller.scroll_offset = self.scroller.max_scroll_offset();
+            "block:scroll_up" => self.block_scroll = self.block_scroll.saturating_sub(1),
+            // Rendering keeps it within the block's output
+            "block:scroll_down" if self.selected_is_expanded() => self.block_scroll += 1,
+            "block:scroll_down" => {}
+            "block:copy_command" => self.copy_selected(|exec| &exec.command, "command"),
+            "block:copy_output" => self.copy_selected(|exec| &exec.output, "output"),
+            "search:start" => {
+                self.search = Some(HistorySearch::new());
+                self.mode = AppMode::Search;
             }
-            KeyCode::Enter => {
+            "input:focus" => {
+                // The search bar stands in for the input bar, so typing a command closes it
+                if self.search.is_some() {
+                    self.close_search();
+                }
                 self.input_mode = InputMode::Editing;
             }
-            _ => {}
+            "palette:toggle" => self.toggle_palette().await,
+            "agent:reset" => self.agent.clear_history(),
+            _ => warn!("Key bound to unknown action '{}'", action),
         }
-        Ok(())
     }
     
-    async fn handle_editing_key(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
+    /// Copy `part` of the selected execution, saying in the status bar where it went
+    fn copy_selected(&mut self, part: fn(&CommandExecution) -> &String, what: &str) {
+        let Some(exec) = self.command_history.get(self.selected_block) else {
+            return;
+        };
+        let notice = match self.clipboard.copy(part(exec)) {
+            Ok(Copied::Clipboard) => format!("📋 Copied {}", what),
+            Ok(Copied::File(path)) => format!("📋 No clipboard; {} saved to {}", what, path.display()),
+            Err(e) => format!("❌ Couldn't copy the {}: {}", what, e),
+        };
+        self.status_bar.notify(notice);
+    }
+    
+    /// Open the palette with the user's recent commands ahead of the defaults, or close it
+    async fn toggle_palette(&mut self) {
+        if !self.command_palette.is_visible {
+            match self.db.get_command_history(PALETTE_HISTORY_LIMIT).await {
+                Ok(history) => self.command_palette.set_history(rank_history(&history)),
+                Err(e) => warn!("Couldn't load the command history for the palette: {}", e),
+            }
+        }
+        self.command_palette.toggle();
+    }
+    
+    /// While the palette is open it takes every key: typing filters, ↑/↓ select, Enter puts the
+    /// selected command in the input bar, and Esc or the toggle key closes it
+    fn handle_palette_key(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyModifiers;
+        if self.is_palette_toggle(&key) {
+            self.command_palette.toggle();
+            return;
+        }
         match key.code {
+            KeyCode::Esc => self.command_palette.toggle(),
             KeyCode::Enter => {
-                if !self.input.trim().is_empty() {
-                    self.execute_command().await?;
+                if let Some(command) = self.command_palette.get_selected_suggestion() {
+                    self.set_input(command);
+                    self.history_cursor = None;
+                    self.input_mode = InputMode::Editing;
                 }
-                self.input.clear();
-                self.input_mode = InputMode::Normal;
+                self.command_palette.toggle();
             }
-            KeyCode::Esc => {
-                self.input_mode = InputMode::Normal;
+            KeyCode::Up => self.command_palette.move_selection(-1),
+            KeyCode::Down => self.command_palette.move_selection(1),
+            KeyCode::Backspace => {
+                let mut filter = self.command_palette.filter.clone();
+                filter.pop();
+                self.command_palette.update_filter(filter);
             }
-            KeyCode::Char(c) => {
-                self.input.push(c);
+            KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                let filter = format!("{}{}", self.command_palette.filter, c);
+                self.command_palette.update_filter(filter);
             }
-            KeyCode::Backspace => {
-                self.input.pop();
+            _ => {}
+        }
+    }
+    
+    fn is_palette_toggle(&self, key: &crossterm::event::KeyEvent) -> bool {
+        self.keybindings.get_command_for_key(key).is_some_and(|action| action == "palette:toggle")
+    }
+    
+    fn toggle_mode(&mut self, mode: AppMode) {
+        self.mode = if self.mode == mode { self.base_mode() } else { mode };
+    }
+    
+    /// The mode closing an overlay returns to: the search when one is open
+    fn base_mode(&self) -> AppMode {
+        if self.search.is_some() { AppMode::Search } else { AppMode::Normal }
+    }
+    
+    /// Show the whole history again, keeping the selected match selected
+    fn close_search(&mut self) {
+        self.search = None;
+        self.mode = AppMode::Normal;
+        self.scroller.scroll_to_item(self.selected_block);
+    }
+    
+    fn handle_inspector_key(&mut self, key: crossterm::event::KeyEvent) {
+        let last_line = self.command_history.get(self.selected_block)
+            .map(|e| e.output.lines().count().saturating_sub(1))
+            .unwrap_or(0)
+            .min(u16::MAX as usize) as u16;
+        match key.code {
+            KeyCode::Char('q') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                self.should_quit = true;
             }
+            KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('q') => {
+                self.mode = self.base_mode();
+            }
+            KeyCode::Up => self.inspector_scroll = self.inspector_scroll.saturating_sub(1),
+            KeyCode::Down => self.inspector_scroll = (self.inspector_scroll + 1).min(last_line),
+            KeyCode::PageUp => self.inspector_scroll = self.inspector_scroll.saturating_sub(10),
+            KeyCode::PageDown => self.inspector_scroll = self.inspector_scroll.saturating_add(10).min(last_line),
+            KeyCode::Home => self.inspector_scroll = 0,
+            KeyCode::End => self.inspector_scroll = last_line,
             _ => {}
         }
-        return Ok(())
+    }
+    
+    fn selected_is_expanded(&self) -> bool {
+        self.command_history.get(self.selected_block).is_some_and(|exec| self.expanded.contains(&exec.id))
+    }
+    
+    /// Highlight the execution at `index`, clamped to the history
+    fn select_block(&mut self, index: usize) {
+        let index = index.min(self.command_history.len().saturating_sub(1));
+        if index != self.selected_block {
+            self.block_scroll = 0;
+        }
+        self.selected_block = index;
+        self.scroller.scroll_to_item(self.selected_block);
+    }
+    
+    /// After scrolling, move the selection onto the nearest visible execution
+    fn keep_selection_visible(&mut self) {
+        let (start, end) = self.scroller.get_visible_range();
+        if start < end {
+            self.selected_block = self.selected_block.clamp(start, end - 1);
+        }
+    }
+    
+    async fn handle_editing_key(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
+        // The palette opens while typing too; its key doesn't insert text
+        if self.is_palette_toggle(&key) {
+            self.toggle_palette().await;
+            return Ok(());
+        }
+        // Up/Down step through earlier commands, like a shell; multi-line input keeps them
+        if !self.input_bar.is_multiline() {
+            if let KeyCode::Up | KeyCode::Down = key.code {
+                self.recall_history(key.code == KeyCode::Up);
+                return Ok(());
+            }
+        }
+        let before = self.input_bar.content.clone();
+        match self.input_bar.handle_key(key) {
+            InputAction::Submit => {
+                self.history_cursor = None;
+                if !self.input_bar.content.trim().is_empty() {
+                    let result = self.execute_command().await;
+                    self.append_transcript();
+                    result?;
+                }
+                self.input_bar.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            InputAction::Cancel => {
+                self.input_mode = InputMode::Normal;
+            }
+            // Editing a recalled command makes it the user's own line
+            InputAction::Edited if self.input_bar.content != before => self.history_cursor = None,
+            InputAction::Edited => {}
+        }
+        Ok(())
+    }
+    
+    /// Put the next `older` (or newer) history command in the input bar. Moving newer than the
+    /// newest entry brings back what was typed before; moving past the oldest stays put.
+    fn recall_history(&mut self, older: bool) {
+        let next = match (self.history_cursor, older) {
+            (None, false) => return,
+            (None, true) => 0,
+            (Some(index), true) => index + 1,
+            (Some(0), false) => {
+                self.history_cursor = None;
+                let draft = std::mem::take(&mut self.history_draft);
+                self.set_input(draft);
+                return;
+            }
+            (Some(index), false) => index - 1,
+        };
+        let Some(command) = self.command_history.get(next).map(|e| e.command.clone()) else {
+            return;
+        };
+        if self.history_cursor.is_none() {
+            self.history_draft = self.input_bar.content.clone();
+        }
+        self.history_cursor = Some(next);
+        self.set_input(command);
+    }
+    
+    fn set_input(&mut self, content: String) {
+        self.input_bar.content = content;
+        self.input_bar.cursor_position = self.input_bar.content.len();
     }
     
     async fn execute_command(&mut self) -> Result<()> {
-        let command = self.input.trim().to_string();
+        let command = self.input_bar.content.trim().to_string();
         info!("Executing command: {}", command);
 
-        let execution = CommandExecution::new(
-            command.clone(),
-            if self.mode == AppMode::Agent {
-                Some(command.clone())
-            } else {
-                None
-            },
-        );
+        // Aliases apply before parsing, so `t list` runs `task list`; shell commands are
+        // expanded again where they run, so they're passed on as typed
+        let expanded = crate::commands::alias::expand(&command, &self.config.aliases);
+        
+        // Use shell_words for proper splitting
+        let args = match &expanded {
+            Ok(expanded) => shell_words::split(expanded).map_err(anyhow::Error::from),
+            Err(e) => Err(anyhow::anyhow!("{}", e)),
+        };
+        let startup_flag = args.as_deref().ok().and_then(startup_flag).map(str::to_string);
+        let parsed = args.map(crate::TuiInput::try_parse_from);
+        
+        // Input that isn't a subcommand goes to the shell or the agent
+        let route = self.route_unparsed(expanded.as_deref().unwrap_or(&command));
+        
+        // Only input that actually reaches the agent is recorded as an agent query
+        let agent_query = match &parsed {
+            Ok(Ok(crate::TuiInput { command: crate::CliCommand::Session(crate::Commands::Agent { agent_cmd: None, query: Some(query), .. }) })) => Some(query.clone()),
+            Ok(Err(_)) if route == Some(InputRoute::Agent) && startup_flag.is_none() => Some(command.clone()),
+            _ => None,
+        };
+        let overrides = match &parsed {
+            Ok(Ok(crate::TuiInput { command: crate::CliCommand::Session(crate::Commands::Agent { model, temp, .. }) })) => {
+                ModelOverrides { model: model.clone(), temperature: *temp }
+            }
+            _ => ModelOverrides::default(),
+        };
+        let mut execution = CommandExecution::new(command.clone(), agent_query);
+        if execution.agent_query.is_some() {
+            self.agent.attribute(&mut execution, &overrides);
+        }
 
-        // Add to history immediately
+        // Add to history immediately, newest first, and select it
         self.command_history.insert(0, execution.clone());
+        self.scroller.update_total_items(self.command_history.len());
+        self.select_block(0);
         self.db.save_command_execution(&execution).await?;
 
-        // Use shell_words for proper splitting
-        let args = match shell_words::split(&command) {
-            Ok(a) => a,
+        if let Some(flag) = startup_flag {
+            let message = format!(
+                "{} only takes effect when starting agentic (e.g. `agentic {}`); here, type a subcommand like `task list`",
+                flag, flag
+            );
+            self.update_execution_output(0, &message, ExecutionStatus::Error, 0).await?;
+            return Ok(());
+        }
+
+        let parsed = match parsed {
+            Ok(parsed) => parsed,
             Err(e) => {
                 self.update_execution_output(0, &format!("Error parsing command: {}", e), ExecutionStatus::Error, 0).await?;
                 return Ok(());
             }
         };
-        let mut cli_args = vec!["agentic-cli"]; // program name for clap
-        cli_args.extend(args.iter().map(|s| s.as_str()));
 
-        match crate::Cli::try_parse_from(cli_args) {
+        match parsed {
             Ok(cli) => {
-                match cli.command {
-                    Some(crate::Commands::Task { task_cmd }) => {
-                        match self.command_registry.execute_task(task_cmd, &self.db).await {
+                let command = match cli.command {
+                    crate::CliCommand::Standalone(command) => return self.run_standalone(command).await,
+                    crate::CliCommand::Session(command) => command,
+                };
+                match command {
+                    crate::Commands::Task { task_cmd } => {
+                        match self.command_registry.execute_task(task_cmd, &self.db, Output::Text).await {
                             Ok(_) => {
                                 self.update_execution_output(0, "Task command executed successfully", ExecutionStatus::Success, 75).await?;
                             }
@@ -685,10 +1190,12 @@ impl App {
                                 self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
                             }
                         }
-                        return Ok(());
                     }
-                    Some(crate::Commands::Prep { prep_cmd }) => {
-                        match self.command_registry.execute_prep(prep_cmd, &self.db).await {
+                    crate::Commands::Prep { prep_cmd: crate::commands::PrepCommand::Start { pomodoro: true, .. } } => {
+                        self.update_execution_output(0, "Pomodoro timers need the terminal; run `agentic prep start --pomodoro` outside the TUI", ExecutionStatus::Error, 0).await?;
+                    }
+                    crate::Commands::Prep { prep_cmd } => {
+                        match self.command_registry.execute_prep(prep_cmd, &self.db, &self.config, Output::Text).await {
                             Ok(_) => {
                                 self.update_execution_output(0, "Prep command executed successfully", ExecutionStatus::Success, 75).await?;
                             }
@@ -696,10 +1203,12 @@ impl App {
                                 self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
                             }
                         }
-                        return Ok(());
                     }
-                    Some(crate::Commands::Blog { blog_cmd }) => {
-                        match self.command_registry.execute_blog(blog_cmd, &self.db).await {
+                    crate::Commands::Blog { blog_cmd: crate::commands::BlogCommand::Edit { .. } } => {
+                        self.update_execution_output(0, "Editing opens $EDITOR; run `agentic blog edit` outside the TUI", ExecutionStatus::Error, 0).await?;
+                    }
+                    crate::Commands::Blog { blog_cmd } => {
+                        match self.command_registry.execute_blog(blog_cmd, &self.db, &self.config, Output::Text).await {
                             Ok(_) => {
                                 self.update_execution_output(0, "Blog command executed successfully", ExecutionStatus::Success, 75).await?;
                             }
@@ -707,113 +1216,651 @@ impl App {
                                 self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
                             }
                         }
-                        return Ok(());
                     }
-                    Some(crate::Commands::Agent { query }) => {
-                        match self.agent.process_query(&query).await {
-                            Ok(response) => {
-                                self.update_execution_output(0, &response, ExecutionStatus::Success, 100).await?;
+                    crate::Commands::Agent { agent_cmd: Some(agent_cmd), .. } => {
+                        match self.command_registry.execute_agent(agent_cmd, &self.config, Output::Text).await {
+                            Ok(output) => {
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 100).await?;
                             }
                             Err(e) => {
                                 self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 50).await?;
                             }
                         }
-                        return Ok(());
                     }
-                    Some(crate::Commands::Warp { request, dry_run }) => {
-                        let pipeline = crate::warp::WarpPipeline::new(&self.config)?;
-                        if dry_run {
-                            let (_plan, command) = pipeline.dry_run(&request).await?;
-                            let output = format!("\n{} Would execute: {}", "📋", command);
-                            self.update_execution_output(0, &output, ExecutionStatus::Success, 100).await?;
-                        } else {
-                            let result = pipeline.execute(&request).await?;
-                            let output = if !result.is_success() && !result.cancelled {
-                                "Pipeline execution failed".to_string()
+                    crate::Commands::Agent { query, export, exec, yes, .. } => {
+                        if let Some(query) = query {
+                            let response = self.ask_agent(&query, &overrides).await?;
+                            if let Some(response) = response.filter(|_| exec) {
+                                self.run_suggestion(&response, yes).await?;
+                            }
+                        }
+                        if let Some(path) = export {
+                            let exported = if self.agent_session.is_empty() {
+                                Err(anyhow::anyhow!("No agent queries in this session yet"))
                             } else {
-                                "Pipeline executed successfully".to_string()
+                                self.agent_session.write_markdown(&path)
+                                    .map(|()| format!("Exported {} agent exchanges to {}", self.agent_session.exchanges(), path.display()))
                             };
-                            self.update_execution_output(0, &output, ExecutionStatus::Success, 100).await?;
+                            match exported {
+                                Ok(message) => self.update_execution_output(0, &message, ExecutionStatus::Success, 0).await?,
+                                Err(e) => self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 0).await?,
+                            }
                         }
-                        return Ok(());
                     }
-                    Some(crate::Commands::Run { command }) => {
-                        match self.command_registry.execute_raw_command(&command).await {
-                            Ok(_) => {
-                                self.update_execution_output(0, "Command executed successfully", ExecutionStatus::Success, 75).await?;
+                    crate::Commands::Warp { warp_cmd: Some(warp_cmd), .. } => {
+                        match self.command_registry.execute_warp(warp_cmd, Output::Text) {
+                            Ok(output) => self.update_execution_output(0, &output, ExecutionStatus::Success, 25).await?,
+                            Err(e) => self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?,
+                        }
+                    }
+                    crate::Commands::Warp { warp_cmd: None, dry_run: false, .. } if self.warp_run.is_some() => {
+                        self.update_execution_output(0, "A warp command is still running; wait for it to finish", ExecutionStatus::Error, 0).await?;
+                    }
+                    crate::Commands::Warp { request, dry_run, yes, cwd, model, temp, .. } => {
+                        let request = request.unwrap_or_default();
+                        // The command's output streams into a block of its own instead of the terminal
+                        let (sink, lines) = tokio::sync::mpsc::unbounded_channel();
+                        // A broken .agentic.toml or env_file fails this command, not the TUI
+                        let pipeline = match self.command_registry.warp_pipeline(&self.config).await {
+                            Ok(pipeline) => pipeline,
+                            Err(e) => {
+                                return self.update_execution_output(0, &format!("Error: {:#}", e), ExecutionStatus::Error, 0).await;
+                            }
+                        };
+                        let pipeline = pipeline
+                            .with_model_overrides(&ModelOverrides { model, temperature: temp })
+                            .with_auto_confirm(yes)
+                            .with_working_dir(cwd.as_deref())
+                            .with_output_sink(sink);
+                        let started = Instant::now();
+                        // The plan and command are generated silently; printing would draw over the TUI
+                        match pipeline.plan_only(&request).await {
+                            Ok(suggestion) if dry_run => {
+                                let output = format!("📝 Plan: {}\n📋 Would execute: {}", suggestion.plan, suggestion.command);
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, started.elapsed().as_millis()).await?;
+                            }
+                            Ok(suggestion) => self.offer_warp(pipeline, suggestion, lines).await?,
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, started.elapsed().as_millis()).await?;
+                            }
+                        }
+                    }
+                    crate::Commands::Plan { goal, yes } => {
+                        match crate::commands::plan::report(&goal, &self.agent, yes).await {
+                            Ok((output, succeeded)) => {
+                                let status = if succeeded { ExecutionStatus::Success } else { ExecutionStatus::Error };
+                                self.update_execution_output(0, &output, status, 100).await?;
                             }
                             Err(e) => {
                                 self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
                             }
                         }
-                        return Ok(());
                     }
-                    Some(crate::Commands::Tui) | None => {
-                        // Already in TUI mode, do nothing
-                        return Ok(());
+                    crate::Commands::Run { command, count, fail_fast, .. } if count > 1 => {
+                        self.run_repeated(&command, count, fail_fast).await?;
                     }
-                }
-
-            }
-            Err(e) => {
-                // If not a recognized CLI command, try agent mode if enabled
-                if self.mode == AppMode::Agent {
-                    match self.agent.process_query(&command).await {
-                        Ok(response) => {
-                            self.update_execution_output(0, &response, ExecutionStatus::Success, 100).await?;
+                    crate::Commands::Run { command, .. } => {
+                        self.run_shell(&command).await?;
+                    }
+                    crate::Commands::Models { models_cmd: Some(crate::commands::models::ModelsCommand::Use { model, save }) } => {
+                        let output = self.switch_model(&model, save).await;
+                        let status = if output.is_ok() { ExecutionStatus::Success } else { ExecutionStatus::Error };
+                        let output = output.unwrap_or_else(|e| format!("Error: {}", e));
+                        self.update_execution_output(0, &output, status, 10).await?;
+                    }
+                    crate::Commands::Embed { text, model, dims } => {
+                        match self.command_registry.execute_embed(&text, model, dims, &self.config, Output::Text).await {
+                            Ok(output) => {
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+                            }
                         }
-                        Err(e) => {
-                            self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 50).await?;
+                    }
+                    crate::Commands::Models { models_cmd: None } => {
+                        match self.command_registry.execute_models(&self.config).await {
+                            Ok(output) => {
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+                            }
                         }
                     }
-                    return Ok(());
-                } else {
-                    self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 0).await?;
-                    Ok(())
+                    crate::Commands::Config { config_cmd } => {
+                        match self.command_registry.execute_config(config_cmd).await {
+                            Ok(output) => {
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+                            }
+                        }
+                    }
+                    crate::Commands::Keys { keys_cmd } => {
+                        match self.command_registry.execute_keys(keys_cmd).await {
+                            Ok(output) => {
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+                            }
+                        }
+                    }
+                    crate::Commands::Theme { theme_cmd: crate::commands::theme::ThemeCommand::Preview { name } } => {
+                        // Colored terminal text can't be shown here, so apply the theme for the session instead
+                        match AppTheme::named(&name) {
+                            Ok(theme) => {
+                                self.theme = theme;
+                                let output = format!("🎨 Previewing {} for this session; `theme set {}` keeps it", name, name);
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 10).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {:#}", e), ExecutionStatus::Error, 10).await?;
+                            }
+                        }
+                    }
+                    crate::Commands::Theme { theme_cmd } => {
+                        match self.command_registry.execute_theme(theme_cmd, &mut self.config).await {
+                            Ok(output) => {
+                                self.theme = AppTheme::from_config(&self.config);
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+                            }
+                        }
+                    }
+                    crate::Commands::History { history_cmd } => {
+                        let clearing = matches!(history_cmd, crate::commands::history::HistoryCommand::Clear { .. });
+                        match self.command_registry.execute_history(history_cmd, &self.db, &self.config, false).await {
+                            Ok(output) if clearing => {
+                                // The blocks on screen may be gone from the database now
+                                let entry = self.command_history.first().cloned();
+                                self.load_command_history().await?;
+                                if let Some(entry) = entry.filter(|entry| self.command_history.first().is_none_or(|first| first.id != entry.id)) {
+                                    self.command_history.insert(0, entry);
+                                    self.scroller.update_total_items(self.command_history.len());
+                                }
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Ok(output) => {
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+                            }
+                        }
+                    }
+                    crate::Commands::Alias { alias_cmd } => {
+                        match self.command_registry.execute_alias(alias_cmd, &mut self.config).await {
+                            Ok(output) => {
+                                self.command_registry = self.command_registry.clone().with_aliases(self.config.aliases.clone());
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+                            }
+                        }
+                    }
+                    crate::Commands::Workflow { workflow_cmd } => {
+                        match self.command_registry.execute_workflow(workflow_cmd, false).await {
+                            Ok(output) => {
+                                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+                            }
+                            Err(e) => {
+                                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+                            }
+                        }
+                    }
+                    crate::Commands::Tui => {
+                        self.update_execution_output(0, "Already running the TUI", ExecutionStatus::Success, 0).await?;
+                    }
+                }
+            }
+            Err(e) => {
+                // Not a recognized CLI command
+                match route {
+                    Some(InputRoute::Agent) => {
+                        self.ask_agent(&command, &overrides).await?;
+                    }
+                    Some(InputRoute::Shell) => self.run_shell(&command).await?,
+                    None => {
+                        self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 0).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run `doctor`, `version` or `completions` typed in the TUI into the newest block
+    async fn run_standalone(&mut self, command: crate::StandaloneCommand) -> Result<()> {
+        match command {
+            crate::StandaloneCommand::Doctor { fix, yes } => {
+                let mut report = self.command_registry.execute_doctor().await;
+                let mut output = report.render();
+                if fix && !yes && !report.fixes().is_empty() {
+                    // No stdin to confirm each fix on here
+                    output.push_str("\nAdd --yes to apply the fixes from the TUI");
+                } else if fix && !report.fixes().is_empty() {
+                    output.push('\n');
+                    // `ollama pull` would draw its progress over the TUI
+                    let actions = crate::commands::doctor::fix(&report, |fix| std::future::ready(!fix.needs_terminal())).await;
+                    output.push_str(&actions.join("\n"));
+                    if report.fixes().iter().any(|fix| fix.needs_terminal()) {
+                        output.push_str("\nRun `agentic doctor --fix` outside the TUI to pull models");
+                    }
+                    report = self.command_registry.execute_doctor().await;
+                    output.push_str(&format!("\n\n{}", report.render()));
+                }
+                let status = if report.passed() { ExecutionStatus::Success } else { ExecutionStatus::Error };
+                self.update_execution_output(0, &output, status, 50).await?;
+            }
+            crate::StandaloneCommand::Version => {
+                let output = self.command_registry.execute_version().await;
+                self.update_execution_output(0, &output, ExecutionStatus::Success, 50).await?;
+            }
+            crate::StandaloneCommand::Completions { shell } => {
+                let output = format!("Completion scripts are meant to be saved to a file; run `agentic completions {}` in your shell\n\n{}", shell, crate::commands::completions::INSTALL_HELP);
+                self.update_execution_output(0, &output, ExecutionStatus::Error, 0).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decide where input goes if clap doesn't recognise it; `None` reports the parse error
+    fn route_unparsed(&self, input: &str) -> Option<InputRoute> {
+        let agent_mode = self.mode == AppMode::Agent;
+        if self.config.agent.smart_routing {
+            Some(classify_input(input, agent_mode, is_on_path))
+        } else if agent_mode {
+            Some(InputRoute::Agent)
+        } else {
+            None
+        }
+    }
+    
+    /// Run `command` in the shell with output captured, as `run` does
+    async fn run_shell(&mut self, command: &str) -> Result<()> {
+        let result = match crate::commands::prep::check_focus_lock(&self.db, &self.config.prep, command).await {
+            Ok(()) => self.command_registry.execute_raw_command(command, false).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(stdout) => {
+                let output = if stdout.trim().is_empty() { "Command executed successfully".to_string() } else { stdout };
+                self.update_execution_output(0, &output, ExecutionStatus::Success, 75).await?;
+            }
+            Err(e) => {
+                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `run -n`: run `command` `count` times and show each run plus the aggregate timing
+    async fn run_repeated(&mut self, command: &str, count: u32, fail_fast: bool) -> Result<()> {
+        let started = Instant::now();
+        let result = match crate::commands::prep::check_focus_lock(&self.db, &self.config.prep, command).await {
+            Ok(()) => {
+                let mut lines = Vec::new();
+                self.command_registry
+                    .execute_repeated(command, crate::commands::RepeatOptions { count, fail_fast }, false, &self.db, cancel_key_pressed(), |run| {
+                        lines.push(run.describe());
+                    })
+                    .await
+                    .map(|summary| (lines, summary))
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok((mut lines, summary)) => {
+                lines.push(String::new());
+                lines.push(summary.render());
+                let status = if summary.all_succeeded() { ExecutionStatus::Success } else { ExecutionStatus::Error };
+                self.update_execution_output(0, &lines.join("\n"), status, started.elapsed().as_millis()).await?;
+            }
+            Err(e) => {
+                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, 25).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Send `query` to the agent as the next turn of the conversation, show the response and add
+    /// the exchange to the session
+    async fn ask_agent(&mut self, query: &str, overrides: &ModelOverrides) -> Result<Option<String>> {
+        let asked_at = crate::offline::now();
+        let started = Instant::now();
+        match self.agent.converse(query, overrides).await {
+            Ok(response) => {
+                self.agent_session.push_exchange(query, asked_at, &response, crate::offline::now());
+                self.update_execution_output(0, &response, ExecutionStatus::Success, started.elapsed().as_millis()).await?;
+                Ok(Some(response))
+            }
+            Err(e) => {
+                self.update_execution_output(0, &format!("Error: {}", e), ExecutionStatus::Error, started.elapsed().as_millis()).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// `agent --exec --yes`: run the command the agent just suggested as a block of its own.
+    /// The TUI can't ask first, so without `yes` the answer only gets a note.
+    async fn run_suggestion(&mut self, response: &str, yes: bool) -> Result<()> {
+        match crate::commands::agent::execute_suggestion(response, &self.db, &self.config, yes, false).await {
+            Ok(Some(run)) => {
+                self.command_history.insert(0, run);
+                self.scroller.update_total_items(self.command_history.len());
+                self.select_block(0);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let output = format!("{}\n\n⚠ {}", response, e);
+                let duration_ms = self.command_history.first().map_or(0, |exec| exec.duration_ms);
+                self.update_execution_output(0, &output, ExecutionStatus::Success, duration_ms as u128).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Show the warp suggestion in the confirmation widget, or run it straight away when it
+    /// needs no confirmation. A dangerous command is refused if it may not run at all.
+    async fn offer_warp(
+        &mut self,
+        pipeline: crate::warp::WarpPipeline,
+        suggestion: crate::warp::pipeline::DryRun,
+        lines: tokio::sync::mpsc::UnboundedReceiver<String>,
+    ) -> Result<()> {
+        let Some(block_id) = self.command_history.first().map(|exec| exec.id.clone()) else {
+            return Ok(());
+        };
+        let summary = warp_summary(&suggestion.plan, &suggestion.command);
+        let dangerous = pipeline.dangerous_match(&suggestion.command).map(str::to_string);
+        if let Some(pattern) = dangerous.as_deref().filter(|_| !pipeline.allows_dangerous()) {
+            let output = format!("{}\n🛑 Refused: it matches the dangerous pattern '{}' (warp.safety.require_confirmation is off)", summary, pattern);
+            return self.update_execution_output(0, &output, ExecutionStatus::Error, 0).await;
+        }
+        let pending = PendingWarp {
+            confirmation: WarpConfirmation::new(suggestion.input.clone(), suggestion.plan.clone(), suggestion.command.clone(), dangerous.clone()),
+            pipeline,
+            suggestion,
+            lines,
+            block_id,
+        };
+        if dangerous.is_none() && pending.pipeline.auto_confirms() {
+            let command = pending.suggestion.command.clone();
+            return self.start_warp_run(pending, command).await;
+        }
+        self.update_execution_output(0, &format!("{}\n❓ Waiting for confirmation", summary), ExecutionStatus::Running, 0).await?;
+        self.pending_warp = Some(pending);
+        Ok(())
+    }
+
+    /// While a warp suggestion is on screen every key answers it
+    async fn handle_confirmation_key(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
+        let Some(pending) = self.pending_warp.as_mut() else {
+            return Ok(());
+        };
+        match pending.confirmation.handle_key(key) {
+            ConfirmAction::Pending => {}
+            ConfirmAction::Edited(command) if command.is_empty() => pending.confirmation.stage = ConfirmStage::Choose,
+            ConfirmAction::Edited(command) => {
+                // The edited command goes through the same checks before it runs
+                let dangerous = pending.pipeline.dangerous_match(&command).map(str::to_string);
+                if let Some(pattern) = dangerous.as_deref().filter(|_| !pending.pipeline.allows_dangerous()) {
+                    let reason = format!("🛑 Refused: the edited command matches the dangerous pattern '{}'", pattern);
+                    return self.close_warp(&command, &reason).await;
                 }
+                pending.confirmation.set_command(command, dangerous);
+            }
+            ConfirmAction::Run(command) => {
+                if let Some(pending) = self.pending_warp.take() {
+                    self.start_warp_run(pending, command).await?;
+                }
+            }
+            ConfirmAction::Cancel(reason) => {
+                let command = pending.confirmation.command.clone();
+                return self.close_warp(&command, &format!("⏹ {}", reason)).await;
             }
         }
-        // Ok(()) <-- REMOVE THIS LINE
+        Ok(())
+    }
+
+    /// Close the confirmation without running anything, noting `reason` on the warp block
+    async fn close_warp(&mut self, command: &str, reason: &str) -> Result<()> {
+        let Some(pending) = self.pending_warp.take() else {
+            return Ok(());
+        };
+        let output = format!("{}\n{}", warp_summary(&pending.suggestion.plan, command), reason);
+        if let Some(index) = self.block_index(&pending.block_id) {
+            self.update_execution_output(index, &output, ExecutionStatus::Cancelled, 0).await?;
+        }
+        Ok(())
+    }
+
+    /// Run the confirmed `command` in the background as a block of its own, which its output
+    /// streams into; `update` picks up the lines and the result
+    async fn start_warp_run(&mut self, pending: PendingWarp, command: String) -> Result<()> {
+        let PendingWarp { pipeline, suggestion, lines, block_id, .. } = pending;
+        if let Some(index) = self.block_index(&block_id) {
+            let output = format!("{}\n🚀 Running it in the block above", warp_summary(&suggestion.plan, &command));
+            self.update_execution_output(index, &output, ExecutionStatus::Success, 0).await?;
+        }
+
+        let run = CommandExecution::new(command.clone(), Some(suggestion.input.clone()));
+        self.db.save_command_execution(&run).await?;
+        self.command_history.insert(0, run.clone());
+        self.scroller.update_total_items(self.command_history.len());
+        self.select_block(0);
+
+        let (cancel, cancelled) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn(async move {
+            let cancelled = async {
+                let _ = cancelled.await;
+            };
+            let result = pipeline.run_confirmed(&suggestion, &command, cancelled).await?;
+            Ok(pipeline.record(&result))
+        });
+        self.warp_run = Some(WarpRun { block_id: run.id, lines, task, cancel: Some(cancel) });
+        Ok(())
+    }
+
+    /// Append the output the running warp command has printed since the last frame, and record
+    /// its result once it finishes
+    async fn poll_warp_run(&mut self) -> Result<()> {
+        let Some(mut run) = self.warp_run.take() else {
+            return Ok(());
+        };
+        let mut streamed = Vec::new();
+        while let Ok(line) = run.lines.try_recv() {
+            streamed.push(line);
+        }
+        let index = self.block_index(&run.block_id);
+        if let Some(exec) = index.and_then(|index| self.command_history.get_mut(index)).filter(|_| !streamed.is_empty()) {
+            if !exec.output.is_empty() {
+                exec.output.push('\n');
+            }
+            exec.output.push_str(&streamed.join("\n"));
+        }
+        if !run.task.is_finished() {
+            self.warp_run = Some(run);
+            return Ok(());
+        }
+
+        let Some(index) = index else {
+            return Ok(());
+        };
+        match run.task.await {
+            Ok(Ok(record)) => {
+                if let Some(exec) = self.command_history.get_mut(index) {
+                    exec.exit_code = record.exit_code;
+                    exec.provider = record.provider.clone();
+                    exec.model = record.model.clone();
+                }
+                self.update_execution_output(index, &record.output, record.status, record.duration_ms as u128).await
+            }
+            Ok(Err(e)) => self.update_execution_output(index, &format!("Error: {}", e), ExecutionStatus::Error, 0).await,
+            Err(e) => self.update_execution_output(index, &format!("Error: the warp run stopped unexpectedly: {}", e), ExecutionStatus::Error, 0).await,
+        }
+    }
+
+    /// Where the execution with `id` is in `command_history`; blocks move as new ones are added
+    fn block_index(&self, id: &str) -> Option<usize> {
+        self.command_history.iter().position(|exec| exec.id == id)
+    }
+
+    /// Switch the agent's model for this session; `save` also makes it the default in the config file
+    async fn switch_model(&mut self, model: &str, save: bool) -> Result<String> {
+        if !self.agent.set_model(model) {
+            return Err(anyhow::anyhow!("the Ollama client isn't running, so there is no model to switch"));
+        }
+        if !save {
+            return Ok(crate::commands::models::switch_message(model, None, &self.config));
+        }
+        let path = crate::config::Config::config_path();
+        crate::commands::models::persist_default_model(&mut self.config, &path, model).await?;
+        Ok(crate::commands::models::switch_message(model, Some(&path), &self.config))
     }
 
     async fn update_execution_output(&mut self, index: usize, output: &str, status: ExecutionStatus, duration_ms: u128) -> Result<()> {
         if let Some(exec) = self.command_history.get_mut(index) {
             exec.output = output.to_string();
             exec.status = status.clone();
+            self.output_languages.remove(&exec.id);
             exec.duration_ms = duration_ms as u64;
             self.db.update_execution_status(
                 &exec.id,
                 status,
                 output,
                 duration_ms as u64,
+                exec.exit_code,
             ).await?;
         }
         Ok(())
     }
     
+    /// Open this session's transcript if enabled; failing to is logged rather than fatal
+    fn start_transcript(&mut self) {
+        if !self.config.transcript.enabled {
+            return;
+        }
+        let secrets = self.config.get_openai_api_key().into_iter().collect();
+        let redactor = Redactor::new(&self.config.transcript.redact_keys, secrets);
+        match Transcript::start(&self.config.transcript, redactor, crate::offline::now().with_timezone(&chrono::Local)) {
+            Ok(transcript) => {
+                info!("Writing session transcript to {:?}", transcript.path());
+                self.transcript = Some(transcript);
+            }
+            Err(e) => warn!("Transcript disabled: {:#}", e),
+        }
+    }
+    
+    /// Log the most recent execution to the transcript
+    fn append_transcript(&self) {
+        let (Some(transcript), Some(execution)) = (&self.transcript, self.command_history.first()) else {
+            return;
+        };
+        if let Err(e) = transcript.append(execution) {
+            warn!("Failed to write transcript: {:#}", e);
+        }
+    }
+    
     async fn load_command_history(&mut self) -> Result<()> {
         // Load command history from database
         self.command_history = self.db.get_command_history(100).await?;
+        self.trim_history();
         
         // Update scroller with the total number of items
         self.scroller.update_total_items(self.command_history.len());
         
-        // Scroll to the bottom to show most recent commands
-        if !self.command_history.is_empty() {
-            self.scroller.scroll_to_item(self.command_history.len() - 1);
-        }
+        // History is newest first, so start at the top with the latest command selected
+        self.select_block(0);
         
         Ok(())
     }
     
+    /// Detect what the output of the execution at `index` is highlighted as, unless that is
+    /// known already; a running command's output is still changing, so it waits until it ends
+    fn cache_output_language(&mut self, index: usize) {
+        let Some(exec) = self.command_history.get(index) else { return };
+        if matches!(exec.status, ExecutionStatus::Running) || self.output_languages.contains_key(&exec.id) {
+            return;
+        }
+        let language = detect_language(&exec.command, &exec.output);
+        self.output_languages.insert(exec.id.clone(), language);
+    }
+    
+    /// Drop the oldest executions beyond `max_history_size`
+    fn trim_history(&mut self) {
+        let len = self.command_history.len();
+        self.performance.optimize_command_history(&mut self.command_history);
+        if self.command_history.len() < len {
+            let kept: HashSet<&str> = self.command_history.iter().map(|exec| exec.id.as_str()).collect();
+            self.output_languages.retain(|id, _| kept.contains(id.as_str()));
+            self.scroller.update_total_items(self.command_history.len());
+            self.select_block(self.selected_block);
+        }
+    }
+    
     async fn update(&mut self) -> Result<()> {
+        // New commands grow the history past its cap now and then
+        self.trim_history();
+        
+        // A running warp command streams output into its block
+        if self.warp_run.is_some() {
+            self.poll_warp_run().await?;
+            self.needs_redraw = true;
+        }
+        
         // Update components
+        let had_notice = self.status_bar.notice.is_some();
         self.input_bar.update();
         self.status_bar.update();
         self.sidebar.update();
+        if had_notice && self.status_bar.notice.is_none() {
+            self.needs_redraw = true;
+        }
+        
+        if self.animate_running_blocks() {
+            self.needs_redraw = true;
+        }
+        self.poll_connectivity().await;
         
         Ok(())
     }
+    
+    /// Start a connectivity check every `CONNECTIVITY_CHECK_INTERVAL`, in the background so a
+    /// slow Ollama doesn't hold up the frame, and pick up its result once it is in
+    async fn poll_connectivity(&mut self) {
+        match &self.connectivity_check {
+            Some(check) if check.is_finished() => {
+                let Some(check) = self.connectivity_check.take() else { return };
+                let online = check.await.unwrap_or(Connectivity::Offline);
+                if self.provider_online != Some(online) {
+                    self.provider_online = Some(online);
+                    self.needs_redraw = true;
+                }
+            }
+            Some(_) => {}
+            None if self.last_connectivity_check.is_none_or(|at| at.elapsed() >= CONNECTIVITY_CHECK_INTERVAL) => {
+                self.last_connectivity_check = Some(Instant::now());
+                self.connectivity_check = Some(tokio::spawn(self.agent.connectivity_check()));
+            }
+            None => {}
+        }
+    }
+    
+    /// Keep the animations of running blocks going and advance them; true while any run
+    fn animate_running_blocks(&mut self) -> bool {
+        for exec in self.command_history.iter().filter(|exec| matches!(exec.status, ExecutionStatus::Running)) {
+            keep_animating(&mut self.animations, &exec.id);
+        }
+        self.animations.update()
+    }
 }
 
 // Helper function to create centered rectangle
@@ -835,4 +1882,642 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
             Constraint::Percentage((100 - percent_x) / 2),
         ])
         .split(popup_layout[1])[1]
-}
\ No newline at end of file
+}
+
+/// Whether `key` asks to stop what is running: Ctrl+C or Esc
+fn is_cancel_key(key: &crossterm::event::KeyEvent) -> bool {
+    use crossterm::event::KeyModifiers;
+    key.code == KeyCode::Esc || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// Completes once Ctrl+C or Esc is pressed, for work the event loop waits on; it reads the
+/// terminal's events itself meanwhile, dropping other keys. Never completes without a terminal.
+async fn cancel_key_pressed() {
+    loop {
+        match event::poll(Duration::ZERO) {
+            Ok(true) => {
+                if let Ok(Event::Key(key)) = event::read() {
+                    if key.kind == KeyEventKind::Press && is_cancel_key(&key) {
+                        return;
+                    }
+                }
+            }
+            Ok(false) => tokio::time::sleep(Duration::from_millis(50)).await,
+            Err(_) => std::future::pending().await,
+        }
+    }
+}
+
+/// What the warp block shows about a suggestion
+fn warp_summary(plan: &str, command: &str) -> String {
+    format!("📝 Plan: {}\n🔧 Command: {}", plan, command)
+}
+
+/// The startup-only flag `args` begins with, such as `--debug`; these mean nothing once the TUI is running
+fn startup_flag(args: &[String]) -> Option<&str> {
+    let first = args.first()?;
+    let name = first.split('=').next().unwrap_or(first);
+    let mut cli = <crate::Cli as clap::CommandFactory>::command();
+    cli.build();
+    let is_startup_flag = cli.get_arguments()
+        .filter(|arg| arg.get_id() != "help")
+        .any(|arg| {
+            arg.get_long().is_some_and(|long| name.strip_prefix("--") == Some(long))
+                || arg.get_short().is_some_and(|short| name.strip_prefix('-') == Some(short.to_string().as_str()))
+        });
+    is_startup_flag.then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    /// An app with the default config, a fresh database and no aliases
+    async fn test_app() -> App {
+        test_app_with(Config::default()).await
+    }
+
+    async fn test_app_with(config: Config) -> App {
+        let agent = Agent::new(&config).unwrap();
+        App::new(config, crate::db::test_database().await, agent, CommandRegistry::new())
+    }
+
+    fn rendered_text(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer.content.chunks(width)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[tokio::test]
+    async fn test_inspector_shows_full_output_of_selected_execution() {
+        let mut app = test_app().await;
+
+        let mut long = CommandExecution::new("cargo test".to_string(), None);
+        long.status = ExecutionStatus::Success;
+        long.output = format!("{}TAIL-OF-FIRST-LINE\n{}\nLAST-LINE", "x".repeat(120), (1..20).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n"));
+        let other = CommandExecution::new("ls".to_string(), None);
+        app.command_history = vec![other, long];
+
+        let mut terminal = Terminal::new(TestBackend::new(160, 50)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert!(!rendered_text(&terminal).contains("TAIL-OF-FIRST-LINE"), "the list only shows a preview");
+
+        app.handle_normal_key(KeyCode::Down.into()).await.unwrap();
+        app.handle_normal_key(KeyCode::Char('i').into()).await.unwrap();
+        assert_eq!(app.mode, AppMode::Inspect);
+        terminal.draw(|f| app.render(f)).unwrap();
+
+        let screen = rendered_text(&terminal);
+        assert!(screen.contains("TAIL-OF-FIRST-LINE"));
+        assert!(screen.contains("LAST-LINE"));
+        assert!(screen.contains("Exit code 0"));
+
+        app.handle_normal_key(KeyCode::Esc.into()).await.unwrap();
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_history_blocks_show_only_the_configured_fields() {
+        let config = Config {
+            history_display: toml::from_str(r#"fields = ["model", "exit_code"]"#).unwrap(),
+            ..Default::default()
+        };
+        let mut app = test_app_with(config).await;
+
+        let mut execution = CommandExecution::new("summarize notes".to_string(), Some("summarize notes".to_string()));
+        execution.status = ExecutionStatus::Error;
+        execution.exit_code = Some(2);
+        execution.duration_ms = 4321;
+        execution.provider = Some("ollama".to_string());
+        execution.model = Some("phi4:latest".to_string());
+        app.command_history = vec![execution.clone()];
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen = rendered_text(&terminal);
+        assert!(screen.contains("phi4:latest • exit 2"), "{}", screen);
+        assert!(!screen.contains("4321ms"));
+        assert!(!screen.contains(&execution.time_label()));
+        assert!(!screen.contains("ollama •"));
+
+        let fields = [crate::config::HistoryField::Timestamp];
+        assert_eq!(crate::ui::blocks::metadata_text(&execution, &fields, " • "), execution.time_label());
+        let invalid: Result<crate::config::HistoryDisplayConfig, _> = toml::from_str(r#"fields = ["hostname"]"#);
+        assert!(invalid.unwrap_err().to_string().contains("unknown variant `hostname`"));
+    }
+
+    #[tokio::test]
+    async fn test_history_is_drawn_as_command_blocks_within_the_visible_range() {
+        let mut app = test_app().await;
+
+        app.command_history = (0..5).map(|i| {
+            let mut execution = CommandExecution::new(format!("echo block-{}", i), None);
+            execution.status = if i == 0 { ExecutionStatus::Running } else { ExecutionStatus::Success };
+            execution.output = format!("output-{}", i);
+            execution
+        }).collect();
+
+        // Room for two blocks: 20 rows less the status bar (1), input bar (3) and pane border (2)
+        let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen = rendered_text(&terminal);
+        assert!(screen.contains("╭"), "blocks have rounded borders: {}", screen);
+        assert!(screen.contains("❯ echo block-0") && screen.contains("output-1"), "{}", screen);
+        assert!(!screen.contains("block-2"), "{}", screen);
+        assert!(["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"].iter().any(|s| screen.contains(s)), "running blocks spin: {}", screen);
+        assert!(screen.contains("Scroll: 1/5"));
+
+        app.select_block(4);
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen = rendered_text(&terminal);
+        assert!(screen.contains("❯ echo block-4") && !screen.contains("block-0"), "{}", screen);
+    }
+
+    #[tokio::test]
+    async fn test_ctrl_p_palette_filters_and_fills_the_input_bar() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let mut app = test_app().await;
+        let ctrl_p = Event::Key(KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL));
+        let press = |code: KeyCode| Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+
+        app.db.save_command_execution(&CommandExecution::new("cargo run --release".to_string(), None)).await.unwrap();
+        app.handle_event(ctrl_p.clone()).await.unwrap();
+        assert!(app.command_palette.is_visible);
+        assert_eq!(app.command_palette.suggestions[0], "cargo run --release", "recent commands come first");
+        for c in "cargo tx".chars() {
+            app.handle_event(press(KeyCode::Char(c))).await.unwrap();
+        }
+        app.handle_event(press(KeyCode::Backspace)).await.unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen = rendered_text(&terminal);
+        assert!(screen.contains("Command Palette") && screen.contains("cargo test") && !screen.contains("git status"), "{}", screen);
+
+        app.handle_event(press(KeyCode::Enter)).await.unwrap();
+        assert!(!app.command_palette.is_visible);
+        assert_eq!(app.input_bar.content, "cargo test");
+        assert_eq!(app.input_mode, InputMode::Editing);
+
+        // Opening it while typing leaves the input alone, and Esc closes it
+        app.handle_event(ctrl_p).await.unwrap();
+        assert!(app.command_palette.is_visible);
+        app.handle_event(press(KeyCode::Esc)).await.unwrap();
+        assert!(!app.command_palette.is_visible);
+        assert_eq!(app.input_bar.content, "cargo test");
+    }
+
+    #[tokio::test]
+    async fn test_up_and_down_recall_history_while_editing() {
+        let mut app = test_app().await;
+        app.command_history = ["git status", "cargo build"]
+            .iter()
+            .map(|command| CommandExecution::new(command.to_string(), None))
+            .collect();
+        app.input_mode = InputMode::Editing;
+        for c in "ls".chars() {
+            app.handle_editing_key(KeyCode::Char(c).into()).await.unwrap();
+        }
+
+        let mut shown = Vec::new();
+        for key in [KeyCode::Up, KeyCode::Up, KeyCode::Up, KeyCode::Down, KeyCode::Down, KeyCode::Down] {
+            app.handle_editing_key(key.into()).await.unwrap();
+            shown.push(app.input_bar.content.clone());
+        }
+        assert_eq!(shown, ["git status", "cargo build", "cargo build", "git status", "ls", "ls"]);
+        assert_eq!(app.history_cursor, None);
+
+        // Editing a recalled line detaches it; Up then starts again from the newest entry
+        app.handle_editing_key(KeyCode::Up.into()).await.unwrap();
+        app.handle_editing_key(KeyCode::Char('!').into()).await.unwrap();
+        assert_eq!((app.input_bar.content.as_str(), app.history_cursor), ("git status!", None));
+        app.handle_editing_key(KeyCode::Up.into()).await.unwrap();
+        app.handle_editing_key(KeyCode::Down.into()).await.unwrap();
+        assert_eq!(app.input_bar.content, "git status!");
+    }
+
+    #[tokio::test]
+    async fn test_multibyte_output_is_truncated_without_panicking() {
+        let mut app = test_app().await;
+
+        // 99 ASCII bytes put the 100th byte inside the first emoji
+        let mut execution = CommandExecution::new("echo".to_string(), None);
+        execution.status = ExecutionStatus::Success;
+        execution.output = format!("{}🎉🎉🎉 café déjà vu {}", "a".repeat(99), "é".repeat(80));
+        // The expanded first block wraps its output in full; the second is cut short
+        let expanded = CommandExecution { id: "expanded".to_string(), ..execution.clone() };
+        app.expanded.insert(expanded.id.clone());
+        app.command_history = vec![expanded, execution.clone()];
+
+        let mut terminal = Terminal::new(TestBackend::new(160, 50)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen = rendered_text(&terminal);
+        assert!(screen.contains("café déjà vu ..."), "{}", screen);
+        assert!(screen.contains(&"é".repeat(80)), "{}", screen);
+
+        let mut narrow = Terminal::new(TestBackend::new(12, 6)).unwrap();
+        narrow.draw(|f| crate::ui::blocks::CommandBlock::new(execution).render(f, f.size())).unwrap();
+
+        let cut = crate::ui::blocks::truncate_to_width("ab🎉cd", 5);
+        assert_eq!(cut, "ab...");
+        assert_eq!(crate::ui::blocks::truncate_to_width("short", 10), "short");
+    }
+
+    #[tokio::test]
+    async fn test_normal_mode_keys_dispatch_through_the_keyset() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let mut app = test_app().await;
+
+        app.handle_normal_key(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL)).await.unwrap();
+        assert_eq!(app.mode, AppMode::Agent);
+        app.handle_normal_key(KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT)).await.unwrap();
+        assert_eq!(app.mode, AppMode::Help);
+        app.handle_normal_key(KeyCode::Char('?').into()).await.unwrap();
+        assert_eq!(app.mode, AppMode::Normal);
+
+        let dir = std::env::temp_dir().join(format!("agentic-keysets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("default.yaml"), "app:quit: ctrl-x\ninput:focus: enter\n").unwrap();
+        let mut keybindings = KeyBindingManager::new();
+        keybindings.add_keyset_directory(&dir);
+        keybindings.load_keyset("default").unwrap();
+        app.keybindings = keybindings;
+        let _ = std::fs::remove_dir_all(&dir);
+
+        app.handle_normal_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)).await.unwrap();
+        assert!(!app.should_quit, "Ctrl+Q was rebound");
+        app.handle_normal_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL)).await.unwrap();
+        assert!(app.should_quit);
+    }
+
+    #[tokio::test]
+    async fn test_warp_with_a_broken_env_file_fails_only_its_block() {
+        let mut app = test_app().await;
+        let env_file = std::env::temp_dir().join(format!("agentic-env-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&env_file, "not a variable\n").unwrap();
+        let toml = format!("[warp.execution]\nenv_file = \"{}\"\n", env_file.display());
+        app.command_registry = CommandRegistry::new().with_agentic_config(toml::from_str(&toml).unwrap());
+
+        app.input_bar.content = "warp list the files here".to_string();
+        app.execute_command().await.unwrap();
+        std::fs::remove_file(&env_file).unwrap();
+
+        let latest = &app.command_history[0];
+        assert!(matches!(latest.status, ExecutionStatus::Error));
+        assert!(latest.output.starts_with("Error: ") && latest.output.contains("Failed to read"), "{}", latest.output);
+    }
+
+    #[tokio::test]
+    async fn test_startup_flags_typed_in_the_tui_are_rejected() {
+        let mut app = test_app().await;
+
+        for (input, flag) in [("--debug", "--debug"), ("-d task list", "-d"), ("--interactive=true", "--interactive")] {
+            app.input_bar.content = input.to_string();
+            app.execute_command().await.unwrap();
+
+            let latest = &app.command_history[0];
+            assert_eq!(latest.command, input);
+            assert!(matches!(latest.status, ExecutionStatus::Error));
+            assert!(latest.output.starts_with(&format!("{} only takes effect when starting agentic", flag)), "{}", latest.output);
+            assert!(latest.agent_query.is_none());
+        }
+
+        assert_eq!(startup_flag(&["task".to_string(), "--debug".to_string()]), None);
+        assert_eq!(startup_flag(&["--help".to_string()]), None, "help is still useful in the TUI");
+        assert!(crate::TuiInput::try_parse_from(["task", "list"]).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_commands_are_appended_to_transcript() {
+        let dir = std::env::temp_dir().join(format!("agentic-transcript-{}", uuid::Uuid::new_v4()));
+        let mut config = Config::default();
+        config.transcript.enabled = true;
+        config.transcript.dir = dir.clone();
+        config.prep.focus_lock = false;
+        let mut app = test_app_with(config).await;
+        app.start_transcript();
+
+        for command in ["run 'echo first'", "run 'echo password=hunter2'"] {
+            app.input_bar.content = command.to_string();
+            app.input_bar.cursor_position = command.len();
+            app.handle_editing_key(KeyCode::Enter.into()).await.unwrap();
+        }
+
+        let path = app.transcript.as_ref().unwrap().path().to_path_buf();
+        let transcript = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(transcript.starts_with("# agentic TUI session started"));
+        let first = transcript.find("$ run 'echo first'\nfirst\n[success").expect(&transcript);
+        let second = transcript.find("$ run 'echo password=[REDACTED]\npassword=[REDACTED]\n[success").expect(&transcript);
+        assert!(first < second);
+        assert!(!transcript.contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn test_mouse_wheel_scrolls_the_pane_under_it_and_clicks_select_blocks() {
+        use crossterm::event::KeyModifiers;
+        let mut app = test_app().await;
+        app.command_history = (0..20).map(|n| CommandExecution::new(format!("echo {}", n), None)).collect();
+        let mouse = |kind: MouseEventKind, column: u16, row: u16| Event::Mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE });
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+
+        app.handle_event(mouse(MouseEventKind::ScrollDown, 110, 10)).await.unwrap();
+        assert_eq!(app.scroller.scroll_offset, 0, "the sidebar doesn't scroll the history");
+        app.handle_event(mouse(MouseEventKind::ScrollDown, 20, 10)).await.unwrap();
+        app.handle_event(mouse(MouseEventKind::ScrollDown, 20, 10)).await.unwrap();
+        assert_eq!(app.scroller.scroll_offset, 2);
+        assert_eq!(app.selected_block, 2, "the selection stays on screen");
+        app.handle_event(mouse(MouseEventKind::ScrollUp, 20, 10)).await.unwrap();
+        assert_eq!(app.scroller.scroll_offset, 1);
+
+        terminal.draw(|f| app.render(f)).unwrap();
+        let (index, area) = app.block_areas[2];
+        app.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), area.x + 5, area.y + 1)).await.unwrap();
+        assert_eq!((index, app.selected_block), (3, 3));
+    }
+
+    #[tokio::test]
+    async fn test_space_expands_the_selected_block_and_shift_arrows_scroll_it() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let mut app = test_app().await;
+        let key = |code: KeyCode, modifiers: KeyModifiers| Event::Key(KeyEvent::new(code, modifiers));
+        for command in ["seq 40", "ls"] {
+            let mut execution = CommandExecution::new(command.to_string(), None);
+            execution.status = ExecutionStatus::Success;
+            execution.output = (1..=40).map(|n| format!("line {}", n)).collect::<Vec<_>>().join("\n");
+            app.command_history.push(execution);
+        }
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        let mut draw = |app: &mut App| {
+            terminal.draw(|f| app.render(f)).unwrap();
+            rendered_text(&terminal)
+        };
+        assert!(!draw(&mut app).contains("line 3 "), "blocks start collapsed");
+
+        app.handle_event(key(KeyCode::Char(' '), KeyModifiers::NONE)).await.unwrap();
+        assert!(app.expanded.contains(&app.command_history[0].id));
+        let screen = draw(&mut app);
+        assert!(screen.contains("line 3 ") && screen.contains("line 20 "), "{}", screen);
+        assert!(!screen.contains("line 40 "), "the block is no taller than the pane: {}", screen);
+
+        for _ in 0..100 {
+            app.handle_event(key(KeyCode::Down, KeyModifiers::SHIFT)).await.unwrap();
+        }
+        let screen = draw(&mut app);
+        assert!(screen.contains("line 40 ") && !screen.contains("line 3 "), "{}", screen);
+        assert!(app.block_scroll < 40, "scrolling stops at the end of the output: {}", app.block_scroll);
+
+        // Moving on leaves the block expanded and the next one collapsed
+        app.handle_event(key(KeyCode::Down, KeyModifiers::NONE)).await.unwrap();
+        assert_eq!((app.selected_block, app.block_scroll), (1, 0));
+        app.handle_event(key(KeyCode::Char(' '), KeyModifiers::NONE)).await.unwrap();
+        app.handle_event(key(KeyCode::Char(' '), KeyModifiers::NONE)).await.unwrap();
+        assert_eq!(app.expanded.len(), 1, "space collapses it again");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_warp_confirmation_runs_the_command_into_a_new_block() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let mut app = test_app().await;
+        let press = |c: char| Event::Key(KeyEvent::new(if c == '\n' { KeyCode::Enter } else { KeyCode::Char(c) }, KeyModifiers::NONE));
+
+        let warp = CommandExecution::new("warp \"say hello\"".to_string(), None);
+        app.db.save_command_execution(&warp).await.unwrap();
+        app.command_history.insert(0, warp);
+        let (sink, lines) = tokio::sync::mpsc::unbounded_channel();
+        let pipeline = crate::warp::WarpPipeline::from_agentic_config(&crate::warp::config::AgenticConfig::default())
+            .unwrap()
+            .with_output_sink(sink);
+        let suggestion = crate::warp::pipeline::DryRun {
+            input: "say hello".to_string(),
+            plan: "Print a greeting".to_string(),
+            command: "echo one".to_string(),
+            model_used: crate::warp::pipeline::StepModels { plan: None, command: None },
+            fallback_used: true,
+        };
+        app.offer_warp(pipeline, suggestion, lines).await.unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen = rendered_text(&terminal);
+        assert!(screen.contains("Warp • confirm command") && screen.contains("Print a greeting"), "{}", screen);
+        assert!(matches!(app.command_history[0].status, ExecutionStatus::Running));
+
+        // Edit the command, then run it
+        for c in "e && echo two\ny".chars() {
+            app.handle_event(press(c)).await.unwrap();
+        }
+        assert!(app.pending_warp.is_none());
+        assert_eq!(app.command_history.len(), 2, "the run gets a block of its own");
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while app.warp_run.is_some() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            app.update().await.unwrap();
+        }
+        let run = &app.command_history[0];
+        assert_eq!(run.command, "echo one && echo two");
+        assert_eq!((run.output.as_str(), run.exit_code), ("one\ntwo", Some(0)));
+        assert!(matches!(run.status, ExecutionStatus::Success));
+        assert!(app.command_history[1].output.contains("🔧 Command: echo one && echo two"), "{}", app.command_history[1].output);
+
+        // Esc kills a run that is still going
+        let (sink, lines) = tokio::sync::mpsc::unbounded_channel();
+        let pipeline = crate::warp::WarpPipeline::from_agentic_config(&crate::warp::config::AgenticConfig::default())
+            .unwrap()
+            .with_output_sink(sink);
+        let suggestion = crate::warp::pipeline::DryRun {
+            input: "wait a while".to_string(),
+            plan: "Sleep".to_string(),
+            command: "sleep 30".to_string(),
+            model_used: crate::warp::pipeline::StepModels { plan: None, command: None },
+            fallback_used: true,
+        };
+        app.offer_warp(pipeline, suggestion, lines).await.unwrap();
+        app.handle_event(press('y')).await.unwrap();
+        assert!(app.warp_run.is_some());
+        app.handle_event(Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE))).await.unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while app.warp_run.is_some() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            app.update().await.unwrap();
+        }
+        assert!(app.warp_run.is_none(), "the run was killed");
+        assert_eq!(app.command_history[0].command, "sleep 30");
+        assert!(matches!(app.command_history[0].status, ExecutionStatus::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_y_copies_the_selected_block_to_a_file_without_a_clipboard() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let mut app = test_app().await;
+        let dir = std::env::temp_dir().join(format!("agentic-copy-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        app.clipboard = Clipboard::files_in(&dir);
+        let mut execution = CommandExecution::new("git status".to_string(), None);
+        execution.output = "nothing to commit".to_string();
+        app.command_history.push(execution);
+
+        let mut copied = Vec::new();
+        for key in [KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE), KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::SHIFT)] {
+            app.handle_event(Event::Key(key)).await.unwrap();
+            let notice = app.status_bar.notice().unwrap().to_string();
+            let path = notice.split(" saved to ").nth(1).unwrap_or_else(|| panic!("{}", notice));
+            copied.push(std::fs::read_to_string(path).unwrap());
+        }
+        assert_eq!(copied, ["git status", "nothing to commit"]);
+
+        let mut terminal = Terminal::new(TestBackend::new(200, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert!(rendered_text(&terminal).contains("No clipboard; output saved to"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_long_output_is_cut_only_where_it_is_drawn() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let mut app = test_app().await;
+        app.performance.max_output_lines = 3;
+        let dir = std::env::temp_dir().join(format!("agentic-copy-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        app.clipboard = Clipboard::files_in(&dir);
+        let output = (1..=6).map(|n| format!("line {}", n)).collect::<Vec<_>>().join("\n");
+        app.command_history.push(CommandExecution::new("seq 6".to_string(), None));
+        app.update_execution_output(0, &output, ExecutionStatus::Success, 5).await.unwrap();
+        app.expanded.insert(app.command_history[0].id.clone());
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen = rendered_text(&terminal);
+        assert!(screen.contains("3 more lines truncated") && !screen.contains("line 4"), "{}", screen);
+        assert_eq!(app.command_history[0].output, output);
+
+        app.handle_event(Event::Key(KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::SHIFT))).await.unwrap();
+        let notice = app.status_bar.notice().unwrap().to_string();
+        let path = notice.split(" saved to ").nth(1).unwrap_or_else(|| panic!("{}", notice));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), output, "copies the whole output");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_slash_searches_the_history_and_esc_shows_all_of_it_again() {
+        use crossterm::event::{KeyEvent, KeyModifiers};
+        let mut app = test_app().await;
+        for command in ["ls", "git status", "cargo build", "git log", "pwd"] {
+            app.command_history.push(CommandExecution::new(command.to_string(), None));
+        }
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        let mut draw = |app: &mut App| {
+            terminal.draw(|f| app.render(f)).unwrap();
+            rendered_text(&terminal)
+        };
+        async fn press(app: &mut App, keys: &str) {
+            for c in keys.chars() {
+                let code = if c == '\n' { KeyCode::Enter } else if c == '\x1b' { KeyCode::Esc } else { KeyCode::Char(c) };
+                app.handle_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE))).await.unwrap();
+            }
+        }
+
+        press(&mut app, "/GIT").await;
+        assert_eq!(app.mode, AppMode::Search);
+        assert_eq!(app.selected_block, 1, "jumps to the first match from the top, the newest");
+        let screen = draw(&mut app);
+        assert!(screen.contains("2 match(es), 1 of 2") && screen.contains("git log"), "{}", screen);
+        assert!(!screen.contains("cargo build"), "non-matches are hidden: {}", screen);
+
+        press(&mut app, "\nn").await;
+        assert_eq!(app.selected_block, 3);
+        press(&mut app, "n").await;
+        assert_eq!(app.selected_block, 1, "wraps around");
+
+        press(&mut app, "\x1b").await;
+        assert_eq!((app.mode.clone(), app.search.is_none()), (AppMode::Normal, true));
+        assert!(draw(&mut app).contains("cargo build"));
+    }
+
+    #[tokio::test]
+    async fn test_running_blocks_animate_and_redraw_only_while_running() {
+        let mut app = test_app().await;
+        let mut execution = CommandExecution::new("ls".to_string(), None);
+        execution.status = ExecutionStatus::Success;
+        app.command_history.push(execution);
+        app.needs_redraw = false;
+        app.update().await.unwrap();
+        assert!(!app.needs_redraw, "nothing animates while no command runs");
+
+        app.command_history[0].status = ExecutionStatus::Running;
+        app.update().await.unwrap();
+        assert!(app.needs_redraw);
+        std::thread::sleep(Duration::from_millis(250));
+        app.update().await.unwrap();
+        let block = CommandBlock::new(app.command_history[0].clone()).animated_by(&app.animations);
+        assert!(block.spinner_frame >= 2 && block.animation_progress > 0.0, "{} {}", block.spinner_frame, block.animation_progress);
+
+        // Once the command is done its animations aren't restarted after they finish
+        app.command_history[0].status = ExecutionStatus::Success;
+        for animation in &mut app.animations.animations {
+            animation.start_time -= Duration::from_secs(10);
+        }
+        app.update().await.unwrap();
+        app.needs_redraw = false;
+        app.update().await.unwrap();
+        assert!(!app.needs_redraw && app.animations.animations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_output_language_is_detected_once_a_command_ends() {
+        let mut app = test_app().await;
+        let mut execution = CommandExecution::new("curl -s api/users".to_string(), None);
+        execution.output = "{\"name\": \"ada\"}".to_string();
+        let id = execution.id.clone();
+        app.command_history.push(execution);
+        app.scroller.update_total_items(1);
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert!(app.output_languages.is_empty(), "running output is still changing");
+        app.command_history[0].status = ExecutionStatus::Success;
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.output_languages.get(&id), Some(&Some("json")));
+
+        app.update_execution_output(0, "plain text", ExecutionStatus::Success, 5).await.unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.output_languages.get(&id), Some(&None), "new output is detected again");
+    }
+
+    #[tokio::test]
+    async fn test_sidebar_shows_the_provider_model_and_whether_it_answers() {
+        async fn sidebar_after_check(config: Config) -> String {
+            let mut app = test_app_with(config).await;
+            app.update().await.unwrap();
+            while app.provider_online.is_none() {
+                tokio::task::yield_now().await;
+                app.update().await.unwrap();
+            }
+            let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+            terminal.draw(|f| app.render(f)).unwrap();
+            rendered_text(&terminal)
+        }
+
+        let mut config = Config::default();
+        config.agent.preferred_provider = "openai".to_string();
+        config.agent.openai_base_url = Some("http://127.0.0.1:9/v1".to_string());
+        config.agent.model = "gpt-4o-mini".to_string();
+        let screen = sidebar_after_check(config).await;
+        assert!(screen.contains("Provider: OpenAI") && screen.contains("Model: gpt-4o-mini"), "{}", screen);
+        assert!(screen.contains("● configured"), "an OpenAI server isn't probed: {}", screen);
+
+        crate::offline::force_for_current_thread();
+        let mut config = Config::default();
+        config.agent.ollama_model = "phi4".to_string();
+        let screen = sidebar_after_check(config).await;
+        assert!(screen.contains("Provider: Ollama") && screen.contains("Model: phi4"), "{}", screen);
+        assert!(screen.contains("● offline"), "{}", screen);
+    }
+}
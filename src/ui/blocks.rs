@@ -5,18 +5,98 @@ use ratatui::{
     widgets::{Block, Borders, BorderType, Gauge, List, ListItem, Paragraph, Wrap},
     Frame,
 };
-use std::time::Instant;
-use unicode_width::UnicodeWidthStr;
+use std::time::Duration;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::config::{HistoryField, DEFAULT_HISTORY_FIELDS};
 use crate::db::{CommandExecution, ExecutionStatus};
 
+use super::performance::{ansi_spans, AnimationSystem, EasingFunction, OptimizedTextRenderer};
+use super::search::match_ranges;
+
+/// Frames of the spinner running commands show
+pub const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How long the spinner shows each frame
+const SPINNER_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// How long the progress bar of a running command takes to fill before starting over
+const PROGRESS_CYCLE: Duration = Duration::from_millis(2500);
+
+/// Rows a compact block takes: its borders, the command, the metadata and two output lines
+pub const BLOCK_HEIGHT: u16 = 6;
+
+/// The recorded exit code, or the one the status implies for entries that didn't keep it
+pub fn exit_code_label(execution: &CommandExecution) -> String {
+    if let Some(code) = execution.exit_code {
+        return code.to_string();
+    }
+    let implied = match execution.status {
+        ExecutionStatus::Success => "0",
+        ExecutionStatus::Error => "1",
+        ExecutionStatus::Running => "...",
+        ExecutionStatus::Cancelled => "130",
+        ExecutionStatus::Unknown => "?",
+    };
+    implied.to_string()
+}
+
+/// `fields` of `execution` in order, joined by `separator`; provider and model are left out
+/// for entries that have none
+pub fn metadata_text(execution: &CommandExecution, fields: &[HistoryField], separator: &str) -> String {
+    let parts: Vec<String> = fields.iter().filter_map(|field| match field {
+        HistoryField::Timestamp => Some(execution.time_label()),
+        HistoryField::Duration if matches!(execution.status, ExecutionStatus::Running) => Some("...".to_string()),
+        HistoryField::Duration => Some(format!("{}ms", execution.duration_ms)),
+        HistoryField::ExitCode => Some(format!("exit {}", exit_code_label(execution))),
+        HistoryField::Provider => execution.provider.clone(),
+        HistoryField::Model => execution.model.clone(),
+    }).collect();
+    parts.join(separator)
+}
+
+/// Shorten `text` to at most `max_width` terminal columns, ending in `...` when cut. Cuts fall
+/// between characters, so multibyte output like emoji or accents is never split.
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    let budget = max_width.saturating_sub(3);
+    let mut used = 0;
+    let mut end = 0;
+    for (index, c) in text.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > budget {
+            break;
+        }
+        end = index + c.len_utf8();
+    }
+    format!("{}...", &text[..end])
+}
+
 /// Warp-style command block that mimics the exact visual design
 #[derive(Debug, Clone)]
 pub struct CommandBlock {
     pub execution: CommandExecution,
     pub is_selected: bool,
+    /// How full the progress bar of a running command is, from 0 to 1
     pub animation_progress: f64,
-    pub created_at: Instant,
+    /// Which `SPINNER_FRAMES` frame a running command shows
+    pub spinner_frame: usize,
+    /// Output scroll offset when showing the full execution, e.g. in the inspector;
+    /// `None` renders the compact block
+    pub detail_scroll: Option<u16>,
+    /// Metadata shown under the command in the compact block
+    pub fields: Vec<HistoryField>,
+    /// Show the whole output in the history instead of its first lines
+    pub expanded: bool,
+    /// Output rows scrolled past in an expanded block too tall for the history pane
+    pub output_scroll: usize,
+    /// Search query whose occurrences in the command and output stand out
+    pub highlight: Option<String>,
+    /// What the output is highlighted as; `detect_language` parses the whole output, so the
+    /// history works it out once per execution rather than every frame
+    pub language: Option<&'static str>,
 }
 
 impl CommandBlock {
@@ -25,10 +105,50 @@ impl CommandBlock {
             execution,
             is_selected: false,
             animation_progress: 0.0,
-            created_at: Instant::now(),
+            spinner_frame: 0,
+            detail_scroll: None,
+            fields: DEFAULT_HISTORY_FIELDS.to_vec(),
+            expanded: false,
+            output_scroll: 0,
+            highlight: None,
+            language: None,
         }
     }
 
+    /// Take the spinner frame and progress bar from the execution's animations in `animations`;
+    /// blocks are rebuilt every frame, so the animations live outside them
+    pub fn animated_by(mut self, animations: &AnimationSystem) -> Self {
+        let id = &self.execution.id;
+        self.animation_progress = animations.get_value(&progress_animation_id(id)).unwrap_or(0.0);
+        self.spinner_frame = animations.get_value(&spinner_animation_id(id)).unwrap_or(0.0) as usize % SPINNER_FRAMES.len();
+        self
+    }
+
+    /// Rows the block needs in the history at `width` columns: `BLOCK_HEIGHT` collapsed, or
+    /// enough for all of its wrapped output when expanded
+    pub fn height(&self, width: u16) -> u16 {
+        if !self.expanded {
+            return BLOCK_HEIGHT;
+        }
+        (self.output_rows(width) + 4).clamp(BLOCK_HEIGHT as usize, u16::MAX as usize) as u16
+    }
+
+    /// Rows the output takes wrapped inside a block `width` columns wide
+    pub fn output_rows(&self, width: u16) -> usize {
+        wrapped_output(&self.execution.output, width.saturating_sub(2) as usize).count()
+    }
+
+    /// Agent answers are prose, so they wrap instead of being cut at the block's edge
+    fn is_agent_response(&self) -> bool {
+        self.execution.agent_query.is_some()
+    }
+
+    /// Show the full command, metadata and output, scrolled down by `scroll` output lines
+    pub fn detailed(mut self, scroll: u16) -> Self {
+        self.detail_scroll = Some(scroll);
+        self
+    }
+
     /// Render the command block in Warp's signature style
     pub fn render(&self, frame: &mut Frame, area: Rect) {
         // Create the main block with Warp-style borders
@@ -42,15 +162,24 @@ impl CommandBlock {
                 .fg(Color::White)
         };
 
-        let main_block = Block::default()
+        let mut main_block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)  // Warp's rounded corners
             .border_style(self.get_border_style())
             .style(block_style);
+        if self.detail_scroll.is_some() {
+            main_block = main_block.title(" Inspector • ↑/↓ PgUp/PgDn scroll • Esc close ");
+        }
 
         let inner_area = main_block.inner(area);
         frame.render_widget(main_block, area);
 
+        if let Some(scroll) = self.detail_scroll {
+            self.render_detailed(frame, inner_area, scroll);
+            self.render_status_indicator(frame, area);
+            return;
+        }
+
         // Split into sections like Warp
         let sections = Layout::default()
             .direction(Direction::Vertical)
@@ -76,12 +205,81 @@ impl CommandBlock {
         self.render_status_indicator(frame, area);
     }
 
+    fn render_detailed(&self, frame: &mut Frame, area: Rect, scroll: u16) {
+        let label_style = Style::default().fg(Color::Rgb(128, 128, 128));
+        let value_style = Style::default().fg(Color::White);
+        let field = |label: &str, value: String| Line::from(vec![
+            Span::styled(format!("  {:<10}", label), label_style),
+            Span::styled(value, value_style),
+        ]);
+
+        let command = Paragraph::new(Line::from(vec![
+            Span::styled(self.prompt(), Style::default().fg(Color::Rgb(98, 209, 248)).add_modifier(Modifier::BOLD)),
+            Span::styled(self.execution.command.as_str(), Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
+        ]))
+        .wrap(Wrap { trim: false });
+        let command_width = self.prompt().width() + self.execution.command.width();
+        let command_height = command_width.div_ceil(area.width.max(1) as usize).max(1) as u16;
+
+        let timestamp = if self.execution.has_unknown_timestamp() {
+            "unknown".to_string()
+        } else {
+            self.execution.timestamp.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S %Z").to_string()
+        };
+        let mut details = vec![
+            field("Started", timestamp),
+            field("Duration", format!("{}ms", self.execution.duration_ms)),
+            field("Exit code", exit_code_label(&self.execution)),
+        ];
+        if let Some(provider) = &self.execution.provider {
+            details.push(field("Provider", provider.clone()));
+        }
+        if let Some(model) = &self.execution.model {
+            details.push(field("Model", model.clone()));
+        }
+        let line_count = self.execution.output.lines().count();
+        details.push(field("Output", format!("{} lines", line_count)));
+
+        let sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(command_height),
+                Constraint::Length(details.len() as u16 + 1),
+                Constraint::Min(1),
+            ])
+            .split(area);
+
+        frame.render_widget(command, sections[0]);
+        frame.render_widget(Paragraph::new(details), sections[1]);
+
+        let output_style = Style::default().fg(Color::White);
+        let language = self.output_language();
+        let lines: Vec<Line> = self.execution.output
+            .lines()
+            .map(|line| Line::from(self.styled_line(line.to_string(), output_style, language)))
+            .collect();
+        let output = Paragraph::new(lines)
+            .style(output_style)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0));
+        frame.render_widget(output, sections[2]);
+    }
+
+    fn prompt(&self) -> &'static str {
+        if self.execution.agent_query.is_some() {
+            "🤖 "
+        } else {
+            "❯ "
+        }
+    }
+
     fn get_border_style(&self) -> Style {
         match self.execution.status {
             ExecutionStatus::Running => Style::default().fg(Color::Yellow),
             ExecutionStatus::Success => Style::default().fg(Color::Green),
             ExecutionStatus::Error => Style::default().fg(Color::Red),
             ExecutionStatus::Cancelled => Style::default().fg(Color::Gray),
+            ExecutionStatus::Unknown => Style::default().fg(Color::Magenta),
         }
     }
 
@@ -94,16 +292,9 @@ impl CommandBlock {
             .fg(Color::White)
             .add_modifier(Modifier::BOLD);
 
-        let prompt = if self.execution.agent_query.is_some() {
-            "🤖 "
-        } else {
-            "❯ "
-        };
-
-        let line = Line::from(vec![
-            Span::styled(prompt, prompt_style),
-            Span::styled(&self.execution.command, command_style),
-        ]);
+        let mut spans = vec![Span::styled(self.prompt(), prompt_style)];
+        spans.extend(self.styled_line(self.execution.command.clone(), command_style, None));
+        let line = Line::from(spans);
 
         let paragraph = Paragraph::new(line);
         frame.render_widget(paragraph, area);
@@ -114,21 +305,7 @@ impl CommandBlock {
             .fg(Color::Rgb(128, 128, 128))  // Warp's gray
             .add_modifier(Modifier::DIM);
 
-        let timestamp = self.execution.timestamp.format("%H:%M:%S").to_string();
-        let duration = if self.execution.duration_ms > 0 {
-            format!("{}ms", self.execution.duration_ms)
-        } else {
-            "...".to_string()
-        };
-
-        let exit_code = match self.execution.status {
-            ExecutionStatus::Success => "0",
-            ExecutionStatus::Error => "1",
-            ExecutionStatus::Running => "...",
-            ExecutionStatus::Cancelled => "130",
-        };
-
-        let metadata_text = format!("{} • {} • exit {}", timestamp, duration, exit_code);
+        let metadata_text = metadata_text(&self.execution, &self.fields, " • ");
 
         let line = Line::from(vec![
             Span::styled("  ", metadata_style),
@@ -141,19 +318,32 @@ impl CommandBlock {
 
     fn render_output_area(&self, frame: &mut Frame, area: Rect) {
         let output_style = Style::default().fg(Color::White);
+        let language = self.output_language();
+        if self.is_agent_response() || self.expanded {
+            let styles = if self.is_agent_response() {
+                agent_response_styles(&self.execution.output)
+            } else {
+                vec![output_style; self.execution.output.lines().count()]
+            };
+            let rows: Vec<(usize, String)> = wrapped_output(&self.execution.output, area.width as usize).collect();
+            let scroll = if self.expanded { self.output_scroll.min(rows.len().saturating_sub(area.height as usize)) } else { 0 };
+            let lines: Vec<Line> = rows
+                .into_iter()
+                .skip(scroll)
+                .take(area.height as usize)
+                .map(|(index, piece)| Line::from(self.styled_line(format!("  {}", piece), styles[index], language)))
+                .collect();
+            frame.render_widget(Paragraph::new(lines), area);
+            return;
+        }
 
         // Split output into lines and handle long lines
         let lines: Vec<Line> = self.execution.output
             .lines()
             .take(area.height as usize)  // Limit to visible area
             .map(|line| {
-                if line.width() > area.width as usize - 4 {
-                    // Truncate long lines
-                    let truncated = format!("{}...", &line[..area.width as usize - 7]);
-                    Line::from(Span::styled(format!("  {}", truncated), output_style))
-                } else {
-                    Line::from(Span::styled(format!("  {}", line), output_style))
-                }
+                let line = truncate_to_width(line, (area.width as usize).saturating_sub(4));
+                Line::from(self.styled_line(format!("  {}", line), output_style, language))
             })
             .collect();
 
@@ -164,6 +354,29 @@ impl CommandBlock {
         frame.render_widget(paragraph, area);
     }
 
+    /// The language `render_with_highlighting` colors the output as; agent answers have their own styles
+    fn output_language(&self) -> Option<&'static str> {
+        self.language.filter(|_| !self.is_agent_response())
+    }
+
+    /// `text` in `style`, syntax-highlighted as `language` and with the search query's
+    /// occurrences picked out
+    fn styled_line(&self, text: String, style: Style, language: Option<&str>) -> Vec<Span<'static>> {
+        let text = match language {
+            Some(language) => OptimizedTextRenderer
+                .render_with_highlighting(&text, Some(language))
+                .into_iter()
+                .next()
+                .unwrap_or_default(),
+            None => text,
+        };
+        let spans = ansi_spans(&text, style);
+        match self.highlight.as_deref() {
+            Some(query) => mark_matches(spans, query),
+            None => spans,
+        }
+    }
+
     fn render_status_indicator(&self, frame: &mut Frame, area: Rect) {
         // Render status icon in top-right corner like Warp
         let status_area = Rect {
@@ -176,14 +389,12 @@ impl CommandBlock {
         let (icon, color) = match self.execution.status {
             ExecutionStatus::Running => {
                 // Animated spinner
-                let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-                let elapsed = self.created_at.elapsed().as_millis();
-                let index = (elapsed / 100) % spinner_chars.len() as u128;
-                (spinner_chars[index as usize], Color::Yellow)
+                (SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()], Color::Yellow)
             },
             ExecutionStatus::Success => ("✓", Color::Green),
             ExecutionStatus::Error => ("✗", Color::Red),
             ExecutionStatus::Cancelled => ("⊘", Color::Gray),
+            ExecutionStatus::Unknown => ("?", Color::Magenta),
         };
 
         let status_line = Line::from(vec![
@@ -203,19 +414,198 @@ impl CommandBlock {
             };
 
             // Animated progress bar
-            let elapsed = self.created_at.elapsed().as_millis() as f64;
-            let progress = ((elapsed / 50.0) % 100.0) / 100.0;
-
             let gauge = Gauge::default()
                 .block(Block::default())
                 .gauge_style(Style::default().fg(Color::Yellow).bg(Color::Rgb(40, 40, 40)))
-                .ratio(progress);
+                .ratio(self.animation_progress.clamp(0.0, 1.0));
 
             frame.render_widget(gauge, progress_area);
         }
     }
 }
 
+/// `spans` with the occurrences of `query` in their text picked out, splitting spans where needed
+fn mark_matches(spans: Vec<Span<'static>>, query: &str) -> Vec<Span<'static>> {
+    let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+    let ranges = match_ranges(&text, query);
+    if ranges.is_empty() {
+        return spans;
+    }
+    let matched = Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let mut marked = Vec::new();
+    let mut offset = 0;
+    for span in spans {
+        let (start, end) = (offset, offset + span.content.len());
+        offset = end;
+        let mut at = start;
+        for range in ranges.iter().filter(|range| range.start < end && range.end > start) {
+            let (from, to) = (range.start.max(start), range.end.min(end));
+            if from > at {
+                marked.push(Span::styled(text[at..from].to_string(), span.style));
+            }
+            marked.push(Span::styled(text[from..to].to_string(), matched));
+            at = to;
+        }
+        if at < end {
+            marked.push(Span::styled(text[at..end].to_string(), span.style));
+        }
+    }
+    marked
+}
+
+/// Restart the spinner and progress bar animations of the running execution `id` that have
+/// finished a cycle, so they loop for as long as it runs
+pub fn keep_animating(animations: &mut AnimationSystem, id: &str) {
+    let progress = progress_animation_id(id);
+    if animations.get_value(&progress).is_none() {
+        animations.start_animation(progress, PROGRESS_CYCLE, 0.0, 1.0, EasingFunction::EaseInOut);
+    }
+    let spinner = spinner_animation_id(id);
+    if animations.get_value(&spinner).is_none() {
+        let frames = SPINNER_FRAMES.len() as f64;
+        animations.start_animation(spinner, SPINNER_FRAME_TIME * SPINNER_FRAMES.len() as u32, 0.0, frames, EasingFunction::Linear);
+    }
+}
+
+fn progress_animation_id(id: &str) -> String {
+    format!("progress:{}", id)
+}
+
+fn spinner_animation_id(id: &str) -> String {
+    format!("spinner:{}", id)
+}
+
+/// Each line of `text` wrapped to fit `width` columns after the two-column indent of output
+/// lines, as (line index, piece) pairs
+fn wrapped_output(text: &str, width: usize) -> impl Iterator<Item = (usize, String)> + '_ {
+    let width = width.saturating_sub(2).max(1);
+    text.lines().enumerate().flat_map(move |(index, line)| wrap_line(line, width).into_iter().map(move |piece| (index, piece)))
+}
+
+/// `line` broken into pieces of at most `width` columns, between words where it can be
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for word in line.split_inclusive(' ') {
+        if current.width() + word.trim_end().width() > width && !current.is_empty() {
+            pieces.push(current.trim_end().to_string());
+            current.clear();
+        }
+        // A word wider than the whole line is split wherever it has to be
+        for c in word.chars() {
+            if current.width() + c.width().unwrap_or(0) > width && !current.is_empty() && c != ' ' {
+                pieces.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+        }
+    }
+    pieces.push(current.trim_end().to_string());
+    pieces
+}
+
+/// The style of each line of an agent response, lightly following its markdown: headings stand
+/// out and fenced code is set apart from the prose
+fn agent_response_styles(response: &str) -> Vec<Style> {
+    let prose = Style::default().fg(Color::Rgb(210, 225, 255));
+    let heading = Style::default().fg(Color::Rgb(98, 209, 248)).add_modifier(Modifier::BOLD);
+    let code = Style::default().fg(Color::Rgb(180, 230, 160));
+    let fence = Style::default().fg(Color::Rgb(128, 128, 128)).add_modifier(Modifier::DIM);
+    let mut in_code = false;
+    response
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") {
+                in_code = !in_code;
+                fence
+            } else if in_code {
+                code
+            } else if trimmed.starts_with('#') {
+                heading
+            } else {
+                prose
+            }
+        })
+        .collect()
+}
+
+/// Score how well `pattern` fuzzily matches `candidate`, ignoring case: its characters must
+/// appear in order. Tighter matches and matches at word starts score higher. Returns the score
+/// and the char positions matched, or `None` when `pattern` isn't a subsequence.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let first = *pattern.first()?;
+    let is_word_start = |i: usize| i == 0 || matches!(chars[i - 1], ' ' | '-' | '_' | '/' | '.' | '\'' | '"');
+
+    // Try each place the first character occurs and keep the best-scoring greedy match from it
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in (0..lower.len()).filter(|&i| lower[i] == first) {
+        let mut positions = vec![start];
+        let mut next = start + 1;
+        for &wanted in &pattern[1..] {
+            match (next..lower.len()).find(|&i| lower[i] == wanted) {
+                Some(i) => {
+                    positions.push(i);
+                    next = i + 1;
+                }
+                None => break,
+            }
+        }
+        if positions.len() < pattern.len() {
+            // Later starts only leave fewer characters to match in
+            break;
+        }
+        let mut score = -(start as i64);
+        for (n, &i) in positions.iter().enumerate() {
+            if is_word_start(i) {
+                score += 10;
+            }
+            if n > 0 {
+                let gap = i - positions[n - 1] - 1;
+                score += if gap == 0 { 5 } else { -(gap as i64) };
+            }
+        }
+        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, positions));
+        }
+    }
+    best
+}
+
+/// Suggestions the palette always offers, after the user's own commands
+const DEFAULT_SUGGESTIONS: &[&str] = &[
+    "task add --title 'New task' --priority high",
+    "prep start --exam CET --duration 60",
+    "blog new --title 'My Blog Post'",
+    "agent 'help me with...'",
+    "git status",
+    "git add .",
+    "git commit -m 'message'",
+    "cargo build",
+    "cargo test",
+    "ls -la",
+    "cd ..",
+    "pwd",
+];
+
+/// Distinct commands from `history` (newest first), the most often run first and the most
+/// recently run among equals
+pub fn rank_history(history: &[CommandExecution]) -> Vec<String> {
+    // Command -> (times run, position of the latest run)
+    let mut seen: std::collections::HashMap<&str, (usize, usize)> = std::collections::HashMap::new();
+    for (position, execution) in history.iter().enumerate() {
+        let command = execution.command.trim();
+        if !command.is_empty() {
+            seen.entry(command).or_insert((0, position)).0 += 1;
+        }
+    }
+    let mut ranked: Vec<(&str, (usize, usize))> = seen.into_iter().collect();
+    ranked.sort_by(|(_, (count_a, latest_a)), (_, (count_b, latest_b))| count_b.cmp(count_a).then(latest_a.cmp(latest_b)));
+    ranked.into_iter().map(|(command, _)| command.to_string()).collect()
+}
+
 /// Command palette for Warp-style command suggestions
 #[derive(Debug)]
 pub struct CommandPalette {
@@ -228,20 +618,7 @@ pub struct CommandPalette {
 impl CommandPalette {
     pub fn new() -> Self {
         Self {
-            suggestions: vec![
-                "task add --title 'New task' --priority high".to_string(),
-                "prep start --exam CET --duration 60".to_string(),
-                "blog new --title 'My Blog Post'".to_string(),
-                "agent 'help me with...'".to_string(),
-                "git status".to_string(),
-                "git add .".to_string(),
-                "git commit -m 'message'".to_string(),
-                "cargo build".to_string(),
-                "cargo test".to_string(),
-                "ls -la".to_string(),
-                "cd ..".to_string(),
-                "pwd".to_string(),
-            ],
+            suggestions: DEFAULT_SUGGESTIONS.iter().map(|s| s.to_string()).collect(),
             selected_index: 0,
             filter: String::new(),
             is_visible: false,
@@ -256,6 +633,18 @@ impl CommandPalette {
         }
     }
 
+    /// Offer `commands` (already ordered, e.g. by `rank_history`) ahead of the default suggestions
+    pub fn set_history(&mut self, commands: Vec<String>) {
+        let mut suggestions: Vec<String> = Vec::new();
+        for command in commands.into_iter().chain(DEFAULT_SUGGESTIONS.iter().map(|s| s.to_string())) {
+            if !command.trim().is_empty() && !suggestions.contains(&command) {
+                suggestions.push(command);
+            }
+        }
+        self.suggestions = suggestions;
+        self.selected_index = 0;
+    }
+
     pub fn update_filter(&mut self, filter: String) {
         self.filter = filter;
         self.selected_index = 0;
@@ -284,15 +673,21 @@ impl CommandPalette {
     }
 
     fn get_filtered_suggestions(&self) -> Vec<String> {
+        self.filtered_matches().into_iter().map(|(suggestion, _)| suggestion).collect()
+    }
+
+    /// Suggestions matching the filter, best match first (ties keep their order), with the char
+    /// positions that matched; an empty filter keeps every suggestion in its original order
+    fn filtered_matches(&self) -> Vec<(String, Vec<usize>)> {
         if self.filter.is_empty() {
-            self.suggestions.clone()
-        } else {
-            self.suggestions
-                .iter()
-                .filter(|s| s.to_lowercase().contains(&self.filter.to_lowercase()))
-                .cloned()
-                .collect()
+            return self.suggestions.iter().map(|s| (s.clone(), Vec::new())).collect();
         }
+        let mut scored: Vec<(i64, String, Vec<usize>)> = self.suggestions
+            .iter()
+            .filter_map(|s| fuzzy_match(&self.filter, s).map(|(score, positions)| (score, s.clone(), positions)))
+            .collect();
+        scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, suggestion, positions)| (suggestion, positions)).collect()
     }
 
     pub fn render(&self, frame: &mut Frame, area: Rect) {
@@ -356,26 +751,31 @@ impl CommandPalette {
     }
 
     fn render_suggestions(&self, frame: &mut Frame, area: Rect) {
-        let filtered_suggestions = self.get_filtered_suggestions();
+        let filtered_suggestions = self.filtered_matches();
         
         let items: Vec<ListItem> = filtered_suggestions
             .iter()
             .enumerate()
-            .map(|(index, suggestion)| {
-                let style = if index == self.selected_index {
-                    Style::default()
+            .map(|(index, (suggestion, positions))| {
+                let (style, matched_style) = if index == self.selected_index {
+                    let style = Style::default()
                         .bg(Color::Rgb(98, 209, 248))
                         .fg(Color::Black)
-                        .add_modifier(Modifier::BOLD)
+                        .add_modifier(Modifier::BOLD);
+                    (style, style.add_modifier(Modifier::UNDERLINED))
                 } else {
-                    Style::default().fg(Color::White)
+                    let style = Style::default().fg(Color::White);
+                    (style, style.fg(Color::Yellow).add_modifier(Modifier::BOLD))
                 };
 
-                let line = Line::from(vec![
-                    Span::styled(suggestion.clone(), style),
-                ]);
+                // Matched characters stand out from the rest of the suggestion
+                let spans: Vec<Span> = suggestion
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| Span::styled(c.to_string(), if positions.contains(&i) { matched_style } else { style }))
+                    .collect();
 
-                ListItem::new(line)
+                ListItem::new(Line::from(spans))
             })
             .collect();
 
@@ -387,7 +787,7 @@ impl CommandPalette {
 }
 
 // Helper function for centered rectangles
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+pub(super) fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -412,3 +812,66 @@ impl Default for CommandPalette {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_filter_matches_fuzzily_and_ranks_tight_matches_first() {
+        assert_eq!(fuzzy_match("gst", "git status").map(|(_, positions)| positions), Some(vec![0, 4, 5]));
+        assert!(fuzzy_match("GST", "git status").is_some(), "matching ignores case");
+        assert!(fuzzy_match("tsg", "git status").is_none(), "characters must be in order");
+        assert!(fuzzy_match("", "git status").is_none());
+
+        let mut palette = CommandPalette::new();
+        let unfiltered = palette.get_filtered_suggestions();
+        assert_eq!(unfiltered, palette.suggestions, "an empty filter keeps the original order");
+
+        palette.update_filter("gst".to_string());
+        assert_eq!(palette.get_selected_suggestion().as_deref(), Some("git status"));
+        palette.update_filter("ct".to_string());
+        let ranked = palette.get_filtered_suggestions();
+        assert_eq!(ranked[0], "cargo test", "word starts beat scattered matches: {:?}", ranked);
+        assert!(ranked.contains(&"git commit -m 'message'".to_string()));
+    }
+
+    #[test]
+    fn test_agent_responses_wrap_and_expand_to_their_full_length() {
+        let response = format!("# Ownership\n\n{}\n```rust\nlet s = String::new();\n```\n- the end", "Each value has one owner. ".repeat(8));
+        let mut execution = CommandExecution::new("explain ownership".to_string(), Some("explain ownership".to_string()));
+        execution.output = response.clone();
+        execution.status = ExecutionStatus::Success;
+        let mut block = CommandBlock::new(execution);
+        assert_eq!(block.height(60), BLOCK_HEIGHT);
+        block.expanded = true;
+        let height = block.height(60);
+        assert!(height > BLOCK_HEIGHT + 7, "{} rows for {} lines plus the wrapped paragraph", height, response.lines().count());
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(60, height)).unwrap();
+        terminal.draw(|f| block.render(f, f.size())).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer.get(x, y).symbol()).collect::<String>() + "\n")
+            .collect();
+        assert!(screen.contains("🤖") && screen.contains("# Ownership") && screen.contains("- the end"), "{}", screen);
+        assert!(!screen.contains("..."), "nothing is cut short: {}", screen);
+        assert_eq!(wrap_line("one two three", 8), ["one two", "three"]);
+        assert_eq!(wrap_line("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn test_history_is_offered_first_by_frequency_then_recency() {
+        let history: Vec<CommandExecution> = ["make", "ls -la", "make", "npm test", " ", "ls -la", "make"]
+            .iter()
+            .map(|command| CommandExecution::new(command.to_string(), None))
+            .collect();
+        assert_eq!(rank_history(&history), ["make", "ls -la", "npm test"]);
+
+        let mut palette = CommandPalette::new();
+        palette.set_history(rank_history(&history));
+        assert_eq!(&palette.suggestions[..3], ["make", "ls -la", "npm test"]);
+        assert_eq!(palette.suggestions.iter().filter(|s| *s == "ls -la").count(), 1, "defaults already in the history aren't repeated");
+        assert!(palette.suggestions.contains(&"cargo test".to_string()));
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Where copied text ended up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Copied {
+    Clipboard,
+    /// No system clipboard (e.g. over SSH without a display); the text was written here instead
+    File(PathBuf),
+}
+
+/// The system clipboard, opened on first use. On X11 and Wayland the copied text only stays
+/// available while the handle is alive, so it is kept for the whole session.
+pub struct Clipboard {
+    system: Option<arboard::Clipboard>,
+    /// Whether opening the system clipboard was tried already; it is only tried once
+    opened: bool,
+    /// Where copies go when there is no system clipboard
+    fallback_dir: PathBuf,
+}
+
+impl Clipboard {
+    pub fn new() -> Self {
+        Self { system: None, opened: false, fallback_dir: std::env::temp_dir() }
+    }
+
+    /// A clipboard that never touches the system one and writes every copy to a file in `dir`
+    #[allow(dead_code)]
+    pub fn files_in(dir: impl Into<PathBuf>) -> Self {
+        Self { system: None, opened: true, fallback_dir: dir.into() }
+    }
+
+    pub fn copy(&mut self, text: &str) -> Result<Copied> {
+        if !self.opened {
+            self.opened = true;
+            self.system = arboard::Clipboard::new()
+                .map_err(|e| debug!("No system clipboard: {}", e))
+                .ok();
+        }
+        if let Some(system) = self.system.as_mut() {
+            match system.set_text(text) {
+                Ok(()) => return Ok(Copied::Clipboard),
+                Err(e) => debug!("Couldn't copy to the system clipboard: {}", e),
+            }
+        }
+        write_fallback(text, &self.fallback_dir).map(Copied::File)
+    }
+}
+
+/// Write `text` to a new `agentic-clipboard-*.txt` file in `dir`
+fn write_fallback(text: &str, dir: &Path) -> Result<PathBuf> {
+    let path = dir.join(format!("agentic-clipboard-{}.txt", uuid::Uuid::new_v4().simple()));
+    fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
@@ -1,4 +1,9 @@
-use ratatui::widgets::ListState;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthStr;
+
+/// How long a status bar notice stays up
+pub const NOTICE_DURATION: Duration = Duration::from_secs(3);
 
 // Placeholder structs for UI components
 // In a full implementation, these would contain more sophisticated state and rendering logic
@@ -23,11 +28,24 @@ impl CommandBlock {
     }
 }
 
+/// Most lines the input bar grows to before it scrolls
+pub const MAX_INPUT_LINES: usize = 8;
+
+/// What the app should do after the input bar handled a key
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputAction {
+    /// The key edited the input (or was ignored)
+    Edited,
+    /// Run the current input
+    Submit,
+    /// Leave editing mode, keeping the input
+    Cancel,
+}
+
 #[derive(Debug)]
 pub struct InputBar {
-    #[allow(dead_code)]
     pub content: String,
-    #[allow(dead_code)]
+    /// Byte offset in `content` where typed text is inserted
     pub cursor_position: usize,
 }
 
@@ -39,6 +57,120 @@ impl InputBar {
         }
     }
     
+    /// Input spans several lines; Enter then inserts a newline and Ctrl+Enter (or Ctrl+D) submits
+    pub fn is_multiline(&self) -> bool {
+        self.content.contains('\n')
+    }
+    
+    pub fn line_count(&self) -> usize {
+        self.content.split('\n').count()
+    }
+    
+    /// Number of input lines to show, capped at `MAX_INPUT_LINES`
+    pub fn visible_lines(&self) -> usize {
+        self.line_count().min(MAX_INPUT_LINES)
+    }
+    
+    /// Line and column of the cursor; the column is in terminal cells, so wide characters
+    /// like emoji count twice
+    pub fn cursor_line_col(&self) -> (usize, usize) {
+        let before = &self.content[..self.cursor_position];
+        let line = before.matches('\n').count();
+        let col = before.rsplit('\n').next().unwrap_or("").width();
+        (line, col)
+    }
+    
+    pub fn insert(&mut self, c: char) {
+        self.content.insert(self.cursor_position, c);
+        self.cursor_position += c.len_utf8();
+    }
+    
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.content[..self.cursor_position].chars().next_back() {
+            self.cursor_position -= c.len_utf8();
+            self.content.remove(self.cursor_position);
+        }
+    }
+    
+    /// Remove the character after the cursor
+    pub fn delete(&mut self) {
+        if self.cursor_position < self.content.len() {
+            self.content.remove(self.cursor_position);
+        }
+    }
+    
+    pub fn move_left(&mut self) {
+        if let Some(c) = self.content[..self.cursor_position].chars().next_back() {
+            self.cursor_position -= c.len_utf8();
+        }
+    }
+    
+    pub fn move_right(&mut self) {
+        if let Some(c) = self.content[self.cursor_position..].chars().next() {
+            self.cursor_position += c.len_utf8();
+        }
+    }
+    
+    /// Move to the start of the cursor's line
+    pub fn move_home(&mut self) {
+        self.cursor_position = self.content[..self.cursor_position].rfind('\n').map_or(0, |i| i + 1);
+    }
+    
+    /// Move to the end of the cursor's line
+    pub fn move_end(&mut self) {
+        self.cursor_position += self.content[self.cursor_position..].find('\n').unwrap_or(self.content.len() - self.cursor_position);
+    }
+    
+    pub fn clear(&mut self) {
+        self.content.clear();
+        self.cursor_position = 0;
+    }
+    
+    pub fn handle_key(&mut self, key: KeyEvent) -> InputAction {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let newline = key.modifiers.intersects(KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Enter if ctrl => InputAction::Submit,
+            KeyCode::Enter if newline || self.is_multiline() => {
+                self.insert('\n');
+                InputAction::Edited
+            }
+            KeyCode::Enter => InputAction::Submit,
+            // Ctrl+Enter is indistinguishable from Enter on terminals without the kitty keyboard protocol
+            KeyCode::Char('d') if ctrl && self.is_multiline() => InputAction::Submit,
+            KeyCode::Esc => InputAction::Cancel,
+            KeyCode::Char(c) if !ctrl => {
+                self.insert(c);
+                InputAction::Edited
+            }
+            KeyCode::Backspace => {
+                self.backspace();
+                InputAction::Edited
+            }
+            KeyCode::Delete => {
+                self.delete();
+                InputAction::Edited
+            }
+            KeyCode::Left => {
+                self.move_left();
+                InputAction::Edited
+            }
+            KeyCode::Right => {
+                self.move_right();
+                InputAction::Edited
+            }
+            KeyCode::Home => {
+                self.move_home();
+                InputAction::Edited
+            }
+            KeyCode::End => {
+                self.move_end();
+                InputAction::Edited
+            }
+            _ => InputAction::Edited,
+        }
+    }
+    
     pub fn update(&mut self) {
         // Update logic for input bar
     }
@@ -50,6 +182,8 @@ pub struct StatusBar {
     pub message: String,
     #[allow(dead_code)]
     pub mode: String,
+    /// Short-lived confirmation such as "Copied", and when it was posted
+    pub notice: Option<(String, Instant)>,
 }
 
 impl StatusBar {
@@ -57,17 +191,31 @@ impl StatusBar {
         Self {
             message: "Ready".to_string(),
             mode: "Normal".to_string(),
+            notice: None,
         }
     }
+
+    /// Show `text` in the status bar for `NOTICE_DURATION`
+    pub fn notify(&mut self, text: impl Into<String>) {
+        self.notice = Some((text.into(), Instant::now()));
+    }
+
+    /// The notice still on screen, if any
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_ref()
+            .filter(|(_, posted)| posted.elapsed() < NOTICE_DURATION)
+            .map(|(text, _)| text.as_str())
+    }
     
     pub fn update(&mut self) {
-        // Update logic for status bar
+        if self.notice().is_none() {
+            self.notice = None;
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct Sidebar {
-    pub list_state: ListState,
     #[allow(dead_code)]
     pub suggestions: Vec<String>,
 }
@@ -75,7 +223,6 @@ pub struct Sidebar {
 impl Sidebar {
     pub fn new() -> Self {
         Self {
-            list_state: ListState::default(),
             suggestions: vec![
                 "task add --title 'New task'".to_string(),
                 "prep start --exam CET".to_string(),
@@ -89,3 +236,98 @@ impl Sidebar {
         // Update logic for sidebar
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn type_text(input: &mut InputBar, text: &str) {
+        for c in text.chars() {
+            input.handle_key(key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_alt_enter_inserts_newline() {
+        let mut input = InputBar::new();
+        type_text(&mut input, "cat <<EOF");
+        assert_eq!(input.handle_key(key(KeyCode::Enter, KeyModifiers::ALT)), InputAction::Edited);
+        type_text(&mut input, "hé");
+
+        assert!(input.is_multiline());
+        assert_eq!(input.content, "cat <<EOF\nhé");
+        assert_eq!(input.cursor_line_col(), (1, 2));
+
+        // Once the input spans lines, plain Enter keeps adding lines
+        assert_eq!(input.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)), InputAction::Edited);
+        type_text(&mut input, "EOF");
+        assert_eq!(input.line_count(), 3);
+
+        input.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE));
+        input.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE));
+        input.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE));
+        input.handle_key(key(KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(input.content, "cat <<EOF\nhé");
+    }
+
+    #[test]
+    fn test_cursor_moves_and_edits_mid_line_across_multibyte_text() {
+        let mut input = InputBar::new();
+        type_text(&mut input, "echo 🎉é");
+        let press = |input: &mut InputBar, code: KeyCode| input.handle_key(key(code, KeyModifiers::NONE));
+
+        press(&mut input, KeyCode::Left);
+        press(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor_line_col(), (0, 5));
+        type_text(&mut input, "x");
+        assert_eq!(input.content, "echo x🎉é");
+        press(&mut input, KeyCode::Right);
+        assert_eq!(input.cursor_line_col(), (0, 8), "the emoji is two cells wide");
+        press(&mut input, KeyCode::Delete);
+        assert_eq!(input.content, "echo x🎉");
+        press(&mut input, KeyCode::Right);
+        assert_eq!(input.cursor_position, input.content.len(), "moving past the end stays there");
+
+        press(&mut input, KeyCode::Home);
+        press(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor_position, 0);
+        type_text(&mut input, "sudo ");
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.content, "sudoecho x🎉");
+
+        press(&mut input, KeyCode::End);
+        input.handle_key(key(KeyCode::Enter, KeyModifiers::ALT));
+        type_text(&mut input, "ab");
+        press(&mut input, KeyCode::Home);
+        assert_eq!(input.cursor_line_col(), (1, 0));
+        press(&mut input, KeyCode::End);
+        assert_eq!(input.cursor_line_col(), (1, 2));
+        press(&mut input, KeyCode::Home);
+        press(&mut input, KeyCode::Left);
+        press(&mut input, KeyCode::Home);
+        press(&mut input, KeyCode::End);
+        assert_eq!(input.cursor_line_col(), (0, 12));
+    }
+
+    #[test]
+    fn test_submission_in_multiline_mode() {
+        let mut input = InputBar::new();
+        type_text(&mut input, "ls");
+        assert_eq!(input.handle_key(key(KeyCode::Enter, KeyModifiers::NONE)), InputAction::Submit);
+
+        input.handle_key(key(KeyCode::Enter, KeyModifiers::SHIFT));
+        type_text(&mut input, "-la");
+        assert_eq!(input.handle_key(key(KeyCode::Enter, KeyModifiers::CONTROL)), InputAction::Submit);
+        assert_eq!(input.handle_key(key(KeyCode::Char('d'), KeyModifiers::CONTROL)), InputAction::Submit);
+        assert_eq!(input.content, "ls\n-la");
+
+        for _ in 0..20 {
+            input.handle_key(key(KeyCode::Enter, KeyModifiers::NONE));
+        }
+        assert_eq!(input.visible_lines(), MAX_INPUT_LINES);
+    }
+}
@@ -0,0 +1,180 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use super::blocks::centered_rect;
+use super::components::{InputAction, InputBar};
+
+/// Where the warp confirmation is: waiting for y/N/e, editing the command, or waiting for a
+/// dangerous command to be typed again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmStage {
+    Choose,
+    Edit,
+    Retype,
+}
+
+/// What a key press in the confirmation asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmAction {
+    /// Nothing to do yet; keep the confirmation open
+    Pending,
+    /// The command was edited; check it again and pass it to `set_command`
+    Edited(String),
+    Run(String),
+    /// Run nothing, for the given reason
+    Cancel(String),
+}
+
+/// The TUI's stand-in for warp's y/N/e prompt: a modal showing the plan and the suggested
+/// command, answered through the event loop rather than stdin
+#[derive(Debug)]
+pub struct WarpConfirmation {
+    pub request: String,
+    pub plan: String,
+    pub command: String,
+    /// The dangerous pattern `command` matches; such a command has to be typed again
+    pub dangerous: Option<String>,
+    pub stage: ConfirmStage,
+    /// Text typed while editing or retyping
+    pub field: InputBar,
+}
+
+impl WarpConfirmation {
+    pub fn new(request: String, plan: String, command: String, dangerous: Option<String>) -> Self {
+        Self { request, plan, command, dangerous, stage: ConfirmStage::Choose, field: InputBar::new() }
+    }
+
+    /// Replace the command after an edit and go back to y/N/e
+    pub fn set_command(&mut self, command: String, dangerous: Option<String>) {
+        self.command = command;
+        self.dangerous = dangerous;
+        self.stage = ConfirmStage::Choose;
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> ConfirmAction {
+        match self.stage {
+            ConfirmStage::Choose => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') if self.dangerous.is_some() => {
+                    self.start_typing(ConfirmStage::Retype, String::new());
+                    ConfirmAction::Pending
+                }
+                KeyCode::Char('y') | KeyCode::Char('Y') => ConfirmAction::Run(self.command.clone()),
+                KeyCode::Char('e') | KeyCode::Char('E') => {
+                    self.start_typing(ConfirmStage::Edit, self.command.clone());
+                    ConfirmAction::Pending
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    ConfirmAction::Cancel("Cancelled, nothing was run".to_string())
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Enter => {
+                    ConfirmAction::Cancel("Cancelled, nothing was run".to_string())
+                }
+                _ => ConfirmAction::Pending,
+            },
+            ConfirmStage::Edit => match self.field.handle_key(key) {
+                InputAction::Submit => ConfirmAction::Edited(self.field.content.trim().to_string()),
+                // Leaving the editor keeps the command as it was
+                InputAction::Cancel => {
+                    self.stage = ConfirmStage::Choose;
+                    ConfirmAction::Pending
+                }
+                InputAction::Edited => ConfirmAction::Pending,
+            },
+            ConfirmStage::Retype => match self.field.handle_key(key) {
+                InputAction::Submit if crate::warp::retyped(&self.field.content, &self.command) => {
+                    ConfirmAction::Run(self.command.clone())
+                }
+                InputAction::Submit => ConfirmAction::Cancel("That doesn't match the command; not running it".to_string()),
+                InputAction::Cancel => ConfirmAction::Cancel("Cancelled, nothing was run".to_string()),
+                InputAction::Edited => ConfirmAction::Pending,
+            },
+        }
+    }
+
+    fn start_typing(&mut self, stage: ConfirmStage, text: String) {
+        self.field.clear();
+        self.field.content = text;
+        self.field.cursor_position = self.field.content.len();
+        self.stage = stage;
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let popup_area = centered_rect(70, 50, area);
+        let accent = if self.dangerous.is_some() { Color::Red } else { Color::Yellow };
+        let label = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+        let mut lines = vec![
+            Line::from(vec![Span::styled("Request  ", label), Span::raw(self.request.clone())]),
+            Line::from(vec![Span::styled("Plan     ", label), Span::raw(self.plan.clone())]),
+            Line::from(vec![
+                Span::styled("Command  ", label),
+                Span::styled(self.command.clone(), Style::default().fg(accent).add_modifier(Modifier::BOLD)),
+            ]),
+            Line::from(""),
+        ];
+        if let Some(pattern) = &self.dangerous {
+            lines.push(Line::from(vec![
+                Span::styled(" DANGEROUS COMMAND ", Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD)),
+                Span::styled(format!(" matches '{}' and may not be undoable", pattern), Style::default().fg(Color::Red)),
+            ]));
+        }
+        let prompt = match self.stage {
+            ConfirmStage::Choose => "Run it? y run • n/Esc cancel • e edit".to_string(),
+            ConfirmStage::Edit => format!("Edit ❯ {}  (Enter done • Esc back)", self.field.content),
+            ConfirmStage::Retype => format!("Type the command again to confirm ❯ {}", self.field.content),
+        };
+        lines.push(Line::from(Span::styled(prompt, Style::default().fg(accent))));
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" Warp • confirm command ")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(accent)),
+            )
+            .wrap(Wrap { trim: false });
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(paragraph, popup_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(confirmation: &mut WarpConfirmation, keys: &str) -> ConfirmAction {
+        let mut action = ConfirmAction::Pending;
+        for c in keys.chars() {
+            let code = if c == '\n' { KeyCode::Enter } else { KeyCode::Char(c) };
+            action = confirmation.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+        action
+    }
+
+    #[test]
+    fn test_dangerous_commands_have_to_be_typed_again() {
+        let mut safe = WarpConfirmation::new("list".to_string(), "List files".to_string(), "ls".to_string(), None);
+        assert_eq!(press(&mut safe, "y"), ConfirmAction::Run("ls".to_string()));
+        assert_eq!(press(&mut safe, "e -la\n"), ConfirmAction::Edited("ls -la".to_string()));
+        safe.set_command("ls -la".to_string(), None);
+        assert_eq!(safe.stage, ConfirmStage::Choose);
+        assert!(matches!(press(&mut safe, "n"), ConfirmAction::Cancel(_)));
+
+        let command = "rm -rf build";
+        let mut dangerous = WarpConfirmation::new("clean".to_string(), "Remove build".to_string(), command.to_string(), Some("rm -rf".to_string()));
+        assert_eq!(press(&mut dangerous, "y"), ConfirmAction::Pending);
+        assert_eq!(dangerous.stage, ConfirmStage::Retype);
+        assert_eq!(press(&mut dangerous, &format!("{}\n", command)), ConfirmAction::Run(command.to_string()));
+
+        let mut mistyped = WarpConfirmation::new("clean".to_string(), "Remove build".to_string(), command.to_string(), Some("rm -rf".to_string()));
+        assert!(matches!(press(&mut mistyped, "yrm -rf buil\n"), ConfirmAction::Cancel(reason) if reason.contains("doesn't match")));
+    }
+}
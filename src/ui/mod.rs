@@ -1,8 +1,14 @@
 use anyhow::Result;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use ratatui::{
     backend::CrosstermBackend,
@@ -17,7 +23,12 @@ pub mod components;
 pub mod events;
 pub mod styles;
 pub mod blocks;
+pub mod clipboard;
+pub mod confirmation;
 pub mod performance;
+pub mod routing;
+pub mod search;
+pub mod transcript;
 
 pub use app::App;
 
@@ -29,6 +40,10 @@ pub fn setup_terminal() -> Result<AppTerminal> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Lets terminals that support it report Ctrl+Enter distinctly from Enter
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        execute!(stdout, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES))?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let terminal = Terminal::new(backend)?;
     
@@ -39,6 +54,9 @@ pub fn setup_terminal() -> Result<AppTerminal> {
 pub fn restore_terminal(terminal: &mut AppTerminal) -> Result<()> {
     info!("Restoring terminal");
     
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
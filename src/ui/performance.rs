@@ -1,7 +1,14 @@
-use std::collections::VecDeque;
 use std::time::{Duration, Instant};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
 use crate::db::CommandExecution;
 
+/// Outputs longer than this aren't parsed to find out whether they are JSON
+const MAX_JSON_DETECT_BYTES: usize = 256 * 1024;
+
+/// Programs that print files, so that the file's extension says what their output is
+const FILE_VIEWERS: &[&str] = &["cat", "bat", "head", "tail", "less", "more"];
+
 /// Performance optimizations for terminal rendering
 /// 
 /// This module implements various optimizations to achieve Warp-level performance:
@@ -13,10 +20,10 @@ use crate::db::CommandExecution;
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct PerformanceManager {
-    #[allow(dead_code)]
     pub max_history_size: usize,
-    #[allow(dead_code)]
     pub max_output_lines: usize,
+    /// Cap on an output's size once cut to `max_output_lines`, for outputs with very long lines
+    pub max_output_bytes: usize,
     #[allow(dead_code)]
     pub animation_frame_rate: u64,
     #[allow(dead_code)]
@@ -43,14 +50,21 @@ pub struct DirtyRegion {
 impl PerformanceManager {
     pub fn new() -> Self {
         Self {
-            max_history_size: 1000,
+            max_history_size: crate::db::DEFAULT_MAX_HISTORY_SIZE,
             max_output_lines: 10000,
+            max_output_bytes: 1024 * 1024,
             animation_frame_rate: 60, // 60 FPS
             last_frame_time: Instant::now(),
             dirty_regions: Vec::new(),
         }
     }
 
+    /// Keep at most `max_history_size` executions in memory
+    pub fn with_max_history(mut self, max_history_size: usize) -> Self {
+        self.max_history_size = max_history_size;
+        self
+    }
+
     /// Check if we should render a new frame based on target FPS
     pub fn should_render_frame(&mut self) -> bool {
         let frame_duration = Duration::from_millis(1000 / self.animation_frame_rate);
@@ -99,7 +113,7 @@ impl PerformanceManager {
     pub fn optimize_command_output(&self, output: &str) -> String {
         let lines: Vec<&str> = output.lines().collect();
         
-        if lines.len() > self.max_output_lines {
+        let mut result = if lines.len() > self.max_output_lines {
             let truncated_lines = &lines[..self.max_output_lines];
             let mut result = truncated_lines.join("\n");
             result.push_str(&format!("\n\n... {} more lines truncated for performance", 
@@ -107,7 +121,18 @@ impl PerformanceManager {
             result
         } else {
             output.to_string()
+        };
+        
+        if result.len() > self.max_output_bytes {
+            let mut end = self.max_output_bytes;
+            while !result.is_char_boundary(end) {
+                end -= 1;
+            }
+            let dropped = result.len() - end;
+            result.truncate(end);
+            result.push_str(&format!("\n\n... {} more bytes truncated for performance", dropped));
         }
+        result
     }
 }
 
@@ -117,7 +142,11 @@ pub struct VirtualScroller {
     pub viewport_height: usize,
     pub total_items: usize,
     pub scroll_offset: usize,
+    /// Height of the items `item_heights` has none for
     pub item_height: usize,
+    /// Height of each item when they differ, e.g. blocks with their output expanded; empty when
+    /// they are all `item_height` tall
+    pub item_heights: Vec<usize>,
 }
 
 impl VirtualScroller {
@@ -127,6 +156,7 @@ impl VirtualScroller {
             total_items: 0,
             scroll_offset: 0,
             item_height,
+            item_heights: Vec::new(),
         }
     }
 
@@ -136,11 +166,49 @@ impl VirtualScroller {
         self.clamp_scroll_offset();
     }
 
+    /// Give every item its own height; this also sets the number of items
+    pub fn set_item_heights(&mut self, heights: Vec<usize>) {
+        self.item_heights = heights;
+        self.update_total_items(self.item_heights.len());
+    }
+
+    fn height_of(&self, index: usize) -> usize {
+        self.item_heights.get(index).copied().unwrap_or(self.item_height)
+    }
+
+    /// How many items from `start` on fit in the viewport; an item taller than the viewport
+    /// on its own still counts, as it is drawn cut to fit
+    fn items_fitting_from(&self, start: usize) -> usize {
+        let mut used = 0;
+        let mut count = 0;
+        for index in start..self.total_items {
+            used += self.height_of(index);
+            if used > self.viewport_height && count > 0 {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// The first item to show for the items up to and including `last` to fill the viewport
+    fn first_item_ending_at(&self, last: usize) -> usize {
+        let mut used = 0;
+        let mut first = last + 1;
+        while first > 0 {
+            used += self.height_of(first - 1);
+            if used > self.viewport_height && first <= last {
+                break;
+            }
+            first -= 1;
+        }
+        first
+    }
+
     /// Get the range of visible items
     pub fn get_visible_range(&self) -> (usize, usize) {
-        let visible_items = self.viewport_height / self.item_height;
         let start = self.scroll_offset;
-        let end = (start + visible_items).min(self.total_items);
+        let end = (start + self.items_fitting_from(start)).min(self.total_items);
         (start, end)
     }
 
@@ -157,12 +225,10 @@ impl VirtualScroller {
 
     /// Scroll to a specific item
     pub fn scroll_to_item(&mut self, item_index: usize) {
-        let visible_items = self.viewport_height / self.item_height;
-        
         if item_index < self.scroll_offset {
             self.scroll_offset = item_index;
-        } else if item_index >= self.scroll_offset + visible_items {
-            self.scroll_offset = item_index.saturating_sub(visible_items - 1);
+        } else if item_index < self.total_items && item_index >= self.get_visible_range().1 {
+            self.scroll_offset = self.first_item_ending_at(item_index);
         }
         
         self.clamp_scroll_offset();
@@ -170,8 +236,10 @@ impl VirtualScroller {
 
     /// Get the maximum scroll offset
     pub fn max_scroll_offset(&self) -> usize {
-        let visible_items = self.viewport_height / self.item_height;
-        self.total_items.saturating_sub(visible_items)
+        match self.total_items {
+            0 => 0,
+            total => self.first_item_ending_at(total - 1),
+        }
     }
 
     fn clamp_scroll_offset(&mut self) {
@@ -199,8 +267,6 @@ pub struct Animation {
 #[derive(Debug, Clone, Copy)]
 pub enum EasingFunction {
     Linear,
-    EaseIn,
-    EaseOut,
     EaseInOut,
 }
 
@@ -277,8 +343,6 @@ impl AnimationSystem {
 fn apply_easing(progress: f64, easing: EasingFunction) -> f64 {
     match easing {
         EasingFunction::Linear => progress,
-        EasingFunction::EaseIn => progress * progress,
-        EasingFunction::EaseOut => 1.0 - (1.0 - progress) * (1.0 - progress),
         EasingFunction::EaseInOut => {
             if progress < 0.5 {
                 2.0 * progress * progress
@@ -291,21 +355,15 @@ fn apply_easing(progress: f64, easing: EasingFunction) -> f64 {
 
 /// Text renderer optimized for terminal performance
 #[derive(Debug)]
-pub struct OptimizedTextRenderer {
-    pub line_cache: VecDeque<String>,
-    pub max_cache_size: usize,
-}
+pub struct OptimizedTextRenderer;
 
 impl OptimizedTextRenderer {
     pub fn new() -> Self {
-        Self {
-            line_cache: VecDeque::new(),
-            max_cache_size: 10000,
-        }
+        Self
     }
 
     /// Render text with syntax highlighting (simplified)
-    pub fn render_with_highlighting(&mut self, text: &str, language: Option<&str>) -> Vec<String> {
+    pub fn render_with_highlighting(&self, text: &str, language: Option<&str>) -> Vec<String> {
         // Simple syntax highlighting based on language
         match language {
             Some("rust") => self.highlight_rust(text),
@@ -319,15 +377,27 @@ impl OptimizedTextRenderer {
         // Simplified Rust syntax highlighting
         text.lines()
             .map(|line| {
-                let mut highlighted = line.to_string();
+                let mut highlighted = String::new();
+                let mut word = String::new();
                 
-                // Highlight keywords (this is very simplified)
-                for keyword in &["fn", "let", "mut", "pub", "struct", "impl", "use"] {
-                    highlighted = highlighted.replace(
-                        keyword,
-                        &format!("\x1b[94m{}\x1b[0m", keyword) // Blue
-                    );
+                // Highlight keywords, as whole words only (this is very simplified)
+                let flush = |word: &mut String, highlighted: &mut String| {
+                    if ["fn", "let", "mut", "pub", "struct", "impl", "use"].contains(&word.as_str()) {
+                        highlighted.push_str(&format!("\x1b[94m{}\x1b[0m", word)); // Blue
+                    } else {
+                        highlighted.push_str(word);
+                    }
+                    word.clear();
+                };
+                for c in line.chars() {
+                    if c.is_alphanumeric() || c == '_' {
+                        word.push(c);
+                    } else {
+                        flush(&mut word, &mut highlighted);
+                        highlighted.push(c);
+                    }
                 }
+                flush(&mut word, &mut highlighted);
                 
                 highlighted
             })
@@ -339,9 +409,11 @@ impl OptimizedTextRenderer {
             .map(|line| {
                 let mut highlighted = line.to_string();
                 
-                // Highlight common shell commands
+                // Highlight common shell commands, after the entry number `history` prints
+                let mut words = line.split_whitespace().skip_while(|word| word.chars().all(|c| c.is_ascii_digit()));
+                let program = words.next().unwrap_or_default();
                 for cmd in &["cd", "ls", "git", "cargo", "npm", "docker"] {
-                    if line.trim_start().starts_with(cmd) {
+                    if program == *cmd {
                         highlighted = format!("\x1b[92m{}\x1b[0m", line); // Green
                         break;
                     }
@@ -366,14 +438,112 @@ impl OptimizedTextRenderer {
             })
             .collect()
     }
+}
+
+/// The language to pass `render_with_highlighting` for `output` of `command`: json when the
+/// output parses as JSON, rust or bash when the command prints a `.rs` file or a shell script,
+/// and bash for shell commands such as those `history` lists; `None` leaves it plain
+pub fn detect_language(command: &str, output: &str) -> Option<&'static str> {
+    let trimmed = output.trim();
+    if trimmed.starts_with(['{', '['])
+        && trimmed.len() <= MAX_JSON_DETECT_BYTES
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+    if trimmed.starts_with("#!") && ["sh", "bash", "zsh"].iter().any(|shell| trimmed.lines().next().unwrap_or_default().ends_with(shell)) {
+        return Some("bash");
+    }
+
+    let mut words = command.split_whitespace();
+    let program = words.next().map(|program| program.rsplit('/').next().unwrap_or(program))?;
+    if program == "history" {
+        return Some("bash");
+    }
+    if !FILE_VIEWERS.contains(&program) {
+        return None;
+    }
+    let file = words.rfind(|word| !word.starts_with('-'))?;
+    match file.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("rs") => Some("rust"),
+        Some("sh" | "bash" | "zsh") => Some("bash"),
+        _ => None,
+    }
+}
 
-    /// Cache frequently used lines for performance
-    pub fn cache_line(&mut self, line: String) {
-        if self.line_cache.len() >= self.max_cache_size {
-            self.line_cache.pop_front();
+/// `line` as spans over `base`, its SGR escape sequences (the colors and bold that
+/// `render_with_highlighting` and colored program output use) turned into span styles;
+/// other escape sequences are dropped, since ratatui would print them as they are
+pub fn ansi_spans(line: &str, base: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut style = base;
+    let mut rest = line;
+    while let Some(at) = rest.find('\x1b') {
+        if at > 0 {
+            spans.push(Span::styled(rest[..at].to_string(), style));
         }
-        self.line_cache.push_back(line);
+        let sequence = &rest[at + 1..];
+        let Some(params) = sequence.strip_prefix('[') else {
+            rest = sequence;
+            continue;
+        };
+        let Some(end) = params.find(|c: char| c.is_ascii_alphabetic() || c == '~') else {
+            rest = "";
+            break;
+        };
+        if params[end..].starts_with('m') {
+            style = apply_sgr(style, base, &params[..end]);
+        }
+        rest = &params[end + 1..];
+    }
+    if !rest.is_empty() || spans.is_empty() {
+        spans.push(Span::styled(rest.to_string(), style));
+    }
+    spans
+}
+
+/// `style` after the SGR parameters `params` (e.g. `1;32`), with resets going back to `base`
+fn apply_sgr(mut style: Style, base: Style, params: &str) -> Style {
+    const COLORS: [Color; 8] = [Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan, Color::Gray];
+    const BRIGHT: [Color; 8] = [Color::DarkGray, Color::LightRed, Color::LightGreen, Color::LightYellow, Color::LightBlue, Color::LightMagenta, Color::LightCyan, Color::White];
+    let codes: Vec<u8> = params.split(';').map(|code| code.parse().unwrap_or(0)).collect();
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => base,
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            30..=37 => style.fg(COLORS[(code - 30) as usize]),
+            90..=97 => style.fg(BRIGHT[(code - 90) as usize]),
+            40..=47 => style.bg(COLORS[(code - 40) as usize]),
+            100..=107 => style.bg(BRIGHT[(code - 100) as usize]),
+            39 => Style { fg: base.fg, ..style },
+            49 => Style { bg: base.bg, ..style },
+            // 256-color and true-color forms: 38;5;n and 38;2;r;g;b
+            38 | 48 => {
+                let color = match codes.next() {
+                    Some(5) => codes.next().map(Color::Indexed),
+                    Some(2) => match (codes.next(), codes.next(), codes.next()) {
+                        (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r, g, b)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match color {
+                    Some(color) if code == 38 => style.fg(color),
+                    Some(color) => style.bg(color),
+                    None => style,
+                }
+            }
+            _ => style,
+        };
     }
+    style
 }
 
 impl Default for PerformanceManager {
@@ -393,3 +563,74 @@ impl Default for OptimizedTextRenderer {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrolling_accounts_for_uneven_item_heights() {
+        let mut scroller = VirtualScroller::new(10, 3);
+        scroller.update_total_items(7);
+        assert_eq!(scroller.get_visible_range(), (0, 3), "fixed heights fit 10 / 3 items");
+        assert_eq!(scroller.max_scroll_offset(), 4);
+
+        scroller.set_item_heights(vec![3, 8, 2, 2, 3, 12, 1]);
+        assert_eq!(scroller.get_visible_range(), (0, 1), "the 8-row item doesn't fit after the first");
+        scroller.scroll_to_item(4);
+        assert_eq!(scroller.get_visible_range(), (2, 5), "items 2 to 4 fill 7 of the 10 rows, item 1 doesn't fit too");
+        scroller.scroll_to_item(5);
+        assert_eq!(scroller.get_visible_range(), (5, 6), "an item taller than the viewport is shown alone");
+        scroller.scroll_to_item(1);
+        assert_eq!(scroller.scroll_offset, 1);
+        assert_eq!(scroller.max_scroll_offset(), 6, "item 6 doesn't fit under item 5, so it is the last page");
+        scroller.scroll_down(100);
+        assert_eq!(scroller.get_visible_range(), (6, 7));
+
+        scroller.set_item_heights(vec![2, 0, 0, 4, 4]);
+        assert_eq!(scroller.get_visible_range(), (0, 5), "hidden items take no room");
+        assert_eq!(scroller.max_scroll_offset(), 0);
+    }
+
+    #[test]
+    fn test_history_and_outputs_are_capped() {
+        let manager = PerformanceManager { max_output_lines: 3, max_output_bytes: 8, ..PerformanceManager::new() }.with_max_history(2);
+        let mut history: Vec<CommandExecution> = (0..5).map(|n| CommandExecution::new(format!("echo {}", n), None)).collect();
+        manager.optimize_command_history(&mut history);
+        assert_eq!(history.iter().map(|exec| exec.command.as_str()).collect::<Vec<_>>(), ["echo 0", "echo 1"], "the newest are kept");
+
+        assert_eq!(manager.optimize_command_output("a\nb"), "a\nb");
+        let lines_only = PerformanceManager { max_output_lines: 3, ..PerformanceManager::new() };
+        assert!(lines_only.optimize_command_output("1\n2\n3\n4\n5").starts_with("1\n2\n3\n\n... 2 more lines truncated"));
+        assert_eq!(manager.optimize_command_output("ééééé"), "éééé\n\n... 2 more bytes truncated for performance");
+        assert_eq!(manager.optimize_command_output("abcdefghé"), "abcdefgh\n\n... 2 more bytes truncated for performance");
+    }
+
+    #[test]
+    fn test_output_language_is_detected_from_the_output_and_command() {
+        assert_eq!(detect_language("curl -s api/users", "{\"name\": \"ada\", \"tags\": [1, 2]}\n"), Some("json"));
+        assert_eq!(detect_language("echo", "[1, 2"), None, "not valid JSON");
+        assert_eq!(detect_language("cat deploy.sh", "set -e\nls"), Some("bash"));
+        assert_eq!(detect_language("/usr/bin/head -n 20 src/main.rs", "fn main() {}"), Some("rust"));
+        assert_eq!(detect_language("history", "  1  git status\n  2  ls"), Some("bash"));
+        assert_eq!(detect_language("./build", "#!/usr/bin/env bash\necho hi"), Some("bash"));
+        assert_eq!(detect_language("ls -la", "total 0"), None);
+        assert_eq!(detect_language("cat notes.txt", "let me know"), None);
+    }
+
+    #[test]
+    fn test_highlighted_lines_become_styled_spans() {
+        let base = Style::default().fg(Color::White);
+        let lines = OptimizedTextRenderer::new().render_with_highlighting("let user = 1;", Some("rust"));
+        let spans = ansi_spans(&lines[0], base);
+        let texts: Vec<&str> = spans.iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(texts, ["let", " user = 1;"], "keywords inside words aren't highlighted");
+        assert_eq!(spans[0].style.fg, Some(Color::LightBlue));
+        assert_eq!(spans[1].style, base, "reset goes back to the base style");
+
+        let spans = ansi_spans("\x1b[1;38;5;208mwarn\x1b[22;39m done\x1b[K", base);
+        assert_eq!(spans[0].style, base.fg(Color::Indexed(208)).add_modifier(Modifier::BOLD));
+        assert_eq!((spans[1].content.as_ref(), spans[1].style.fg), (" done", Some(Color::White)));
+        assert_eq!(spans.len(), 2, "other escape sequences are dropped");
+    }
+}
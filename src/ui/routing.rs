@@ -0,0 +1,104 @@
+use std::path::Path;
+
+/// Where input that isn't an `agentic` subcommand should go
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputRoute {
+    /// Run it in the shell, like `run`
+    Shell,
+    /// Send it to the agent
+    Agent,
+}
+
+const QUESTION_WORDS: &[&str] = &[
+    "what", "why", "how", "when", "where", "who", "which", "can", "could", "should", "would",
+    "is", "are", "does", "do", "explain", "help", "tell", "please", "suggest", "i", "i'm",
+];
+
+/// Words that rarely appear in shell commands but are everywhere in prose
+const PROSE_WORDS: &[&str] = &["a", "an", "the", "me", "my", "for", "to", "about", "with", "of"];
+
+/// Guess whether `input` is a shell command or a question. When the signals agree the mode
+/// doesn't matter; when they conflict or there are none, `agent_mode` decides.
+pub fn classify_input(input: &str, agent_mode: bool, is_program: impl Fn(&str) -> bool) -> InputRoute {
+    let input = input.trim();
+    let words: Vec<String> = input.split_whitespace().map(|w| w.to_lowercase()).collect();
+    let Some(first) = words.first() else {
+        return if agent_mode { InputRoute::Agent } else { InputRoute::Shell };
+    };
+
+    let looks_like_command = is_program(first)
+        || first.starts_with("./")
+        || first.starts_with('/')
+        || ["|", "&&", "||", ";", ">", "$("].iter().any(|op| input.contains(op));
+
+    let prose_words = words.iter().filter(|w| PROSE_WORDS.contains(&w.as_str())).count();
+    let looks_like_question = input.ends_with('?')
+        || QUESTION_WORDS.contains(&first.trim_end_matches(','))
+        || (words.len() >= 4 && prose_words >= 2 && !words.iter().any(|w| w.starts_with('-')));
+
+    match (looks_like_command, looks_like_question) {
+        (true, false) => InputRoute::Shell,
+        (false, true) => InputRoute::Agent,
+        _ if agent_mode => InputRoute::Agent,
+        _ => InputRoute::Shell,
+    }
+}
+
+/// Whether `program` names an executable in one of the `PATH` directories
+pub fn is_on_path(program: &str) -> bool {
+    if program.contains(['/', '\\']) {
+        return false;
+    }
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| is_executable(&dir.join(program))
+        || (cfg!(windows) && is_executable(&dir.join(format!("{}.exe", program)))))
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata().map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_path(program: &str) -> bool {
+        ["ls", "git", "which", "make", "cargo"].contains(&program)
+    }
+
+    #[test]
+    fn test_classifies_commands_and_questions() {
+        for agent_mode in [false, true] {
+            assert_eq!(classify_input("ls -la", agent_mode, fake_path), InputRoute::Shell);
+            assert_eq!(classify_input("git status", agent_mode, fake_path), InputRoute::Shell);
+            assert_eq!(classify_input("./deploy.sh --prod", agent_mode, fake_path), InputRoute::Shell);
+            assert_eq!(classify_input("echo hi | wc -c", agent_mode, fake_path), InputRoute::Shell);
+            assert_eq!(classify_input("how do I undo a commit?", agent_mode, fake_path), InputRoute::Agent);
+            assert_eq!(classify_input("Explain async Rust", agent_mode, fake_path), InputRoute::Agent);
+            assert_eq!(classify_input("plan a study week for the CET exam", agent_mode, fake_path), InputRoute::Agent);
+        }
+
+        // Conflicting or missing signals fall back to the mode
+        assert_eq!(classify_input("which python", false, fake_path), InputRoute::Shell);
+        assert_eq!(classify_input("which python", true, fake_path), InputRoute::Agent);
+        assert_eq!(classify_input("frobnicate", false, fake_path), InputRoute::Shell);
+        assert_eq!(classify_input("frobnicate", true, fake_path), InputRoute::Agent);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_on_path() {
+        assert!(is_on_path("sh"));
+        assert!(!is_on_path("definitely-not-a-real-program-xyz"));
+    }
+}
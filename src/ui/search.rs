@@ -0,0 +1,153 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::ops::Range;
+
+use crate::db::CommandExecution;
+
+use super::components::{InputAction, InputBar};
+
+/// What a key press while searching the history asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchAction {
+    /// Nothing to do; keep searching
+    Pending,
+    /// Select the matching execution at this index into the history
+    Jump(usize),
+    /// Leave the search and show the whole history again
+    Exit,
+    /// Not a search key; run it as a normal-mode key instead
+    Unhandled,
+}
+
+/// `/` in the TUI: while it is open the history shows only the executions whose command or
+/// output contain the query. Typing edits the query and jumps to the first match from the top,
+/// which is the newest since the history is newest first; after Enter, `n`/`N` step to older
+/// and newer matches and other keys work as usual on the selected one.
+#[derive(Debug)]
+pub struct HistorySearch {
+    pub field: InputBar,
+    /// Whether keys edit the query; Enter stops and `/` starts again
+    pub typing: bool,
+}
+
+impl HistorySearch {
+    pub fn new() -> Self {
+        Self { field: InputBar::new(), typing: true }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.field.content
+    }
+
+    /// Whether `execution` is shown; everything is while the query is empty
+    pub fn is_match(&self, execution: &CommandExecution) -> bool {
+        let query = self.query();
+        query.is_empty() || contains(&execution.command, query) || contains(&execution.output, query)
+    }
+
+    /// Indices into `history` of the executions shown
+    pub fn matches(&self, history: &[CommandExecution]) -> Vec<usize> {
+        (0..history.len()).filter(|&index| self.is_match(&history[index])).collect()
+    }
+
+    /// The match after (or before) `from`, wrapping around the ends of the history
+    pub fn next_match(&self, history: &[CommandExecution], from: usize, forward: bool) -> Option<usize> {
+        let matches = self.matches(history);
+        if forward {
+            matches.iter().find(|&&index| index > from).or(matches.first()).copied()
+        } else {
+            matches.iter().rev().find(|&&index| index < from).or(matches.last()).copied()
+        }
+    }
+
+    /// Handle `key` with `selected` the index of the selected execution in `history`
+    pub fn handle_key(&mut self, key: KeyEvent, history: &[CommandExecution], selected: usize) -> SearchAction {
+        if key.code == KeyCode::Esc {
+            return SearchAction::Exit;
+        }
+        if !self.typing {
+            return match key.code {
+                KeyCode::Char('n') | KeyCode::Down => self.jump(self.next_match(history, selected, true)),
+                KeyCode::Char('N') | KeyCode::Up => self.jump(self.next_match(history, selected, false)),
+                KeyCode::Char('/') => {
+                    self.typing = true;
+                    SearchAction::Pending
+                }
+                _ => SearchAction::Unhandled,
+            };
+        }
+        match key.code {
+            KeyCode::Enter => {
+                self.typing = false;
+                SearchAction::Pending
+            }
+            KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => SearchAction::Unhandled,
+            _ => {
+                let before = self.field.content.clone();
+                if self.field.handle_key(key) == InputAction::Edited && self.field.content != before {
+                    self.jump(self.matches(history).first().copied())
+                } else {
+                    SearchAction::Pending
+                }
+            }
+        }
+    }
+
+    fn jump(&self, index: Option<usize>) -> SearchAction {
+        index.map_or(SearchAction::Pending, SearchAction::Jump)
+    }
+}
+
+/// Whether `text` contains `query`, ignoring ASCII case
+fn contains(text: &str, query: &str) -> bool {
+    !match_ranges(text, query).is_empty()
+}
+
+/// The byte ranges of `text` where `query` occurs, ignoring ASCII case, without overlaps
+pub fn match_ranges(text: &str, query: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if query.is_empty() {
+        return ranges;
+    }
+    let mut start = 0;
+    while let Some(offset) = text[start..].char_indices().map(|(i, _)| start + i).find(|&at| {
+        text.get(at..at + query.len()).is_some_and(|candidate| candidate.eq_ignore_ascii_case(query))
+    }) {
+        ranges.push(offset..offset + query.len());
+        start = offset + query.len();
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_ignore_case_and_wrap_around() {
+        let history: Vec<CommandExecution> = ["git status", "ls", "echo GIT", "cargo build"]
+            .iter()
+            .map(|command| CommandExecution::new(command.to_string(), None))
+            .collect();
+        // Newest first, as in the TUI: "git status" is the latest command
+        let mut search = HistorySearch::new();
+        assert_eq!(search.matches(&history), vec![0, 1, 2, 3], "an empty query shows everything");
+
+        let key = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        assert_eq!(search.handle_key(key('g'), &history, 3), SearchAction::Jump(0), "typing jumps to the newest match");
+        assert_eq!(search.handle_key(key('i'), &history, 0), SearchAction::Jump(0));
+        assert_eq!(search.handle_key(key('t'), &history, 0), SearchAction::Jump(0));
+        assert_eq!(search.matches(&history), vec![0, 2]);
+        assert_eq!(search.handle_key(key('n'), &history, 0), SearchAction::Pending, "n is part of the query while typing");
+        assert_eq!(search.query(), "gitn");
+
+        search.field.backspace();
+        search.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), &history, 0);
+        assert_eq!(search.handle_key(key('n'), &history, 0), SearchAction::Jump(2), "n steps to an older match");
+        assert_eq!(search.handle_key(key('n'), &history, 2), SearchAction::Jump(0), "wraps to the first match");
+        assert_eq!(search.handle_key(key('N'), &history, 0), SearchAction::Jump(2));
+        assert_eq!(search.handle_key(key('i'), &history, 2), SearchAction::Unhandled);
+
+        assert_eq!(match_ranges("Git push; git pull", "git"), vec![0..3, 10..13]);
+        assert_eq!(match_ranges("é git", "git"), vec![3..6]);
+    }
+}
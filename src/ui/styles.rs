@@ -1,42 +1,105 @@
+use anyhow::{anyhow, Context, Result};
 use ratatui::style::{Color, Style};
+use tracing::warn;
+
 use crate::config::Config;
+use crate::themes::theme_manager::{Theme, ThemeManager};
 
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AppTheme {
-    #[allow(dead_code)]
     pub primary_color: Color,
-    #[allow(dead_code)]
     pub secondary_color: Color,
-    #[allow(dead_code)]
     pub accent_color: Color,
-    #[allow(dead_code)]
     pub background_color: Color,
-    #[allow(dead_code)]
     pub text_color: Color,
-    #[allow(dead_code)]
     pub success_color: Color,
-    #[allow(dead_code)]
     pub error_color: Color,
-    #[allow(dead_code)]
     pub warning_color: Color,
     #[allow(dead_code)]
     pub info_color: Color,
 }
 
+/// Parse a `#rrggbb` or `#rgb` color, as used in the config and theme files
+pub fn parse_hex_color(hex: &str) -> Result<Color> {
+    let invalid = || anyhow!("'{}' is not a #rrggbb or #rgb color", hex);
+    let digits = hex.trim().strip_prefix('#').ok_or_else(invalid)?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+    match digits.len() {
+        6 => Ok(Color::Rgb(channel(&digits[0..2])?, channel(&digits[2..4])?, channel(&digits[4..6])?)),
+        // Each digit is doubled: #1af is #11aaff
+        3 => {
+            let short = |at: usize| channel(&digits[at..at + 1].repeat(2));
+            Ok(Color::Rgb(short(0)?, short(1)?, short(2)?))
+        }
+        _ => Err(invalid()),
+    }
+}
+
 impl AppTheme {
+    /// The theme named by `theme.name` when set, otherwise the built-in dark or light palette
+    /// with the configured primary and accent colors. A named theme that can't be found or has a
+    /// bad color falls back to the dark palette; a bad configured color keeps the built-in one.
     pub fn from_config(config: &Config) -> Self {
-        if config.theme.dark_mode {
+        if let Some(name) = &config.theme.name {
+            return Self::named(name).unwrap_or_else(|e| {
+                warn!("Using the default dark theme: {:#}", e);
+                Self::dark_theme()
+            });
+        }
+        let mut theme = if config.theme.dark_mode {
             Self::dark_theme()
         } else {
             Self::light_theme()
+        };
+        for (key, hex, color) in [
+            ("primary_color", &config.theme.primary_color, &mut theme.primary_color),
+            ("accent_color", &config.theme.accent_color, &mut theme.accent_color),
+        ] {
+            match parse_hex_color(hex) {
+                Ok(parsed) => *color = parsed,
+                Err(e) => warn!("Ignoring theme.{}: {}", key, e),
+            }
         }
+        theme
+    }
+    
+    /// Load the theme called `name` from the theme directories
+    pub fn named(name: &str) -> Result<Self> {
+        let mut manager = ThemeManager::new();
+        manager.load_themes()?;
+        let theme = manager.find_theme(name).ok_or_else(|| anyhow!("Theme '{}' not found", name))?;
+        Self::from_theme(theme).with_context(|| format!("Theme '{}' has an invalid color", name))
+    }
+    
+    /// Map a theme file onto the TUI palette: the theme's own accent, background and foreground,
+    /// and its terminal colors for the rest
+    pub fn from_theme(theme: &Theme) -> Result<Self> {
+        let terminal = |colors: &std::collections::HashMap<String, String>, name: &str| {
+            colors.get(name)
+                .ok_or_else(|| anyhow!("no '{}' terminal color", name))
+                .and_then(|hex| parse_hex_color(hex))
+        };
+        let normal = &theme.terminal_colors.normal;
+        Ok(Self {
+            primary_color: terminal(normal, "blue")?,
+            secondary_color: terminal(&theme.terminal_colors.bright, "black")?,
+            accent_color: parse_hex_color(&theme.accent)?,
+            background_color: parse_hex_color(&theme.background)?,
+            text_color: parse_hex_color(&theme.foreground)?,
+            success_color: terminal(normal, "green")?,
+            error_color: terminal(normal, "red")?,
+            warning_color: terminal(normal, "yellow")?,
+            info_color: terminal(normal, "cyan")?,
+        })
     }
     
     pub fn dark_theme() -> Self {
         Self {
             primary_color: Color::Blue,
-            secondary_color: Color::DarkGray,
+            secondary_color: Color::Gray,
             accent_color: Color::Cyan,
             background_color: Color::Black,
             text_color: Color::White,
@@ -89,3 +152,63 @@ impl AppTheme {
         Style::default().fg(self.info_color)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::themes::theme_manager::TerminalColors;
+    use std::collections::HashMap;
+
+    fn theme(accent: &str) -> Theme {
+        let colors = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>();
+        let normal = colors(&[("blue", "#0000ff"), ("green", "#00ff00"), ("red", "#ff0000"), ("yellow", "#ffff00"), ("cyan", "#00ffff")]);
+        Theme {
+            name: "Test".to_string(),
+            accent: accent.to_string(),
+            background: "#0a0a0a".to_string(),
+            details: "darker".to_string(),
+            foreground: "#ffffff".to_string(),
+            terminal_colors: TerminalColors { normal, bright: colors(&[("black", "#8e8e8e")]) },
+            author: None,
+            description: None,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#61dafb").unwrap(), Color::Rgb(0x61, 0xda, 0xfb));
+        assert_eq!(parse_hex_color("#61DAFB").unwrap(), Color::Rgb(0x61, 0xda, 0xfb));
+        assert_eq!(parse_hex_color("#1af").unwrap(), Color::Rgb(0x11, 0xaa, 0xff));
+        assert_eq!(parse_hex_color(" #000000 ").unwrap(), Color::Rgb(0, 0, 0));
+        for invalid in ["61dafb", "#61dafg", "#61daf", "#", "", "#+1+2+3", "#ééé"] {
+            assert!(parse_hex_color(invalid).is_err(), "{:?} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn test_config_colors_drive_the_builtin_palette() {
+        let mut config = Config::default();
+        config.theme.primary_color = "#fff".to_string();
+        config.theme.accent_color = "not a color".to_string();
+        let theme = AppTheme::from_config(&config);
+        assert_eq!(theme.primary_color, Color::Rgb(0xff, 0xff, 0xff));
+        assert_eq!(theme.accent_color, AppTheme::dark_theme().accent_color);
+    }
+
+    #[test]
+    fn test_theme_file_colors_become_rgb_and_bad_ones_fall_back() {
+        let app_theme = AppTheme::from_theme(&theme("#00c2ff")).unwrap();
+        assert_eq!(app_theme.accent_color, Color::Rgb(0x00, 0xc2, 0xff));
+        assert_eq!(app_theme.background_color, Color::Rgb(0x0a, 0x0a, 0x0a));
+        assert_eq!(app_theme.text_color, Color::Rgb(0xff, 0xff, 0xff));
+        assert_eq!(app_theme.primary_color, Color::Rgb(0, 0, 0xff));
+        assert_eq!(app_theme.secondary_color, Color::Rgb(0x8e, 0x8e, 0x8e));
+        assert!(AppTheme::from_theme(&theme("cyan")).is_err());
+        assert_eq!(AppTheme::named("Agentic Dark").unwrap().accent_color, Color::Rgb(0x00, 0xc2, 0xff));
+
+        let mut config = Config::default();
+        config.theme.name = Some("No Such Theme".to_string());
+        assert_eq!(AppTheme::from_config(&config), AppTheme::dark_theme());
+    }
+}
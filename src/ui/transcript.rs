@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::config::TranscriptConfig;
+use crate::db::{CommandExecution, ExecutionStatus};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Prefixes of well-known API token formats, redacted wherever they appear
+const SECRET_PREFIXES: &[&str] = &["sk-", "ghp_", "gho_", "github_pat_", "xoxb-", "xoxp-", "AKIA"];
+
+/// Masks secrets before text is written somewhere human-readable
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    keys: Vec<String>,
+    /// Exact values to mask, such as the configured OpenAI key
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(keys: &[String], secrets: Vec<String>) -> Self {
+        Self {
+            keys: keys.iter().map(|k| k.to_lowercase()).collect(),
+            secrets: secrets.into_iter().filter(|s| !s.is_empty()).collect(),
+        }
+    }
+
+    fn is_sensitive_key(&self, key: &str) -> bool {
+        let key = key.trim_start_matches('-').to_lowercase();
+        !key.is_empty() && self.keys.iter().any(|k| key.contains(k.as_str()))
+    }
+
+    fn looks_like_token(word: &str) -> bool {
+        let word = word.trim_matches(|c| c == '"' || c == '\'');
+        SECRET_PREFIXES.iter().any(|prefix| word.starts_with(prefix) && word.len() >= prefix.len() + 8)
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for secret in &self.secrets {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+        text.lines().map(|line| self.redact_line(line)).collect::<Vec<_>>().join("\n")
+    }
+
+    /// Splitting and rejoining on single spaces keeps the rest of the line intact
+    fn redact_line(&self, line: &str) -> String {
+        let mut redact_next = false;
+        let words: Vec<String> = line.split(' ').map(|word| {
+            if word.is_empty() {
+                return String::new();
+            }
+            let is_auth_scheme = word.eq_ignore_ascii_case("bearer") || word.eq_ignore_ascii_case("basic");
+            if redact_next && is_auth_scheme {
+                // `Authorization: Bearer abc123` masks the credential after the scheme
+                return word.to_string();
+            }
+            if std::mem::take(&mut redact_next) || Self::looks_like_token(word) {
+                return REDACTED.to_string();
+            }
+            if let Some(at) = word.find(['=', ':']) {
+                let (key, value) = (&word[..at], &word[at + 1..]);
+                if self.is_sensitive_key(key) {
+                    if value.is_empty() {
+                        // `password: hunter2`
+                        redact_next = true;
+                        return word.to_string();
+                    }
+                    return format!("{}{}{}", key, &word[at..at + 1], REDACTED);
+                }
+            } else if word.starts_with('-') && self.is_sensitive_key(word) {
+                // `--token abc123`
+                redact_next = true;
+            }
+            word.to_string()
+        }).collect();
+        words.join(" ")
+    }
+}
+
+/// Human-readable log of a TUI session, appended to as each command finishes
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    path: PathBuf,
+    redactor: Redactor,
+}
+
+impl Transcript {
+    /// Create `session-<timestamp>.log` in the configured directory
+    pub fn start(config: &TranscriptConfig, redactor: Redactor, started_at: DateTime<Local>) -> Result<Self> {
+        std::fs::create_dir_all(&config.dir)
+            .with_context(|| format!("Failed to create transcript directory {:?}", config.dir))?;
+        let path = config.dir.join(format!("session-{}.log", started_at.format("%Y%m%d-%H%M%S")));
+        let transcript = Self { path, redactor };
+        transcript.write(&format!("# agentic TUI session started {}\n\n", started_at.format("%Y-%m-%d %H:%M:%S")))?;
+        Ok(transcript)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, execution: &CommandExecution) -> Result<()> {
+        self.write(&format_entry(execution, &self.redactor))
+    }
+
+    fn write(&self, text: &str) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open transcript {:?}", self.path))?;
+        file.write_all(text.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn format_entry(execution: &CommandExecution, redactor: &Redactor) -> String {
+    let prompt = if execution.agent_query.is_some() { "🤖" } else { "$" };
+    let status = match execution.status {
+        ExecutionStatus::Running => "running",
+        ExecutionStatus::Success => "success",
+        ExecutionStatus::Error => "error",
+        ExecutionStatus::Cancelled => "cancelled",
+        ExecutionStatus::Unknown => "unknown",
+    };
+    let mut entry = format!(
+        "[{}] {} {}\n",
+        execution.timestamp.with_timezone(&Local).format("%H:%M:%S"),
+        prompt,
+        redactor.redact(&execution.command)
+    );
+    let output = execution.output.trim_end();
+    if !output.is_empty() {
+        entry.push_str(&redactor.redact(output));
+        entry.push('\n');
+    }
+    entry.push_str(&format!("[{} • {}ms]\n\n", status, execution.duration_ms));
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_secrets_and_keeps_the_rest() {
+        let redactor = Redactor::new(&TranscriptConfig::default().redact_keys, vec!["my-openai-key".to_string()]);
+
+        assert_eq!(redactor.redact("mysql -u root --password=hunter2 db"), "mysql -u root --password=[REDACTED] db");
+        assert_eq!(redactor.redact("export GITHUB_TOKEN=abc123"), "export GITHUB_TOKEN=[REDACTED]");
+        assert_eq!(redactor.redact("login --token abc123 now"), "login --token [REDACTED] now");
+        assert_eq!(redactor.redact("Authorization: Bearer abc.def"), "Authorization: Bearer [REDACTED]");
+        assert_eq!(redactor.redact("key is sk-abcdefghijklmnop"), "key is [REDACTED]");
+        assert_eq!(redactor.redact("using my-openai-key here"), "using [REDACTED] here");
+        assert_eq!(redactor.redact("ls -la  src | grep x=1"), "ls -la  src | grep x=1");
+    }
+}
@@ -3,12 +3,23 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use tracing::warn;
 
+use crate::agent::ModelOverrides;
+use crate::retry::RetryPolicy;
+
 /// Ollama API request structure
 #[derive(Debug, Serialize)]
 struct OllamaRequest {
     model: String,
     prompt: String,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<GenerateOptions>,
+}
+
+/// Sampling settings; only sent when a run overrides the model's defaults
+#[derive(Debug, Serialize)]
+struct GenerateOptions {
+    temperature: f32,
 }
 
 /// Ollama API response structure
@@ -17,6 +28,138 @@ struct OllamaResponse {
     response: String,
 }
 
+/// One line of a streamed Ollama reply
+#[derive(Debug, Deserialize)]
+struct OllamaStreamChunk {
+    #[serde(default)]
+    response: String,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+}
+
+/// Receives each piece of a response as the model generates it
+pub type TokenSink<'a> = &'a mut dyn FnMut(&str);
+
+/// Who wrote a plan or command: the configured model, the fallback model after the configured one
+/// failed, or the built-in patterns after both did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Primary(String),
+    Fallback(String),
+    Pattern,
+}
+
+impl Source {
+    /// The model that answered, or `None` for the built-in patterns
+    pub fn model(&self) -> Option<&str> {
+        match self {
+            Source::Primary(model) | Source::Fallback(model) => Some(model),
+            Source::Pattern => None,
+        }
+    }
+
+    pub fn is_fallback(&self) -> bool {
+        !matches!(self, Source::Primary(_))
+    }
+}
+
+/// A generated plan or command and who wrote it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generated {
+    pub text: String,
+    pub source: Source,
+}
+
+impl Generated {
+    fn new(text: &str, source: Source) -> Self {
+        Self { text: text.trim().to_string(), source }
+    }
+}
+
+/// Joins the `response` pieces of Ollama's newline-delimited streaming replies, which can be
+/// split anywhere across network chunks
+#[derive(Debug, Default)]
+pub struct StreamAssembler {
+    pending: Vec<u8>,
+    text: String,
+    done: bool,
+}
+
+impl StreamAssembler {
+    /// Add a network chunk, passing every completed piece to `on_token`
+    pub fn push(&mut self, chunk: &[u8], on_token: TokenSink) -> Result<()> {
+        self.pending.extend_from_slice(chunk);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.parse_line(&line, on_token)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the final `"done": true` line has arrived
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The assembled text, after any last line without a trailing newline
+    pub fn finish(mut self, on_token: TokenSink) -> Result<String> {
+        let rest = std::mem::take(&mut self.pending);
+        self.parse_line(&rest, on_token)?;
+        Ok(self.text)
+    }
+
+    fn parse_line(&mut self, line: &[u8], on_token: TokenSink) -> Result<()> {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return Ok(());
+        }
+        let chunk: OllamaStreamChunk = serde_json::from_slice(line)?;
+        if let Some(error) = chunk.error {
+            return Err(anyhow!("Ollama API error: {}", error));
+        }
+        if !chunk.response.is_empty() {
+            on_token(&chunk.response);
+            self.text.push_str(&chunk.response);
+        }
+        self.done |= chunk.done;
+        Ok(())
+    }
+}
+
+/// Send `request` to `/api/generate`, retrying transient failures under `retry`, and stream
+/// the reply into `on_token` when one is given
+async fn generate(
+    client: &Client,
+    ollama_host: &str,
+    retry: RetryPolicy,
+    mut request: OllamaRequest,
+    on_token: Option<TokenSink<'_>>,
+) -> Result<String> {
+    crate::offline::check_network()?;
+    request.stream = on_token.is_some();
+    let url = format!("{}/api/generate", ollama_host);
+    let mut response = retry
+        .send(&format!("Ollama request to {}", request.model), || client.post(&url).json(&request).send())
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Ollama API error: {}", response.status()));
+    }
+
+    let Some(on_token) = on_token else {
+        let ollama_response: OllamaResponse = response.json().await?;
+        return Ok(ollama_response.response);
+    };
+    let mut assembler = StreamAssembler::default();
+    while let Some(chunk) = response.chunk().await? {
+        assembler.push(&chunk, on_token)?;
+        if assembler.is_done() {
+            break;
+        }
+    }
+    assembler.finish(on_token)
+}
+
 /// Planning Agent - converts natural language to structured plans
 #[derive(Debug, Clone)]
 pub struct PlannerAgent {
@@ -24,6 +167,8 @@ pub struct PlannerAgent {
     ollama_host: String,
     model: String,
     fallback_model: String,
+    retry: RetryPolicy,
+    temperature: Option<f32>,
 }
 
 impl PlannerAgent {
@@ -33,11 +178,29 @@ impl PlannerAgent {
             ollama_host,
             model,
             fallback_model,
+            retry: RetryPolicy::default(),
+            temperature: None,
         }
     }
 
-    /// Generate a structured plan from natural language input
-    pub async fn generate_plan(&self, input: &str) -> Result<String> {
+    /// Retry model requests that fail with a 429, 5xx or timeout under `retry`
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Use the `--model`/`--temp` overrides for this run; the fallback model stays as configured
+    pub fn with_overrides(mut self, overrides: &ModelOverrides) -> Self {
+        if let Some(model) = &overrides.model {
+            self.model = model.clone();
+        }
+        self.temperature = overrides.temperature.or(self.temperature);
+        self
+    }
+
+    /// Generate a structured plan from natural language input; with `on_token` the primary
+    /// model's reply is streamed into it as it is generated
+    pub async fn generate_plan(&self, input: &str, on_token: Option<TokenSink<'_>>) -> Result<Generated> {
         let system_prompt = r#"You are a planning agent that converts natural language requests into clear, structured plans.
 
 Your role:
@@ -66,42 +229,30 @@ Output: "Create a database backup, compress the backup file, and save it to a se
         let prompt = format!("{}\n\nUser Request: {}\nPlan:", system_prompt, input);
 
         // Try primary model first
-        match self.query_model(&self.model, &prompt).await {
-            Ok(response) => Ok(response.trim().to_string()),
+        match self.query_model(&self.model, &prompt, on_token).await {
+            Ok(response) => Ok(Generated::new(&response, Source::Primary(self.model.clone()))),
             Err(_) => {
                 warn!("Primary model {} failed, trying fallback {}", self.model, self.fallback_model);
                 // Try fallback model
-                match self.query_model(&self.fallback_model, &prompt).await {
-                    Ok(response) => Ok(response.trim().to_string()),
+                match self.query_model(&self.fallback_model, &prompt, None).await {
+                    Ok(response) => Ok(Generated::new(&response, Source::Fallback(self.fallback_model.clone()))),
                     Err(_) => {
                         // Use pattern-based fallback
-                        Ok(self.generate_fallback_plan(input))
+                        Ok(Generated::new(&self.generate_fallback_plan(input), Source::Pattern))
                     }
                 }
             }
         }
     }
 
-    async fn query_model(&self, model: &str, prompt: &str) -> Result<String> {
+    async fn query_model(&self, model: &str, prompt: &str, on_token: Option<TokenSink<'_>>) -> Result<String> {
         let request = OllamaRequest {
             model: model.to_string(),
             prompt: prompt.to_string(),
             stream: false,
+            options: self.temperature.map(|temperature| GenerateOptions { temperature }),
         };
-
-        let response = self
-            .client
-            .post(&format!("{}/api/generate", self.ollama_host))
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!("Ollama API error: {}", response.status()));
-        }
-
-        let ollama_response: OllamaResponse = response.json().await?;
-        Ok(ollama_response.response)
+        generate(&self.client, &self.ollama_host, self.retry, request, on_token).await
     }
 
     fn generate_fallback_plan(&self, input: &str) -> String {
@@ -132,6 +283,8 @@ pub struct CoderAgent {
     ollama_host: String,
     model: String,
     fallback_model: String,
+    retry: RetryPolicy,
+    temperature: Option<f32>,
 }
 
 impl CoderAgent {
@@ -141,11 +294,28 @@ impl CoderAgent {
             ollama_host,
             model,
             fallback_model,
+            retry: RetryPolicy::default(),
+            temperature: None,
+        }
+    }
+
+    /// Retry model requests that fail with a 429, 5xx or timeout under `retry`
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Use the `--model`/`--temp` overrides for this run; the fallback model stays as configured
+    pub fn with_overrides(mut self, overrides: &ModelOverrides) -> Self {
+        if let Some(model) = &overrides.model {
+            self.model = model.clone();
         }
+        self.temperature = overrides.temperature.or(self.temperature);
+        self
     }
 
-    /// Generate shell commands from a structured plan
-    pub async fn generate_command(&self, plan: &str) -> Result<String> {
+    /// Generate shell commands from a structured plan, streaming into `on_token` like `generate_plan`
+    pub async fn generate_command(&self, plan: &str, on_token: Option<TokenSink<'_>>) -> Result<Generated> {
         let system_prompt = r#"You are a coding agent that converts structured plans into precise shell commands.
 
 Your role:
@@ -176,42 +346,56 @@ Command: mysqldump -u root -p mydb > backup.sql && gzip backup.sql && mv backup.
         let prompt = format!("{}\n\nPlan: {}\nCommand:", system_prompt, plan);
 
         // Try primary model first
-        match self.query_model(&self.model, &prompt).await {
-            Ok(response) => Ok(response.trim().to_string()),
+        match self.query_model(&self.model, &prompt, on_token).await {
+            Ok(response) => Ok(Generated::new(&response, Source::Primary(self.model.clone()))),
             Err(_) => {
                 warn!("Primary model {} failed, trying fallback {}", self.model, self.fallback_model);
                 // Try fallback model
-                match self.query_model(&self.fallback_model, &prompt).await {
-                    Ok(response) => Ok(response.trim().to_string()),
+                match self.query_model(&self.fallback_model, &prompt, None).await {
+                    Ok(response) => Ok(Generated::new(&response, Source::Fallback(self.fallback_model.clone()))),
                     Err(_) => {
                         // Use pattern-based fallback
-                        Ok(self.generate_fallback_command(plan))
+                        Ok(Generated::new(&self.generate_fallback_command(plan), Source::Pattern))
                     }
                 }
             }
         }
     }
 
-    async fn query_model(&self, model: &str, prompt: &str) -> Result<String> {
+    async fn query_model(&self, model: &str, prompt: &str, on_token: Option<TokenSink<'_>>) -> Result<String> {
         let request = OllamaRequest {
             model: model.to_string(),
             prompt: prompt.to_string(),
             stream: false,
+            options: self.temperature.map(|temperature| GenerateOptions { temperature }),
         };
+        generate(&self.client, &self.ollama_host, self.retry, request, on_token).await
+    }
 
-        let response = self
-            .client
-            .post(&format!("{}/api/generate", self.ollama_host))
-            .json(&request)
-            .send()
-            .await?;
+    /// Ask for a corrected version of a command that failed, given what it printed to stderr
+    pub async fn fix_command(&self, command: &str, stderr: &str, exit_code: i32) -> Result<String> {
+        let prompt = format!(
+            r#"You are a coding agent fixing a shell command that failed.
 
-        if !response.status().is_success() {
-            return Err(anyhow!("Ollama API error: {}", response.status()));
-        }
+Command: {}
+Exit code: {}
+Error output:
+{}
 
-        let ollama_response: OllamaResponse = response.json().await?;
-        Ok(ollama_response.response)
+Reply with ONLY the corrected command, no explanations. If the command cannot be fixed, reply with the same command."#,
+            command,
+            exit_code,
+            stderr.trim()
+        );
+
+        match self.query_model(&self.model, &prompt, None).await {
+            Ok(response) => Ok(response.trim().to_string()),
+            Err(_) => {
+                warn!("Primary model {} failed, trying fallback {}", self.model, self.fallback_model);
+                // No pattern-based fallback here; guessing a fix is worse than stopping
+                Ok(self.query_model(&self.fallback_model, &prompt, None).await?.trim().to_string())
+            }
+        }
     }
 
     fn generate_fallback_command(&self, plan: &str) -> String {
@@ -236,3 +420,33 @@ Command: mysqldump -u root -p mydb > backup.sql && gzip backup.sql && mv backup.
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streamed_chunks_assemble_into_the_full_reply() {
+        let lines = concat!(
+            "{\"response\":\"List \",\"done\":false}\n",
+            "{\"response\":\"the files \",\"done\":false}\n",
+            "{\"response\":\"in ~/café\",\"done\":false}\n",
+            "{\"response\":\"\",\"done\":true}",
+        );
+        let mut tokens = Vec::new();
+        let mut on_token = |token: &str| tokens.push(token.to_string());
+        let mut assembler = StreamAssembler::default();
+        // Network chunks split lines, and even characters, at arbitrary points
+        for chunk in lines.as_bytes().chunks(7) {
+            assembler.push(chunk, &mut on_token).unwrap();
+        }
+        let text = assembler.finish(&mut on_token).unwrap();
+
+        assert_eq!(tokens, ["List ", "the files ", "in ~/café"]);
+        assert_eq!(text, tokens.concat());
+
+        let mut assembler = StreamAssembler::default();
+        let error = assembler.push(b"{\"error\":\"model not found\"}\n", &mut |_| {}).unwrap_err();
+        assert!(error.to_string().contains("model not found"));
+    }
+}
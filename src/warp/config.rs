@@ -1,17 +1,21 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use tokio::fs;
+use tracing::warn;
 
 /// Configuration for the Warp pipeline loaded from .agentic.toml
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AgenticConfig {
     pub warp: WarpConfig,
 }
 
 /// Warp-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WarpConfig {
     pub models: ModelConfig,
     pub execution: ExecutionConfig,
@@ -20,28 +24,49 @@ pub struct WarpConfig {
 
 /// Model configuration for different agents
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModelConfig {
     pub planner: String,
     pub coder: String,
     pub fallback: String,
     pub ollama_host: String,
     pub timeout_seconds: u64,
+    /// How many times a model request is resent after a 429, 5xx or timeout
+    pub max_retries: u32,
 }
 
 /// Execution configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ExecutionConfig {
     pub streaming: bool,
+    /// Run suggested commands without the y/N prompt, like `warp --yes`
     pub auto_confirm: bool,
+    /// How many times a failing command is sent back to the coder for a corrected version
+    pub max_retries: u32,
     pub max_execution_time: u64,
     pub working_directory: Option<String>,
+    /// A `.env` file whose variables commands get, e.g. `.env`; relative paths are resolved
+    /// against the current directory and a missing file is skipped
+    pub env_file: Option<String>,
+    /// Shell commands run in: `sh`, `bash`, `zsh` or `fish`, or on Windows `pwsh`, `powershell`
+    /// or `cmd`; bash (PowerShell on Windows) when unset
+    pub shell: Option<String>,
+    /// Variables set for commands, overriding `env_file` and the inherited environment
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
 }
 
 /// Safety configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SafetyConfig {
     pub enable_safety_checks: bool,
+    /// Commands to double-check, matched word by word (`*` is a wildcard); see
+    /// `shell_runner::dangerous_pattern`
     pub dangerous_commands: Vec<String>,
+    /// Offer to run a command matching `dangerous_commands` once it is confirmed and typed
+    /// again; when off such commands are refused
     pub require_confirmation: bool,
     pub allowed_directories: Vec<String>,
 }
@@ -72,6 +97,7 @@ impl Default for ModelConfig {
             fallback: "gemma3".to_string(),
             ollama_host: "http://localhost:11434".to_string(),
             timeout_seconds: 30,
+            max_retries: crate::retry::DEFAULT_MAX_RETRIES,
         }
     }
 }
@@ -81,8 +107,12 @@ impl Default for ExecutionConfig {
         Self {
             streaming: true,
             auto_confirm: false,
+            max_retries: 2,
             max_execution_time: 300, // 5 minutes
             working_directory: None,
+            env_file: None,
+            shell: None,
+            env: HashMap::new(),
         }
     }
 }
@@ -99,7 +129,8 @@ impl Default for SafetyConfig {
                 "reboot".to_string(),
                 "dd if=".to_string(),
                 "mkfs.".to_string(),
-                "> /dev/".to_string(),
+                "> /dev/sd*".to_string(),
+                "> /dev/nvme*".to_string(),
                 "chmod 777 /".to_string(),
                 "chown root /".to_string(),
             ],
@@ -114,6 +145,108 @@ impl Default for SafetyConfig {
     }
 }
 
+/// Expand a leading `~` and make `dir` absolute against the current directory, resolving
+/// `.` and `..` without touching the filesystem
+pub fn resolve_dir(dir: &str) -> PathBuf {
+    let expanded = match dir.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            home_dir().unwrap_or_default().join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(dir),
+    };
+    let absolute = if expanded.is_absolute() {
+        expanded
+    } else {
+        std::env::current_dir().unwrap_or_default().join(expanded)
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// The `KEY=VALUE` lines of a `.env` file. Blank lines and `#` comments are skipped, `export`
+/// in front is allowed and quotes around a value are dropped; values are taken literally, so
+/// `$OTHER` isn't expanded.
+pub fn parse_dotenv(text: &str) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Line {} is not KEY=VALUE: {}", n + 1, line))?;
+        let key = key.trim();
+        let valid_key = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            anyhow::bail!("Line {} has an invalid variable name '{}'", n + 1, key);
+        }
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) if value.len() > 1 && value.ends_with(quote) => &value[1..value.len() - 1],
+            // An unquoted value ends at a ` #` comment
+            _ => value.split(" #").next().unwrap_or_default().trim_end(),
+        };
+        vars.push((key.to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+/// `path` with its symlinks followed, so that a link inside an allowed directory can't lead out
+/// of it; the part that doesn't exist yet is kept as it is
+fn resolve_links(path: &Path) -> PathBuf {
+    for ancestor in path.ancestors() {
+        if let Ok(real) = ancestor.canonicalize() {
+            return real.join(path.strip_prefix(ancestor).unwrap_or(Path::new("")));
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Saved `dangerous_commands` entries that match nothing as whole words, and the patterns that
+/// protect what they were meant to
+const LEGACY_PATTERNS: &[(&str, &[&str])] = &[("> /dev/", &["> /dev/sd*", "> /dev/nvme*"])];
+
+impl SafetyConfig {
+    /// Whether `dir` (already resolved) is inside one of `allowed_directories` once symlinks are
+    /// followed; an empty list allows everything
+    pub fn is_directory_allowed(&self, dir: &Path) -> bool {
+        let dir = resolve_links(dir);
+        self.allowed_directories.is_empty()
+            || self.allowed_directories.iter().any(|allowed| {
+                allowed.contains('*') // Wildcard support
+                    || dir.starts_with(resolve_links(&resolve_dir(allowed)))
+            })
+    }
+
+    /// The patterns to flag; none when safety checks are disabled. Patterns from before they
+    /// were matched word by word are replaced with what they used to catch.
+    pub fn active_patterns(&self) -> Vec<String> {
+        if !self.enable_safety_checks {
+            return Vec::new();
+        }
+        let mut patterns = Vec::new();
+        for pattern in &self.dangerous_commands {
+            match LEGACY_PATTERNS.iter().find(|(legacy, _)| legacy == pattern) {
+                Some((_, replacements)) => patterns.extend(replacements.iter().map(|r| r.to_string())),
+                None => patterns.push(pattern.clone()),
+            }
+        }
+        patterns
+    }
+}
+
 impl AgenticConfig {
     /// Load configuration from .agentic.toml file
     pub async fn load() -> Result<Self> {
@@ -121,7 +254,8 @@ impl AgenticConfig {
         
         if config_path.exists() {
             let content = fs::read_to_string(&config_path).await?;
-            let config: AgenticConfig = toml::from_str(&content)?;
+            let config: AgenticConfig = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", config_path.display()))?;
             Ok(config)
         } else {
             let config = Self::default();
@@ -156,46 +290,43 @@ impl AgenticConfig {
         home.join(".agentic").join("agentic.toml")
     }
 
-    /// Check if a command is dangerous based on configuration
-    pub fn is_dangerous_command(&self, command: &str) -> bool {
-        if !self.warp.safety.enable_safety_checks {
-            return false;
-        }
-
-        let command_lower = command.to_lowercase();
-        self.warp.safety.dangerous_commands
-            .iter()
-            .any(|pattern| command_lower.contains(&pattern.to_lowercase()))
-    }
-
-    /// Check if execution in a directory is allowed
-    pub fn is_directory_allowed(&self, directory: &str) -> bool {
-        if self.warp.safety.allowed_directories.is_empty() {
-            return true; // No restrictions
-        }
-
-        let dir_path = PathBuf::from(directory);
-        
-        self.warp.safety.allowed_directories
-            .iter()
-            .any(|allowed| {
-                let allowed_path = PathBuf::from(allowed);
-                dir_path.starts_with(&allowed_path) || 
-                allowed.contains("*") || // Wildcard support
-                directory.starts_with(allowed)
-            })
-    }
-
     /// Get the working directory for command execution
     pub fn get_working_directory(&self) -> Option<PathBuf> {
         self.warp.execution.working_directory
-            .as_ref()
-            .map(|dir| PathBuf::from(dir))
+            .as_deref()
+            .map(resolve_dir)
+    }
+
+    /// Variables to set for commands: those in `env_file`, overridden by the `env` table. The
+    /// inherited environment fills in everything else.
+    pub fn command_env(&self) -> Result<HashMap<String, String>> {
+        let execution = &self.warp.execution;
+        let mut env = HashMap::new();
+        if let Some(file) = &execution.env_file {
+            let path = resolve_dir(file);
+            match std::fs::read_to_string(&path) {
+                Ok(text) => env.extend(parse_dotenv(&text).with_context(|| format!("Failed to read {}", path.display()))?),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+            }
+        }
+        env.extend(execution.env.iter().map(|(key, value)| (key.clone(), value.clone())));
+        Ok(env)
     }
 
-    /// Check if auto-confirmation is enabled
-    pub fn auto_confirm_enabled(&self) -> bool {
-        self.warp.execution.auto_confirm
+    /// The configured `shell`, if any, with a warning when it can't be found; commands would then
+    /// fail to start, so the warning says why ahead of time
+    pub fn checked_shell(&self) -> Option<String> {
+        let shell = self.warp.execution.shell.as_deref().map(str::trim).filter(|shell| !shell.is_empty())?;
+        let found = if shell.contains(['/', '\\']) {
+            Path::new(shell).is_file()
+        } else {
+            crate::ui::routing::is_on_path(shell)
+        };
+        if !found {
+            warn!("warp.execution.shell '{}' isn't on PATH; commands will fail to start until it is installed or the setting changed", shell);
+        }
+        Some(shell.to_string())
     }
 }
 
@@ -229,17 +360,28 @@ streaming = true
 # Auto-confirm command execution (dangerous! only for trusted environments)
 auto_confirm = false
 
-# Maximum execution time in seconds
+# Seconds a command may run before it is killed (0 = no limit)
 max_execution_time = 300
 
 # Working directory for command execution (optional)
 # working_directory = "/path/to/project"
 
+# Shell commands run in: sh, bash, zsh, fish, or pwsh, powershell, cmd on Windows
+# (optional; bash, or powershell on Windows, when unset)
+# shell = "zsh"
+
+# Variables for commands: [warp.execution.env] overrides env_file, which overrides the
+# inherited environment (optional)
+# env_file = ".env"
+# [warp.execution.env]
+# NODE_ENV = "development"
+
 [warp.safety]
 # Enable safety checks for dangerous commands
 enable_safety_checks = true
 
-# Require confirmation before executing commands
+# Offer to run dangerous commands after a double confirmation (type the command again)
+# instead of refusing them
 require_confirmation = true
 
 # Additional dangerous command patterns to block
@@ -283,22 +425,104 @@ mod tests {
         assert!(!config.warp.execution.auto_confirm);
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_config_env_overrides_dotenv() {
+        use crate::warp::shell_runner::{ExecutionResult, ShellRunner};
+
+        let vars = parse_dotenv("# db\nexport DATABASE_URL=\"postgres://localhost/app\"\n\nNODE_ENV=test # for CI\nEMPTY=\nQUOTED='a # b'\n").unwrap();
+        assert_eq!(vars, [
+            ("DATABASE_URL".to_string(), "postgres://localhost/app".to_string()),
+            ("NODE_ENV".to_string(), "test".to_string()),
+            ("EMPTY".to_string(), String::new()),
+            ("QUOTED".to_string(), "a # b".to_string()),
+        ]);
+        assert!(parse_dotenv("just words").unwrap_err().to_string().contains("Line 1"));
+        assert!(parse_dotenv("1BAD=x").is_err());
+
+        let dir = std::env::temp_dir().join(format!("agentic-env-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".env"), "NODE_ENV=development\nFROM_DOTENV=yes\n").unwrap();
+        let toml = format!(
+            "[warp.execution]\nenv_file = \"{}\"\n\n[warp.execution.env]\nNODE_ENV = \"production\"\n",
+            dir.join(".env").display()
+        );
+        let config: AgenticConfig = toml::from_str(&toml).unwrap();
+        let env = config.command_env().unwrap();
+        assert_eq!(env["NODE_ENV"], "production", "the config table wins over .env");
+        assert_eq!(env["FROM_DOTENV"], "yes");
+
+        let runner = ShellRunner::new(false).with_env(env);
+        let result = runner.execute("echo $NODE_ENV $FROM_DOTENV").await.unwrap();
+        assert!(
+            matches!(&result, ExecutionResult::Success { stdout, .. } if stdout.trim() == "production yes"),
+            "{:?}", result
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(config.command_env().unwrap().contains_key("NODE_ENV"), "a missing env_file is skipped");
+    }
+
+    #[test]
+    fn test_partial_file_keeps_other_defaults() {
+        let config: AgenticConfig = toml::from_str("[warp.models]\nplanner = \"llama3.2\"\n").unwrap();
+
+        assert_eq!(config.warp.models.planner, "llama3.2");
+        assert_eq!(config.warp.models.coder, "codellama");
+        assert!(config.warp.execution.streaming);
+    }
+
     #[test]
     fn test_dangerous_command_detection() {
-        let config = AgenticConfig::default();
+        let patterns = AgenticConfig::default().warp.safety.active_patterns();
+        let is_dangerous = |command: &str| crate::warp::shell_runner::dangerous_pattern(command, &patterns).is_some();
         
-        assert!(config.is_dangerous_command("rm -rf /"));
-        assert!(config.is_dangerous_command("shutdown now"));
-        assert!(!config.is_dangerous_command("ls -la"));
-        assert!(!config.is_dangerous_command("npm install"));
+        assert!(is_dangerous("rm -rf /"));
+        assert!(is_dangerous("shutdown now"));
+        assert!(!is_dangerous("ls -la"));
+        assert!(!is_dangerous("npm install"));
+    }
+
+    #[test]
+    fn test_a_saved_dev_redirect_pattern_still_protects_disks() {
+        let config: AgenticConfig = toml::from_str("[warp.safety]\ndangerous_commands = [\"> /dev/\", \"shutdown\"]\n").unwrap();
+        let patterns = config.warp.safety.active_patterns();
+        let is_dangerous = |command: &str| crate::warp::shell_runner::dangerous_pattern(command, &patterns).is_some();
+
+        assert!(is_dangerous("cat image.iso > /dev/sda"));
+        assert!(is_dangerous("echo 0 >/dev/nvme0n1"));
+        assert!(is_dangerous("shutdown now"));
+        assert!(!is_dangerous("make 2> /dev/null"));
     }
 
     #[test]
     fn test_directory_allowlist() {
-        let config = AgenticConfig::default();
+        let safety = AgenticConfig::default().warp.safety;
+        let is_allowed = |dir: &str| safety.is_directory_allowed(&resolve_dir(dir));
         
-        assert!(config.is_directory_allowed("~/projects"));
-        assert!(config.is_directory_allowed("./src"));
-        assert!(config.is_directory_allowed("/tmp/test"));
+        assert!(is_allowed("~/projects"));
+        assert!(is_allowed("./src"));
+        assert!(is_allowed("/tmp/test"));
+        assert!(!is_allowed("/etc"));
+        assert!(!is_allowed("./../../../../../../../../etc"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_a_symlink_cant_lead_out_of_an_allowed_directory() {
+        let root = std::env::temp_dir().join(format!("agentic-allowed-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("allowed/real")).unwrap();
+        std::os::unix::fs::symlink("/etc", root.join("allowed/escape")).unwrap();
+        let safety = SafetyConfig {
+            allowed_directories: vec![root.join("allowed").display().to_string()],
+            ..SafetyConfig::default()
+        };
+
+        let allowed = safety.is_directory_allowed(&root.join("allowed/real"));
+        let not_yet_created = safety.is_directory_allowed(&root.join("allowed/real/new"));
+        let escaped = safety.is_directory_allowed(&root.join("allowed/escape"));
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(allowed && not_yet_created);
+        assert!(!escaped, "allowed/escape is /etc");
     }
 }
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use colored::*;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::io::Write;
+
+use crate::ui::components::{InputAction, InputBar};
+
+const PROMPT: &str = "✏️  ";
+/// Terminal columns the prompt takes up; the emoji is two wide
+const PROMPT_WIDTH: usize = 4;
+
+/// Feed one key to the editor; `Some` once editing is over, holding `None` if it was abandoned
+pub fn edit_step(input: &mut InputBar, key: KeyEvent) -> Option<Option<String>> {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Some(None);
+    }
+    match input.handle_key(key) {
+        InputAction::Submit => Some(Some(input.content.clone())),
+        InputAction::Cancel => Some(None),
+        InputAction::Edited => None,
+    }
+}
+
+/// Let the user change `command` before it runs. An empty or abandoned edit keeps the original.
+pub async fn edit_command(command: &str) -> String {
+    use std::io::IsTerminal;
+    let edited = if std::io::stdin().is_terminal() {
+        let initial = command.to_string();
+        // Blocks on terminal events, so keep it off the runtime's threads
+        tokio::task::spawn_blocking(move || edit_line(&initial)).await.ok().and_then(Result::ok).flatten()
+    } else {
+        // Nothing to prefill on a pipe; the next line replaces the command
+        println!("{} Enter the new command: ", "✏️".yellow());
+        super::read_answer().await
+    };
+    edited_or_original(edited, command)
+}
+
+fn edited_or_original(edited: Option<String>, original: &str) -> String {
+    match edited.as_deref().map(str::trim) {
+        Some(edited) if !edited.is_empty() => edited.to_string(),
+        _ => original.to_string(),
+    }
+}
+
+/// Edit `initial` in place on the terminal: Enter accepts, Esc or Ctrl+C abandons
+fn edit_line(initial: &str) -> Result<Option<String>> {
+    let mut input = InputBar::new();
+    input.content = initial.to_string();
+    input.cursor_position = initial.len();
+
+    crossterm::terminal::enable_raw_mode()?;
+    let result = (|| loop {
+        redraw(&input)?;
+        if let Event::Key(key) = crossterm::event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(done) = edit_step(&mut input, key) {
+                return Ok(done);
+            }
+        }
+    })();
+    crossterm::terminal::disable_raw_mode()?;
+    println!();
+    result
+}
+
+fn redraw(input: &InputBar) -> Result<()> {
+    use crossterm::{cursor, queue, terminal};
+    let mut stdout = std::io::stdout();
+    let column = PROMPT_WIDTH + input.cursor_line_col().1;
+    queue!(stdout, cursor::MoveToColumn(0), terminal::Clear(terminal::ClearType::CurrentLine))?;
+    write!(stdout, "{}{}", PROMPT, input.content)?;
+    queue!(stdout, cursor::MoveToColumn(column as u16))?;
+    stdout.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editing_replaces_the_command_and_empty_keeps_it() {
+        let mut input = InputBar::new();
+        input.content = "ls -la".to_string();
+        input.cursor_position = input.content.len();
+
+        assert_eq!(edit_step(&mut input, KeyCode::Backspace.into()), None);
+        assert_eq!(edit_step(&mut input, KeyCode::Char('h').into()), None);
+        let edited = edit_step(&mut input, KeyCode::Enter.into()).unwrap();
+        assert_eq!(edited.as_deref(), Some("ls -lh"));
+        assert_eq!(edited_or_original(edited, "ls -la"), "ls -lh");
+
+        assert_eq!(edit_step(&mut input, KeyCode::Esc.into()), Some(None));
+        assert_eq!(edited_or_original(None, "ls -la"), "ls -la");
+        assert_eq!(edited_or_original(Some("  ".to_string()), "ls -la"), "ls -la");
+    }
+}
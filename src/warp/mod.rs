@@ -1,15 +1,18 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use colored::*;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 pub mod agents;
 pub mod config;
+pub mod line_editor;
 pub mod pipeline;
 pub mod shell_runner;
 
 use crate::config::Config;
+use crate::retry::RetryPolicy;
 
 /// Core Warp pipeline that orchestrates the three-agent system
 #[derive(Debug, Clone)]
@@ -18,6 +21,13 @@ pub struct WarpPipeline {
     coder: agents::CoderAgent,
     shell_runner: shell_runner::ShellRunner,
     config: WarpConfig,
+    safety: config::SafetyConfig,
+    /// Run suggested commands without the y/N prompt; dangerous ones are still confirmed twice
+    auto_confirm: bool,
+    /// Directory commands run in, from `working_directory` or `--cwd`
+    working_dir: Option<PathBuf>,
+    /// Where `execute` adds each run to the `PipelineStats`; none keeps no stats
+    stats_path: Option<PathBuf>,
 }
 
 /// Configuration for the Warp pipeline
@@ -29,6 +39,12 @@ pub struct WarpConfig {
     pub ollama_host: String,
     pub timeout_seconds: u64,
     pub streaming: bool,
+    /// How many times a failing command is sent back to the coder for a fix
+    pub max_retries: u32,
+    /// How many times a model request is resent after a 429, 5xx or timeout
+    pub model_retries: u32,
+    /// Seconds a captured command may run before it is killed; 0 for no limit
+    pub max_execution_time: u64,
 }
 
 impl Default for WarpConfig {
@@ -40,14 +56,213 @@ impl Default for WarpConfig {
             ollama_host: "http://localhost:11434".to_string(),
             timeout_seconds: 30,
             streaming: true,
+            max_retries: 2,
+            model_retries: crate::retry::DEFAULT_MAX_RETRIES,
+            max_execution_time: 300,
         }
     }
 }
 
+impl From<&config::AgenticConfig> for WarpConfig {
+    fn from(agentic: &config::AgenticConfig) -> Self {
+        let models = &agentic.warp.models;
+        Self {
+            planner_model: models.planner.clone(),
+            coder_model: models.coder.clone(),
+            fallback_model: models.fallback.clone(),
+            ollama_host: models.ollama_host.clone(),
+            timeout_seconds: models.timeout_seconds,
+            streaming: agentic.warp.execution.streaming,
+            max_retries: agentic.warp.execution.max_retries,
+            model_retries: models.max_retries,
+            max_execution_time: agentic.warp.execution.max_execution_time,
+        }
+    }
+}
+
+impl WarpConfig {
+    /// How long a captured command may run, or `None` when `max_execution_time` is 0
+    pub fn execution_timeout(&self) -> Option<Duration> {
+        (self.max_execution_time > 0).then(|| Duration::from_secs(self.max_execution_time))
+    }
+
+    /// Load the pipeline settings from `.agentic.toml`
+    pub async fn load() -> Result<Self> {
+        Ok(Self::from(&config::AgenticConfig::load().await?))
+    }
+}
+
+/// How usable a request is before any model sees it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RequestCheck {
+    /// Enough to plan from
+    Clear,
+    /// A single word like `disk`: could mean many things, so ask what's wanted
+    Unclear,
+    /// Empty or only punctuation, like `?`
+    Empty,
+}
+
+const REQUEST_EXAMPLE: &str = "e.g. `agentic warp \"show disk usage of this folder\"`";
+
+/// Judge a natural language request; near-empty input would only get a made-up plan
+pub fn check_request(input: &str) -> RequestCheck {
+    let words: Vec<&str> = input
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .collect();
+    let letters: usize = words.iter().map(|word| word.chars().filter(|c| c.is_alphanumeric()).count()).sum();
+    match words.len() {
+        0 => RequestCheck::Empty,
+        1 => RequestCheck::Unclear,
+        _ if letters < 3 => RequestCheck::Unclear,
+        _ => RequestCheck::Clear,
+    }
+}
+
+/// How long to wait for an answer on a stdin that isn't a terminal, e.g. in CI, before cancelling
+const NON_INTERACTIVE_ANSWER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read a line from stdin; `None` on EOF, or when a non-terminal stdin stays silent
+pub async fn read_answer() -> Option<String> {
+    use std::io::IsTerminal;
+    let timeout = (!std::io::stdin().is_terminal()).then_some(NON_INTERACTIVE_ANSWER_TIMEOUT);
+    let answer = shell_runner::exit_on_ctrl_c(answer_within(|| {
+        let mut input_line = String::new();
+        match std::io::stdin().read_line(&mut input_line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(input_line),
+        }
+    }, timeout)).await;
+    if answer.is_none() {
+        println!("{} No answer on stdin, cancelling", "⏱".yellow());
+    }
+    answer
+}
+
+/// Run the blocking `read` on its own thread, giving up after `timeout`. A plain thread rather
+/// than `spawn_blocking` so an abandoned read doesn't hold up runtime shutdown.
+async fn answer_within(read: impl FnOnce() -> Option<String> + Send + 'static, timeout: Option<Duration>) -> Option<String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(read());
+    });
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, rx).await.ok()?.ok().flatten(),
+        None => rx.await.ok().flatten(),
+    }
+}
+
+/// Only the exact word `YES` overrides a blocked command
+pub fn override_confirmed(answer: &str) -> bool {
+    answer.trim() == "YES"
+}
+
+/// Whether `answer` repeats `command` exactly, the second confirmation of a dangerous command
+pub fn retyped(answer: &str, command: &str) -> bool {
+    answer.trim() == command.trim()
+}
+
+/// Prints a labelled model reply piece by piece as it streams in
+struct LivePrinter {
+    label: String,
+    color: Color,
+    /// Everything printed so far
+    streamed: String,
+}
+
+impl LivePrinter {
+    fn new(label: String, color: Color) -> Self {
+        Self { label, color, streamed: String::new() }
+    }
+
+    fn print(&mut self, token: &str) {
+        use std::io::Write;
+        if self.streamed.is_empty() {
+            print!("{}", self.label);
+        }
+        self.streamed.push_str(token);
+        print!("{}", token.color(self.color));
+        let _ = std::io::stdout().flush();
+    }
+
+    /// Whether the final `text` still has to be printed: nothing was streamed (streaming off),
+    /// or the stream broke off and a fallback model or pattern answered instead
+    fn needs_reprint(&self, text: &str) -> bool {
+        self.streamed.trim() != text.trim()
+    }
+
+    /// End the streamed line, printing `text` under the label again when the stream didn't show it
+    fn finish(&self, text: &str) {
+        if !self.streamed.is_empty() {
+            println!();
+        }
+        if self.needs_reprint(text) {
+            println!("{}{}", self.label, text.color(self.color));
+        }
+    }
+}
+
+/// An answer to the confirmation prompt
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Choice {
+    Run,
+    Edit,
+    Cancel,
+}
+
+/// Read a y/N/e prompt answer
+fn parse_choice(answer: &str) -> Choice {
+    let answer = answer.trim();
+    if answer.eq_ignore_ascii_case("e") || answer.eq_ignore_ascii_case("edit") {
+        Choice::Edit
+    } else if answer.to_lowercase().starts_with('y') {
+        Choice::Run
+    } else {
+        Choice::Cancel
+    }
+}
+
+/// The double confirmation for a command matching the dangerous `pattern`: a loud warning, a
+/// y/N/e answer, then the command typed out again. `--yes` and `auto_confirm` skip neither.
+async fn confirm_dangerous(command: &str, pattern: &str) -> Choice {
+    println!(
+        "\n{} {} matches the dangerous pattern '{}' and may not be undoable:",
+        "🛑".red(), " DANGEROUS COMMAND ".white().bold().on_red(), pattern.red().bold()
+    );
+    println!("    {}", command.red().bold());
+    println!("{} Run it anyway? (y/N, e to edit): ", "❓".red());
+    let choice = read_answer().await.map(|answer| parse_choice(&answer)).unwrap_or(Choice::Cancel);
+    if choice != Choice::Run {
+        return choice;
+    }
+    println!("{} Type the command again to confirm: ", "❓".red());
+    if read_answer().await.is_some_and(|answer| retyped(&answer, command)) {
+        Choice::Run
+    } else {
+        println!("{} That doesn't match the command; not running it", "⏹".yellow());
+        Choice::Cancel
+    }
+}
+
+/// Resolve `--stream`/`--no-stream` into an override of the configured streaming behavior
+pub fn streaming_override(stream: bool, no_stream: bool) -> Option<bool> {
+    match (stream, no_stream) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
 impl WarpPipeline {
-    /// Create a new Warp pipeline instance
-    pub fn new(_config: &Config) -> Result<Self> {
-        let warp_config = WarpConfig::default(); // TODO: Load from .agentic.toml
+    /// Create a new Warp pipeline instance configured from `.agentic.toml`
+    pub async fn new(_config: &Config) -> Result<Self> {
+        Ok(Self::from_agentic_config(&config::AgenticConfig::load().await?)?
+            .with_stats_file(Some(pipeline::PipelineStats::default_path())))
+    }
+
+    pub fn from_agentic_config(agentic: &config::AgenticConfig) -> Result<Self> {
+        let warp_config = WarpConfig::from(agentic);
         
         let client = Client::builder()
             .timeout(Duration::from_secs(warp_config.timeout_seconds))
@@ -58,62 +273,348 @@ impl WarpPipeline {
             warp_config.ollama_host.clone(),
             warp_config.planner_model.clone(),
             warp_config.fallback_model.clone(),
-        );
+        ).with_retry_policy(RetryPolicy::new(warp_config.model_retries));
 
         let coder = agents::CoderAgent::new(
             client.clone(),
             warp_config.ollama_host.clone(),
             warp_config.coder_model.clone(),
             warp_config.fallback_model.clone(),
-        );
+        ).with_retry_policy(RetryPolicy::new(warp_config.model_retries));
 
-        let shell_runner = shell_runner::ShellRunner::new(warp_config.streaming);
+        let shell_runner = shell_runner::ShellRunner::configured(warp_config.streaming, agentic)?;
 
         Ok(Self {
             planner,
             coder,
             shell_runner,
             config: warp_config,
+            safety: agentic.warp.safety.clone(),
+            auto_confirm: agentic.warp.execution.auto_confirm,
+            working_dir: agentic.get_working_directory(),
+            stats_path: None,
         })
     }
 
-    /// Execute the full pipeline: natural language -> plan -> command -> execution
+    /// Keep the run statistics `agentic warp stats` shows in `path`
+    pub fn with_stats_file(mut self, path: Option<PathBuf>) -> Self {
+        self.stats_path = path;
+        self
+    }
+
+    /// Override the configured streaming behavior for this invocation
+    pub fn with_streaming(mut self, streaming: Option<bool>) -> Self {
+        if let Some(streaming) = streaming {
+            self.config.streaming = streaming;
+            self.shell_runner = self.shell_runner.with_streaming(streaming);
+        }
+        self
+    }
+
+    /// Plan and write the command with `--model` at `--temp` for this invocation
+    pub fn with_model_overrides(mut self, overrides: &crate::agent::ModelOverrides) -> Self {
+        if let Some(model) = &overrides.model {
+            self.config.planner_model = model.clone();
+            self.config.coder_model = model.clone();
+        }
+        self.planner = self.planner.with_overrides(overrides);
+        self.coder = self.coder.with_overrides(overrides);
+        self
+    }
+
+    /// Skip the confirmation prompt for this invocation (`--yes`); `false` keeps the configured behavior
+    pub fn with_auto_confirm(mut self, yes: bool) -> Self {
+        self.auto_confirm |= yes;
+        self
+    }
+
+    /// Send the command's output to `sink` line by line as it runs, instead of the terminal
+    pub fn with_output_sink(mut self, sink: tokio::sync::mpsc::UnboundedSender<String>) -> Self {
+        self.shell_runner = self.shell_runner.with_output_sink(sink);
+        self
+    }
+
+    /// Run in `dir` for this invocation (`--cwd`) instead of the configured working directory
+    pub fn with_working_dir(mut self, dir: Option<&str>) -> Self {
+        if let Some(dir) = dir {
+            self.working_dir = Some(config::resolve_dir(dir));
+        }
+        self
+    }
+
+    /// The working directory, checked to exist and to be inside `allowed_directories`
+    fn checked_working_dir(&self) -> Result<Option<&Path>> {
+        let Some(dir) = self.working_dir.as_deref() else {
+            return Ok(None);
+        };
+        if !self.safety.is_directory_allowed(dir) {
+            return Err(anyhow!(
+                "Working directory {} is outside warp.safety.allowed_directories ({})",
+                dir.display(),
+                self.safety.allowed_directories.join(", ")
+            ));
+        }
+        if !dir.is_dir() {
+            return Err(anyhow!("Working directory {} does not exist", dir.display()));
+        }
+        Ok(Some(dir))
+    }
+
+    /// History record of a pipeline run, attributed to the coder model that wrote the command
+    pub fn record(&self, result: &pipeline::PipelineResult) -> crate::db::CommandExecution {
+        let mut execution = result.to_execution();
+        execution.provider = Some("ollama".to_string());
+        execution.model = Some(self.config.coder_model.clone());
+        execution
+    }
+    
+    /// Execute the full pipeline: natural language -> plan -> command -> execution.
+    /// A failing command is sent back to the coder for a fix, up to `max_retries` times.
+    /// The outcome is added to the run statistics when a stats file is set.
     pub async fn execute(&self, input: &str) -> Result<pipeline::PipelineResult> {
+        let result = self.run_pipeline(input).await?;
+        self.record_stats(&result);
+        Ok(result)
+    }
+
+    /// Run `command`, which the caller has already had confirmed, for the request and plan in
+    /// `suggestion`, printing and asking nothing, as the TUI does once its confirmation widget is
+    /// answered. Interactive commands are refused since they need the terminal, and a failure isn't
+    /// sent back for a correction as there would be nobody to confirm it. `cancel` stops the command.
+    pub async fn run_confirmed(
+        &self,
+        suggestion: &pipeline::DryRun,
+        command: &str,
+        cancel: impl std::future::Future<Output = ()>,
+    ) -> Result<pipeline::PipelineResult> {
+        let working_dir = self.checked_working_dir()?;
+        if self.shell_runner.execution_mode(command) == shell_runner::ExecutionMode::Interactive {
+            return Err(anyhow!("'{}' looks interactive and needs the terminal; run it with `agentic warp` outside the TUI", command));
+        }
+        let runner = self.shell_runner.clone().with_working_dir(working_dir.map(Path::to_path_buf));
+        let execution_result = runner.run_captured(command, cancel, self.config.execution_timeout()).await?;
+        let result = pipeline::PipelineResult {
+            original_input: suggestion.input.clone(),
+            plan: suggestion.plan.clone(),
+            command: command.to_string(),
+            execution_result: Some(execution_result),
+            cancelled: false,
+        };
+        self.record_stats(&result);
+        Ok(result)
+    }
+
+    /// Add `result` to the run statistics when a stats file is set
+    fn record_stats(&self, result: &pipeline::PipelineResult) {
+        if let Some(path) = &self.stats_path {
+            // Losing a stats update isn't worth failing a run that already happened
+            if let Err(e) = pipeline::PipelineStats::record(path, result) {
+                tracing::warn!("Couldn't update the warp stats: {:#}", e);
+            }
+        }
+    }
+
+    /// The dangerous pattern `command` matches, if any
+    pub fn dangerous_match(&self, command: &str) -> Option<&str> {
+        self.shell_runner.dangerous_match(command)
+    }
+
+    /// Whether a dangerous command may run once confirmed and typed again; when not, it is refused
+    pub fn allows_dangerous(&self) -> bool {
+        self.safety.require_confirmation
+    }
+
+    /// Whether ordinary commands run without asking (`--yes` or `auto_confirm`)
+    pub fn auto_confirms(&self) -> bool {
+        self.auto_confirm
+    }
+
+    async fn run_pipeline(&self, input: &str) -> Result<pipeline::PipelineResult> {
+        let input = &self.clarified(input).await?;
+        // Refuse a bad directory before spending time on the models
+        let working_dir = self.checked_working_dir()?;
+        
         println!("{} {}", "🧠".blue(), "Planning...".cyan());
         
         // Step 1: Planning Agent
-        let plan = self.planner.generate_plan(input).await?;
-        println!("{} {}: {}", "📝".green(), "Plan".green().bold(), plan.cyan());
+        let plan = self.plan(input).await?.text;
         
         println!("\n{} {}", "💻".blue(), "Translating to shell...".cyan());
         
         // Step 2: Coder Agent
-        let command = self.coder.generate_command(&plan).await?;
-        println!("{} {}: {}", "🔧".green(), "Suggested Command".green().bold(), command.yellow());
+        let mut command = self.suggest_command(&plan).await?.text;
         
-        // Ask for confirmation
-        println!("\n{} Execute this command? (y/N): ", "❓".yellow());
-        let mut input_line = String::new();
-        std::io::stdin().read_line(&mut input_line)?;
-        
-        if !input_line.trim().to_lowercase().starts_with('y') {
-            return Ok(pipeline::PipelineResult {
-                original_input: input.to_string(),
-                plan: plan.clone(),
-                command: command.clone(),
-                execution_result: None,
-                cancelled: true,
-            });
+        let finished = |command: String, execution_result: Option<shell_runner::ExecutionResult>| pipeline::PipelineResult {
+            original_input: input.to_string(),
+            plan: plan.clone(),
+            command,
+            cancelled: execution_result.is_none(),
+            execution_result,
+        };
+        let mut attempt = 1;
+        let mut previous = None;
+        loop {
+            let Some(confirmed) = self.confirm(&command).await else {
+                // Declining a correction leaves the last failed attempt as the outcome
+                return Ok(match previous {
+                    Some((command, result)) => finished(command, Some(result)),
+                    None => finished(command, None),
+                });
+            };
+            command = confirmed;
+            
+            // Step 3: Shell Runner
+            let (execution_result, captured) = self.run(&command, working_dir).await?;
+            Self::print_result(&execution_result);
+            
+            let shell_runner::ExecutionResult::Error { stderr, exit_code, .. } = &execution_result else {
+                return Ok(finished(command, Some(execution_result)));
+            };
+            // Interactive commands leave no stderr to learn from
+            if attempt > self.config.max_retries || !captured {
+                return Ok(finished(command, Some(execution_result)));
+            }
+            
+            println!(
+                "\n{} Attempt {} failed with exit code {}; asking for a correction (retry {} of {})...",
+                "🔁".yellow(), attempt, exit_code, attempt, self.config.max_retries
+            );
+            let fixed = match shell_runner::exit_on_ctrl_c(self.coder.fix_command(&command, stderr, *exit_code)).await {
+                Ok(fixed) if !fixed.is_empty() && fixed != command => fixed,
+                Ok(_) => {
+                    println!("{} The coder had no different command to suggest", "⚠️".yellow());
+                    return Ok(finished(command, Some(execution_result)));
+                }
+                Err(e) => {
+                    println!("{} Couldn't get a correction: {}", "⚠️".yellow(), e);
+                    return Ok(finished(command, Some(execution_result)));
+                }
+            };
+            attempt += 1;
+            println!("{} {} (attempt {}): {}", "🔧".green(), "Corrected Command".green().bold(), attempt, fixed.yellow());
+            previous = Some((std::mem::replace(&mut command, fixed), execution_result));
         }
+    }
+    
+    /// Check `input` before planning: refuse an empty one, and ask what a one-word request means.
+    /// With confirmation turned off there is nobody to ask, so an unclear request is refused too.
+    async fn clarified(&self, input: &str) -> Result<String> {
+        let input = input.trim();
+        match check_request(input) {
+            RequestCheck::Clear => return Ok(input.to_string()),
+            RequestCheck::Empty => {
+                return Err(anyhow!("Nothing to do: describe what you want in words, {}", REQUEST_EXAMPLE));
+            }
+            RequestCheck::Unclear if self.auto_confirm => {
+                return Err(anyhow!("'{}' is too vague to turn into a command; say what you want done, {}", input, REQUEST_EXAMPLE));
+            }
+            RequestCheck::Unclear => {}
+        }
+        println!("{} '{}' is too short to plan from. What would you like to do? (Enter to cancel): ", "🤔".yellow(), input);
+        let answer = read_answer().await.unwrap_or_default();
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Err(anyhow!("No clearer request given, nothing was run"));
+        }
+        if check_request(answer) != RequestCheck::Clear {
+            return Err(anyhow!("'{}' is still too vague to plan from, {}", answer, REQUEST_EXAMPLE));
+        }
+        Ok(answer.to_string())
+    }
 
+    /// Generate and show the plan, streaming it as it is written when output streaming is on
+    async fn plan(&self, input: &str) -> Result<agents::Generated> {
+        let label = format!("{} {}: ", "📝".green(), "Plan".green().bold());
+        let mut printer = LivePrinter::new(label, Color::Cyan);
+        let plan = if self.config.streaming {
+            self.planner.generate_plan(input, Some(&mut |token| printer.print(token))).await?
+        } else {
+            self.planner.generate_plan(input, None).await?
+        };
+        printer.finish(&plan.text);
+        Ok(plan)
+    }
+    
+    /// Generate and show the command for `plan`, streamed like the plan; the caller only asks
+    /// for confirmation once the whole command has arrived
+    async fn suggest_command(&self, plan: &str) -> Result<agents::Generated> {
+        let label = format!("{} {}: ", "🔧".green(), "Suggested Command".green().bold());
+        let mut printer = LivePrinter::new(label, Color::Yellow);
+        let command = if self.config.streaming {
+            self.coder.generate_command(plan, Some(&mut |token| printer.print(token))).await?
+        } else {
+            self.coder.generate_command(plan, None).await?
+        };
+        printer.finish(&command.text);
+        Ok(command)
+    }
+    
+    /// Ask before running `command`, returning the command to run (the user may edit it first) or
+    /// `None` to cancel. A dangerous command has to be confirmed and then typed again, or is refused
+    /// outright when `require_confirmation` is off; anything else needs a plain confirmation.
+    async fn confirm(&self, command: &str) -> Option<String> {
+        let mut command = command.to_string();
+        loop {
+            let choice = if let Some(pattern) = self.shell_runner.dangerous_match(&command) {
+                if !self.safety.require_confirmation {
+                    println!(
+                        "\n{} {} this command matches the dangerous pattern '{}' (warp.safety.require_confirmation is off)",
+                        "🛑".red(), "Refused:".red().bold(), pattern.red().bold()
+                    );
+                    return None;
+                }
+                confirm_dangerous(&command, pattern).await
+            } else if self.auto_confirm {
+                Choice::Run
+            } else {
+                println!("\n{} Execute this command? (y/N, e to edit): ", "❓".yellow());
+                read_answer().await.map(|answer| parse_choice(&answer)).unwrap_or(Choice::Cancel)
+            };
+            match choice {
+                Choice::Run => return Some(command),
+                Choice::Cancel => return None,
+                Choice::Edit => {
+                    // The edited command goes through the same checks before it runs
+                    command = line_editor::edit_command(&command).await;
+                    println!("{} {}: {}", "🔧".green(), "Command".green().bold(), command.yellow());
+                }
+            }
+        }
+    }
+    
+    /// Run `command`, also returning whether its output was captured (interactive commands aren't)
+    async fn run(&self, command: &str, working_dir: Option<&Path>) -> Result<(shell_runner::ExecutionResult, bool)> {
         println!("\n{} {}", "🚀".blue(), "Running Command...".cyan());
+        if let Some(dir) = working_dir {
+            println!("{} in {}", "📁".blue(), dir.display());
+        }
         
-        // Step 3: Shell Runner
-        let execution_result = self.shell_runner.execute(&command).await?;
-        
-        // Display results
-        match &execution_result {
-            shell_runner::ExecutionResult::Success { stdout, stderr, duration } => {
+        match self.shell_runner.execution_mode(command) {
+            shell_runner::ExecutionMode::Interactive => {
+                println!("{} This command looks interactive; running it attached to your terminal (output won't be captured).", "⚠️".yellow());
+                let result = self.shell_runner.clone()
+                    .with_working_dir(working_dir.map(Path::to_path_buf))
+                    .execute_interactive(command)
+                    .await?;
+                Ok((result, false))
+            }
+            shell_runner::ExecutionMode::Captured => {
+                let runner = match working_dir {
+                    Some(dir) => self.shell_runner.clone().with_working_dir(Some(dir.to_path_buf())),
+                    None => self.shell_runner.clone(),
+                };
+                let result = match self.config.execution_timeout() {
+                    Some(timeout) => runner.execute_with_timeout(command, timeout).await?,
+                    None => runner.execute(command).await?,
+                };
+                Ok((result, true))
+            }
+        }
+    }
+    
+    fn print_result(execution_result: &shell_runner::ExecutionResult) {
+        match execution_result {
+            shell_runner::ExecutionResult::Success { stdout, stderr, exit_code, duration } => {
                 println!("{} {}:", "✅".green(), "Output".green().bold());
                 if !stdout.is_empty() {
                     println!("{}", stdout);
@@ -122,34 +623,304 @@ impl WarpPipeline {
                     println!("{} {}:", "⚠️".yellow(), "Warnings".yellow());
                     println!("{}", stderr.yellow());
                 }
-                println!("\n{} Completed in {:.2}s", "⚡".green(), duration.as_secs_f64());
+                println!("\n{} Completed in {:.2}s (exit code: {})", "⚡".green(), duration.as_secs_f64(), exit_code);
             }
             shell_runner::ExecutionResult::Error { stderr, exit_code, duration } => {
                 println!("{} {} (exit code: {}):", "❌".red(), "Error".red().bold(), exit_code);
                 println!("{}", stderr.red());
                 println!("\n{} Failed after {:.2}s", "💥".red(), duration.as_secs_f64());
             }
+            shell_runner::ExecutionResult::Cancelled { duration } => {
+                println!("\n{} Interrupted after {:.2}s, command stopped", "⏹".yellow(), duration.as_secs_f64());
+            }
+            shell_runner::ExecutionResult::TimedOut { stdout, stderr, duration } => {
+                if !stdout.is_empty() {
+                    println!("{} {}:", "📄".yellow(), "Output before the timeout".yellow().bold());
+                    println!("{}", stdout);
+                }
+                if !stderr.is_empty() {
+                    println!("{}", stderr.yellow());
+                }
+                println!(
+                    "\n{} {} after {}s, command stopped (warp.execution.max_execution_time)",
+                    "⏱".red(), "Timed out".red().bold(), duration.as_secs()
+                );
+            }
         }
-
-        Ok(pipeline::PipelineResult {
-            original_input: input.to_string(),
-            plan,
-            command,
-            execution_result: Some(execution_result),
-            cancelled: false,
-        })
     }
 
     /// Execute only the planning and coding steps (no execution)
-    pub async fn dry_run(&self, input: &str) -> Result<(String, String)> {
+    pub async fn dry_run(&self, input: &str) -> Result<pipeline::DryRun> {
+        let input = &self.clarified(input).await?;
         println!("{} {} (dry run)", "🧠".blue(), "Planning...".cyan());
-        let plan = self.planner.generate_plan(input).await?;
-        println!("{} {}: {}", "📝".green(), "Plan".green().bold(), plan.cyan());
+        let plan = self.plan(input).await?;
         
         println!("\n{} {} (dry run)", "💻".blue(), "Translating to shell...".cyan());
-        let command = self.coder.generate_command(&plan).await?;
-        println!("{} {}: {}", "🔧".green(), "Suggested Command".green().bold(), command.yellow());
+        let command = self.suggest_command(&plan.text).await?;
+        if let Some(pattern) = self.shell_runner.dangerous_match(&command.text) {
+            println!("{} Matches the dangerous pattern '{}'; running it would need it typed again to confirm", "🛑".red(), pattern.red());
+        }
         
-        Ok((plan, command))
+        Ok(pipeline::DryRun::new(input, plan, command))
+    }
+
+    /// Like `dry_run` but silent, for `--dry-run --json`: nothing is printed or streamed, and an
+    /// unclear request is refused since stdout is for the JSON alone
+    pub async fn plan_only(&self, input: &str) -> Result<pipeline::DryRun> {
+        let input = input.trim();
+        match check_request(input) {
+            RequestCheck::Clear => {}
+            RequestCheck::Empty => return Err(anyhow!("Nothing to do: describe what you want in words, {}", REQUEST_EXAMPLE)),
+            RequestCheck::Unclear => {
+                return Err(anyhow!("'{}' is too vague to turn into a command; say what you want done, {}", input, REQUEST_EXAMPLE));
+            }
+        }
+        let plan = self.planner.generate_plan(input, None).await?;
+        let command = self.coder.generate_command(&plan.text, None).await?;
+        Ok(pipeline::DryRun::new(input, plan, command))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_http;
+
+    /// Answer one `/api/generate` request per response in order, returning the requests
+    async fn mock_ollama(responses: Vec<String>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        let replies = responses.into_iter()
+            .map(|response| (200, serde_json::json!({ "response": response, "done": true }).to_string()))
+            .collect();
+        mock_http::serve_in_order(replies).await
+    }
+
+    #[tokio::test]
+    async fn test_empty_request_is_rejected_before_any_model_call() {
+        let (host, server) = mock_ollama(vec!["ls".to_string()]).await;
+        let toml = format!("[warp.models]\nollama_host = \"{}\"\n\n[warp.execution]\nauto_confirm = true\n", host);
+        let agentic: config::AgenticConfig = toml::from_str(&toml).unwrap();
+        let pipeline = WarpPipeline::from_agentic_config(&agentic).unwrap();
+
+        for input in ["", "   ", "?", "disk"] {
+            let err = pipeline.execute(input).await.unwrap_err().to_string();
+            assert!(err.contains("agentic warp"), "{:?} should get guidance: {}", input, err);
+        }
+        assert!(pipeline.dry_run("?!").await.is_err());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), server).await.is_err(),
+            "no request should reach the models"
+        );
+
+        assert_eq!(check_request("?"), RequestCheck::Empty);
+        assert_eq!(check_request("disk"), RequestCheck::Unclear);
+        assert_eq!(check_request("a b"), RequestCheck::Unclear);
+        assert_eq!(check_request("list large files"), RequestCheck::Clear);
+    }
+
+    #[tokio::test]
+    async fn test_plan_only_reports_which_model_wrote_each_step() {
+        // The server answers the plan and then goes away, so both coder models fail
+        let (host, server) = mock_ollama(vec!["List the files in the current directory".to_string()]).await;
+        let toml = format!("[warp.models]\nollama_host = \"{}\"\nplanner = \"phi4\"\n", host);
+        let agentic: config::AgenticConfig = toml::from_str(&toml).unwrap();
+        let pipeline = WarpPipeline::from_agentic_config(&agentic).unwrap();
+
+        let dry_run = pipeline.plan_only("list the files here").await.unwrap();
+        server.await.unwrap();
+        assert_eq!(dry_run.plan, "List the files in the current directory");
+        assert_eq!(dry_run.model_used.plan.as_deref(), Some("phi4"));
+        assert_eq!(dry_run.model_used.command, None, "the patterns wrote the command");
+        assert!(dry_run.fallback_used);
+
+        let json = serde_json::to_value(&dry_run).unwrap();
+        assert_eq!(json["input"], "list the files here");
+        assert_eq!(json["command"], dry_run.command.as_str());
+        assert_eq!(json["model_used"]["plan"], "phi4");
+        assert!(pipeline.plan_only("disk").await.is_err(), "nobody can be asked what a vague request means");
+    }
+
+    #[test]
+    fn test_streamed_reply_is_reprinted_only_when_a_fallback_answered() {
+        let mut printer = LivePrinter::new(String::new(), Color::Cyan);
+        assert!(printer.needs_reprint("List files"), "nothing streamed");
+        printer.print(" List");
+        printer.print(" files\n");
+        assert!(!printer.needs_reprint("List files"), "the stream showed the trimmed reply");
+
+        let mut broken = LivePrinter::new(String::new(), Color::Cyan);
+        broken.print("List the fi");
+        assert!(broken.needs_reprint("List files in the current directory"));
+    }
+
+    #[test]
+    fn test_no_stream_selects_buffered_runner() {
+        let pipeline = WarpPipeline::from_agentic_config(&config::AgenticConfig::default()).unwrap();
+        assert!(pipeline.config.streaming, "default config streams output");
+
+        let pipeline = pipeline.with_streaming(streaming_override(false, true));
+        assert!(!pipeline.config.streaming);
+        assert!(!pipeline.shell_runner.is_streaming());
+
+        let pipeline = pipeline.with_streaming(streaming_override(false, false));
+        assert!(!pipeline.shell_runner.is_streaming(), "no flag keeps the current behavior");
+    }
+
+    #[tokio::test]
+    async fn test_failed_command_is_retried_with_a_correction() {
+        let missing = std::env::temp_dir().join(format!("agentic-missing-{}", uuid::Uuid::new_v4()));
+        let first = format!("ls {}", missing.display());
+        let (host, server) = mock_ollama(vec!["List the directory".to_string(), first, "echo fixed".to_string()]).await;
+
+        let toml = format!(
+            "[warp.models]\nollama_host = \"{}\"\n\n[warp.execution]\nstreaming = false\nauto_confirm = true\nmax_retries = 1\n",
+            host
+        );
+        let agentic: config::AgenticConfig = toml::from_str(&toml).unwrap();
+        let result = WarpPipeline::from_agentic_config(&agentic).unwrap().execute("list it").await.unwrap();
+
+        assert!(result.is_success(), "{:?}", result);
+        assert_eq!(result.command, "echo fixed");
+        assert_eq!(result.to_execution().output, "fixed");
+        let bodies = server.await.unwrap();
+        assert_eq!(bodies.len(), 3);
+        // The fix request carries the failed command and what it printed
+        assert!(bodies[2].contains(&missing.display().to_string()), "fix request: {}", bodies[2]);
+        assert!(bodies[2].contains("Exit code: 2"), "fix request: {}", bodies[2]);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_run_is_saved_to_history() {
+        let pipeline = WarpPipeline::from_agentic_config(&config::AgenticConfig::default()).unwrap();
+        let result = pipeline::PipelineResult {
+            original_input: "list files".to_string(),
+            plan: "List the files in the current directory".to_string(),
+            command: "ls".to_string(),
+            execution_result: Some(shell_runner::ExecutionResult::Error {
+                stderr: "ls: cannot open directory".to_string(),
+                exit_code: 2,
+                duration: std::time::Duration::from_millis(40),
+            }),
+            cancelled: false,
+        };
+
+        let db = crate::db::test_database().await;
+        db.save_command_execution(&pipeline.record(&result)).await.unwrap();
+
+        let history = db.get_command_history(10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].command, "ls");
+        assert_eq!(history[0].agent_query.as_deref(), Some("list files"));
+        assert!(matches!(history[0].status, crate::db::ExecutionStatus::Error));
+        assert_eq!(history[0].output, "ls: cannot open directory");
+        assert_eq!(history[0].duration_ms, 40);
+        assert_eq!(history[0].model.as_deref(), Some("codellama"));
+    }
+
+    #[tokio::test]
+    async fn test_agentic_toml_selects_the_queried_models() {
+        let (host, server) = mock_ollama(vec!["ls -la".to_string(), "ls -la".to_string()]).await;
+
+        let toml = format!(
+            "[warp.models]\nplanner = \"llama3.2\"\ncoder = \"qwen2.5-coder\"\nollama_host = \"{}\"\n\n[warp.execution]\nstreaming = false\n",
+            host
+        );
+        let agentic: config::AgenticConfig = toml::from_str(&toml).unwrap();
+        let pipeline = WarpPipeline::from_agentic_config(&agentic).unwrap();
+        assert!(!pipeline.shell_runner.is_streaming());
+
+        pipeline.planner.generate_plan("list files", None).await.unwrap();
+        pipeline.coder.generate_command("list files", None).await.unwrap();
+
+        let bodies = server.await.unwrap();
+        assert!(bodies[0].contains(r#""model":"llama3.2""#), "planner request: {}", bodies[0]);
+        assert!(bodies[1].contains(r#""model":"qwen2.5-coder""#), "coder request: {}", bodies[1]);
+    }
+
+    #[test]
+    fn test_safety_patterns_come_from_agentic_toml() {
+        let pipeline = WarpPipeline::from_agentic_config(&config::AgenticConfig::default()).unwrap();
+        assert_eq!(pipeline.shell_runner.dangerous_match("sudo shutdown -h now"), Some("shutdown"));
+        assert_eq!(pipeline.shell_runner.dangerous_match("terraform destroy"), None);
+
+        let agentic: config::AgenticConfig =
+            toml::from_str("[warp.safety]\ndangerous_commands = [\"terraform destroy\"]\n").unwrap();
+        let pipeline = WarpPipeline::from_agentic_config(&agentic).unwrap().with_streaming(Some(false));
+        assert_eq!(pipeline.shell_runner.dangerous_match("Terraform Destroy -auto-approve"), Some("terraform destroy"));
+        assert_eq!(pipeline.shell_runner.dangerous_match("shutdown"), None);
+
+        let disabled: config::AgenticConfig =
+            toml::from_str("[warp.safety]\nenable_safety_checks = false\n").unwrap();
+        let pipeline = WarpPipeline::from_agentic_config(&disabled).unwrap();
+        assert_eq!(pipeline.shell_runner.dangerous_match("shutdown"), None);
+
+        assert!(override_confirmed("YES\n"));
+        assert!(!override_confirmed("yes"));
+        assert!(!override_confirmed("y"));
+
+        assert_eq!(parse_choice("y\n"), Choice::Run);
+        assert_eq!(parse_choice("e\n"), Choice::Edit);
+        assert_eq!(parse_choice("E"), Choice::Edit);
+        assert_eq!(parse_choice(""), Choice::Cancel);
+
+        assert!(retyped("shutdown -h now\n", "shutdown -h now"));
+        assert!(!retyped("y", "shutdown -h now"));
+        assert!(!retyped("shutdown", "shutdown -h now"));
+    }
+
+    #[test]
+    fn test_auto_confirm_from_config_or_yes_flag() {
+        let pipeline = WarpPipeline::from_agentic_config(&config::AgenticConfig::default()).unwrap();
+        assert!(!pipeline.auto_confirm);
+        assert!(!pipeline.clone().with_auto_confirm(false).auto_confirm);
+        assert!(pipeline.with_auto_confirm(true).auto_confirm);
+
+        let agentic: config::AgenticConfig =
+            toml::from_str("[warp.execution]\nauto_confirm = true\n").unwrap();
+        let pipeline = WarpPipeline::from_agentic_config(&agentic).unwrap();
+        assert!(pipeline.with_auto_confirm(false).auto_confirm, "no --yes keeps the configured auto_confirm");
+    }
+
+    #[tokio::test]
+    async fn test_silent_non_interactive_stdin_cancels() {
+        let started = std::time::Instant::now();
+        let answer = answer_within(|| {
+            std::thread::sleep(Duration::from_secs(30));
+            Some("y\n".to_string())
+        }, Some(Duration::from_millis(50))).await;
+        assert_eq!(answer, None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let answer = answer_within(|| Some("y\n".to_string()), Some(Duration::from_secs(5))).await;
+        assert_eq!(answer.as_deref(), Some("y\n"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_commands_run_in_the_configured_working_directory() {
+        let dir = std::env::temp_dir().join(format!("agentic-cwd-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let toml = format!(
+            "[warp.execution]\nworking_directory = \"{}\"\n\n[warp.safety]\nallowed_directories = [\"{}\"]\n",
+            dir.display(), dir.display()
+        );
+        let agentic: config::AgenticConfig = toml::from_str(&toml).unwrap();
+        let pipeline = WarpPipeline::from_agentic_config(&agentic).unwrap().with_streaming(Some(false));
+
+        let working_dir = pipeline.checked_working_dir().unwrap().unwrap();
+        assert_eq!(working_dir, dir.as_path());
+        let result = pipeline.shell_runner.execute_in_dir("pwd", working_dir).await.unwrap();
+        match result {
+            shell_runner::ExecutionResult::Success { stdout, .. } => assert_eq!(stdout.trim(), dir.display().to_string()),
+            other => panic!("pwd failed: {:?}", other),
+        }
+
+        // --cwd overrides the config but is still held to the allowlist
+        let outside = pipeline.clone().with_working_dir(Some("/"));
+        let err = outside.checked_working_dir().unwrap_err();
+        assert!(err.to_string().contains("outside warp.safety.allowed_directories"), "{}", err);
+        let missing = pipeline.with_working_dir(Some(&format!("{}/missing", dir.display())));
+        assert!(missing.checked_working_dir().unwrap_err().to_string().contains("does not exist"));
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
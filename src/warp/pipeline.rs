@@ -1,7 +1,12 @@
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use super::agents::Generated;
 use super::shell_runner::ExecutionResult;
+use crate::db::{CommandExecution, ExecutionStatus};
 
 /// Result of a complete Warp pipeline execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,15 +28,23 @@ impl PipelineResult {
         match &self.execution_result {
             Some(ExecutionResult::Success { .. }) => true,
             Some(ExecutionResult::Error { .. }) => false,
+            Some(ExecutionResult::Cancelled { .. } | ExecutionResult::TimedOut { .. }) => false,
             None => false,
         }
     }
 
+    /// Whether the command was started but stopped with Ctrl+C
+    pub fn is_interrupted(&self) -> bool {
+        matches!(self.execution_result, Some(ExecutionResult::Cancelled { .. }))
+    }
+
     /// Get the total execution time if available
     pub fn execution_duration(&self) -> Option<Duration> {
         match &self.execution_result {
             Some(ExecutionResult::Success { duration, .. }) => Some(*duration),
             Some(ExecutionResult::Error { duration, .. }) => Some(*duration),
+            Some(ExecutionResult::Cancelled { duration }) => Some(*duration),
+            Some(ExecutionResult::TimedOut { duration, .. }) => Some(*duration),
             None => None,
         }
     }
@@ -55,11 +68,38 @@ impl PipelineResult {
 
     /// Get the exit code if command was executed
     pub fn exit_code(&self) -> Option<i32> {
-        match &self.execution_result {
-            Some(ExecutionResult::Success { .. }) => Some(0),
-            Some(ExecutionResult::Error { exit_code, .. }) => Some(*exit_code),
-            None => None,
+        self.execution_result.as_ref().map(ExecutionResult::exit_code)
+    }
+
+    /// History record of the generated command, with the natural language request as its query
+    pub fn to_execution(&self) -> CommandExecution {
+        let mut execution = CommandExecution::new(self.command.clone(), Some(self.original_input.clone()));
+        execution.status = match &self.execution_result {
+            _ if self.cancelled => ExecutionStatus::Cancelled,
+            Some(ExecutionResult::Success { .. }) => ExecutionStatus::Success,
+            Some(ExecutionResult::Error { .. } | ExecutionResult::TimedOut { .. }) => ExecutionStatus::Error,
+            Some(ExecutionResult::Cancelled { .. }) | None => ExecutionStatus::Cancelled,
+        };
+        execution.output = match &self.execution_result {
+            Some(ExecutionResult::Success { stdout, stderr, .. } | ExecutionResult::TimedOut { stdout, stderr, .. }) => [stdout.trim_end(), stderr.trim_end()]
+                .iter()
+                .filter(|part| !part.is_empty())
+                .copied()
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Some(ExecutionResult::Error { stderr, .. }) => stderr.clone(),
+            _ => String::new(),
+        };
+        if execution.output.is_empty() {
+            execution.output = self.summary();
         }
+        execution.exit_code = self.exit_code();
+        if let Some(duration) = self.execution_duration() {
+            execution.duration_ms = duration.as_millis() as u64;
+            // The record is made once the command finishes; date it from when it started
+            execution.timestamp -= chrono::Duration::from_std(duration).unwrap_or_default();
+        }
+        execution
     }
 
     /// Generate a summary of the pipeline execution
@@ -75,39 +115,110 @@ impl PipelineResult {
             Some(ExecutionResult::Error { exit_code, duration, .. }) => {
                 format!("❌ Command failed with exit code {} after {:.2}s", exit_code, duration.as_secs_f64())
             }
+            Some(ExecutionResult::Cancelled { duration }) => {
+                format!("⏹ Command interrupted after {:.2}s", duration.as_secs_f64())
+            }
+            Some(ExecutionResult::TimedOut { duration, .. }) => {
+                format!("⏱ Command timed out after {}s and was stopped", duration.as_secs())
+            }
             None => "⚠️ Command was not executed".to_string(),
         }
     }
 }
 
-/// Pipeline execution statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The plan and command a dry run came up with, as `agentic warp --dry-run --json` prints them
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRun {
+    pub input: String,
+    pub plan: String,
+    pub command: String,
+    /// The model that wrote each step; `null` where the built-in patterns did
+    pub model_used: StepModels,
+    /// Whether any step came from the fallback model or the patterns instead of the configured model
+    pub fallback_used: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepModels {
+    pub plan: Option<String>,
+    pub command: Option<String>,
+}
+
+impl DryRun {
+    pub fn new(input: &str, plan: Generated, command: Generated) -> Self {
+        Self {
+            input: input.to_string(),
+            fallback_used: plan.source.is_fallback() || command.source.is_fallback(),
+            model_used: StepModels {
+                plan: plan.source.model().map(str::to_string),
+                command: command.source.model().map(str::to_string),
+            },
+            plan: plan.text,
+            command: command.text,
+        }
+    }
+}
+
+/// How many of the most frequent commands `most_common_commands` lists
+pub const MOST_COMMON_LIMIT: usize = 5;
+
+/// Pipeline execution statistics, kept across runs in `warp_stats.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PipelineStats {
     pub total_executions: usize,
     pub successful_executions: usize,
     pub failed_executions: usize,
     pub cancelled_executions: usize,
     pub average_duration: Duration,
+    /// The most frequent commands with their counts, most frequent first
     pub most_common_commands: Vec<(String, usize)>,
+    /// How often each command ran; `most_common_commands` is derived from it
+    pub command_counts: HashMap<String, usize>,
+    /// Runs that reported a duration, which `average_duration` is taken over
+    pub timed_executions: usize,
 }
 
 impl PipelineStats {
     pub fn new() -> Self {
-        Self {
-            total_executions: 0,
-            successful_executions: 0,
-            failed_executions: 0,
-            cancelled_executions: 0,
-            average_duration: Duration::from_secs(0),
-            most_common_commands: Vec::new(),
+        Self::default()
+    }
+
+    /// `~/.agentic/warp_stats.json`
+    pub fn default_path() -> PathBuf {
+        dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".agentic").join("warp_stats.json")
+    }
+
+    /// The stats saved at `path`, or empty ones when nothing has been saved yet
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
         }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Add `result` to the stats saved at `path`
+    pub fn record(path: &Path, result: &PipelineResult) -> Result<Self> {
+        let mut stats = Self::load(path)?;
+        stats.update(result);
+        stats.save(path)?;
+        Ok(stats)
     }
 
     /// Update statistics with a new pipeline result
     pub fn update(&mut self, result: &PipelineResult) {
         self.total_executions += 1;
 
-        if result.cancelled {
+        if result.cancelled || result.is_interrupted() {
             self.cancelled_executions += 1;
         } else if result.is_success() {
             self.successful_executions += 1;
@@ -117,10 +228,18 @@ impl PipelineStats {
 
         // Update average duration
         if let Some(duration) = result.execution_duration() {
-            let total_time = self.average_duration.as_secs_f64() * (self.total_executions - 1) as f64;
-            let new_average = (total_time + duration.as_secs_f64()) / self.total_executions as f64;
+            self.timed_executions += 1;
+            let total_time = self.average_duration.as_secs_f64() * (self.timed_executions - 1) as f64;
+            let new_average = (total_time + duration.as_secs_f64()) / self.timed_executions as f64;
             self.average_duration = Duration::from_secs_f64(new_average);
         }
+
+        // Only commands that were started count towards the most common ones
+        let command = result.command.trim();
+        if result.execution_result.is_some() && !command.is_empty() {
+            *self.command_counts.entry(command.to_string()).or_default() += 1;
+            self.most_common_commands = most_common(&self.command_counts, MOST_COMMON_LIMIT);
+        }
     }
 
     /// Get success rate as a percentage
@@ -131,3 +250,50 @@ impl PipelineStats {
         (self.successful_executions as f64 / self.total_executions as f64) * 100.0
     }
 }
+
+/// The `limit` highest counts, ties broken alphabetically so the order is stable
+fn most_common(counts: &HashMap<String, usize>, limit: usize) -> Vec<(String, usize)> {
+    let mut sorted: Vec<(String, usize)> = counts.iter().map(|(command, count)| (command.clone(), *count)).collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted.truncate(limit);
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(command: &str, execution_result: Option<ExecutionResult>) -> PipelineResult {
+        PipelineResult {
+            original_input: "do it".to_string(),
+            plan: "Do it".to_string(),
+            command: command.to_string(),
+            cancelled: execution_result.is_none(),
+            execution_result,
+        }
+    }
+
+    #[test]
+    fn test_stats_tally_commands_and_survive_a_reload() {
+        let ok = |secs| Some(ExecutionResult::Success {
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: 0,
+            duration: Duration::from_secs(secs),
+        });
+        let failed = Some(ExecutionResult::Error { stderr: "no".to_string(), exit_code: 1, duration: Duration::from_secs(4) });
+
+        let path = std::env::temp_dir().join(format!("agentic-warp-stats-{}.json", uuid::Uuid::new_v4()));
+        for run in [result("ls", ok(1)), result("git status", ok(1)), result("ls", failed), result("rm -rf build", None)] {
+            PipelineStats::record(&path, &run).unwrap();
+        }
+
+        let stats = PipelineStats::load(&path).unwrap();
+        assert_eq!((stats.total_executions, stats.successful_executions, stats.failed_executions, stats.cancelled_executions), (4, 2, 1, 1));
+        assert_eq!(stats.success_rate(), 50.0);
+        assert_eq!(stats.average_duration, Duration::from_secs(2), "declined runs have no duration");
+        assert_eq!(stats.most_common_commands, [("ls".to_string(), 2), ("git status".to_string(), 1)], "declined commands aren't counted");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(PipelineStats::load(&path).unwrap().total_executions, 0);
+    }
+}
@@ -1,18 +1,24 @@
 use anyhow::{anyhow, Result};
 use colored::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::time::{Duration, Instant};
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc::UnboundedSender;
 use tracing::debug;
 
 /// Result of command execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ExecutionResult {
+    /// The command exited successfully; it may still have written to stderr
     Success {
         stdout: String,
         stderr: String,
+        exit_code: i32,
         duration: Duration,
     },
     Error {
@@ -20,34 +26,405 @@ pub enum ExecutionResult {
         exit_code: i32,
         duration: Duration,
     },
+    /// Interrupted (e.g. by Ctrl+C) before the command finished
+    Cancelled {
+        duration: Duration,
+    },
+    /// Killed for running longer than allowed; holds what it printed until then
+    TimedOut {
+        stdout: String,
+        stderr: String,
+        duration: Duration,
+    },
+}
+
+impl ExecutionResult {
+    /// The process's exit status; an interrupted command reports the conventional Ctrl+C code
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ExecutionResult::Success { exit_code, .. } | ExecutionResult::Error { exit_code, .. } => *exit_code,
+            ExecutionResult::Cancelled { .. } => INTERRUPTED_EXIT_CODE,
+            ExecutionResult::TimedOut { .. } => TIMED_OUT_EXIT_CODE,
+        }
+    }
+}
+
+/// How a command's stdio should be wired up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecutionMode {
+    /// Output is piped and captured, stdin is closed
+    Captured,
+    /// The command inherits the terminal so it can prompt the user; nothing is captured
+    Interactive,
+}
+
+/// Programs that always take over the terminal
+const INTERACTIVE_PROGRAMS: &[&str] = &[
+    "vi", "vim", "nvim", "nano", "emacs", "less", "more", "man", "top", "htop",
+    "ssh", "sftp", "ftp", "telnet", "mysql", "psql",
+];
+
+/// Programs that start a REPL when run without arguments
+const REPL_PROGRAMS: &[&str] = &["python", "python3", "node", "irb", "bash", "sh", "zsh", "fish"];
+
+/// Heuristically decide whether any part of `command` is likely to wait for user input
+pub fn is_interactive_command(command: &str) -> bool {
+    command
+        .split([';', '|', '&'])
+        .any(|segment| {
+            let mut tokens: Vec<&str> = segment.split_whitespace().collect();
+            // Skip `sudo` and leading VAR=value assignments to find the real program
+            while let Some(first) = tokens.first() {
+                if *first == "sudo" || (first.contains('=') && !first.starts_with('-')) {
+                    tokens.remove(0);
+                } else {
+                    break;
+                }
+            }
+            segment_is_interactive(&tokens)
+        })
+}
+
+fn segment_is_interactive(tokens: &[&str]) -> bool {
+    let Some(program) = tokens.first() else {
+        return false;
+    };
+    let program = program.rsplit('/').next().unwrap_or(program);
+    let args = &tokens[1..];
+    let has = |flags: &[&str]| args.iter().any(|a| flags.contains(a));
+
+    if INTERACTIVE_PROGRAMS.contains(&program) {
+        return true;
+    }
+    if REPL_PROGRAMS.contains(&program) && args.is_empty() {
+        return true;
+    }
+    if has(&["--interactive", "-it", "-ti"]) {
+        return true;
+    }
+
+    match (program, args.first().copied()) {
+        // Project scaffolders walk through a wizard
+        ("npm" | "pnpm" | "yarn" | "bun", Some("create")) => true,
+        ("npm" | "pnpm" | "yarn", Some("init")) => !has(&["-y", "--yes"]),
+        ("npx", Some(pkg)) => pkg.starts_with("create-"),
+        ("cargo", Some("generate")) => true,
+        // Git opens an editor or asks hunk by hunk
+        ("git", Some("commit")) => !has(&["-m", "--message", "-F", "--file", "--no-edit"])
+            && !args.iter().any(|a| a.starts_with("-m") || a.starts_with("--message=")),
+        ("git", Some("rebase")) => has(&["-i"]),
+        ("git", Some("add")) => has(&["-p", "-i", "--patch"]),
+        _ => false,
+    }
+}
+
+/// Exit status conventionally used by processes stopped with Ctrl+C
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Exit status reported for a command killed by a timeout, as coreutils `timeout` does
+pub const TIMED_OUT_EXIT_CODE: i32 = 124;
+
+/// How long output still arriving after a timeout kill is waited for, in case something that
+/// left the process group holds the pipes open
+const DRAIN_AFTER_KILL: Duration = Duration::from_secs(1);
+
+/// Resolves on Ctrl+C; never resolves if the handler can't be installed
+pub async fn ctrl_c() {
+    if tokio::signal::ctrl_c().await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Wait for `future`, exiting the CLI with `INTERRUPTED_EXIT_CODE` on Ctrl+C. Once `ctrl_c` has
+/// been awaited, SIGINT no longer stops the process by itself, so waits after a command ran (a
+/// model call, a prompt) go through this to stay interruptible.
+pub async fn exit_on_ctrl_c<T>(future: impl Future<Output = T>) -> T {
+    tokio::select! {
+        value = future => value,
+        _ = ctrl_c() => {
+            eprintln!("\n⏹ Interrupted");
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+    }
+}
+
+/// Kill a child and, on Unix, every process in its process group
+async fn kill_process_tree(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: plain FFI call; the child leads its own group because of `process_group(0)`
+        unsafe {
+            libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill().await;
+}
+
+/// The shell commands run in unless `warp.execution.shell` picks another: PowerShell on
+/// Windows for better command support, bash elsewhere
+pub fn shell_program() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "powershell"
+    } else {
+        "bash"
+    }
+}
+
+/// The flag that makes `shell` run a command string: `/C` for cmd, `-Command` for PowerShell
+/// and `-c` for sh, bash, zsh, fish and the like
+fn command_flag(shell: &str) -> &'static str {
+    let name = Path::new(shell).file_stem().and_then(|stem| stem.to_str()).unwrap_or(shell).to_lowercase();
+    match name.as_str() {
+        "cmd" => "/C",
+        "powershell" | "pwsh" => "-Command",
+        _ => "-c",
+    }
+}
+
+/// Characters split off as tokens of their own, so `x>/dev/sda` and `a;shutdown` are seen
+const SHELL_OPERATORS: &[char] = &[';', '|', '&', '>', '<', '(', ')', '`'];
+
+/// The first of `patterns` that `command` matches, ignoring case. Both are compared as shell
+/// tokens, so a pattern only matches whole words: `rm -rf /` flags `rm -rf /` but not
+/// `rm -rf /tmp/build`, and `format c:` doesn't flag `performatter c:`. Within a pattern, `*`
+/// matches any characters and a word ending in `=` or `.` matches words it starts (`dd if=`,
+/// `mkfs.`). A program also matches by its basename, so `shutdown` flags `/sbin/shutdown`, and a
+/// path by its `*` glob, so `rm -rf /` flags `rm -rf /*`.
+pub fn dangerous_pattern<'a>(command: &str, patterns: &'a [String]) -> Option<&'a str> {
+    let command_tokens = shell_tokens(command);
+    patterns
+        .iter()
+        .find(|pattern| {
+            let pattern_tokens = shell_tokens(pattern);
+            !pattern_tokens.is_empty()
+                && command_tokens.windows(pattern_tokens.len()).any(|window| {
+                    window.iter().zip(&pattern_tokens).all(|(token, pattern)| token_matches(pattern, token))
+                })
+        })
+        .map(String::as_str)
+}
+
+/// Lowercased words of `text` with shell operators split off and surrounding quotes dropped, so
+/// `bash -c "rm -rf /"` is seen too
+fn shell_tokens(text: &str) -> Vec<String> {
+    let mut spaced = String::with_capacity(text.len());
+    for c in text.to_lowercase().chars() {
+        if SHELL_OPERATORS.contains(&c) {
+            spaced.extend([' ', c, ' ']);
+        } else {
+            spaced.push(c);
+        }
+    }
+    spaced
+        .split_whitespace()
+        .map(|token| token.trim_matches(['\'', '"']).to_string())
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+fn token_matches(pattern: &str, token: &str) -> bool {
+    if pattern.contains('*') {
+        return glob_matches(pattern, token);
+    }
+    if pattern.ends_with(['=', '.']) {
+        return token.starts_with(pattern);
+    }
+    let unglobbed = token.strip_suffix('*').filter(|path| path.ends_with('/'));
+    token == pattern || unglobbed == Some(pattern) || (!pattern.contains('/') && token.rsplit('/').next() == Some(pattern))
+}
+
+/// Whether `text` matches `pattern`, where each `*` stands for any run of characters
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if text.len() < first.len() + last.len() || !text.starts_with(first) || !text.ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    true
 }
 
 /// Shell runner that executes commands with streaming output
 #[derive(Debug, Clone)]
 pub struct ShellRunner {
     streaming: bool,
+    dangerous_patterns: Vec<String>,
+    /// Directory commands start in; the current one when unset
+    working_dir: Option<PathBuf>,
+    /// Variables set on top of the inherited environment
+    env: HashMap<String, String>,
+    /// Shell to run commands in instead of `shell_program()`
+    shell: Option<String>,
+    /// Where streamed lines go instead of the terminal, e.g. a TUI block
+    output_sink: Option<UnboundedSender<String>>,
 }
 
 impl ShellRunner {
-    /// Create a new shell runner
+    /// Create a new shell runner that flags the default dangerous patterns
     pub fn new(streaming: bool) -> Self {
-        Self { streaming }
+        Self {
+            streaming,
+            dangerous_patterns: super::config::SafetyConfig::default().dangerous_commands,
+            working_dir: None,
+            env: HashMap::new(),
+            shell: None,
+            output_sink: None,
+        }
+    }
+
+    /// A runner with the safety patterns, environment and shell from `.agentic.toml`
+    pub fn configured(streaming: bool, agentic: &super::config::AgenticConfig) -> Result<Self> {
+        Ok(Self::new(streaming)
+            .with_dangerous_patterns(agentic.warp.safety.active_patterns())
+            .with_env(agentic.command_env()?)
+            .with_shell(agentic.checked_shell()))
+    }
+
+    /// Run commands in `shell` (e.g. `sh`, `zsh`, `pwsh`, `cmd`); `None` keeps the platform default
+    pub fn with_shell(mut self, shell: Option<String>) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Echo output line by line (or not) from now on
+    pub fn with_streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+
+    /// Stream output line by line into `sink` rather than printing it
+    pub fn with_output_sink(mut self, sink: UnboundedSender<String>) -> Self {
+        self.streaming = true;
+        self.output_sink = Some(sink);
+        self
+    }
+
+    /// Set `env` for commands, on top of (and overriding) the inherited environment
+    pub fn with_env(mut self, env: HashMap<String, String>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Start commands in `dir` instead of the current directory
+    pub fn with_working_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.working_dir = dir;
+        self
+    }
+
+    /// A `Command` running `command` in the platform shell and the configured directory, with
+    /// the configured variables
+    fn shell_command(&self, command: &str) -> Command {
+        let (shell, args) = self.get_shell_command(command);
+        let mut cmd = Command::new(shell);
+        cmd.args(args).envs(&self.env);
+        if let Some(dir) = &self.working_dir {
+            cmd.current_dir(dir);
+        }
+        cmd
+    }
+
+    /// Flag `patterns` instead of the defaults, e.g. those from `.agentic.toml`
+    pub fn with_dangerous_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.dangerous_patterns = patterns;
+        self
     }
 
-    /// Execute a shell command with optional streaming output
+    /// Whether output is echoed line by line as the command runs
+    #[cfg(test)]
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    /// Pick captured or interactive execution for `command`
+    pub fn execution_mode(&self, command: &str) -> ExecutionMode {
+        if is_interactive_command(command) {
+            ExecutionMode::Interactive
+        } else {
+            ExecutionMode::Captured
+        }
+    }
+
+    /// Run a command attached to the terminal so it can prompt the user.
+    /// Output goes straight to the terminal, so the result carries no captured text.
+    pub async fn execute_interactive(&self, command: &str) -> Result<ExecutionResult> {
+        let start_time = Instant::now();
+        
+        debug!("Executing interactive command: {}", command);
+
+        let mut child = self.shell_command(command)
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to spawn command '{}': {}", command, e))?;
+
+        // The command shares the terminal, so Ctrl+C reaches it directly; listening here only
+        // keeps it from killing the CLI too, and the command decides whether to exit
+        let mut interrupted = false;
+        let status = loop {
+            tokio::select! {
+                status = child.wait() => break status,
+                _ = ctrl_c() => interrupted = true,
+            }
+        }
+        .map_err(|e| anyhow!("Failed to wait for command: {}", e))?;
+
+        let duration = start_time.elapsed();
+        if interrupted && !status.success() {
+            Ok(ExecutionResult::Cancelled { duration })
+        } else if status.success() {
+            Ok(ExecutionResult::Success {
+                stdout: String::new(),
+                stderr: String::new(),
+                exit_code: status.code().unwrap_or(0),
+                duration,
+            })
+        } else {
+            Ok(ExecutionResult::Error {
+                stderr: String::new(),
+                exit_code: status.code().unwrap_or(-1),
+                duration,
+            })
+        }
+    }
+
+    /// Execute a shell command with optional streaming output; Ctrl+C stops the command
+    /// (and anything it spawned) and yields `ExecutionResult::Cancelled`
     pub async fn execute(&self, command: &str) -> Result<ExecutionResult> {
+        self.execute_until(command, ctrl_c()).await
+    }
+
+    /// Execute a shell command, killing its process group if `cancel` completes first
+    pub async fn execute_until(&self, command: &str, cancel: impl Future<Output = ()>) -> Result<ExecutionResult> {
+        self.run_captured(command, cancel, None).await
+    }
+
+    /// Spawn `command` with piped output, killing its process group when `cancel` completes
+    /// (`Cancelled`) or `timeout` passes (`TimedOut`) before it exits
+    pub async fn run_captured(
+        &self,
+        command: &str,
+        cancel: impl Future<Output = ()>,
+        timeout: Option<Duration>,
+    ) -> Result<ExecutionResult> {
         let start_time = Instant::now();
         
         debug!("Executing command: {}", command);
 
-        // Parse the command for cross-platform execution
-        let (shell, args) = self.get_shell_command(command);
-
-        let mut cmd = Command::new(&shell);
-        cmd.args(&args)
-            .stdout(Stdio::piped())
+        let mut cmd = self.shell_command(command);
+        cmd.stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .stdin(Stdio::null());
+        
+        // Run in a fresh process group so a cancel reaches every process the command starts
+        #[cfg(unix)]
+        cmd.process_group(0);
 
         let mut child = cmd.spawn().map_err(|e| {
             anyhow!("Failed to spawn command '{}': {}", command, e)
@@ -61,180 +438,149 @@ impl ShellRunner {
             anyhow!("Failed to capture stderr")
         })?;
 
-        let mut stdout_lines = Vec::new();
-        let mut stderr_lines = Vec::new();
-
-        if self.streaming {
-            // Stream output in real-time
-            let stdout_reader = BufReader::new(stdout);
-            let stderr_reader = BufReader::new(stderr);
-
-            let stdout_handle = tokio::spawn(async move {
-                let mut lines = stdout_reader.lines();
+        // Streaming echoes output line by line as it arrives; otherwise it is collected verbatim
+        let streaming = self.streaming;
+        let sink = self.output_sink.clone();
+        let stdout_handle = tokio::spawn(async move {
+            if streaming {
+                let mut lines = BufReader::new(stdout).lines();
                 let mut collected = Vec::new();
-                
                 while let Ok(Some(line)) = lines.next_line().await {
-                    println!("{}", line);
+                    match &sink {
+                        Some(sink) => {
+                            let _ = sink.send(line.clone());
+                        }
+                        None => println!("{}", line),
+                    }
                     collected.push(line);
                 }
-                collected
-            });
+                collected.join("\n")
+            } else {
+                let mut buf = Vec::new();
+                let _ = BufReader::new(stdout).read_to_end(&mut buf).await;
+                String::from_utf8_lossy(&buf).to_string()
+            }
+        });
 
-            let stderr_handle = tokio::spawn(async move {
-                let mut lines = stderr_reader.lines();
+        let sink = self.output_sink.clone();
+        let stderr_handle = tokio::spawn(async move {
+            if streaming {
+                let mut lines = BufReader::new(stderr).lines();
                 let mut collected = Vec::new();
-                
                 while let Ok(Some(line)) = lines.next_line().await {
-                    eprintln!("{}", line.yellow());
-                    collected.push(line);
-                }
-                collected
-            });
-
-            // Wait for both streams and the process
-            let (stdout_result, stderr_result, exit_status) = tokio::join!(
-                stdout_handle,
-                stderr_handle,
-                child.wait()
-            );
-
-            stdout_lines = stdout_result.unwrap_or_default();
-            stderr_lines = stderr_result.unwrap_or_default();
-
-            let duration = start_time.elapsed();
-
-            match exit_status {
-                Ok(status) => {
-                    if status.success() {
-                        Ok(ExecutionResult::Success {
-                            stdout: stdout_lines.join("\n"),
-                            stderr: stderr_lines.join("\n"),
-                            duration,
-                        })
-                    } else {
-                        let exit_code = status.code().unwrap_or(-1);
-                        Ok(ExecutionResult::Error {
-                            stderr: stderr_lines.join("\n"),
-                            exit_code,
-                            duration,
-                        })
+                    match &sink {
+                        Some(sink) => {
+                            let _ = sink.send(line.clone());
+                        }
+                        None => eprintln!("{}", line.yellow()),
                     }
+                    collected.push(line);
                 }
-                Err(e) => Err(anyhow!("Failed to wait for command: {}", e)),
-            }
-        } else {
-            // Collect all output at once
-            let output = child.wait_with_output().await.map_err(|e| {
-                anyhow!("Failed to execute command: {}", e)
-            })?;
-
-            let duration = start_time.elapsed();
-            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-
-            if output.status.success() {
-                Ok(ExecutionResult::Success {
-                    stdout,
-                    stderr,
-                    duration,
-                })
+                collected.join("\n")
             } else {
-                let exit_code = output.status.code().unwrap_or(-1);
-                Ok(ExecutionResult::Error {
-                    stderr,
-                    exit_code,
+                let mut buf = Vec::new();
+                let _ = BufReader::new(stderr).read_to_end(&mut buf).await;
+                String::from_utf8_lossy(&buf).to_string()
+            }
+        });
+
+        let deadline = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        let exit_status = tokio::select! {
+            status = child.wait() => status,
+            _ = cancel => {
+                kill_process_tree(&mut child).await;
+                stdout_handle.abort();
+                stderr_handle.abort();
+                return Ok(ExecutionResult::Cancelled {
+                    duration: start_time.elapsed(),
+                });
+            }
+            _ = deadline => {
+                kill_process_tree(&mut child).await;
+                let duration = start_time.elapsed();
+                let drained = |handle: tokio::task::JoinHandle<String>| async move {
+                    let abort = handle.abort_handle();
+                    let output = tokio::time::timeout(DRAIN_AFTER_KILL, handle).await;
+                    abort.abort();
+                    output.ok().and_then(Result::ok).unwrap_or_default()
+                };
+                return Ok(ExecutionResult::TimedOut {
+                    stdout: drained(stdout_handle).await,
+                    stderr: drained(stderr_handle).await,
                     duration,
-                })
+                });
+            }
+        };
+
+        let stdout = stdout_handle.await.unwrap_or_default();
+        let stderr = stderr_handle.await.unwrap_or_default();
+        let duration = start_time.elapsed();
+
+        match exit_status {
+            Ok(status) => {
+                if status.success() {
+                    Ok(ExecutionResult::Success {
+                        stdout,
+                        stderr,
+                        exit_code: status.code().unwrap_or(0),
+                        duration,
+                    })
+                } else {
+                    let exit_code = status.code().unwrap_or(-1);
+                    Ok(ExecutionResult::Error {
+                        stderr,
+                        exit_code,
+                        duration,
+                    })
+                }
             }
+            Err(e) => Err(anyhow!("Failed to wait for command: {}", e)),
         }
     }
 
+    /// The shell commands run in
+    pub fn shell(&self) -> &str {
+        self.shell.as_deref().unwrap_or(shell_program())
+    }
+
     /// Get the appropriate shell command for the current platform
     fn get_shell_command(&self, command: &str) -> (String, Vec<String>) {
-        if cfg!(target_os = "windows") {
-            // Use PowerShell on Windows for better command support
-            ("powershell".to_string(), vec!["-Command".to_string(), command.to_string()])
-        } else {
-            // Use bash on Unix-like systems
-            ("bash".to_string(), vec!["-c".to_string(), command.to_string()])
-        }
+        let shell = self.shell();
+        (shell.to_string(), vec![command_flag(shell).to_string(), command.to_string()])
     }
 
-    /// Execute a command with a timeout
+    /// Like `execute`, but a command still running after `timeout` is killed with everything it
+    /// spawned and yields `ExecutionResult::TimedOut`
     pub async fn execute_with_timeout(&self, command: &str, timeout: Duration) -> Result<ExecutionResult> {
-        match tokio::time::timeout(timeout, self.execute(command)).await {
-            Ok(result) => result,
-            Err(_) => Err(anyhow!("Command timed out after {:.2}s", timeout.as_secs_f64())),
-        }
+        self.run_captured(command, ctrl_c(), Some(timeout)).await
     }
 
     /// Execute a command in a specific directory
-    pub async fn execute_in_dir(&self, command: &str, dir: &str) -> Result<ExecutionResult> {
-        let start_time = Instant::now();
-        
-        debug!("Executing command in {}: {}", dir, command);
-
-        let (shell, mut args) = self.get_shell_command(command);
-        
-        // Modify command to change directory first
-        let full_command = if cfg!(target_os = "windows") {
-            format!("cd '{}'; {}", dir, command)
-        } else {
-            format!("cd '{}' && {}", dir, command)
-        };
-        
-        args[1] = full_command;
-
-        let mut cmd = Command::new(&shell);
-        cmd.args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .stdin(Stdio::null());
-
-        let output = cmd.output().await.map_err(|e| {
-            anyhow!("Failed to execute command in directory '{}': {}", dir, e)
-        })?;
-
-        let duration = start_time.elapsed();
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-
-        if output.status.success() {
-            Ok(ExecutionResult::Success {
-                stdout,
-                stderr,
-                duration,
-            })
-        } else {
-            let exit_code = output.status.code().unwrap_or(-1);
-            Ok(ExecutionResult::Error {
-                stderr,
-                exit_code,
-                duration,
-            })
+    pub async fn execute_in_dir(&self, command: &str, dir: &Path) -> Result<ExecutionResult> {
+        debug!("Executing command in {}: {}", dir.display(), command);
+        if !dir.is_dir() {
+            return Err(anyhow!("Working directory '{}' does not exist", dir.display()));
         }
+        self.clone().with_working_dir(Some(dir.to_path_buf())).execute(command).await
     }
 
-    /// Check if a command is potentially dangerous
+    /// The dangerous pattern `command` matches, if any
+    pub fn dangerous_match(&self, command: &str) -> Option<&str> {
+        dangerous_pattern(command, &self.dangerous_patterns)
+    }
+
+    /// Check if a command is potentially dangerous; see `dangerous_pattern` for how patterns match
     pub fn is_dangerous_command(&self, command: &str) -> bool {
-        let dangerous_patterns = [
-            "rm -rf /",
-            "del /s /q c:",
-            "format c:",
-            "shutdown",
-            "reboot",
-            "dd if=",
-            "mkfs.",
-            "> /dev/",
-            "chmod 777 /",
-            "chown root /",
-        ];
-
-        let command_lower = command.to_lowercase();
-        dangerous_patterns.iter().any(|pattern| command_lower.contains(pattern))
-    }
-
-    /// Execute a command with safety checks
+        self.dangerous_match(command).is_some()
+    }
+
+    /// Execute a command, refusing dangerous ones outright; for callers with nobody to confirm
+    /// them, where the warp pipeline would ask instead
     pub async fn execute_safely(&self, command: &str) -> Result<ExecutionResult> {
         if self.is_dangerous_command(command) {
             return Err(anyhow!(
@@ -262,6 +608,33 @@ mod tests {
         assert!(!runner.is_dangerous_command("npm install"));
     }
 
+    #[test]
+    fn test_dangerous_patterns_match_whole_words() {
+        let runner = ShellRunner::new(false);
+        assert!(runner.is_dangerous_command("rm -rf /"));
+        assert!(runner.is_dangerous_command("sudo rm -rf / --no-preserve-root"));
+        assert!(runner.is_dangerous_command("rm -rf /*"));
+        assert!(!runner.is_dangerous_command("rm -rf /home/me/build"));
+        assert!(!runner.is_dangerous_command("rm -rf /tmp/foo"));
+        assert!(!runner.is_dangerous_command("rm -rf /tmp/*"));
+
+        assert!(runner.is_dangerous_command("cd /tmp && /sbin/shutdown -h now"));
+        assert!(runner.is_dangerous_command("bash -c \"reboot\""));
+        assert!(!runner.is_dangerous_command("./shutdown-notifier.sh"));
+        assert!(!runner.is_dangerous_command("performatter c:"));
+        assert!(runner.is_dangerous_command("format C:"));
+
+        assert!(runner.is_dangerous_command("dd if=/dev/zero of=/dev/sda"));
+        assert!(runner.is_dangerous_command("mkfs.ext4 /dev/sdb1"));
+        assert!(runner.is_dangerous_command("cat image >/dev/sda"));
+        assert!(!runner.is_dangerous_command("make 2>/dev/null"));
+        assert!(!runner.is_dangerous_command("chmod 777 /tmp/shared"));
+
+        let custom = vec!["git push * --force".to_string()];
+        assert_eq!(dangerous_pattern("git push origin --force", &custom), Some("git push * --force"));
+        assert_eq!(dangerous_pattern("git push origin --force-with-lease", &custom), None);
+    }
+
     #[test]
     fn test_shell_command_parsing() {
         let runner = ShellRunner::new(false);
@@ -277,5 +650,170 @@ mod tests {
         }
         
         assert_eq!(args[1], "echo hello");
+
+        for (shell, flag) in [("sh", "-c"), ("/usr/bin/fish", "-c"), ("pwsh", "-Command"), ("CMD.EXE", "/C")] {
+            let (program, args) = runner.clone().with_shell(Some(shell.to_string())).get_shell_command("dir");
+            assert_eq!((program.as_str(), args[0].as_str()), (shell, flag));
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_configured_shell_runs_commands() {
+        let agentic: crate::warp::config::AgenticConfig = toml::from_str("[warp.execution]\nshell = \"sh\"\n").unwrap();
+        let runner = ShellRunner::configured(false, &agentic).unwrap();
+        match runner.execute("echo $0").await.unwrap() {
+            ExecutionResult::Success { stdout, .. } => assert_eq!(stdout.trim(), "sh"),
+            other => panic!("sh failed: {:?}", other),
+        }
+
+        let missing: crate::warp::config::AgenticConfig =
+            toml::from_str("[warp.execution]\nshell = \"no-such-shell\"\n").unwrap();
+        assert_eq!(missing.checked_shell().as_deref(), Some("no-such-shell"), "kept, with a warning");
+        assert!(ShellRunner::configured(false, &missing).unwrap().execute("true").await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_ctrl_c_after_a_run_still_stops_the_cli() {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+        const CHILD: &str = "AGENTIC_CTRL_C_CHILD";
+        if std::env::var_os(CHILD).is_some() {
+            // Running the command leaves tokio in charge of SIGINT, as a warp run does
+            ShellRunner::new(false).execute("true").await.unwrap();
+            exit_on_ctrl_c(async {
+                println!("waiting");
+                std::future::pending::<()>().await
+            }).await;
+            return;
+        }
+
+        // This test again, in a process of its own that can be interrupted
+        let mut child = tokio::process::Command::new(std::env::current_exe().unwrap())
+            .args(["warp::shell_runner::tests::test_ctrl_c_after_a_run_still_stops_the_cli", "--exact", "--nocapture"])
+            .env(CHILD, "1")
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        // libtest prints the test's name before it runs, on the same line
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if line.ends_with("waiting") {
+                break;
+            }
+        }
+        // SAFETY: plain FFI call on the child's pid
+        unsafe {
+            libc::kill(child.id().unwrap() as libc::pid_t, libc::SIGINT);
+        }
+        let status = tokio::time::timeout(Duration::from_secs(5), child.wait()).await
+            .expect("Ctrl+C was swallowed")
+            .unwrap();
+        assert_eq!(status.code(), Some(INTERRUPTED_EXIT_CODE));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_cancel_kills_running_command() {
+        let runner = ShellRunner::new(false);
+        let started = Instant::now();
+        let cancel = tokio::time::sleep(Duration::from_millis(200));
+
+        let result = runner.execute_until("sleep 30 | cat", cancel).await.unwrap();
+
+        assert!(matches!(result, ExecutionResult::Cancelled { .. }));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout_kills_a_hung_command_and_keeps_its_output() {
+        let runner = ShellRunner::new(false);
+        let started = Instant::now();
+        let result = runner.execute_with_timeout("echo $$; sleep 30", Duration::from_millis(300)).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let ExecutionResult::TimedOut { stdout, .. } = &result else {
+            panic!("expected a timeout: {:?}", result);
+        };
+        assert_eq!(result.exit_code(), TIMED_OUT_EXIT_CODE);
+        let pid: libc::pid_t = stdout.trim().parse().unwrap();
+        // SAFETY: signal 0 only checks whether the process exists
+        assert_eq!(unsafe { libc::kill(pid, 0) }, -1, "the shell was left running");
+
+        let quick = runner.execute_with_timeout("echo done", Duration::from_secs(5)).await.unwrap();
+        assert!(matches!(quick, ExecutionResult::Success { .. }));
+    }
+
+    /// Processes in process group `pgid` that haven't exited, from `/proc/<pid>/stat`
+    #[cfg(target_os = "linux")]
+    fn live_processes_in_group(pgid: &str) -> usize {
+        std::fs::read_dir("/proc")
+            .unwrap()
+            .filter_map(|entry| std::fs::read_to_string(entry.ok()?.path().join("stat")).ok())
+            .filter(|stat| {
+                // After the command name: state, ppid, pgrp, ...
+                let fields: Vec<&str> = stat.rsplit(')').next().unwrap_or_default().split_whitespace().collect();
+                fields.len() > 2 && fields[0] != "Z" && fields[2] == pgid
+            })
+            .count()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_timeout_kills_every_process_of_a_pipeline() {
+        let runner = ShellRunner::new(false);
+        let started = Instant::now();
+        let result = runner.execute_with_timeout("echo $$; sleep 30 | sleep 31", Duration::from_millis(300)).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2), "took {:?}", started.elapsed());
+
+        let ExecutionResult::TimedOut { stdout, .. } = &result else {
+            panic!("expected a timeout: {:?}", result);
+        };
+        // The shell leads the command's process group, so its pid is the group id
+        assert_eq!(live_processes_in_group(stdout.trim()), 0, "both sleeps should be gone");
+    }
+
+    #[test]
+    fn test_interactive_commands_are_routed_to_terminal() {
+        let runner = ShellRunner::new(false);
+
+        assert_eq!(runner.execution_mode("npm create vite@latest"), ExecutionMode::Interactive);
+        assert_eq!(runner.execution_mode("cd app && npm init"), ExecutionMode::Interactive);
+        assert_eq!(runner.execution_mode("git commit"), ExecutionMode::Interactive);
+        assert_eq!(runner.execution_mode("docker run -it ubuntu"), ExecutionMode::Interactive);
+        assert_eq!(runner.execution_mode("sudo vim /etc/hosts"), ExecutionMode::Interactive);
+
+        assert_eq!(runner.execution_mode("npm init -y"), ExecutionMode::Captured);
+        assert_eq!(runner.execution_mode("git commit -m 'fix'"), ExecutionMode::Captured);
+        assert_eq!(runner.execution_mode("python3 script.py"), ExecutionMode::Captured);
+        assert_eq!(runner.execution_mode("ls -la | grep src"), ExecutionMode::Captured);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_execution_captures_output() {
+        let runner = ShellRunner::new(false);
+        match runner.execute("echo hello").await.unwrap() {
+            ExecutionResult::Success { stdout, .. } => assert_eq!(stdout.trim(), "hello"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_success_keeps_exit_code_and_stderr() {
+        let runner = ShellRunner::new(false);
+        let result = runner.execute("echo careful >&2").await.unwrap();
+        match &result {
+            ExecutionResult::Success { stderr, exit_code, .. } => {
+                assert_eq!(*exit_code, 0);
+                assert_eq!(stderr.trim(), "careful");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let failed = runner.execute("exit 3").await.unwrap();
+        assert_eq!(failed.exit_code(), 3);
+        assert_eq!(result.exit_code(), 0);
     }
 }
@@ -32,24 +32,150 @@ pub struct Workflow {
     pub shells: Vec<String>,
 }
 
+impl Workflow {
+    /// Whether the workflow can run under `shell`; an empty `shells` list means any shell
+    pub fn supports_shell(&self, shell: &str) -> bool {
+        let shell = normalize_shell(shell);
+        self.shells.is_empty() || self.shells.iter().any(|s| normalize_shell(s) == shell)
+    }
+
+    /// Whether `arg` must be given a value: it is marked required, or it has no default and the
+    /// command uses it. Warp's workflow files have no `required` key, so the second case is how
+    /// their arguments come out required.
+    pub fn requires(&self, arg: &WorkflowArgument) -> bool {
+        arg.required || (arg.default_value.is_none() && self.command.contains(&placeholder(&arg.name)))
+    }
+}
+
+/// How `name` appears in a workflow command
+fn placeholder(name: &str) -> String {
+    format!("{{{{{}}}}}", name)
+}
+
+/// Map shell names and paths onto the names used in workflow `shells` lists
+pub fn normalize_shell(shell: &str) -> String {
+    let name = shell
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(shell)
+        .trim_end_matches(".exe")
+        .to_lowercase();
+    match name.as_str() {
+        "powershell" => "pwsh".to_string(),
+        _ => name,
+    }
+}
+
+/// Detect the shell commands will run under from the environment
+pub fn detect_shell() -> String {
+    if cfg!(target_os = "windows") {
+        "pwsh".to_string()
+    } else {
+        std::env::var("SHELL")
+            .map(|s| normalize_shell(&s))
+            .unwrap_or_else(|_| "bash".to_string())
+    }
+}
+
+/// Where user workflows live: `~/.agentic/workflows`
+pub fn user_workflow_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".agentic").join("workflows"))
+}
+
+/// What `import_workflows` copied and what it skipped, as paths relative to the source
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: Vec<PathBuf>,
+    /// Files that aren't valid workflows, with the parse error
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+fn is_workflow_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|s| s.to_str()), Some("yaml") | Some("yml"))
+}
+
+/// Copy every workflow file under `source` into `dest`, keeping the directory layout so ids stay
+/// the same. Files that don't parse as a `Workflow` are reported instead of copied; hidden
+/// directories such as `.git` are skipped.
+pub fn import_workflows(source: &Path, dest: &Path) -> Result<ImportReport> {
+    if !source.is_dir() {
+        anyhow::bail!("Workflow source {:?} is not a directory", source);
+    }
+    let mut report = ImportReport::default();
+    import_directory(source, source, dest, &mut report)?;
+    report.imported.sort();
+    report.failed.sort();
+    Ok(report)
+}
+
+fn import_directory(dir: &Path, source: &Path, dest: &Path, report: &mut ImportReport) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read workflow directory: {:?}", dir))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        let relative = path.strip_prefix(source).unwrap_or(&path).to_path_buf();
+        if path.is_dir() {
+            if !path.file_name().and_then(|s| s.to_str()).is_some_and(|name| name.starts_with('.')) {
+                import_directory(&path, source, dest, report)?;
+            }
+        } else if is_workflow_file(&path) {
+            let parsed = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| serde_yaml::from_str::<Workflow>(&content).map_err(anyhow::Error::from));
+            if let Err(e) = parsed {
+                report.failed.push((relative, e.to_string()));
+                continue;
+            }
+            let target = dest.join(&relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create workflow directory: {:?}", parent))?;
+            }
+            fs::copy(&path, &target)
+                .with_context(|| format!("Failed to copy {:?} to {:?}", path, target))?;
+            report.imported.push(relative);
+        }
+    }
+    Ok(())
+}
+
 pub struct WorkflowManager {
     workflows: HashMap<String, Workflow>,
     workflow_directories: Vec<PathBuf>,
     favorites: Vec<String>,
+    current_shell: String,
 }
 
 impl WorkflowManager {
     pub fn new() -> Self {
         Self {
             workflows: HashMap::new(),
-            workflow_directories: vec![
-                PathBuf::from("workflows"),
-                PathBuf::from("~/.agentic/workflows"),
-            ],
+            workflow_directories: [Some(PathBuf::from("workflows")), user_workflow_dir()]
+                .into_iter()
+                .flatten()
+                .collect(),
             favorites: Vec::new(),
+            current_shell: detect_shell(),
         }
     }
 
+    /// Override the detected shell, e.g. from configuration
+    pub fn set_current_shell(&mut self, shell: &str) {
+        self.current_shell = normalize_shell(shell);
+    }
+
+    pub fn current_shell(&self) -> &str {
+        &self.current_shell
+    }
+
+    /// Whether a loaded workflow can run under the current shell
+    pub fn is_compatible(&self, workflow_id: &str) -> bool {
+        self.get_workflow(workflow_id)
+            .map(|workflow| workflow.supports_shell(&self.current_shell))
+            .unwrap_or(false)
+    }
+
     pub fn add_workflow_directory<P: AsRef<Path>>(&mut self, path: P) {
         self.workflow_directories.push(path.as_ref().to_path_buf());
     }
@@ -57,13 +183,14 @@ impl WorkflowManager {
     pub fn load_workflows(&mut self) -> Result<()> {
         for workflow_dir in &self.workflow_directories.clone() {
             if workflow_dir.exists() {
-                self.load_workflows_from_directory(workflow_dir)?;
+                self.load_workflows_from_directory(workflow_dir, workflow_dir)?;
             }
         }
         Ok(())
     }
 
-    fn load_workflows_from_directory(&mut self, dir: &Path) -> Result<()> {
+    /// Load the workflows under `dir`; ids are relative to `base`, the workflow directory being loaded
+    fn load_workflows_from_directory(&mut self, dir: &Path, base: &Path) -> Result<()> {
         let entries = fs::read_dir(dir)
             .with_context(|| format!("Failed to read workflow directory: {:?}", dir))?;
 
@@ -73,12 +200,11 @@ impl WorkflowManager {
             
             if path.is_dir() {
                 // Recursively load workflows from subdirectories
-                self.load_workflows_from_directory(&path)?;
-            } else if path.extension().and_then(|s| s.to_str()) == Some("yaml") 
-                   || path.extension().and_then(|s| s.to_str()) == Some("yml") {
+                self.load_workflows_from_directory(&path, base)?;
+            } else if is_workflow_file(&path) {
                 if let Ok(workflow) = self.load_workflow_from_file(&path) {
                     // Use relative path as ID (e.g., "git/clone_with_ssh")
-                    let id = self.generate_workflow_id(&path, dir);
+                    let id = self.generate_workflow_id(&path, base);
                     self.workflows.insert(id, workflow);
                 }
             }
@@ -183,21 +309,29 @@ impl WorkflowManager {
         let workflow = self.get_workflow(workflow_id)
             .ok_or_else(|| anyhow::anyhow!("Workflow '{}' not found", workflow_id))?;
 
+        if !workflow.supports_shell(&self.current_shell) {
+            anyhow::bail!(
+                "Workflow '{}' supports [{}] but the current shell is {}",
+                workflow_id,
+                workflow.shells.join(", "),
+                self.current_shell
+            );
+        }
+
         let mut command = workflow.command.clone();
         
         // Replace placeholders with provided arguments
         for (key, value) in args {
-            let placeholder = format!("{{{{{}}}}}", key);
-            command = command.replace(&placeholder, &value);
+            command = command.replace(&placeholder(&key), &value);
         }
         
         // Replace remaining placeholders with default values
         for arg in &workflow.arguments {
-            let placeholder = format!("{{{{{}}}}}", arg.name);
+            let placeholder = placeholder(&arg.name);
             if command.contains(&placeholder) {
                 if let Some(default_value) = &arg.default_value {
                     command = command.replace(&placeholder, default_value);
-                } else if arg.required {
+                } else {
                     anyhow::bail!("Required argument '{}' not provided for workflow '{}'", arg.name, workflow_id);
                 }
             }
@@ -207,16 +341,22 @@ impl WorkflowManager {
     }
 
     pub fn validate_workflow_args(&self, workflow_id: &str, args: &HashMap<String, String>) -> Result<()> {
+        if let Some(arg) = self.missing_args(workflow_id, args)?.first() {
+            anyhow::bail!("Required argument '{}' missing for workflow '{}'", arg.name, workflow_id);
+        }
+        Ok(())
+    }
+
+    /// The arguments `Workflow::requires` that have neither a value in `args` nor a default
+    pub fn missing_args(&self, workflow_id: &str, args: &HashMap<String, String>) -> Result<Vec<&WorkflowArgument>> {
         let workflow = self.get_workflow(workflow_id)
             .ok_or_else(|| anyhow::anyhow!("Workflow '{}' not found", workflow_id))?;
 
-        for arg in &workflow.arguments {
-            if arg.required && !args.contains_key(&arg.name) && arg.default_value.is_none() {
-                anyhow::bail!("Required argument '{}' missing for workflow '{}'", arg.name, workflow_id);
-            }
-        }
-        
-        Ok(())
+        Ok(workflow
+            .arguments
+            .iter()
+            .filter(|arg| workflow.requires(arg) && !args.contains_key(&arg.name) && arg.default_value.is_none())
+            .collect())
     }
 
     pub fn reload_workflows(&mut self) -> Result<()> {
@@ -232,6 +372,7 @@ impl WorkflowManager {
                 id.to_lowercase().starts_with(&partial)
                     || workflow.name.to_lowercase().contains(&partial)
             })
+            .filter(|(_, workflow)| workflow.supports_shell(&self.current_shell))
             .take(10) // Limit suggestions
             .collect()
     }
@@ -242,3 +383,110 @@ impl Default for WorkflowManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workflow_for(shells: &[&str]) -> Workflow {
+        Workflow {
+            name: "Zsh only".to_string(),
+            command: "echo {{word}}".to_string(),
+            description: "Test workflow".to_string(),
+            tags: Vec::new(),
+            arguments: Vec::new(),
+            author: None,
+            author_url: None,
+            source_url: None,
+            shells: shells.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_zsh_only_workflow_incompatible_with_bash() {
+        let mut manager = WorkflowManager::new();
+        manager.workflows.insert("test/zsh_only".to_string(), workflow_for(&["zsh"]));
+        manager.set_current_shell("/bin/bash");
+
+        assert!(!manager.is_compatible("test/zsh_only"));
+        assert!(manager.execute_workflow("test/zsh_only", HashMap::new()).is_err());
+        assert!(manager.get_workflow_suggestions("test").is_empty());
+
+        manager.set_current_shell("/usr/bin/zsh");
+        assert!(manager.is_compatible("test/zsh_only"));
+    }
+
+    #[test]
+    fn test_missing_args_lists_every_required_arg_without_default() {
+        let mut workflow = workflow_for(&[]);
+        workflow.command = "echo {{greeting}} {{name}} {{punctuation}}".to_string();
+        workflow.arguments = vec![
+            WorkflowArgument { name: "greeting".to_string(), description: "Greeting".to_string(), default_value: None, required: true },
+            WorkflowArgument { name: "name".to_string(), description: "Who to greet".to_string(), default_value: None, required: true },
+            WorkflowArgument { name: "punctuation".to_string(), description: "Ending".to_string(), default_value: Some("!".to_string()), required: true },
+        ];
+        let mut manager = WorkflowManager::new();
+        manager.workflows.insert("test/greet".to_string(), workflow);
+
+        let missing: Vec<_> = manager.missing_args("test/greet", &HashMap::new()).unwrap()
+            .iter().map(|arg| arg.name.clone()).collect();
+        assert_eq!(missing, ["greeting", "name"]);
+
+        let args = HashMap::from([("greeting".to_string(), "hello".to_string()), ("name".to_string(), "world".to_string())]);
+        assert!(manager.missing_args("test/greet", &args).unwrap().is_empty());
+        assert_eq!(manager.execute_workflow("test/greet", args).unwrap(), "echo hello world !");
+        assert!(manager.missing_args("test/unknown", &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_arguments_without_a_default_are_required_when_the_command_uses_them() {
+        let mut workflow = workflow_for(&[]);
+        workflow.command = "git checkout {{branch}}".to_string();
+        let imported: WorkflowArgument = serde_yaml::from_str("name: branch\ndescription: Branch to check out\ndefault_value: ~\n").unwrap();
+        assert!(!imported.required, "Warp workflows have no required key");
+        let unused = WorkflowArgument { name: "unused".to_string(), description: "Not in the command".to_string(), default_value: None, required: false };
+        workflow.arguments = vec![imported, unused];
+        let mut manager = WorkflowManager::new();
+        manager.workflows.insert("git/checkout".to_string(), workflow);
+
+        let missing: Vec<_> = manager.missing_args("git/checkout", &HashMap::new()).unwrap()
+            .iter().map(|arg| arg.name.clone()).collect();
+        assert_eq!(missing, ["branch"]);
+        assert!(manager.execute_workflow("git/checkout", HashMap::new()).is_err(), "never runs with a literal {{{{branch}}}}");
+        let args = HashMap::from([("branch".to_string(), "main".to_string())]);
+        assert_eq!(manager.execute_workflow("git/checkout", args).unwrap(), "git checkout main");
+    }
+
+    #[test]
+    fn test_import_copies_valid_workflows_and_reports_broken_ones() {
+        let root = std::env::temp_dir().join(format!("agentic-workflow-import-{}", uuid::Uuid::new_v4()));
+        let (source, dest) = (root.join("source"), root.join("dest"));
+        fs::create_dir_all(source.join("git")).unwrap();
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::write(source.join("git/status.yaml"), "name: Status\ncommand: git status\ndescription: Show status\n").unwrap();
+        fs::write(source.join("broken.yml"), "name: [unclosed\n").unwrap();
+        fs::write(source.join("missing_command.yaml"), "name: No command\ndescription: Nothing to run\n").unwrap();
+        fs::write(source.join(".git/config.yaml"), "not: a workflow\n").unwrap();
+        fs::write(source.join("README.md"), "# Workflows\n").unwrap();
+
+        let report = import_workflows(&source, &dest).unwrap();
+        assert_eq!(report.imported, [PathBuf::from("git/status.yaml")]);
+        let failed: Vec<_> = report.failed.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(failed, [PathBuf::from("broken.yml"), PathBuf::from("missing_command.yaml")]);
+        assert!(report.failed[1].1.contains("command"), "{}", report.failed[1].1);
+
+        let mut manager = WorkflowManager::new();
+        manager.add_workflow_directory(&dest);
+        manager.reload_workflows().unwrap();
+        assert_eq!(manager.get_workflow("git/status").unwrap().command, "git status");
+        assert!(import_workflows(&root.join("nowhere"), &dest).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_shell_normalization() {
+        assert_eq!(normalize_shell("/usr/local/bin/fish"), "fish");
+        assert_eq!(normalize_shell("C:\\Windows\\powershell.exe"), "pwsh");
+        assert!(workflow_for(&[]).supports_shell("anything"));
+    }
+}